@@ -0,0 +1,102 @@
+// Service worker for the timer page, served from /sw.js under the base path
+//
+// Toggles which can't reach the server are queued in IndexedDB with the time
+// they happened and an idempotency key, then replayed in order through
+// /timer/sync once the connection is back.
+
+const DATABASE = "sprite-offline";
+const QUEUE = "toggles";
+const SYNC_TAG = "toggles";
+
+// The base path the server runs under, the worker's scope is the root of the site
+const BASE_PATH = new URL(self.registration.scope).pathname.replace(/\/$/, "");
+
+self.addEventListener("install", () => self.skipWaiting());
+self.addEventListener("activate", (event) => event.waitUntil(self.clients.claim()));
+
+self.addEventListener("fetch", (event) => {
+    const url = new URL(event.request.url);
+    if (event.request.method === "POST" && url.pathname === BASE_PATH + "/timer/toggle") {
+        event.respondWith(toggle(event.request));
+    }
+});
+
+self.addEventListener("sync", (event) => {
+    if (event.tag === SYNC_TAG) {
+        event.waitUntil(flush());
+    }
+});
+
+// Pages ask for a flush when the browser comes back online, for browsers
+// without background sync
+self.addEventListener("message", (event) => {
+    if (event.data === "flush") {
+        event.waitUntil(flush());
+    }
+});
+
+async function toggle(request) {
+    const body = await request.clone().json();
+    try {
+        const response = await fetch(request);
+        // The server is reachable again, so send whatever piled up
+        flush();
+        return response;
+    } catch (err) {
+        await enqueue({
+            ...body,
+            "event-time": body["event-time"] ?? Math.floor(Date.now() / 1000),
+            "idempotency-key": request.headers.get("idempotency-key") ?? crypto.randomUUID(),
+        });
+        if (self.registration.sync) {
+            await self.registration.sync.register(SYNC_TAG);
+        }
+
+        return new Response(JSON.stringify({ queued: true }), {
+            status: 202,
+            headers: { "content-type": "application/json" },
+        });
+    }
+}
+
+async function flush() {
+    const queued = await withQueue("readonly", (queue) => queue.getAll());
+    if (queued.length === 0) {
+        return;
+    }
+
+    const toggles = queued.map(({ id, ...toggle }) => toggle);
+    const response = await fetch(BASE_PATH + "/timer/sync", {
+        method: "POST",
+        headers: { "content-type": "application/json" },
+        body: JSON.stringify({ toggles }),
+    });
+    if (!response.ok) {
+        throw new Error("Unable to sync toggles: " + response.status);
+    }
+
+    // Every toggle got a result, including the ones the server rejected
+    await withQueue("readwrite", (queue) => {
+        queued.forEach(({ id }) => queue.delete(id));
+    });
+}
+
+function enqueue(toggle) {
+    return withQueue("readwrite", (queue) => queue.add(toggle));
+}
+
+function withQueue(mode, action) {
+    return new Promise((resolve, reject) => {
+        const open = indexedDB.open(DATABASE, 1);
+        open.onupgradeneeded = () => {
+            open.result.createObjectStore(QUEUE, { keyPath: "id", autoIncrement: true });
+        };
+        open.onerror = () => reject(open.error);
+        open.onsuccess = () => {
+            const transaction = open.result.transaction(QUEUE, mode);
+            const request = action(transaction.objectStore(QUEUE));
+            transaction.oncomplete = () => resolve(request ? request.result : undefined);
+            transaction.onerror = () => reject(transaction.error);
+        };
+    });
+}
@@ -0,0 +1,77 @@
+timers-title = Timer - { $tag }
+shared-title = Geteilte Timer
+read-only = Schreibgeschützt
+
+filter-label = Nach Label filtern
+filter = Filtern
+project-name = Projektname
+project-template = Vorlage
+no-project-template = Keine Vorlage
+create = Erstellen
+
+find-project = Projekt suchen
+preset-project = Projekt der Vorlage
+preset-name = Name der Vorlage
+minutes = Minuten
+note = Notiz
+save-preset = Vorlage speichern
+
+chart-last-days = Letzte { $days } Tage
+chart-tracked = { $duration } erfasst
+long-timer-alert = Dieser Timer läuft seit { $duration }, hast du vergessen, ihn zu stoppen?
+
+export = Exportieren
+clone-project = Duplizieren
+start = Beginn
+end = Ende
+duration = Dauer
+labels = Labels
+invoiced = Abgerechnet
+select-timer = Timer auswählen
+move-timers = Ausgewählte Timer verschieben nach
+move = Verschieben
+add-label = Label hinzufügen
+remove-label = Label entfernen
+add-link = Issue oder Commit verlinken
+open-link = Link öffnen
+start-timer = Timer starten
+stop-timer = Timer stoppen
+tracking = Erfasse: { $project }
+streak = Tage in Folge: { $days }
+longest-streak = Längste Serie: { $days } Tage
+goal-reached = Tagesziel von { $duration } erreicht!
+theme = Design
+theme-light = Hell
+theme-dark = Dunkel
+theme-auto = Wie das System
+week-title = Woche vom { $date }
+previous-week = Vorige Woche
+next-week = Nächste Woche
+project = Projekt
+running = Läuft
+nothing-tracked = Nichts erfasst
+back-to-timers = Alle Projekte
+archived = Archiviert
+sessions = Sitzungen
+total-tracked = Gesamt
+average-session = Durchschnittliche Sitzung
+longest-session = Längste Sitzung
+busiest-day = Aktivster Tag
+first-activity = Zuerst erfasst
+last-activity = Zuletzt erfasst
+export-decimal = Mit Dezimalstunden exportieren
+export-invoiced = Mit abgerechneten exportieren
+archive-project = Archivieren
+restore-project = Wiederherstellen
+budget-hours = Budget in Stunden
+set-budget = Budget festlegen
+activity-title = Letzte Aktivität
+activity-started = { $project } gestartet
+activity-stopped = { $project } nach { $duration } gestoppt
+activity-discarded = Timer von { $duration } für { $project } verworfen
+activity-created = Projekt { $project } angelegt
+no-activity = Noch nichts passiert
+ago-now = gerade eben
+ago-minutes = vor { $count } Min.
+ago-hours = vor { $count } Std.
+ago-days = vor { $count } T.
@@ -0,0 +1,77 @@
+timers-title = Timers - { $tag }
+shared-title = Shared timers
+read-only = Read-only
+
+filter-label = Filter by label
+filter = Filter
+project-name = Project name
+project-template = Template
+no-project-template = No template
+create = Create
+
+find-project = Find project
+preset-project = Preset project
+preset-name = Preset name
+minutes = Minutes
+note = Note
+save-preset = Save preset
+
+chart-last-days = Last { $days } days
+chart-tracked = { $duration } tracked
+long-timer-alert = This timer has been running for { $duration }, did you forget to stop it?
+
+export = Export
+clone-project = Clone
+start = Start
+end = End
+duration = Duration
+labels = Labels
+invoiced = Invoiced
+select-timer = Select timer
+move-timers = Move selected timers to
+move = Move
+add-label = Add label
+remove-label = Remove label
+add-link = Link issue or commit
+open-link = Open link
+start-timer = Start timer
+stop-timer = Stop timer
+tracking = Tracking: { $project }
+streak = Days in a row: { $days }
+longest-streak = Longest streak: { $days } days
+goal-reached = Daily goal of { $duration } reached!
+theme = Theme
+theme-light = Light
+theme-dark = Dark
+theme-auto = Like the system
+week-title = Week of { $date }
+previous-week = Previous week
+next-week = Next week
+project = Project
+running = Running
+nothing-tracked = Nothing tracked
+back-to-timers = All projects
+archived = Archived
+sessions = Sessions
+total-tracked = Total
+average-session = Average session
+longest-session = Longest session
+busiest-day = Busiest day
+first-activity = First tracked
+last-activity = Last tracked
+export-decimal = Export with decimal hours
+export-invoiced = Export including invoiced
+archive-project = Archive
+restore-project = Restore
+budget-hours = Budget in hours
+set-budget = Set budget
+activity-title = Recent activity
+activity-started = Started { $project }
+activity-stopped = Stopped { $project } after { $duration }
+activity-discarded = Discarded a { $duration } timer on { $project }
+activity-created = Created project { $project }
+no-activity = Nothing happened yet
+ago-now = just now
+ago-minutes = { $count } min ago
+ago-hours = { $count } h ago
+ago-days = { $count } d ago
@@ -0,0 +1,77 @@
+timers-title = Temporizadores - { $tag }
+shared-title = Temporizadores compartidos
+read-only = Solo lectura
+
+filter-label = Filtrar por etiqueta
+filter = Filtrar
+project-name = Nombre del proyecto
+project-template = Plantilla
+no-project-template = Sin plantilla
+create = Crear
+
+find-project = Buscar proyecto
+preset-project = Proyecto del atajo
+preset-name = Nombre del atajo
+minutes = Minutos
+note = Nota
+save-preset = Guardar atajo
+
+chart-last-days = Últimos { $days } días
+chart-tracked = { $duration } registradas
+long-timer-alert = Este temporizador lleva { $duration } en marcha, ¿olvidaste detenerlo?
+
+export = Exportar
+clone-project = Duplicar
+start = Inicio
+end = Fin
+duration = Duración
+labels = Etiquetas
+invoiced = Facturado
+select-timer = Seleccionar temporizador
+move-timers = Mover los temporizadores seleccionados a
+move = Mover
+add-label = Añadir etiqueta
+remove-label = Quitar etiqueta
+add-link = Enlazar issue o commit
+open-link = Abrir enlace
+start-timer = Iniciar temporizador
+stop-timer = Detener temporizador
+tracking = Registrando: { $project }
+streak = Días seguidos: { $days }
+longest-streak = Racha más larga: { $days } días
+goal-reached = ¡Objetivo diario de { $duration } alcanzado!
+theme = Tema
+theme-light = Claro
+theme-dark = Oscuro
+theme-auto = Como el sistema
+week-title = Semana del { $date }
+previous-week = Semana anterior
+next-week = Semana siguiente
+project = Proyecto
+running = En curso
+nothing-tracked = Nada registrado
+back-to-timers = Todos los proyectos
+archived = Archivado
+sessions = Sesiones
+total-tracked = Total
+average-session = Sesión media
+longest-session = Sesión más larga
+busiest-day = Día más activo
+first-activity = Primer registro
+last-activity = Último registro
+export-decimal = Exportar con horas decimales
+export-invoiced = Exportar incluyendo facturados
+archive-project = Archivar
+restore-project = Restaurar
+budget-hours = Presupuesto en horas
+set-budget = Fijar presupuesto
+activity-title = Actividad reciente
+activity-started = { $project } iniciado
+activity-stopped = { $project } detenido tras { $duration }
+activity-discarded = Temporizador de { $duration } en { $project } descartado
+activity-created = Proyecto { $project } creado
+no-activity = Todavía no ha pasado nada
+ago-now = ahora mismo
+ago-minutes = hace { $count } min
+ago-hours = hace { $count } h
+ago-days = hace { $count } d
@@ -0,0 +1,15 @@
+-- Tracks the last time each tag interacted with sprite
+CREATE TABLE IF NOT EXISTS TAGS (
+    unique_id   TEXT PRIMARY KEY NOT NULL,
+    last_seen   INTEGER NOT NULL, -- Unix epoch of the last activity in UTC
+    archived    BOOLEAN NOT NULL DEFAULT 0 CHECK (archived IN (0, 1)) -- Boolean value 0 false 1 true
+);
+
+-- Backfill activity for tags which already have projects or timers
+INSERT OR IGNORE INTO TAGS (unique_id, last_seen)
+SELECT unique_id, MAX(last_seen) FROM (
+    SELECT unique_id, start_time + duration AS last_seen FROM TIMERS
+    UNION ALL
+    SELECT unique_id, created AS last_seen FROM PROJECTS
+)
+GROUP BY unique_id;
@@ -0,0 +1,6 @@
+-- Per tag settings
+CREATE TABLE IF NOT EXISTS SETTINGS (
+    unique_id               TEXT PRIMARY KEY NOT NULL,
+    short_timer_threshold   INTEGER NOT NULL DEFAULT 0, -- Timers shorter than this many seconds are handled by the policy
+    short_timer_policy      TEXT NOT NULL DEFAULT 'keep' CHECK (short_timer_policy IN ('keep', 'discard', 'merge'))
+);
@@ -0,0 +1,15 @@
+-- Free-form note attached to a timer
+ALTER TABLE TIMERS ADD COLUMN note TEXT;
+
+-- Saved presets for quickly adding a finished timer
+CREATE TABLE IF NOT EXISTS PRESETS (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    unique_id   TEXT NOT NULL,
+    project_id  INTEGER NOT NULL,
+    name        TEXT NOT NULL,
+    duration    INTEGER NOT NULL, -- The number of seconds timers created from this preset last for
+    note        TEXT,
+    FOREIGN KEY (project_id) -- Foreign key to projects
+        REFERENCES PROJECTS (id)
+        ON DELETE CASCADE
+);
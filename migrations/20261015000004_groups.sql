@@ -0,0 +1,6 @@
+-- Groups of tags shown together on a wall display
+CREATE TABLE IF NOT EXISTS GROUP_MEMBERS (
+    group_id    TEXT NOT NULL,
+    unique_id   TEXT NOT NULL,
+    PRIMARY KEY (group_id, unique_id)
+);
@@ -0,0 +1,4 @@
+-- Human friendly alias which can be used in place of the TagId in URLs
+ALTER TABLE TAGS ADD COLUMN alias TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS tags_alias ON TAGS (alias);
@@ -0,0 +1,8 @@
+-- Maps TagIds generated with the legacy DefaultHasher scheme to their stable replacements.
+--
+-- The raw tag names were never stored, so rows are added the first time a device
+-- presents a tag whose legacy ID still owns data.
+CREATE TABLE IF NOT EXISTS TAG_REDIRECTS (
+    legacy_id   TEXT PRIMARY KEY NOT NULL,
+    unique_id   TEXT NOT NULL
+);
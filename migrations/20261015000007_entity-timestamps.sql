@@ -0,0 +1,52 @@
+-- Creation and last-modified metadata, as unix epochs in UTC.
+--
+-- Projects already record their creation time in `created`.
+ALTER TABLE PROJECTS ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE TIMERS ADD COLUMN created_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE TIMERS ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE SETTINGS ADD COLUMN created_at INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE SETTINGS ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+
+-- Best effort backfill of existing rows
+UPDATE PROJECTS SET updated_at = created;
+UPDATE TIMERS SET created_at = start_time, updated_at = start_time + duration;
+
+-- Columns added with ALTER TABLE can't default to the current time, so triggers maintain them
+CREATE TRIGGER IF NOT EXISTS projects_inserted AFTER INSERT ON PROJECTS
+BEGIN
+    UPDATE PROJECTS SET updated_at = CAST(strftime('%s', 'now') AS INTEGER) WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_updated AFTER UPDATE ON PROJECTS
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+    UPDATE PROJECTS SET updated_at = CAST(strftime('%s', 'now') AS INTEGER) WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS timers_inserted AFTER INSERT ON TIMERS
+BEGIN
+    UPDATE TIMERS
+    SET created_at = CAST(strftime('%s', 'now') AS INTEGER),
+        updated_at = CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS timers_updated AFTER UPDATE ON TIMERS
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+    UPDATE TIMERS SET updated_at = CAST(strftime('%s', 'now') AS INTEGER) WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS settings_inserted AFTER INSERT ON SETTINGS
+BEGIN
+    UPDATE SETTINGS
+    SET created_at = CAST(strftime('%s', 'now') AS INTEGER),
+        updated_at = CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE unique_id = NEW.unique_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS settings_updated AFTER UPDATE ON SETTINGS
+WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+    UPDATE SETTINGS SET updated_at = CAST(strftime('%s', 'now') AS INTEGER) WHERE unique_id = NEW.unique_id;
+END;
@@ -0,0 +1,13 @@
+-- Devices (phones, NFC readers) which have toggled timers for a tag
+CREATE TABLE IF NOT EXISTS DEVICES (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    unique_id   TEXT NOT NULL,
+    details     TEXT NOT NULL, -- The device details reported by the device itself
+    first_seen  INTEGER NOT NULL, -- Unix epoch in UTC
+    last_seen   INTEGER NOT NULL, -- Unix epoch in UTC
+    revoked     BOOLEAN NOT NULL DEFAULT 0 CHECK (revoked IN (0, 1)), -- Boolean value 0 false 1 true
+    UNIQUE (unique_id, details)
+);
+
+-- The device which started a timer, if known
+ALTER TABLE TIMERS ADD COLUMN device_id INTEGER REFERENCES DEVICES (id) ON DELETE SET NULL;
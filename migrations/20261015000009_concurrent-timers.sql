@@ -0,0 +1,5 @@
+-- Opt-in mode where every project of a tag can have its own running timer
+ALTER TABLE SETTINGS ADD COLUMN concurrent_timers BOOLEAN NOT NULL DEFAULT 0 CHECK (concurrent_timers IN (0, 1));
+
+-- At most one running timer per project
+CREATE UNIQUE INDEX IF NOT EXISTS timers_current_per_project ON TIMERS (project_id) WHERE is_current = 1;
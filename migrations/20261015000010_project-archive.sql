@@ -0,0 +1,2 @@
+-- Archived projects are hidden from search by default
+ALTER TABLE PROJECTS ADD COLUMN archived BOOLEAN NOT NULL DEFAULT 0 CHECK (archived IN (0, 1));
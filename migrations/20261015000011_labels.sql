@@ -0,0 +1,16 @@
+-- Free-form labels such as "meeting" or "deep-work", scoped to a tag
+CREATE TABLE IF NOT EXISTS LABELS (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    unique_id   TEXT NOT NULL,
+    name        TEXT NOT NULL,
+    UNIQUE (unique_id, name)
+);
+
+-- The labels attached to each timer
+CREATE TABLE IF NOT EXISTS TIMER_LABELS (
+    timer_id    INTEGER NOT NULL REFERENCES TIMERS (id) ON DELETE CASCADE,
+    label_id    INTEGER NOT NULL REFERENCES LABELS (id) ON DELETE CASCADE,
+    PRIMARY KEY (timer_id, label_id)
+);
+
+CREATE INDEX IF NOT EXISTS timer_labels_label ON TIMER_LABELS (label_id);
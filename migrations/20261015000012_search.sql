@@ -0,0 +1,47 @@
+-- Full text indexes over timer notes and project names, kept in sync by triggers
+CREATE VIRTUAL TABLE IF NOT EXISTS TIMER_NOTES_FTS USING fts5 (
+    note,
+    content = 'TIMERS',
+    content_rowid = 'id'
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS PROJECT_NAMES_FTS USING fts5 (
+    name,
+    content = 'PROJECTS',
+    content_rowid = 'id'
+);
+
+INSERT INTO TIMER_NOTES_FTS (TIMER_NOTES_FTS) VALUES ('rebuild');
+INSERT INTO PROJECT_NAMES_FTS (PROJECT_NAMES_FTS) VALUES ('rebuild');
+
+CREATE TRIGGER IF NOT EXISTS timers_fts_insert AFTER INSERT ON TIMERS
+BEGIN
+    INSERT INTO TIMER_NOTES_FTS (rowid, note) VALUES (new.id, new.note);
+END;
+
+CREATE TRIGGER IF NOT EXISTS timers_fts_delete AFTER DELETE ON TIMERS
+BEGIN
+    INSERT INTO TIMER_NOTES_FTS (TIMER_NOTES_FTS, rowid, note) VALUES ('delete', old.id, old.note);
+END;
+
+CREATE TRIGGER IF NOT EXISTS timers_fts_update AFTER UPDATE OF note ON TIMERS
+BEGIN
+    INSERT INTO TIMER_NOTES_FTS (TIMER_NOTES_FTS, rowid, note) VALUES ('delete', old.id, old.note);
+    INSERT INTO TIMER_NOTES_FTS (rowid, note) VALUES (new.id, new.note);
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_fts_insert AFTER INSERT ON PROJECTS
+BEGIN
+    INSERT INTO PROJECT_NAMES_FTS (rowid, name) VALUES (new.id, new.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_fts_delete AFTER DELETE ON PROJECTS
+BEGIN
+    INSERT INTO PROJECT_NAMES_FTS (PROJECT_NAMES_FTS, rowid, name) VALUES ('delete', old.id, old.name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_fts_update AFTER UPDATE OF name ON PROJECTS
+BEGIN
+    INSERT INTO PROJECT_NAMES_FTS (PROJECT_NAMES_FTS, rowid, name) VALUES ('delete', old.id, old.name);
+    INSERT INTO PROJECT_NAMES_FTS (rowid, name) VALUES (new.id, new.name);
+END;
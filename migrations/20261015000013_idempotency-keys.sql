@@ -0,0 +1,8 @@
+-- Idempotency keys of toggles which have been applied, so replayed requests are applied once
+CREATE TABLE IF NOT EXISTS IDEMPOTENCY_KEYS (
+    unique_id   TEXT NOT NULL,
+    key         TEXT NOT NULL,
+    timer_id    INTEGER, -- The timer the toggle started or stopped, NULL while it's being applied
+    created_at  INTEGER NOT NULL, -- Unix epoch in UTC
+    PRIMARY KEY (unique_id, key)
+);
@@ -0,0 +1,9 @@
+-- Read-only share links for a tag, the token is the secret part of the URL
+CREATE TABLE IF NOT EXISTS SHARE_TOKENS (
+    token       TEXT PRIMARY KEY NOT NULL,
+    unique_id   TEXT NOT NULL,
+    created_at  INTEGER NOT NULL, -- Unix epoch in UTC
+    revoked     BOOLEAN NOT NULL DEFAULT 0 CHECK (revoked IN (0, 1)) -- Boolean value 0 false 1 true
+);
+
+CREATE INDEX IF NOT EXISTS SHARE_TOKENS_BY_TAG ON SHARE_TOKENS (unique_id);
@@ -0,0 +1,26 @@
+-- Teams share projects between the tags of their members
+CREATE TABLE IF NOT EXISTS TEAMS (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name        TEXT NOT NULL,
+    created_at  INTEGER NOT NULL -- Unix epoch in UTC
+);
+
+CREATE TABLE IF NOT EXISTS TEAM_MEMBERS (
+    team_id     INTEGER NOT NULL REFERENCES TEAMS (id) ON DELETE CASCADE,
+    unique_id   TEXT NOT NULL,
+    role        TEXT NOT NULL CHECK (role IN ('owner', 'member', 'viewer')),
+    joined_at   INTEGER NOT NULL, -- Unix epoch in UTC
+    PRIMARY KEY (team_id, unique_id)
+);
+
+CREATE INDEX IF NOT EXISTS TEAM_MEMBERS_BY_TAG ON TEAM_MEMBERS (unique_id);
+
+-- The projects of a team, owned by the tag which created them
+CREATE TABLE IF NOT EXISTS TEAM_PROJECTS (
+    project_id  INTEGER PRIMARY KEY NOT NULL REFERENCES PROJECTS (id) ON DELETE CASCADE,
+    team_id     INTEGER NOT NULL REFERENCES TEAMS (id) ON DELETE CASCADE
+);
+
+-- Every member runs their own timer on a team project
+DROP INDEX IF EXISTS timers_current_per_project;
+CREATE UNIQUE INDEX IF NOT EXISTS timers_current_per_member ON TIMERS (project_id, unique_id) WHERE is_current = 1;
@@ -0,0 +1,11 @@
+-- Where and whether to send the weekly summary of hours per project
+ALTER TABLE SETTINGS ADD COLUMN email TEXT;
+ALTER TABLE SETTINGS ADD COLUMN weekly_digest BOOLEAN NOT NULL DEFAULT 0 CHECK (weekly_digest IN (0, 1));
+
+-- The weeks a digest has been sent for, so restarts never send one twice
+CREATE TABLE IF NOT EXISTS DIGESTS_SENT (
+    unique_id   TEXT NOT NULL,
+    week_start  INTEGER NOT NULL, -- Unix epoch in UTC
+    sent_at     INTEGER NOT NULL, -- Unix epoch in UTC
+    PRIMARY KEY (unique_id, week_start)
+);
@@ -0,0 +1,12 @@
+-- When to remind a tag that no timer is running, like "Mon-Fri 09:00-17:00 Europe/Berlin"
+ALTER TABLE SETTINGS ADD COLUMN reminder_schedule TEXT;
+-- How many minutes without a running timer before a reminder is sent
+ALTER TABLE SETTINGS ADD COLUMN reminder_after INTEGER NOT NULL DEFAULT 15 CHECK (reminder_after > 0);
+-- A URL reminders are posted to, in addition to the email address
+ALTER TABLE SETTINGS ADD COLUMN reminder_webhook TEXT;
+
+-- The last reminder sent to a tag, so every idle stretch is only reminded once
+CREATE TABLE IF NOT EXISTS REMINDERS (
+    unique_id   TEXT PRIMARY KEY NOT NULL,
+    reminded_at INTEGER NOT NULL -- Unix epoch in UTC
+);
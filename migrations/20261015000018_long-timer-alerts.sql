@@ -0,0 +1,11 @@
+-- How many minutes a timer can run before it's assumed to be forgotten
+ALTER TABLE SETTINGS ADD COLUMN long_timer_after INTEGER CHECK (long_timer_after > 0);
+
+-- The running timers an alert was sent for, so every timer is only alerted once
+CREATE TABLE IF NOT EXISTS LONG_TIMER_ALERTS (
+    timer_id    INTEGER PRIMARY KEY NOT NULL,
+    alerted_at  INTEGER NOT NULL, -- Unix epoch in UTC
+    FOREIGN KEY (timer_id)
+        REFERENCES TIMERS (id)
+        ON DELETE CASCADE
+);
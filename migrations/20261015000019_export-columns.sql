@@ -0,0 +1,4 @@
+-- The columns exports have by default, a comma separated list like "start,end,duration"
+ALTER TABLE SETTINGS ADD COLUMN export_columns TEXT;
+-- How durations are written by default, checked by the application since more formats may be added
+ALTER TABLE SETTINGS ADD COLUMN duration_format TEXT NOT NULL DEFAULT 'clock';
@@ -0,0 +1,4 @@
+-- How dates are written on pages and in exports: iso, dmy or mdy
+ALTER TABLE SETTINGS ADD COLUMN date_order TEXT NOT NULL DEFAULT 'iso';
+-- Whether times are written on a 24h or 12h clock
+ALTER TABLE SETTINGS ADD COLUMN clock TEXT NOT NULL DEFAULT '24h';
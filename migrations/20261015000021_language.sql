@@ -0,0 +1,2 @@
+-- The language the web UI is shown in, instead of negotiating it from Accept-Language
+ALTER TABLE SETTINGS ADD COLUMN language TEXT;
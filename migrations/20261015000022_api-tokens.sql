@@ -0,0 +1,9 @@
+-- Bearer tokens which let integrations like Home Assistant read and toggle a tag's timer
+CREATE TABLE IF NOT EXISTS API_TOKENS (
+    token       TEXT PRIMARY KEY NOT NULL,
+    unique_id   TEXT NOT NULL,
+    created_at  INTEGER NOT NULL, -- Unix epoch in UTC
+    revoked     BOOLEAN NOT NULL DEFAULT 0 CHECK (revoked IN (0, 1)) -- Boolean value 0 false 1 true
+);
+
+CREATE INDEX IF NOT EXISTS API_TOKENS_BY_TAG ON API_TOKENS (unique_id);
@@ -0,0 +1,5 @@
+-- When a tag's toggle link was last used, so a link opened twice in a row only toggles once
+CREATE TABLE IF NOT EXISTS TOGGLE_LINKS (
+    unique_id   TEXT PRIMARY KEY NOT NULL,
+    used_at     INTEGER NOT NULL -- Unix epoch in UTC
+);
@@ -0,0 +1,8 @@
+-- Short slugs for timer page URLs, which fit on small NFC tags
+CREATE TABLE IF NOT EXISTS SLUGS (
+    slug        TEXT PRIMARY KEY NOT NULL,
+    unique_id   TEXT NOT NULL,
+    created_at  INTEGER NOT NULL -- Unix epoch in UTC
+);
+
+CREATE INDEX IF NOT EXISTS SLUGS_BY_TAG ON SLUGS (unique_id);
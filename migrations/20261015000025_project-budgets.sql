@@ -0,0 +1,12 @@
+-- Hour budgets of projects, for fixed-bid work
+CREATE TABLE IF NOT EXISTS PROJECT_BUDGETS (
+    project_id  INTEGER PRIMARY KEY NOT NULL,
+    unique_id   TEXT NOT NULL,
+    budget      INTEGER NOT NULL CHECK (budget > 0), -- Seconds
+    alerted     INTEGER NOT NULL DEFAULT 0, -- The highest percentage of the budget an alert was sent for
+    FOREIGN KEY (project_id)
+        REFERENCES PROJECTS (id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS PROJECT_BUDGETS_BY_TAG ON PROJECT_BUDGETS (unique_id);
@@ -0,0 +1,17 @@
+-- CSV exports of projects delivered at the end of every week or month
+CREATE TABLE IF NOT EXISTS SCHEDULED_EXPORTS (
+    id          INTEGER PRIMARY KEY NOT NULL,
+    project_id  INTEGER NOT NULL,
+    unique_id   TEXT NOT NULL,
+    schedule    TEXT NOT NULL, -- 'weekly' or 'monthly'
+    timezone    TEXT NOT NULL, -- Where periods start at midnight
+    email       TEXT,
+    webhook     TEXT,
+    created_at  INTEGER NOT NULL,
+    last_sent   INTEGER, -- The start of the latest period which was delivered
+    FOREIGN KEY (project_id)
+        REFERENCES PROJECTS (id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS SCHEDULED_EXPORTS_BY_TAG ON SCHEDULED_EXPORTS (unique_id);
@@ -0,0 +1,16 @@
+-- Weekly timesheets members submit to the owners of a team for approval
+CREATE TABLE IF NOT EXISTS TIMESHEETS (
+    id            INTEGER PRIMARY KEY NOT NULL,
+    team_id       INTEGER NOT NULL REFERENCES TEAMS (id) ON DELETE CASCADE,
+    unique_id     TEXT NOT NULL, -- The member the timesheet is for
+    week_start    INTEGER NOT NULL, -- Unix epoch in UTC of midnight on Monday
+    week_end      INTEGER NOT NULL, -- Unix epoch in UTC of midnight on the Monday after
+    status        TEXT NOT NULL, -- 'submitted', 'approved' or 'rejected'
+    comment       TEXT, -- Left by the owner reviewing the timesheet
+    submitted_at  INTEGER NOT NULL,
+    reviewed_by   TEXT,
+    reviewed_at   INTEGER,
+    UNIQUE (team_id, unique_id, week_start)
+);
+
+CREATE INDEX IF NOT EXISTS TIMESHEETS_BY_TAG ON TIMESHEETS (unique_id, week_start);
@@ -0,0 +1,2 @@
+-- Timers which were billed already, left out of exports and protected from edits
+ALTER TABLE TIMERS ADD COLUMN invoiced BOOLEAN NOT NULL DEFAULT 0 CHECK (invoiced IN (0, 1)); -- Boolean value 0 false 1 true
@@ -0,0 +1,4 @@
+-- Projects nested under a parent, like the tasks of a project or the projects of a client
+ALTER TABLE PROJECTS ADD COLUMN parent_id INTEGER REFERENCES PROJECTS (id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS PROJECTS_BY_PARENT ON PROJECTS (parent_id);
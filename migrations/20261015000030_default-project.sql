@@ -0,0 +1,2 @@
+-- Opt-in to starting timers on a made up "new-project" when a tag has no project
+ALTER TABLE SETTINGS ADD COLUMN default_project BOOLEAN NOT NULL DEFAULT 0 CHECK (default_project IN (0, 1));
@@ -0,0 +1,2 @@
+-- How many minutes a day needs to count towards a tag's streak
+ALTER TABLE SETTINGS ADD COLUMN streak_minutes INTEGER NOT NULL DEFAULT 30 CHECK (streak_minutes > 0);
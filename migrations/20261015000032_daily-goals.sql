@@ -0,0 +1,12 @@
+-- How many minutes a tag wants to track every day, if it has a goal
+ALTER TABLE SETTINGS ADD COLUMN daily_goal INTEGER CHECK (daily_goal > 0);
+-- The timezone the days of the goal start in
+ALTER TABLE SETTINGS ADD COLUMN goal_timezone TEXT NOT NULL DEFAULT 'US/Pacific';
+-- Whether reaching the goal is also posted to the reminder webhook
+ALTER TABLE SETTINGS ADD COLUMN goal_webhook BOOLEAN NOT NULL DEFAULT 0;
+
+-- When a tag last reached its daily goal, so every day is only celebrated once
+CREATE TABLE IF NOT EXISTS GOALS_REACHED (
+    unique_id  TEXT PRIMARY KEY NOT NULL,
+    reached_at INTEGER NOT NULL -- Unix epoch in UTC
+);
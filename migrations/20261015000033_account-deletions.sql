@@ -0,0 +1,6 @@
+-- Tokens confirming that a tag asked for all its data to be deleted
+CREATE TABLE IF NOT EXISTS ACCOUNT_DELETIONS (
+    unique_id   TEXT PRIMARY KEY NOT NULL,
+    token       TEXT NOT NULL,
+    expires_at  INTEGER NOT NULL -- Unix epoch in UTC
+);
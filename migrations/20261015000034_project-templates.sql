@@ -0,0 +1,10 @@
+-- Reusable settings for new projects, like the client and budget of a kind of engagement
+CREATE TABLE IF NOT EXISTS PROJECT_TEMPLATES (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    unique_id   TEXT NOT NULL,
+    name        TEXT NOT NULL,
+    budget      INTEGER CHECK (budget > 0), -- Seconds
+    parent_id   INTEGER REFERENCES PROJECTS (id) ON DELETE SET NULL,
+    created_at  INTEGER NOT NULL, -- Unix epoch in UTC
+    UNIQUE (unique_id, name)
+);
@@ -0,0 +1,6 @@
+-- Which instance runs a background task, so instances sharing a database don't all run it
+CREATE TABLE IF NOT EXISTS TASK_LEASES (
+    task        TEXT PRIMARY KEY NOT NULL,
+    holder      TEXT NOT NULL,
+    expires_at  INTEGER NOT NULL -- Unix epoch in UTC
+);
@@ -0,0 +1,54 @@
+-- Counts the changes to the projects and timers of each tag, so a cached
+-- copy of them can tell whether it's still current with a single lookup
+CREATE TABLE IF NOT EXISTS TAG_VERSIONS (
+    unique_id   TEXT PRIMARY KEY NOT NULL,
+    version     INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT OR IGNORE INTO TAG_VERSIONS (unique_id, version)
+SELECT DISTINCT unique_id, 1 FROM PROJECTS WHERE true;
+
+-- Every tag with projects has a version from the first one on, so changes
+-- which take something away only bump an existing version. That way purging
+-- a tag doesn't leave a version behind.
+CREATE TRIGGER IF NOT EXISTS projects_version_insert AFTER INSERT ON PROJECTS
+BEGIN
+    INSERT INTO TAG_VERSIONS (unique_id, version) VALUES (NEW.unique_id, 1)
+    ON CONFLICT (unique_id) DO UPDATE SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_version_update AFTER UPDATE ON PROJECTS
+BEGIN
+    UPDATE TAG_VERSIONS SET version = version + 1 WHERE unique_id = OLD.unique_id;
+    INSERT INTO TAG_VERSIONS (unique_id, version) VALUES (NEW.unique_id, 1)
+    ON CONFLICT (unique_id) DO UPDATE SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_version_delete AFTER DELETE ON PROJECTS
+BEGIN
+    UPDATE TAG_VERSIONS SET version = version + 1 WHERE unique_id = OLD.unique_id;
+END;
+
+-- Timers belong to the tag owning their project, which for team projects
+-- isn't the tag tracking the time
+CREATE TRIGGER IF NOT EXISTS timers_version_insert AFTER INSERT ON TIMERS
+BEGIN
+    INSERT INTO TAG_VERSIONS (unique_id, version)
+    SELECT unique_id, 1 FROM PROJECTS WHERE id = NEW.project_id
+    ON CONFLICT (unique_id) DO UPDATE SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS timers_version_update AFTER UPDATE ON TIMERS
+BEGIN
+    UPDATE TAG_VERSIONS SET version = version + 1
+    WHERE unique_id = (SELECT unique_id FROM PROJECTS WHERE id = OLD.project_id);
+    INSERT INTO TAG_VERSIONS (unique_id, version)
+    SELECT unique_id, 1 FROM PROJECTS WHERE id = NEW.project_id
+    ON CONFLICT (unique_id) DO UPDATE SET version = version + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS timers_version_delete AFTER DELETE ON TIMERS
+BEGIN
+    UPDATE TAG_VERSIONS SET version = version + 1
+    WHERE unique_id = (SELECT unique_id FROM PROJECTS WHERE id = OLD.project_id);
+END;
@@ -0,0 +1,7 @@
+-- Timers are looked up by the tag tracking them and by their project, mostly
+-- for the running one or the finished ones over a period
+CREATE INDEX IF NOT EXISTS TIMERS_BY_TAG ON TIMERS (unique_id, is_current, start_time);
+CREATE INDEX IF NOT EXISTS TIMERS_BY_PROJECT ON TIMERS (project_id, is_current, start_time);
+
+-- Projects are listed per tag, newest first
+CREATE INDEX IF NOT EXISTS PROJECTS_BY_TAG ON PROJECTS (unique_id, created);
@@ -0,0 +1,49 @@
+-- Start times and durations to the millisecond, for the accuracy of short timers.
+--
+-- The columns in seconds stay what everything else reads. They're the whole
+-- seconds of the start and end, so `duration` is the difference of those and
+-- `start_time + duration` still is the second a timer ended in.
+ALTER TABLE TIMERS ADD COLUMN start_time_ms INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE TIMERS ADD COLUMN duration_ms INTEGER NOT NULL DEFAULT 0;
+
+UPDATE TIMERS SET start_time_ms = start_time * 1000, duration_ms = duration * 1000;
+
+-- Timers written in seconds only, like imported or edited ones, get their
+-- milliseconds from the seconds. Milliseconds which agree with the seconds
+-- are kept.
+CREATE TRIGGER IF NOT EXISTS timers_ms_inserted AFTER INSERT ON TIMERS
+WHEN NEW.start_time_ms / 1000 != NEW.start_time
+    OR (NEW.start_time_ms + NEW.duration_ms) / 1000 != NEW.start_time + NEW.duration
+BEGIN
+    UPDATE TIMERS
+    SET start_time_ms = CASE
+        WHEN start_time_ms / 1000 = start_time THEN start_time_ms
+        ELSE start_time * 1000
+    END
+    WHERE id = NEW.id;
+    UPDATE TIMERS
+    SET duration_ms = CASE
+        WHEN (start_time_ms + duration_ms) / 1000 = start_time + duration THEN duration_ms
+        ELSE (start_time + duration) * 1000 - start_time_ms
+    END
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS timers_ms_updated
+AFTER UPDATE OF start_time, duration, start_time_ms, duration_ms ON TIMERS
+WHEN NEW.start_time_ms / 1000 != NEW.start_time
+    OR (NEW.start_time_ms + NEW.duration_ms) / 1000 != NEW.start_time + NEW.duration
+BEGIN
+    UPDATE TIMERS
+    SET start_time_ms = CASE
+        WHEN start_time_ms / 1000 = start_time THEN start_time_ms
+        ELSE start_time * 1000
+    END
+    WHERE id = NEW.id;
+    UPDATE TIMERS
+    SET duration_ms = CASE
+        WHEN (start_time_ms + duration_ms) / 1000 = start_time + duration THEN duration_ms
+        ELSE (start_time + duration) * 1000 - start_time_ms
+    END
+    WHERE id = NEW.id;
+END;
@@ -0,0 +1,2 @@
+-- How durations are rounded to their format: nearest, down or up
+ALTER TABLE SETTINGS ADD COLUMN duration_rounding TEXT NOT NULL DEFAULT 'nearest';
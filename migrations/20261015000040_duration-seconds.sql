@@ -0,0 +1,2 @@
+-- Whether durations under an hour are written with their seconds
+ALTER TABLE SETTINGS ADD COLUMN duration_seconds BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- The color scheme of the web UI: light, dark or auto
+ALTER TABLE SETTINGS ADD COLUMN theme TEXT NOT NULL DEFAULT 'light';
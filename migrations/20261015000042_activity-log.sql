@@ -0,0 +1,52 @@
+-- What happened to the timers and projects of a tag, so a tag can check its
+-- reader registered a tap. Filled in by triggers, which see every way a timer
+-- gets started or stopped.
+CREATE TABLE IF NOT EXISTS ACTIVITY_LOG (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    unique_id   TEXT NOT NULL,
+    -- started, stopped, discarded or created
+    kind        TEXT NOT NULL,
+    -- The name of the project at the time
+    project     TEXT NOT NULL,
+    at          INTEGER NOT NULL,
+    -- Seconds tracked by a stopped or discarded timer
+    duration    INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS activity_log_tag ON ACTIVITY_LOG (unique_id, at);
+
+-- Imported and added timers are finished right away, they weren't a tap
+CREATE TRIGGER IF NOT EXISTS activity_timer_started AFTER INSERT ON TIMERS
+WHEN NEW.is_current = 1
+BEGIN
+    INSERT INTO ACTIVITY_LOG (unique_id, kind, project, at)
+    SELECT NEW.unique_id, 'started', name, NEW.start_time FROM PROJECTS WHERE id = NEW.project_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS activity_timer_stopped AFTER UPDATE OF is_current ON TIMERS
+WHEN OLD.is_current = 1 AND NEW.is_current = 0
+BEGIN
+    INSERT INTO ACTIVITY_LOG (unique_id, kind, project, at, duration)
+    SELECT NEW.unique_id, 'stopped', name, NEW.start_time + NEW.duration, NEW.duration
+    FROM PROJECTS WHERE id = NEW.project_id;
+END;
+
+-- Short timers can be dropped while they're still running
+CREATE TRIGGER IF NOT EXISTS activity_timer_discarded AFTER DELETE ON TIMERS
+WHEN OLD.is_current = 1
+BEGIN
+    INSERT INTO ACTIVITY_LOG (unique_id, kind, project, at, duration)
+    SELECT
+        OLD.unique_id,
+        'discarded',
+        name,
+        CAST(strftime('%s', 'now') AS INTEGER),
+        MAX(CAST(strftime('%s', 'now') AS INTEGER) - OLD.start_time, 0)
+    FROM PROJECTS WHERE id = OLD.project_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS activity_project_created AFTER INSERT ON PROJECTS
+BEGIN
+    INSERT INTO ACTIVITY_LOG (unique_id, kind, project, at)
+    VALUES (NEW.unique_id, 'created', NEW.name, NEW.created);
+END;
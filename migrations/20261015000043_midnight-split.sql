@@ -0,0 +1,3 @@
+-- Whether running timers are split at midnight in the goal timezone, so every
+-- timer falls on a single day
+ALTER TABLE SETTINGS ADD COLUMN split_at_midnight BOOLEAN NOT NULL DEFAULT 0;
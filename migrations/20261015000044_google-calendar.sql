@@ -0,0 +1,31 @@
+-- Google accounts tags connected to push their finished timers to as
+-- calendar events
+CREATE TABLE IF NOT EXISTS GOOGLE_ACCOUNTS (
+    unique_id       TEXT PRIMARY KEY NOT NULL,
+    refresh_token   TEXT NOT NULL,
+    access_token    TEXT NOT NULL,
+    expires_at      INTEGER NOT NULL, -- When the access token stops working
+    calendar_id     TEXT NOT NULL DEFAULT 'primary', -- Unless the project has its own
+    created_at      INTEGER NOT NULL -- Timers which ended before aren't pushed
+);
+
+-- The calendar a project's timers go to instead of the account's
+CREATE TABLE IF NOT EXISTS GOOGLE_CALENDARS (
+    project_id  INTEGER PRIMARY KEY NOT NULL,
+    unique_id   TEXT NOT NULL,
+    calendar_id TEXT NOT NULL,
+    FOREIGN KEY (project_id)
+        REFERENCES PROJECTS (id)
+        ON DELETE CASCADE
+);
+
+-- The event each pushed timer became, so it's pushed only once
+CREATE TABLE IF NOT EXISTS GOOGLE_EVENTS (
+    timer_id    INTEGER PRIMARY KEY NOT NULL,
+    unique_id   TEXT NOT NULL,
+    event_id    TEXT NOT NULL,
+    synced_at   INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS GOOGLE_CALENDARS_BY_TAG ON GOOGLE_CALENDARS (unique_id);
+CREATE INDEX IF NOT EXISTS GOOGLE_EVENTS_BY_TAG ON GOOGLE_EVENTS (unique_id);
@@ -0,0 +1,2 @@
+-- The issue, commit or branch a timer was spent on, as a URL
+ALTER TABLE TIMERS ADD COLUMN link TEXT;
@@ -0,0 +1,31 @@
+-- Jira sites tags push their finished timers to as worklogs
+CREATE TABLE IF NOT EXISTS JIRA_ACCOUNTS (
+    unique_id   TEXT PRIMARY KEY NOT NULL,
+    site        TEXT NOT NULL, -- Like https://example.atlassian.net
+    email       TEXT NOT NULL,
+    api_token   TEXT NOT NULL,
+    created_at  INTEGER NOT NULL -- Timers which ended before aren't pushed
+);
+
+-- The issue a project's timers are logged on, projects without one aren't pushed
+CREATE TABLE IF NOT EXISTS JIRA_ISSUES (
+    project_id  INTEGER PRIMARY KEY NOT NULL,
+    unique_id   TEXT NOT NULL,
+    issue_key   TEXT NOT NULL,
+    created_at  INTEGER NOT NULL, -- Timers which ended before aren't pushed
+    FOREIGN KEY (project_id)
+        REFERENCES PROJECTS (id)
+        ON DELETE CASCADE
+);
+
+-- The worklog each pushed timer became, so it's pushed only once
+CREATE TABLE IF NOT EXISTS JIRA_WORKLOGS (
+    timer_id    INTEGER PRIMARY KEY NOT NULL,
+    unique_id   TEXT NOT NULL,
+    issue_key   TEXT NOT NULL,
+    worklog_id  TEXT NOT NULL,
+    synced_at   INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS JIRA_ISSUES_BY_TAG ON JIRA_ISSUES (unique_id);
+CREATE INDEX IF NOT EXISTS JIRA_WORKLOGS_BY_TAG ON JIRA_WORKLOGS (unique_id);
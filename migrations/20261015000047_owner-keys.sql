@@ -0,0 +1,24 @@
+-- The key proving ownership of a tag, issued when it's registered. Only a
+-- hash of the key is kept, registering the tag again replaces it
+CREATE TABLE IF NOT EXISTS OWNER_KEYS (
+    unique_id   TEXT PRIMARY KEY NOT NULL,
+    key_hash    TEXT NOT NULL UNIQUE,
+    created_at  INTEGER NOT NULL -- Unix epoch in UTC
+);
+
+-- Share links and API tokens, keeping only a hash of their secret
+CREATE TABLE IF NOT EXISTS TOKENS (
+    token_id    INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind        TEXT NOT NULL CHECK (kind IN ('share', 'api')),
+    token_hash  TEXT NOT NULL UNIQUE,
+    unique_id   TEXT NOT NULL,
+    created_at  INTEGER NOT NULL, -- Unix epoch in UTC
+    revoked     BOOLEAN NOT NULL DEFAULT 0 CHECK (revoked IN (0, 1)) -- Boolean value 0 false 1 true
+);
+
+CREATE INDEX IF NOT EXISTS TOKENS_BY_TAG ON TOKENS (unique_id, kind);
+
+-- The old tables kept the secrets themselves and listed them to anyone who
+-- knew the tag, so their tokens can't be trusted and aren't carried over
+DROP TABLE SHARE_TOKENS;
+DROP TABLE API_TOKENS;
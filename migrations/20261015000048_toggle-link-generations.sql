@@ -0,0 +1,4 @@
+-- Getting a new toggle link for a tag bumps the generation, only links signed
+-- for the current one toggle. Links from before weren't signed for any and
+-- were handed to anyone who knew the tag, so none of them toggles anymore
+ALTER TABLE TOGGLE_LINKS ADD COLUMN generation INTEGER NOT NULL DEFAULT 0;
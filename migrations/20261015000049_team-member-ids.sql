@@ -0,0 +1,17 @@
+-- Members are told apart by an ID numbered within their team, so what one
+-- member sees of a team doesn't include the tags of the others
+ALTER TABLE TEAM_MEMBERS ADD COLUMN member_id INTEGER NOT NULL DEFAULT 0;
+
+UPDATE TEAM_MEMBERS
+SET member_id = (
+    SELECT COUNT(*) FROM TEAM_MEMBERS AS earlier
+    WHERE earlier.team_id = TEAM_MEMBERS.team_id AND earlier.rowid <= TEAM_MEMBERS.rowid
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS TEAM_MEMBERS_BY_ID ON TEAM_MEMBERS (team_id, member_id);
+
+-- The last member ID handed out, so the IDs of removed members aren't reused
+ALTER TABLE TEAMS ADD COLUMN last_member_id INTEGER NOT NULL DEFAULT 0;
+
+UPDATE TEAMS
+SET last_member_id = (SELECT COUNT(*) FROM TEAM_MEMBERS WHERE team_id = TEAMS.id);
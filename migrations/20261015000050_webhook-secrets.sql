@@ -0,0 +1,9 @@
+-- The secret outgoing webhooks to a URL are signed with, one per tag and URL.
+-- Signing needs the secret itself, so it's kept rather than a hash of it
+CREATE TABLE IF NOT EXISTS WEBHOOK_SECRETS (
+    unique_id   TEXT NOT NULL,
+    url         TEXT NOT NULL,
+    secret      TEXT NOT NULL,
+    created_at  INTEGER NOT NULL, -- Unix epoch in UTC
+    PRIMARY KEY (unique_id, url)
+);
@@ -0,0 +1,16 @@
+-- Webhook deliveries which kept failing, kept until they're redelivered
+CREATE TABLE IF NOT EXISTS FAILED_WEBHOOKS (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    unique_id       TEXT NOT NULL,
+    url             TEXT NOT NULL,
+    kind            TEXT NOT NULL,
+    content_type    TEXT NOT NULL,
+    filename        TEXT, -- Sent as an attachment when set, like a CSV export
+    body            BLOB NOT NULL,
+    status          INTEGER, -- The HTTP status of the last attempt, unless it got no response
+    response        TEXT NOT NULL, -- The body of the last response, or why there was none
+    attempts        INTEGER NOT NULL,
+    failed_at       INTEGER NOT NULL -- Unix epoch in UTC
+);
+
+CREATE INDEX IF NOT EXISTS FAILED_WEBHOOKS_BY_TAG ON FAILED_WEBHOOKS (unique_id);
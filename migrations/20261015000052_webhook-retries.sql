@@ -0,0 +1,6 @@
+-- Failed webhooks are retried in the background a few times before they're
+-- left for an admin to redeliver. NULL once the retries gave up
+ALTER TABLE FAILED_WEBHOOKS ADD COLUMN next_attempt_at INTEGER;
+
+CREATE INDEX IF NOT EXISTS FAILED_WEBHOOKS_BY_NEXT_ATTEMPT
+    ON FAILED_WEBHOOKS (next_attempt_at) WHERE next_attempt_at IS NOT NULL;
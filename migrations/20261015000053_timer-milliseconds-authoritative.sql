@@ -0,0 +1,31 @@
+-- The columns in milliseconds are what timers are read by now. The ones in
+-- seconds follow them, still as the whole seconds of the start and end, and
+-- writes in seconds only no longer win over the milliseconds.
+DROP TRIGGER IF EXISTS timers_ms_inserted;
+DROP TRIGGER IF EXISTS timers_ms_updated;
+
+CREATE TRIGGER IF NOT EXISTS timers_seconds_inserted AFTER INSERT ON TIMERS
+WHEN NEW.start_time != NEW.start_time_ms / 1000
+    OR NEW.duration != (NEW.start_time_ms + NEW.duration_ms) / 1000 - NEW.start_time_ms / 1000
+BEGIN
+    UPDATE TIMERS
+    SET start_time = start_time_ms / 1000,
+        duration = (start_time_ms + duration_ms) / 1000 - start_time_ms / 1000
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS timers_seconds_updated
+AFTER UPDATE OF start_time, duration, start_time_ms, duration_ms ON TIMERS
+WHEN NEW.start_time != NEW.start_time_ms / 1000
+    OR NEW.duration != (NEW.start_time_ms + NEW.duration_ms) / 1000 - NEW.start_time_ms / 1000
+BEGIN
+    UPDATE TIMERS
+    SET start_time = start_time_ms / 1000,
+        duration = (start_time_ms + duration_ms) / 1000 - start_time_ms / 1000
+    WHERE id = NEW.id;
+END;
+
+DROP INDEX IF EXISTS TIMERS_BY_TAG;
+DROP INDEX IF EXISTS TIMERS_BY_PROJECT;
+CREATE INDEX IF NOT EXISTS TIMERS_BY_TAG ON TIMERS (unique_id, is_current, start_time_ms);
+CREATE INDEX IF NOT EXISTS TIMERS_BY_PROJECT ON TIMERS (project_id, is_current, start_time_ms);
@@ -0,0 +1,65 @@
+//! The credential guarding the `/admin` routes
+
+use std::env;
+
+use anyhow::{anyhow, Result};
+
+/// Checks the token sent to the `/admin` routes
+///
+/// Configured through `ADMIN_TOKEN`, any string which is kept private.
+/// Without it every admin request is refused, since the routes list every tag
+/// and start backups.
+#[derive(Clone, Default)]
+pub(crate) struct AdminToken {
+    hash: Option<blake3::Hash>,
+}
+
+impl std::fmt::Debug for AdminToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminToken")
+            .field("configured", &self.hash.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl AdminToken {
+    pub(crate) fn from_env() -> Result<Self> {
+        let Ok(token) = env::var("ADMIN_TOKEN") else {
+            return Ok(Self::default());
+        };
+        if token.len() < 16 {
+            return Err(anyhow!("ADMIN_TOKEN must be at least 16 characters"));
+        }
+
+        Ok(Self::new(&token))
+    }
+
+    pub(crate) fn new(token: &str) -> Self {
+        Self {
+            hash: Some(blake3::hash(token.as_bytes())),
+        }
+    }
+
+    /// Whether `token` is the admin token, never while none is configured
+    ///
+    /// Hashes are compared in constant time, so the comparison doesn't leak
+    /// how much of the token matched.
+    pub(crate) fn verify(&self, token: &str) -> bool {
+        self.hash
+            .is_some_and(|hash| hash == blake3::hash(token.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_the_configured_token_is_accepted() {
+        let admin = AdminToken::new("correct horse battery staple");
+
+        assert!(admin.verify("correct horse battery staple"));
+        assert!(!admin.verify("correct horse"));
+        assert!(!AdminToken::default().verify(""));
+    }
+}
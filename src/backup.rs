@@ -0,0 +1,179 @@
+use std::{env, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use s3::{creds::Credentials, Bucket, Region};
+use tracing::{error, info, instrument};
+
+use crate::{
+    leases,
+    timer_store::{DataStore, DatabaseConfig},
+};
+
+/// Snapshots of the database stored in S3-compatible object storage
+///
+/// Configured through `BACKUP_S3_BUCKET`, `BACKUP_S3_REGION`,
+/// `BACKUP_S3_ENDPOINT` (for non-AWS storage), `BACKUP_S3_PREFIX` and the
+/// usual `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`.
+#[derive(Debug, Clone)]
+pub struct Backups {
+    bucket: Bucket,
+    prefix: String,
+
+    /// Whether a missing database is restored on startup, from `BACKUP_RESTORE_ON_BOOT`
+    restore_on_boot: bool,
+}
+
+impl Backups {
+    /// Creates the backup storage from the environment, if a bucket is configured
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(name) = env::var("BACKUP_S3_BUCKET") else {
+            return Ok(None);
+        };
+
+        let region = env::var("BACKUP_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        let region = match env::var("BACKUP_S3_ENDPOINT") {
+            Ok(endpoint) => Region::Custom { region, endpoint },
+            Err(_) => region.parse()?,
+        };
+        let bucket = Bucket::new(&name, region, Credentials::from_env()?)?.with_path_style();
+        let prefix = env::var("BACKUP_S3_PREFIX").unwrap_or_else(|_| "backups/".to_string());
+        let restore_on_boot = env::var("BACKUP_RESTORE_ON_BOOT").is_ok_and(|value| value == "true");
+
+        Ok(Some(Self {
+            bucket,
+            prefix,
+            restore_on_boot,
+        }))
+    }
+
+    /// Snapshots the database and uploads it, returning the key of the backup
+    #[instrument(skip_all)]
+    pub(crate) async fn backup(&self, store: &DataStore) -> Result<String> {
+        let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+        // Backups taken within the same second each need their own snapshot file
+        let path = env::temp_dir().join(format!(
+            "sprite-backup-{}-{}.db",
+            timestamp,
+            uuid::Uuid::new_v4().simple()
+        ));
+
+        store.snapshot(&path).await?;
+        let snapshot = tokio::fs::read(&path).await;
+        tokio::fs::remove_file(&path).await?;
+
+        let key = format!("{}sprite-{}.db", self.prefix, timestamp);
+        self.bucket.put_object(&key, &snapshot?).await?;
+        info!(key, "Uploaded backup");
+
+        Ok(key)
+    }
+
+    /// Gets the key of the most recent backup, if there is one
+    ///
+    /// Backup keys embed their timestamp, so the latest one sorts last.
+    #[instrument(skip_all)]
+    pub(crate) async fn latest(&self) -> Result<Option<String>> {
+        let pages = self.bucket.list(self.prefix.clone(), None).await?;
+
+        Ok(pages
+            .into_iter()
+            .flat_map(|page| page.contents)
+            .map(|object| object.key)
+            .filter(|key| key.ends_with(".db"))
+            .max())
+    }
+
+    /// Downloads a backup
+    #[instrument(skip(self))]
+    pub(crate) async fn download(&self, key: &str) -> Result<Vec<u8>> {
+        let response = self.bucket.get_object(key).await?;
+        if response.status_code() != 200 {
+            return Err(anyhow!(
+                "Unable to download backup {}: {}",
+                key,
+                response.status_code()
+            ));
+        }
+
+        Ok(response.to_vec())
+    }
+
+    /// Restores the latest backup if the database file is missing
+    ///
+    /// An empty file counts as missing since the container entrypoint creates
+    /// it before starting. Returns whether a backup was restored.
+    #[instrument(skip_all)]
+    pub(crate) async fn restore_if_missing(&self, config: &DatabaseConfig) -> Result<bool> {
+        let Some(path) = config.file_path().filter(|_| self.restore_on_boot) else {
+            return Ok(false);
+        };
+
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) if metadata.len() > 0 => return Ok(false),
+            Ok(_) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let Some(key) = self.latest().await? else {
+            info!("Database missing but there are no backups, starting fresh");
+            return Ok(false);
+        };
+
+        info!(key, path = %path.display(), "Restoring database from backup");
+        let snapshot = self.download(&key).await?;
+
+        // Write next to the database and rename so a partial download is never used
+        let partial = path.with_extension("restoring");
+        tokio::fs::write(&partial, snapshot).await?;
+        for suffix in ["-wal", "-shm"] {
+            let mut stale = path.clone().into_os_string();
+            stale.push(suffix);
+            let _ = tokio::fs::remove_file(stale).await;
+        }
+        tokio::fs::rename(&partial, &path).await?;
+
+        Ok(true)
+    }
+}
+
+/// How often a backup is taken, from `BACKUP_INTERVAL_HOURS`
+fn backup_interval() -> Duration {
+    let hours = match env::var("BACKUP_INTERVAL_HOURS").map(|hours| hours.parse::<u64>()) {
+        Ok(Ok(hours)) if hours > 0 => hours,
+        Ok(_) => {
+            error!("Invalid BACKUP_INTERVAL_HOURS, backing up daily");
+            24
+        }
+        Err(_) => 24,
+    };
+
+    Duration::from_secs(hours * 60 * 60)
+}
+
+/// Spawns the background task taking periodic backups
+pub(crate) fn spawn(store: DataStore, backups: Option<Backups>) {
+    let Some(backups) = backups else {
+        info!("Backups disabled");
+        return;
+    };
+
+    tokio::spawn(run(store, backups, backup_interval()));
+}
+
+#[instrument(skip(store, backups))]
+async fn run(store: DataStore, backups: Backups, every: Duration) {
+    let mut interval = tokio::time::interval(every);
+    // The first tick completes immediately, skip it so restarts don't upload
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if !leases::held(&store, "backup", every).await {
+            continue;
+        }
+        if let Err(err) = backups.backup(&store).await {
+            error!(%err, "Unable to back up the database");
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! HTTP caching for static assets and exports
+//!
+//! Asset URLs in the templates carry a hash of the file's contents, so those
+//! responses can be cached forever and a deploy with new assets changes the
+//! URL. Exports are revalidated with an `ETag` of their contents instead.
+
+use std::{collections::HashMap, path::Path, sync::OnceLock};
+
+use axum::{
+    body::{Bytes, Full},
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::{info, warn};
+
+use crate::base_path;
+
+/// The directory the `/assets` route serves from
+pub(crate) const ASSET_DIR: &str = "assets/dist";
+
+static ASSET_HASHES: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Hashes every file in [`ASSET_DIR`] for [`asset_url`]
+pub(crate) fn hash_assets() {
+    let hashes = match read_hashes(Path::new(ASSET_DIR)) {
+        Ok(hashes) => hashes,
+        Err(err) => {
+            warn!(%err, "Unable to hash assets, they won't be cached");
+            HashMap::new()
+        }
+    };
+
+    info!(count = hashes.len(), "Hashed assets");
+    let _ = ASSET_HASHES.set(hashes);
+}
+
+fn read_hashes(dir: &Path) -> std::io::Result<HashMap<String, String>> {
+    let mut hashes = HashMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let hash = blake3::hash(&std::fs::read(&path)?).to_hex();
+        hashes.insert(name.to_string(), hash[..16].to_string());
+    }
+
+    Ok(hashes)
+}
+
+/// The URL of an asset, versioned by its contents when known
+pub fn asset_url(name: &str) -> String {
+    match ASSET_HASHES.get().and_then(|hashes| hashes.get(name)) {
+        Some(hash) => format!("{}/assets/{}?v={}", base_path(), name, hash),
+        None => format!("{}/assets/{}", base_path(), name),
+    }
+}
+
+/// Middleware for `/assets` letting browsers keep versioned assets forever
+///
+/// Unversioned requests are revalidated using the `Last-Modified` header
+/// the file service already sets.
+pub(crate) async fn asset_headers<B>(request: Request<B>, next: Next<B>) -> Response {
+    let versioned = request
+        .uri()
+        .query()
+        .is_some_and(|query| query.split('&').any(|pair| pair.starts_with("v=")));
+    let mut response = next.run(request).await;
+
+    let cache_control = if versioned && response.status().is_success() {
+        "public, max-age=31536000, immutable"
+    } else {
+        "no-cache"
+    };
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+
+    response
+}
+
+/// Responds with a body which clients revalidate through its `ETag`
+///
+/// Returns `304 Not Modified` without the body if the request's
+/// `If-None-Match` already has the current version.
+pub(crate) fn conditional(
+    request_headers: &HeaderMap,
+    content_type: &'static str,
+    body: Vec<u8>,
+) -> Response {
+    let etag = format!("\"{}\"", blake3::hash(&body).to_hex());
+    let headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, "private, no-cache".to_string()),
+    ];
+
+    if matches_etag(request_headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+
+    (
+        headers,
+        [(header::CONTENT_TYPE, content_type)],
+        Full::new(Bytes::from(body)),
+    )
+        .into_response()
+}
+
+fn matches_etag(request_headers: &HeaderMap, etag: &str) -> bool {
+    request_headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|candidate| candidate.trim().trim_start_matches("W/"))
+        .any(|candidate| candidate == "*" || candidate == etag)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn matching_etags_are_not_modified() {
+        let body = b"project,start\n".to_vec();
+        let fresh = conditional(&HeaderMap::new(), "text/csv", body.clone());
+        assert_eq!(fresh.status(), StatusCode::OK);
+
+        let etag = fresh.headers()[header::ETAG].clone();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", {}", etag.to_str().unwrap())).unwrap(),
+        );
+        let cached = conditional(&headers, "text/csv", body.clone());
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+
+        let changed = conditional(&headers, "text/csv", b"project,start,end\n".to_vec());
+        assert_eq!(changed.status(), StatusCode::OK);
+    }
+}
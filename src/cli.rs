@@ -0,0 +1,189 @@
+use std::{fs, io::Write, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use tracing::info;
+
+use crate::{
+    backup::Backups,
+    demo,
+    duration::DurationFormat,
+    plugins::Plugins,
+    templates::from_render_timezone,
+    timer_store::{DataStore, DatabaseConfig},
+    timer_utils::{export_project, ExportFilter},
+    uid::TagId,
+};
+
+/// Time tracking with NFC tags
+#[derive(Debug, Parser)]
+#[command(version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Runs the web server (the default)
+    Serve,
+
+    /// Writes the finished timers of a project as CSV
+    Export {
+        /// The ID of the project to export
+        project_id: i64,
+
+        /// The timezone to show times in, with '/' replaced by '-'
+        #[arg(long, default_value = "US-Pacific")]
+        timezone: String,
+
+        /// Where to write the CSV to instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// The columns to export, like "start,end,duration", instead of the tag's default
+        #[arg(long)]
+        columns: Option<String>,
+
+        /// How to write durations instead of the tag's default, "clock" or "decimal"
+        #[arg(long)]
+        duration: Option<DurationFormat>,
+
+        /// Also export the timers which were invoiced already
+        #[arg(long)]
+        include_invoiced: bool,
+    },
+
+    /// Applies any pending database migrations
+    Migrate,
+
+    /// Permanently deletes all data of the given tags
+    Purge {
+        /// The tags (IDs or aliases) to purge
+        tags: Vec<String>,
+
+        /// Also purge every archived tag
+        #[arg(long)]
+        archived: bool,
+    },
+
+    /// Fills a tag without any data with demo projects and timers, printing its ID
+    Seed {
+        /// The tag to seed, as it would be read from an NFC tag
+        #[arg(long, default_value = "demo")]
+        tag: String,
+
+        /// How many weeks of working days to generate timers for
+        #[arg(long, default_value_t = 6)]
+        weeks: u32,
+
+        /// The timezone of the working hours, with '/' replaced by '-'
+        #[arg(long, default_value = "US-Pacific")]
+        timezone: String,
+    },
+}
+
+impl Command {
+    /// Runs a command which doesn't need the web server
+    pub async fn run(self) -> Result<()> {
+        let plugins = Plugins::from_env()?;
+        let backups = Backups::from_env()?;
+        let config = DatabaseConfig::from_env()?;
+        // Connecting restores a missing database and applies pending migrations
+        let store = DataStore::new(&config, plugins.clone(), backups.as_ref()).await?;
+
+        match self {
+            Command::Serve => unreachable!("serve is handled by main"),
+            Command::Export {
+                project_id,
+                timezone,
+                output,
+                columns,
+                duration,
+                include_invoiced,
+            } => {
+                let options = ExportArgs {
+                    timezone,
+                    columns,
+                    duration,
+                    include_invoiced,
+                };
+                export(&store, &plugins, project_id, options, output).await
+            }
+            Command::Migrate => {
+                info!("Database is up to date");
+                Ok(())
+            }
+            Command::Purge { tags, archived } => purge(&store, tags, archived).await,
+            Command::Seed {
+                tag,
+                weeks,
+                timezone,
+            } => {
+                let uid = TagId::new(&tag)?;
+                demo::seed(&store, &uid, from_render_timezone(&timezone)?, weeks).await?;
+                println!("{}", uid.as_ref());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How `export` writes the CSV
+struct ExportArgs {
+    timezone: String,
+    columns: Option<String>,
+    duration: Option<DurationFormat>,
+    include_invoiced: bool,
+}
+
+async fn export(
+    store: &DataStore,
+    plugins: &Plugins,
+    project_id: i64,
+    args: ExportArgs,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let project = store.project(project_id).await?;
+    let csv = export_project(
+        store,
+        &project,
+        &args.timezone,
+        args.columns.as_deref(),
+        args.duration,
+        &ExportFilter {
+            invoiced: args.include_invoiced,
+            ..Default::default()
+        },
+        plugins,
+    )
+    .await?;
+
+    match output {
+        Some(path) => fs::write(path, csv)?,
+        None => std::io::stdout().write_all(&csv)?,
+    }
+
+    Ok(())
+}
+
+async fn purge(store: &DataStore, tags: Vec<String>, archived: bool) -> Result<()> {
+    let mut uids = Vec::new();
+    for tag in &tags {
+        uids.push(store.resolve_tag(tag).await?);
+    }
+    if archived {
+        uids.extend(store.archived_tags().await?);
+    }
+
+    if uids.is_empty() {
+        return Err(anyhow!("Nothing to purge, pass tags or --archived"));
+    }
+
+    for uid in uids {
+        let deleted = store.purge_tag(&uid).await?;
+        info!(%uid, deleted, "Purged tag");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! CORS for browser clients served from other origins, like an SPA or an extension
+
+use std::{env, time::Duration};
+
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::{error, info};
+
+use crate::server::IDEMPOTENCY_KEY;
+
+/// How long browsers may cache the answer to a preflight request
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Builds the CORS layer from `CORS_ALLOWED_ORIGINS`
+///
+/// The variable holds a comma separated list of origins, or `*` to allow any
+/// origin. Without it the layer allows no origins, so browsers keep to the
+/// same-origin policy.
+pub(crate) fn layer() -> CorsLayer {
+    let Ok(origins) = env::var("CORS_ALLOWED_ORIGINS") else {
+        info!("CORS disabled");
+        return CorsLayer::new();
+    };
+
+    let allow_origin = if origins.trim() == "*" {
+        AllowOrigin::any()
+    } else {
+        let origins = parse_origins(&origins);
+        if origins.is_empty() {
+            error!("No valid origins in CORS_ALLOWED_ORIGINS, CORS disabled");
+            return CorsLayer::new();
+        }
+        AllowOrigin::list(origins)
+    };
+
+    let request_id = HeaderName::from_static("x-request-id");
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static(IDEMPOTENCY_KEY),
+            request_id.clone(),
+        ])
+        .expose_headers([request_id])
+        .max_age(PREFLIGHT_MAX_AGE)
+}
+
+fn parse_origins(origins: &str) -> Vec<HeaderValue> {
+    origins
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(origin) => Some(origin),
+            Err(err) => {
+                error!(origin, %err, "Ignoring invalid origin in CORS_ALLOWED_ORIGINS");
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn origins_are_split_and_trimmed() {
+        let origins = parse_origins("https://app.example.com, chrome-extension://abc,,");
+
+        assert_eq!(
+            origins,
+            vec![
+                HeaderValue::from_static("https://app.example.com"),
+                HeaderValue::from_static("chrome-extension://abc"),
+            ]
+        );
+        assert!(parse_origins(" , ").is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+//! Formatting dates and times the way a tag prefers to read them
+
+use serde::{Deserialize, Serialize};
+
+/// The order the day, month and year of a date are written in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DateOrder {
+    /// Year first, like `2026-10-15`
+    #[default]
+    Iso,
+
+    /// Day first, like `15/10/2026`
+    Dmy,
+
+    /// Month first, like `10/15/2026`
+    Mdy,
+}
+
+/// Whether times are written on a 24 or 12 hour clock
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+pub enum Clock {
+    /// Like `14:30`
+    #[default]
+    #[sqlx(rename = "24h")]
+    #[serde(rename = "24h")]
+    H24,
+
+    /// Like `2:30 PM`
+    #[sqlx(rename = "12h")]
+    #[serde(rename = "12h")]
+    H12,
+}
+
+/// How a tag's timestamps are written on its pages and in its exports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DateFormat {
+    pub order: DateOrder,
+    pub clock: Clock,
+}
+
+impl DateFormat {
+    pub fn new(order: DateOrder, clock: Clock) -> Self {
+        Self { order, clock }
+    }
+
+    /// The `strftime` pattern for pages, like `Thu, 2026-10-15 14:30`
+    pub fn long(self) -> String {
+        format!("%a, {}", self.short())
+    }
+
+    /// The `strftime` pattern for exports, like `2026-10-15 14:30`
+    pub fn short(self) -> String {
+        format!("{} {}", self.date(), self.time())
+    }
+
+    /// The `strftime` pattern for just the date, like `2026-10-15`
+    pub fn date(self) -> &'static str {
+        match self.order {
+            DateOrder::Iso => "%F",
+            DateOrder::Dmy => "%d/%m/%Y",
+            DateOrder::Mdy => "%m/%d/%Y",
+        }
+    }
+
+    /// The `strftime` pattern for just the time of day, like `14:30`
+    pub fn time(self) -> &'static str {
+        match self.clock {
+            Clock::H24 => "%H:%M",
+            Clock::H12 => "%-I:%M %p",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn timestamps_follow_the_date_format() {
+        let time = chrono_tz::US::Pacific
+            .with_ymd_and_hms(2026, 10, 15, 14, 5, 0)
+            .unwrap();
+        let format = |order, clock| {
+            time.format(&DateFormat::new(order, clock).long())
+                .to_string()
+        };
+
+        assert_eq!(format(DateOrder::Iso, Clock::H24), "Thu, 2026-10-15 14:05");
+        assert_eq!(format(DateOrder::Dmy, Clock::H24), "Thu, 15/10/2026 14:05");
+        assert_eq!(
+            format(DateOrder::Mdy, Clock::H12),
+            "Thu, 10/15/2026 2:05 PM"
+        );
+        assert_eq!(
+            time.format(&DateFormat::default().short()).to_string(),
+            "2026-10-15 14:05"
+        );
+    }
+}
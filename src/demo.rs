@@ -0,0 +1,216 @@
+//! Realistic demo data, so the pages and reports can be tried out without
+//! weeks of real tracking
+//!
+//! The timers are generated from a fixed seed, so seeding on the same day
+//! always produces the same history.
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use tracing::info;
+
+use crate::{
+    error::Error,
+    time::{StartTime, TrackedDuration},
+    timer_store::{DataStore, ToggleOptions},
+    uid::TagId,
+};
+
+/// The clients the demo projects are nested under
+const CLIENTS: [&str; 2] = ["Acme Corp", "Globex"];
+
+/// A project of the demo tag
+struct DemoProject {
+    name: &'static str,
+
+    /// Index into [`CLIENTS`], if the project is client work
+    client: Option<usize>,
+
+    /// The labels every timer of the project gets
+    labels: &'static [&'static str],
+}
+
+const PROJECTS: [DemoProject; 5] = [
+    DemoProject {
+        name: "Website redesign",
+        client: Some(0),
+        labels: &["billable"],
+    },
+    DemoProject {
+        name: "Support",
+        client: Some(0),
+        labels: &["billable", "support"],
+    },
+    DemoProject {
+        name: "Mobile app",
+        client: Some(1),
+        labels: &["billable"],
+    },
+    DemoProject {
+        name: "Internal",
+        client: None,
+        labels: &[],
+    },
+    DemoProject {
+        name: "Learning",
+        client: None,
+        labels: &["learning"],
+    },
+];
+
+/// When the demo working day starts and ends, in local time
+const WORKDAY_START_HOUR: u32 = 9;
+const WORKDAY_END_HOUR: u32 = 18;
+
+/// A finished timer of the demo history
+#[derive(Debug, PartialEq, Eq)]
+struct DemoTimer {
+    /// Index into [`PROJECTS`]
+    project: usize,
+    start: StartTime,
+    tracked: TrackedDuration,
+}
+
+/// A xorshift generator, random enough for demo data without another dependency
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+}
+
+/// Generates the timers of every working day in the `weeks` before the day of `now`
+fn demo_timers(timezone: Tz, now: DateTime<Utc>, weeks: u32) -> Vec<DemoTimer> {
+    let mut rng = Rng(0x5eed_5917_e000_0001);
+    let today = now.with_timezone(&timezone).date_naive();
+    let first_day = today - Duration::weeks(weeks.into());
+
+    let mut timers = Vec::new();
+    for day in first_day.iter_days().take_while(|day| *day < today) {
+        if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+            continue;
+        }
+
+        let at = |hour| {
+            timezone
+                .from_local_datetime(&day.and_time(NaiveTime::from_hms_opt(hour, 0, 0)?))
+                .earliest()
+                .map(|time| time.with_timezone(&Utc))
+        };
+        let (Some(mut cursor), Some(end_of_day)) = (at(WORKDAY_START_HOUR), at(WORKDAY_END_HOUR))
+        else {
+            continue;
+        };
+
+        loop {
+            // Sessions of 20 minutes to 2.5 hours in 5 minute steps, with short breaks in between
+            cursor += Duration::minutes(5 + 5 * rng.below(9) as i64);
+            let tracked = TrackedDuration(Duration::minutes(20 + 5 * rng.below(27) as i64));
+            let start = StartTime(cursor);
+            if start.end(tracked) > end_of_day {
+                break;
+            }
+
+            timers.push(DemoTimer {
+                project: rng.below(PROJECTS.len() as u64) as usize,
+                start,
+                tracked,
+            });
+            cursor = start.end(tracked);
+        }
+    }
+
+    timers
+}
+
+/// Fills a tag without any data with demo projects and timers
+///
+/// The projects are nested under two clients, the timers span the working
+/// days of the last `weeks` and one timer is left running.
+pub(crate) async fn seed(store: &DataStore, uid: &TagId, timezone: Tz, weeks: u32) -> Result<()> {
+    if store.find_tag(uid.as_ref()).await.is_ok() {
+        return Err(
+            Error::Conflict(format!("{} already has data, seed a different tag", uid)).into(),
+        );
+    }
+
+    let mut clients = Vec::new();
+    for name in CLIENTS {
+        clients.push(store.create_project(uid, name).await?);
+    }
+
+    let mut project_ids = Vec::new();
+    for project in &PROJECTS {
+        let id = store.create_project(uid, project.name).await?;
+        if let Some(client) = project.client {
+            store
+                .set_project_parent(uid, id, Some(clients[client]))
+                .await?;
+        }
+        project_ids.push(id);
+    }
+
+    let now = Utc::now();
+    let timers = demo_timers(timezone, now, weeks);
+    for (index, demo) in PROJECTS.iter().enumerate() {
+        let project = store.project(project_ids[index]).await?;
+        let history = timers
+            .iter()
+            .filter(|timer| timer.project == index)
+            .map(|timer| (timer.start, timer.tracked));
+        store.import_timers(&project, history).await?;
+
+        for timer in store.exportable_timers_by_project(&project.id).await? {
+            for label in demo.labels {
+                store.add_timer_label(uid, timer.id(), label).await?;
+            }
+        }
+    }
+
+    let options = ToggleOptions {
+        project_id: project_ids.first().copied(),
+        at: Some((now - Duration::minutes(40)).timestamp()),
+        ..Default::default()
+    };
+    store.toggle(uid, &options).await?;
+
+    info!(%uid, timers = timers.len(), "Seeded demo data");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Timelike;
+
+    #[test]
+    fn demo_timers_fill_working_hours() {
+        let timezone = chrono_tz::US::Pacific;
+        // Thursday 2026-10-15
+        let now = timezone
+            .with_ymd_and_hms(2026, 10, 15, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let timers = demo_timers(timezone, now, 4);
+        assert!(timers.len() > 20 * 3);
+        assert_eq!(timers, demo_timers(timezone, now, 4));
+
+        for pair in timers.windows(2) {
+            assert!(pair[0].start.end(pair[0].tracked) <= pair[1].start.0);
+        }
+        for timer in &timers {
+            let start = timer.start.0.with_timezone(&timezone);
+            let end = timer.start.end(timer.tracked).with_timezone(&timezone);
+            assert!(!matches!(start.weekday(), Weekday::Sat | Weekday::Sun));
+            assert!(start.hour() >= WORKDAY_START_HOUR);
+            assert!(end.hour() < WORKDAY_END_HOUR || (end.hour(), end.minute()) == (18, 0));
+            assert!(start.date_naive() < now.with_timezone(&timezone).date_naive());
+            assert!(timer.project < PROJECTS.len());
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::{env, time::Duration};
+
+use askama::Template;
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use tracing::{error, info, instrument};
+
+use crate::{email::Mailer, leases, templates::WeeklyDigest, timer_store::DataStore, uid::TagId};
+
+/// How often the scheduler checks for digests which are due
+const DIGEST_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The timezone weeks start in, from `DIGEST_TIMEZONE`
+fn digest_timezone() -> Tz {
+    let Ok(timezone) = env::var("DIGEST_TIMEZONE") else {
+        return chrono_tz::US::Pacific;
+    };
+
+    timezone.parse().unwrap_or_else(|err| {
+        error!(timezone, %err, "Invalid DIGEST_TIMEZONE, using US/Pacific");
+        chrono_tz::US::Pacific
+    })
+}
+
+/// The hour on Monday digests are sent at, from `DIGEST_HOUR`
+fn digest_hour() -> u32 {
+    match env::var("DIGEST_HOUR").map(|hour| hour.parse::<u32>()) {
+        Ok(Ok(hour)) if hour < 24 => hour,
+        Ok(_) => {
+            error!("Invalid DIGEST_HOUR, sending at 8am");
+            8
+        }
+        Err(_) => 8,
+    }
+}
+
+/// The start of the latest week whose digest is due at `now`
+///
+/// A week runs from Monday to Monday, and its digest is due at `hour` on the
+/// Monday after. Until then, this is still the week before.
+fn digest_week(now: DateTime<Tz>, hour: u32) -> Option<DateTime<Tz>> {
+    let monday =
+        now.date_naive() - chrono::Duration::days(now.weekday().num_days_from_monday().into());
+    let send_at = now
+        .timezone()
+        .from_local_datetime(&monday.and_time(NaiveTime::from_hms_opt(hour, 0, 0)?))
+        .earliest()?;
+
+    let weeks_back = if now < send_at { 2 } else { 1 };
+    now.timezone()
+        .from_local_datetime(
+            &(monday - chrono::Duration::weeks(weeks_back)).and_time(NaiveTime::MIN),
+        )
+        .earliest()
+}
+
+/// Spawns the background task emailing the weekly digests
+pub(crate) fn spawn(store: DataStore, mailer: Option<Mailer>) {
+    let Some(mailer) = mailer else {
+        info!("Email disabled, weekly digests won't be sent");
+        return;
+    };
+
+    tokio::spawn(run(store, mailer, digest_timezone(), digest_hour()));
+}
+
+#[instrument(skip(store, mailer))]
+async fn run(store: DataStore, mailer: Mailer, timezone: Tz, hour: u32) {
+    let mut interval = tokio::time::interval(DIGEST_INTERVAL);
+    loop {
+        interval.tick().await;
+        if !leases::held(&store, "digest", DIGEST_INTERVAL).await {
+            continue;
+        }
+        let Some(week_start) = digest_week(Utc::now().with_timezone(&timezone), hour) else {
+            continue;
+        };
+
+        match send_digests(&store, &mailer, week_start).await {
+            Ok(sent) if sent > 0 => info!(sent, "Sent weekly digests"),
+            Ok(_) => {}
+            Err(err) => error!(%err, "Unable to send weekly digests"),
+        }
+    }
+}
+
+/// Emails the digest for the week starting at `week_start` to everyone who hasn't had it
+async fn send_digests(
+    store: &DataStore,
+    mailer: &Mailer,
+    week_start: DateTime<Tz>,
+) -> anyhow::Result<usize> {
+    let from = week_start.timestamp();
+    let to = (week_start + chrono::Duration::weeks(1)).timestamp();
+
+    let mut sent = 0;
+    for recipient in store.digest_recipients(from).await? {
+        let uid = TagId::from(recipient.unique_id);
+        let projects = store.hours_by_project(&uid, from, to).await?;
+        let settings = store.settings(&uid).await?;
+        let digest = WeeklyDigest::new(&uid, week_start, projects, settings.duration_display(None));
+
+        let result = mailer
+            .send(
+                &recipient.email,
+                &digest.subject(),
+                digest.render()?,
+                digest.html().render()?,
+            )
+            .await;
+        match result {
+            Ok(()) => {
+                store.mark_digest_sent(&uid, from).await?;
+                sent += 1;
+            }
+            // Leave it unmarked so the next run retries
+            Err(err) => error!(%uid, %err, "Unable to send weekly digest"),
+        }
+    }
+
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn pacific(y: i32, m: u32, d: u32, h: u32) -> DateTime<Tz> {
+        chrono_tz::US::Pacific
+            .with_ymd_and_hms(y, m, d, h, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn digests_cover_the_week_before_monday_morning() {
+        // Monday 2026-10-12 is the first day of the week after 2026-10-05
+        let last_week = Some(pacific(2026, 10, 5, 0));
+
+        assert_eq!(digest_week(pacific(2026, 10, 12, 8), 8), last_week);
+        assert_eq!(digest_week(pacific(2026, 10, 15, 12), 8), last_week);
+        assert_eq!(digest_week(pacific(2026, 10, 18, 23), 8), last_week);
+
+        // Before the send time on Monday it's still the week before
+        assert_eq!(
+            digest_week(pacific(2026, 10, 12, 7), 8),
+            Some(pacific(2026, 9, 28, 0))
+        );
+    }
+}
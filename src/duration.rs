@@ -0,0 +1,257 @@
+//! Formatting durations for the pages, emails and exports
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// How durations are written out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DurationFormat {
+    /// Hours and minutes, like `7:30`
+    #[default]
+    Clock,
+
+    /// Hours with a fraction, like `7.50`
+    Decimal,
+
+    /// An ISO-8601 duration, like `PT7H30M`
+    Iso,
+}
+
+impl DurationFormat {
+    /// Writes a duration in seconds in this format, rounded to the nearest unit
+    ///
+    /// See [`DurationDisplay::format`] for rounding the other ways.
+    pub fn format(self, seconds: i64) -> String {
+        DurationDisplay::from(self).format(seconds)
+    }
+}
+
+/// How durations are rounded to the precision of their format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DurationRounding {
+    /// To the closest unit, halfway rounds up
+    #[default]
+    Nearest,
+
+    /// Dropping whatever doesn't make up a whole unit
+    Down,
+
+    /// Counting any part of a unit as a whole one
+    Up,
+}
+
+impl DurationRounding {
+    /// How many whole `unit`s `value` makes up
+    fn units(self, value: u64, unit: u64) -> u64 {
+        match self {
+            DurationRounding::Nearest => (value + unit / 2) / unit,
+            DurationRounding::Down => value / unit,
+            DurationRounding::Up => value.div_ceil(unit),
+        }
+    }
+}
+
+/// The format durations are written in together with how they're rounded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DurationDisplay {
+    pub format: DurationFormat,
+    pub rounding: DurationRounding,
+
+    /// Whether the clock format writes the seconds of durations under an hour
+    pub seconds: bool,
+}
+
+impl DurationDisplay {
+    pub fn new(format: DurationFormat, rounding: DurationRounding) -> Self {
+        Self {
+            format,
+            rounding,
+            seconds: false,
+        }
+    }
+
+    /// Writes the seconds of durations under an hour, like `0:05:42`
+    pub fn with_seconds(self, seconds: bool) -> Self {
+        Self { seconds, ..self }
+    }
+
+    /// Writes a duration in seconds
+    ///
+    /// The clock format is rounded to minutes and the decimal one to
+    /// hundredths of an hour. The ISO format keeps the seconds, so it's never
+    /// rounded.
+    pub fn format(self, seconds: i64) -> String {
+        let sign = if seconds < 0 { "-" } else { "" };
+        let seconds = seconds.unsigned_abs();
+
+        match self.format {
+            DurationFormat::Clock if self.seconds && seconds < 3600 => {
+                format!("{}0:{:02}:{:02}", sign, seconds / 60, seconds % 60)
+            }
+            DurationFormat::Clock => {
+                let minutes = self.rounding.units(seconds, 60);
+                format!("{}{}:{:02}", sign, minutes / 60, minutes % 60)
+            }
+            DurationFormat::Decimal => {
+                let hundredths = self.rounding.units(seconds, 36);
+                format!("{}{}.{:02}", sign, hundredths / 100, hundredths % 100)
+            }
+            DurationFormat::Iso => {
+                if seconds == 0 {
+                    return "PT0S".to_string();
+                }
+
+                let (hours, minutes) = (seconds / 3600, seconds % 3600 / 60);
+                let mut iso = format!("{}PT", sign);
+                for (value, unit) in [(hours, 'H'), (minutes, 'M'), (seconds % 60, 'S')] {
+                    if value > 0 {
+                        iso.push_str(&format!("{}{}", value, unit));
+                    }
+                }
+                iso
+            }
+        }
+    }
+}
+
+impl From<DurationFormat> for DurationDisplay {
+    fn from(format: DurationFormat) -> Self {
+        Self::new(format, DurationRounding::default())
+    }
+}
+
+impl From<&DurationFormat> for DurationDisplay {
+    fn from(format: &DurationFormat) -> Self {
+        Self::from(*format)
+    }
+}
+
+impl From<&DurationDisplay> for DurationDisplay {
+    fn from(display: &DurationDisplay) -> Self {
+        *display
+    }
+}
+
+impl FromStr for DurationFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "clock" => Ok(DurationFormat::Clock),
+            "decimal" => Ok(DurationFormat::Decimal),
+            "iso" => Ok(DurationFormat::Iso),
+            _ => Err(Error::Validation(format!(
+                "Unknown duration format {:?}, expected clock, decimal or iso",
+                format
+            ))
+            .into()),
+        }
+    }
+}
+
+impl FromStr for DurationRounding {
+    type Err = anyhow::Error;
+
+    fn from_str(rounding: &str) -> Result<Self, Self::Err> {
+        match rounding {
+            "nearest" => Ok(DurationRounding::Nearest),
+            "down" => Ok(DurationRounding::Down),
+            "up" => Ok(DurationRounding::Up),
+            _ => Err(Error::Validation(format!(
+                "Unknown duration rounding {:?}, expected nearest, down or up",
+                rounding
+            ))
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn durations_are_formatted() {
+        let cases = [
+            (29, "0:00", "0.01", "PT29S"),
+            (59, "0:01", "0.02", "PT59S"),
+            (45 * 60, "0:45", "0.75", "PT45M"),
+            (3600, "1:00", "1.00", "PT1H"),
+            (3600 + 5 * 60, "1:05", "1.08", "PT1H5M"),
+            (7 * 3600 + 30 * 60, "7:30", "7.50", "PT7H30M"),
+            (74_242, "20:37", "20.62", "PT20H37M22S"),
+            (0, "0:00", "0.00", "PT0S"),
+        ];
+
+        for (seconds, clock, decimal, iso) in cases {
+            assert_eq!(DurationFormat::Clock.format(seconds), clock);
+            assert_eq!(DurationFormat::Decimal.format(seconds), decimal);
+            assert_eq!(DurationFormat::Iso.format(seconds), iso);
+        }
+        assert_eq!(DurationFormat::Iso.format(-90), "-PT1M30S");
+    }
+
+    #[test]
+    fn durations_are_rounded() {
+        let cases = [
+            (DurationRounding::Nearest, "0:01", "0:02", "0.24"),
+            (DurationRounding::Down, "0:00", "0:01", "0.24"),
+            (DurationRounding::Up, "0:01", "0:02", "0.25"),
+        ];
+
+        for (rounding, under_a_minute, ninety_seconds, decimal) in cases {
+            let clock = DurationDisplay::new(DurationFormat::Clock, rounding);
+            assert_eq!(clock.format(59), under_a_minute);
+            assert_eq!(clock.format(90), ninety_seconds);
+            assert_eq!(clock.format(-59), format!("-{}", under_a_minute));
+
+            let hours = DurationDisplay::new(DurationFormat::Decimal, rounding);
+            assert_eq!(hours.format(15 * 60 - 30), decimal);
+        }
+        assert_eq!(
+            DurationDisplay::new(DurationFormat::Clock, DurationRounding::Up).format(3600),
+            "1:00"
+        );
+        assert_eq!(
+            DurationDisplay::new(DurationFormat::Iso, DurationRounding::Up).format(59),
+            "PT59S"
+        );
+        assert_eq!(
+            "down".parse::<DurationRounding>().unwrap(),
+            DurationRounding::Down
+        );
+    }
+
+    #[test]
+    fn short_durations_can_show_seconds() {
+        let clock =
+            DurationDisplay::new(DurationFormat::Clock, DurationRounding::Down).with_seconds(true);
+        assert_eq!(clock.format(0), "0:00:00");
+        assert_eq!(clock.format(42), "0:00:42");
+        assert_eq!(clock.format(5 * 60 + 42), "0:05:42");
+        assert_eq!(clock.format(-42), "-0:00:42");
+        // An hour and more is written as usual
+        assert_eq!(clock.format(3600 + 59), "1:00");
+
+        let decimal = DurationDisplay::new(DurationFormat::Decimal, DurationRounding::Nearest)
+            .with_seconds(true);
+        assert_eq!(decimal.format(42), "0.01");
+    }
+
+    #[test]
+    fn formats_are_parsed() {
+        assert_eq!(
+            "iso".parse::<DurationFormat>().unwrap(),
+            DurationFormat::Iso
+        );
+        assert!("hours".parse::<DurationFormat>().is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Sending email over SMTP
+
+use std::env;
+
+use anyhow::{anyhow, Result};
+use lettre::{
+    message::{header::ContentType, Attachment, Mailbox, MessageBuilder, MultiPart, SinglePart},
+    transport::smtp::authentication::Credentials,
+    Address, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use tracing::{info, instrument};
+
+use crate::error::Error;
+
+/// Sends email through the SMTP server configured in the environment
+///
+/// Configured through `SMTP_HOST`, `SMTP_PORT`, `SMTP_USERNAME`,
+/// `SMTP_PASSWORD` and `SMTP_FROM`. `SMTP_TLS` picks how the connection is
+/// secured: `starttls` (the default), `tls` or `none` for local relays.
+#[derive(Clone)]
+pub(crate) struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl std::fmt::Debug for Mailer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mailer").field("from", &self.from).finish()
+    }
+}
+
+impl Mailer {
+    /// Creates the mailer from the environment, if an SMTP host is configured
+    pub(crate) fn from_env() -> Result<Option<Self>> {
+        let Ok(host) = env::var("SMTP_HOST") else {
+            return Ok(None);
+        };
+
+        let mut transport = match env::var("SMTP_TLS").as_deref() {
+            Ok("starttls") | Err(_) => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)?,
+            Ok("tls") => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)?,
+            Ok("none") => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+            Ok(other) => return Err(anyhow!("Unknown SMTP_TLS {:?}", other)),
+        };
+        if let Ok(port) = env::var("SMTP_PORT") {
+            transport = transport.port(port.parse()?);
+        }
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD"))
+        {
+            transport = transport.credentials(Credentials::new(username, password));
+        }
+
+        let from = env::var("SMTP_FROM")
+            .map_err(|_| anyhow!("SMTP_FROM must be set when SMTP_HOST is"))?
+            .parse()?;
+
+        Ok(Some(Self {
+            transport: transport.build(),
+            from,
+        }))
+    }
+
+    /// Sends an email with a plain text body and an HTML alternative
+    #[instrument(skip(self, text, html))]
+    pub(crate) async fn send(
+        &self,
+        to: &str,
+        subject: &str,
+        text: String,
+        html: String,
+    ) -> Result<()> {
+        let message = self
+            .builder(to, subject)?
+            .multipart(MultiPart::alternative_plain_html(text, html))?;
+
+        self.deliver(message).await
+    }
+
+    /// Sends an email with just a plain text body
+    #[instrument(skip(self, text))]
+    pub(crate) async fn send_text(&self, to: &str, subject: &str, text: String) -> Result<()> {
+        let message = self.builder(to, subject)?.body(text)?;
+
+        self.deliver(message).await
+    }
+
+    /// Sends an email with a plain text body and a file attached
+    #[instrument(skip(self, text, content))]
+    pub(crate) async fn send_attachment(
+        &self,
+        to: &str,
+        subject: &str,
+        text: String,
+        filename: &str,
+        content_type: &str,
+        content: Vec<u8>,
+    ) -> Result<()> {
+        let attachment =
+            Attachment::new(filename.to_string()).body(content, ContentType::parse(content_type)?);
+        let message = self.builder(to, subject)?.multipart(
+            MultiPart::mixed()
+                .singlepart(SinglePart::plain(text))
+                .singlepart(attachment),
+        )?;
+
+        self.deliver(message).await
+    }
+
+    fn builder(&self, to: &str, subject: &str) -> Result<MessageBuilder> {
+        Ok(Message::builder()
+            .from(self.from.clone())
+            .to(to.parse()?)
+            .subject(subject))
+    }
+
+    async fn deliver(&self, message: Message) -> Result<()> {
+        self.transport.send(message).await?;
+        info!("Sent email");
+
+        Ok(())
+    }
+}
+
+/// Ensures an email address can be sent to
+pub(crate) fn validate_address(address: &str) -> Result<()> {
+    address.parse::<Address>().map_err(|err| {
+        Error::Validation(format!("Invalid email address {:?}: {}", address, err))
+    })?;
+
+    Ok(())
+}
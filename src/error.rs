@@ -0,0 +1,226 @@
+//! Errors returned by handlers and how they turn into responses
+
+use axum::{
+    extract::rejection::{PathRejection, QueryRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::{debug, error};
+
+use crate::{request_id, server::into_response, templates::ErrorPage};
+
+/// A failure caused by the request rather than by the server
+///
+/// Store and handler code returns these inside an `anyhow::Error`, which
+/// [`AppError`] looks through to pick the status code of the response.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Validation(String),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{0}")]
+    Forbidden(String),
+
+    /// A timer was started for a tag without a project to track it on
+    #[error("{0}")]
+    NoProject(String),
+
+    /// The server can't make the change right now, like while it's read-only
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+/// An error returned by an API handler, rendered as JSON
+#[derive(Debug)]
+pub(crate) enum AppError {
+    NotFound(String),
+    Validation(String),
+    Conflict(String),
+    Forbidden(String),
+    NoProject(String),
+    Unavailable(String),
+
+    /// Anything unexpected, the details are logged but never sent to the client
+    Internal(anyhow::Error),
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: &'static str,
+    message: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+impl AppError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NoProject(_) => StatusCode::CONFLICT,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Validation(_) => "validation",
+            AppError::Conflict(_) => "conflict",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NoProject(_) => "no_project",
+            AppError::Unavailable(_) => "unavailable",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    pub(crate) fn message(&self) -> String {
+        match self {
+            AppError::NotFound(message)
+            | AppError::Validation(message)
+            | AppError::Conflict(message)
+            | AppError::Forbidden(message)
+            | AppError::NoProject(message)
+            | AppError::Unavailable(message) => message.clone(),
+            AppError::Internal(_) => "Something went wrong".to_string(),
+        }
+    }
+
+    fn log(&self) {
+        match self {
+            AppError::Internal(err) => error!(error = %err, "backtrace: {}", err.backtrace()),
+            err => debug!(
+                kind = err.kind(),
+                message = err.message(),
+                "Rejected request"
+            ),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        self.log();
+        let body = ErrorBody {
+            error: self.kind(),
+            message: self.message(),
+            request_id: request_id::current(),
+        };
+
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+// This enables using `?` on functions that return `Result<_, anyhow::Error>` to turn them into
+// `Result<_, AppError>`, classifying the error on the way.
+impl<E> From<E> for AppError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(err: E) -> Self {
+        let err = match err.into().downcast::<Error>() {
+            Ok(Error::NotFound(message)) => return AppError::NotFound(message),
+            Ok(Error::Validation(message)) => return AppError::Validation(message),
+            Ok(Error::Conflict(message)) => return AppError::Conflict(message),
+            Ok(Error::Forbidden(message)) => return AppError::Forbidden(message),
+            Ok(Error::NoProject(message)) => return AppError::NoProject(message),
+            Ok(Error::Unavailable(message)) => return AppError::Unavailable(message),
+            Err(err) => err,
+        };
+
+        // Rejected requests never reach a handler, they're only seen by the page extractors
+        if let Some(rejection) = err.downcast_ref::<PathRejection>() {
+            return AppError::Validation(rejection.body_text());
+        }
+        if let Some(rejection) = err.downcast_ref::<QueryRejection>() {
+            return AppError::Validation(rejection.body_text());
+        }
+
+        match err.downcast_ref::<sqlx::Error>() {
+            Some(sqlx::Error::RowNotFound) => AppError::NotFound("Not found".to_string()),
+            Some(sqlx::Error::Database(db)) if db.is_unique_violation() => {
+                AppError::Conflict("Conflicts with a concurrent change, try again".to_string())
+            }
+            _ => AppError::Internal(err),
+        }
+    }
+}
+
+/// An error returned by a page handler, rendered as an HTML page
+#[derive(Debug)]
+pub(crate) struct PageError(AppError);
+
+impl IntoResponse for PageError {
+    fn into_response(self) -> Response {
+        self.0.log();
+        let status = self.0.status();
+        let page = ErrorPage::new(status, self.0.message(), request_id::current());
+
+        (status, into_response(&page)).into_response()
+    }
+}
+
+impl From<AppError> for PageError {
+    fn from(err: AppError) -> Self {
+        Self(err)
+    }
+}
+
+impl<E> From<E> for PageError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(err: E) -> Self {
+        Self(err.into().into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn errors_are_classified() {
+        let not_found: AppError = anyhow::Error::from(Error::NotFound("gone".into())).into();
+        assert_eq!(not_found.status(), StatusCode::NOT_FOUND);
+        assert_eq!(not_found.message(), "gone");
+
+        let invalid: AppError = Error::Validation("bad".into()).into();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+
+        let no_project: AppError = Error::NoProject("create one".into()).into();
+        assert_eq!(no_project.status(), StatusCode::CONFLICT);
+        assert_eq!(no_project.kind(), "no_project");
+
+        let unavailable: AppError = Error::Unavailable("read-only".into()).into();
+        assert_eq!(unavailable.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let missing_row: AppError = sqlx::Error::RowNotFound.into();
+        assert_eq!(missing_row.status(), StatusCode::NOT_FOUND);
+
+        let internal: AppError = anyhow::anyhow!("disk on fire").into();
+        assert_eq!(internal.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(internal.message(), "Something went wrong");
+    }
+
+    #[test]
+    fn context_keeps_the_classification() {
+        let err = anyhow::Error::from(Error::Conflict("taken".into())).context("Saving alias");
+        let err: AppError = err.into();
+
+        assert_eq!(err.status(), StatusCode::CONFLICT);
+    }
+}
@@ -0,0 +1,108 @@
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::debug;
+
+/// Number of events buffered for slow subscribers before they start lagging
+const EVENT_CAPACITY: usize = 64;
+
+/// Something that happened to the timers of a tag
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum TimerEvent {
+    /// The current timer of the tag was started or stopped
+    Toggled { unique_id: String },
+
+    /// The running timer has passed the tag's long timer limit, it was
+    /// probably forgotten
+    LongRunning {
+        unique_id: String,
+        timer_id: i64,
+        minutes: i64,
+    },
+
+    /// A project has used up a threshold percentage of its hour budget
+    BudgetReached {
+        unique_id: String,
+        project_id: i64,
+        percent: i64,
+    },
+
+    /// The time tracked today has reached the tag's daily goal
+    GoalReached {
+        unique_id: String,
+        goal: i64,
+        minutes: i64,
+    },
+}
+
+impl TimerEvent {
+    /// The TagId this event is associated with
+    pub(crate) fn unique_id(&self) -> &str {
+        match self {
+            TimerEvent::Toggled { unique_id }
+            | TimerEvent::LongRunning { unique_id, .. }
+            | TimerEvent::BudgetReached { unique_id, .. }
+            | TimerEvent::GoalReached { unique_id, .. } => unique_id,
+        }
+    }
+}
+
+/// Broadcasts [`TimerEvent`]s to everyone who is listening, such as SSE streams
+#[derive(Debug, Clone)]
+pub(crate) struct EventBus {
+    sender: broadcast::Sender<TimerEvent>,
+}
+
+impl EventBus {
+    pub(crate) fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        Self { sender }
+    }
+
+    /// Publishes an event, dropping it if nobody is listening
+    ///
+    /// This never waits on subscribers, so it is safe to call from the toggle
+    /// path: slow subscribers lag behind and miss events instead.
+    pub(crate) fn publish(&self, event: TimerEvent) {
+        debug!(?event, "Publishing event");
+        let _ = self.sender.send(event);
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<TimerEvent> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn publish_never_waits_for_subscribers() {
+        let bus = EventBus::new();
+        // A subscriber which never reads, like a stalled SSE connection
+        let _stalled = bus.subscribe();
+
+        let start = Instant::now();
+        for _ in 0..EVENT_CAPACITY * 10 {
+            bus.publish(TimerEvent::Toggled {
+                unique_id: "test-tag".to_string(),
+            });
+        }
+
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn publish_without_subscribers_is_dropped() {
+        let bus = EventBus::new();
+        bus.publish(TimerEvent::Toggled {
+            unique_id: "test-tag".to_string(),
+        });
+
+        let mut late = bus.subscribe();
+        assert!(late.try_recv().is_err());
+    }
+}
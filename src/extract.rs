@@ -0,0 +1,204 @@
+//! Extractors shared by routes, most of which reject with an error page
+
+use std::{collections::HashMap, convert::Infallible};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, Query},
+    headers::{Cookie, HeaderMapExt},
+    http::{
+        header::{ACCEPT, ACCEPT_LANGUAGE, AUTHORIZATION},
+        request::Parts,
+    },
+};
+
+use crate::{
+    error::{AppError, Error, PageError},
+    server::App,
+    timer_store::TokenKind,
+    uid::TagId,
+};
+
+/// Like [`Path`], but an unparsable path renders the error page
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(Path), rejection(PageError))]
+pub(crate) struct PagePath<T>(pub(crate) T);
+
+/// Like [`Query`], but an unparsable query renders the error page
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(Query), rejection(PageError))]
+pub(crate) struct PageQuery<T>(pub(crate) T);
+
+/// The tag a share link in the `:token` path segment grants read-only access to
+#[derive(Debug)]
+pub(crate) struct SharedTag {
+    pub(crate) uid: TagId,
+    pub(crate) token: String,
+}
+
+#[async_trait]
+impl FromRequestParts<App> for SharedTag {
+    type Rejection = PageError;
+
+    async fn from_request_parts(parts: &mut Parts, app: &App) -> Result<Self, Self::Rejection> {
+        let PagePath(mut params) =
+            PagePath::<HashMap<String, String>>::from_request_parts(parts, app).await?;
+        let token = params
+            .remove("token")
+            .ok_or_else(|| anyhow::anyhow!("Shared route without a :token segment"))?;
+        let uid = app.timer_store.token_tag(TokenKind::Share, &token).await?;
+
+        Ok(Self { uid, token })
+    }
+}
+
+/// The tag an API token in the `Authorization: Bearer` header acts for
+#[derive(Debug)]
+pub(crate) struct ApiTag(pub(crate) TagId);
+
+#[async_trait]
+impl FromRequestParts<App> for ApiTag {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, app: &App) -> Result<Self, Self::Rejection> {
+        let token = bearer(parts)
+            .ok_or_else(|| Error::Forbidden("An API token is required".to_string()))?;
+
+        Ok(Self(
+            app.timer_store
+                .token_tag(TokenKind::Api, token.trim())
+                .await?,
+        ))
+    }
+}
+
+/// A request carrying the admin token in the `Authorization: Bearer` header,
+/// see [`AdminToken`][crate::admin::AdminToken]
+#[derive(Debug)]
+pub(crate) struct Admin;
+
+#[async_trait]
+impl FromRequestParts<App> for Admin {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, app: &App) -> Result<Self, Self::Rejection> {
+        match bearer(parts) {
+            Some(token) if app.admin.verify(token.trim()) => Ok(Self),
+            _ => Err(Error::Forbidden("The admin token is required".to_string()).into()),
+        }
+    }
+}
+
+/// The token of an `Authorization: Bearer` header, if the request has one
+fn bearer(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// The name of the cookie holding the owner key of a tag
+///
+/// Each tag has its own, so one browser can be signed in to several tags.
+pub(crate) fn owner_cookie_name(uid: &TagId) -> String {
+    format!("sprite-owner-{}", uid.as_ref())
+}
+
+/// The tag of the `:timer_tag` or `:tag` path segment, when the request
+/// proves to own it
+///
+/// The owner key issued when the tag was registered is sent either in the
+/// `Authorization: Bearer` header or in the tag's cookie, see
+/// [`owner_cookie_name`]. Knowing the tag alone isn't enough, since it's part
+/// of every URL of the tag.
+#[derive(Debug)]
+pub(crate) struct OwnerTag(pub(crate) TagId);
+
+#[async_trait]
+impl FromRequestParts<App> for OwnerTag {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, app: &App) -> Result<Self, Self::Rejection> {
+        let Path(mut params) =
+            Path::<HashMap<String, String>>::from_request_parts(parts, app).await?;
+        let tag = params
+            .remove("timer_tag")
+            .or_else(|| params.remove("tag"))
+            .ok_or_else(|| anyhow::anyhow!("Owner route without a :timer_tag segment"))?;
+        let uid = app.timer_store.resolve_tag(&tag).await?;
+
+        let cookie = parts
+            .headers
+            .typed_get::<Cookie>()
+            .and_then(|cookie| cookie.get(&owner_cookie_name(&uid)).map(str::to_string));
+        let key = bearer(parts)
+            .map(|key| key.trim().to_string())
+            .or(cookie)
+            .ok_or_else(|| Error::Forbidden("Sign in as the owner of this tag".to_string()))?;
+
+        let owner = app.timer_store.owner_tag(&key).await?;
+        if owner.as_ref() != uid.as_ref() {
+            return Err(Error::Forbidden("Sign in as the owner of this tag".to_string()).into());
+        }
+
+        Ok(Self(uid))
+    }
+}
+
+/// Like [`OwnerTag`], but failing to prove ownership renders the error page
+#[derive(Debug)]
+pub(crate) struct PageOwnerTag(pub(crate) TagId);
+
+#[async_trait]
+impl FromRequestParts<App> for PageOwnerTag {
+    type Rejection = PageError;
+
+    async fn from_request_parts(parts: &mut Parts, app: &App) -> Result<Self, Self::Rejection> {
+        let OwnerTag(uid) = OwnerTag::from_request_parts(parts, app).await?;
+        Ok(Self(uid))
+    }
+}
+
+/// Whether the request asks for JSON rather than a page
+///
+/// Browsers list `text/html` in their `Accept` header, so only clients asking
+/// for `application/json` without it get JSON.
+#[derive(Debug)]
+pub(crate) struct WantsJson(pub(crate) bool);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for WantsJson {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let wants_json = parts
+            .headers
+            .get(ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|accept| {
+                accept.contains("application/json") && !accept.contains("text/html")
+            });
+
+        Ok(Self(wants_json))
+    }
+}
+
+/// The raw `Accept-Language` header of the request, if it has one
+#[derive(Debug)]
+pub(crate) struct AcceptLanguage(pub(crate) Option<String>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AcceptLanguage {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let header = parts
+            .headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        Ok(Self(header))
+    }
+}
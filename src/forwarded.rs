@@ -0,0 +1,266 @@
+//! Who a request comes from and where it was sent to, also when it's relayed by a reverse proxy
+//!
+//! A proxy connects with its own address and passes the client's along in
+//! `X-Forwarded-For`, the scheme the client used in `X-Forwarded-Proto` and
+//! the host it asked for in `X-Forwarded-Host`. Anyone can send those
+//! headers, so they're only believed from the proxies listed in
+//! `TRUSTED_PROXIES`, a comma separated list of addresses or networks like
+//! `10.0.0.0/8`.
+
+use std::{
+    env,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, Request},
+    middleware::Next,
+    response::Response,
+};
+use ipnet::IpNet;
+use tracing::{error, field, info, Span};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+tokio::task_local! {
+    static CURRENT: Client;
+}
+
+/// The client of the request currently being handled
+pub(crate) fn current() -> Option<Client> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+/// The client of a request, as far as the proxies in between can be trusted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Client {
+    /// Unknown for requests without a peer address, like the ones of tests
+    pub(crate) ip: Option<IpAddr>,
+
+    /// Whether the client used HTTPS, to reach the server itself or a trusted proxy
+    pub(crate) https: bool,
+
+    /// The host the client sent the request to, if it's safe to put into links
+    pub(crate) host: Option<String>,
+}
+
+impl Client {
+    /// The scheme and host the client reached the server at, like `https://example.com`
+    pub(crate) fn origin(&self) -> Option<String> {
+        let scheme = if self.https { "https" } else { "http" };
+        self.host
+            .as_ref()
+            .map(|host| format!("{}://{}", scheme, host))
+    }
+}
+
+/// How requests reach the server, to work out their [`Client`]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Forwarding {
+    proxies: TrustedProxies,
+
+    /// Whether the server terminates TLS itself, see [`crate::tls`]
+    tls: bool,
+}
+
+impl Forwarding {
+    pub(crate) fn new(proxies: TrustedProxies, tls: bool) -> Self {
+        Self { proxies, tls }
+    }
+}
+
+/// The proxies whose forwarded headers are believed
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TrustedProxies(Arc<Vec<IpNet>>);
+
+impl TrustedProxies {
+    /// Reads the trusted proxies from `TRUSTED_PROXIES`, trusting none without it
+    pub(crate) fn from_env() -> Self {
+        let Ok(proxies) = env::var("TRUSTED_PROXIES") else {
+            info!("No trusted proxies, forwarded headers are ignored");
+            return Self::default();
+        };
+
+        Self::parse(&proxies)
+    }
+
+    fn parse(proxies: &str) -> Self {
+        let networks = proxies
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .filter_map(|proxy| {
+                let network = proxy
+                    .parse::<IpNet>()
+                    .or_else(|_| proxy.parse::<IpAddr>().map(IpNet::from));
+                match network {
+                    Ok(network) => Some(network),
+                    Err(err) => {
+                        error!(proxy, %err, "Ignoring invalid address in TRUSTED_PROXIES");
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self(Arc::new(networks))
+    }
+
+    fn trusts(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|network| network.contains(&ip))
+    }
+
+    /// Works out the client of a request which arrived from `peer`, over TLS if `tls`
+    ///
+    /// The forwarded addresses are walked from the closest hop outwards, and
+    /// the first one which isn't a trusted proxy is the client.
+    fn client(&self, peer: Option<IpAddr>, headers: &HeaderMap, tls: bool) -> Client {
+        let host = header_value(headers, header::HOST.as_str()).filter(|host| valid_host(host));
+        let Some(peer) = peer.filter(|peer| self.trusts(*peer)) else {
+            return Client {
+                ip: peer,
+                https: tls,
+                host: host.map(str::to_string),
+            };
+        };
+
+        let forwarded_for: Vec<IpAddr> = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|hop| hop.trim().parse().ok())
+            .collect();
+        let ip = forwarded_for
+            .iter()
+            .rev()
+            .find(|hop| !self.trusts(**hop))
+            .or(forwarded_for.first())
+            .copied()
+            .unwrap_or(peer);
+
+        // The first proxy recorded the scheme and host the client used
+        let https = match header_value(headers, X_FORWARDED_PROTO) {
+            Some(proto) => proto.eq_ignore_ascii_case("https"),
+            None => tls,
+        };
+        let host = header_value(headers, X_FORWARDED_HOST)
+            .filter(|host| valid_host(host))
+            .or(host);
+
+        Client {
+            ip: Some(ip),
+            https,
+            host: host.map(str::to_string),
+        }
+    }
+}
+
+/// The first of the comma separated values of a header
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    let value = headers.get(name)?.to_str().ok()?;
+    let first = value.split(',').next()?.trim();
+
+    (!first.is_empty()).then_some(first)
+}
+
+/// Whether a host, with an optional port, can be put into links without escaping
+fn valid_host(host: &str) -> bool {
+    host.len() <= 255
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+}
+
+/// Middleware making the client available through [`current`] and recording it on the request span
+pub(crate) async fn scope<B>(
+    State(forwarding): State<Forwarding>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(peer)| peer.ip());
+    let client = forwarding
+        .proxies
+        .client(peer, request.headers(), forwarding.tls);
+    if let Some(ip) = client.ip {
+        Span::current().record("client_ip", field::display(ip));
+    }
+
+    CURRENT.scope(client, next.run(request)).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::http::HeaderValue;
+    use pretty_assertions::assert_eq;
+
+    fn headers(forwarded_for: &str, proto: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, HeaderValue::from_static("10.0.0.5:3000"));
+        headers.insert(
+            X_FORWARDED_FOR,
+            HeaderValue::from_str(forwarded_for).unwrap(),
+        );
+        headers.insert(X_FORWARDED_PROTO, HeaderValue::from_str(proto).unwrap());
+        headers.insert(X_FORWARDED_HOST, HeaderValue::from_static("sprite.example"));
+        headers
+    }
+
+    #[test]
+    fn forwarded_headers_are_only_trusted_from_proxies() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8, 192.168.1.2, not-an-address");
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+
+        // Straight from the client, the headers are made up
+        let direct = proxies.client(Some(client), &headers("198.51.100.1", "https"), false);
+        assert_eq!(
+            direct,
+            Client {
+                ip: Some(client),
+                https: false,
+                host: Some("10.0.0.5:3000".to_string()),
+            }
+        );
+        assert_eq!(direct.origin().unwrap(), "http://10.0.0.5:3000");
+
+        // Through two proxies, anything before the client is ignored
+        let proxied = proxies.client(
+            Some("192.168.1.2".parse().unwrap()),
+            &headers("198.51.100.1, 203.0.113.7, 10.1.2.3", "https"),
+            false,
+        );
+        assert_eq!(proxied.ip, Some(client));
+        assert_eq!(proxied.origin().unwrap(), "https://sprite.example");
+
+        // Only proxies in the chain, so the first hop is the best guess
+        let internal = proxies.client(
+            Some("10.0.0.1".parse().unwrap()),
+            &headers("10.9.9.9, 10.1.2.3", "http"),
+            true,
+        );
+        assert_eq!(internal.ip, Some("10.9.9.9".parse().unwrap()));
+        assert!(!internal.https);
+    }
+
+    #[test]
+    fn origins_come_from_the_host_header() {
+        let proxies = TrustedProxies::default();
+        let mut headers = HeaderMap::new();
+        assert_eq!(proxies.client(None, &headers, false).origin(), None);
+
+        headers.insert(header::HOST, HeaderValue::from_static("sprite.local:3000"));
+        let client = proxies.client(None, &headers, true);
+        assert_eq!(client.origin().unwrap(), "https://sprite.local:3000");
+
+        headers.insert(header::HOST, HeaderValue::from_static("evil\"><script>"));
+        assert_eq!(proxies.client(None, &headers, false).origin(), None);
+    }
+}
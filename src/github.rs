@@ -0,0 +1,222 @@
+//! Webhooks from GitHub, linking the running timer to what is being worked on
+//!
+//! A repository's webhook pointing at `/integrations/github/:timer_tag`
+//! links the tag's running timer to the branch pushed to or created, or to
+//! the issue or pull request opened or assigned.
+
+use std::env;
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+/// Verifies the signatures GitHub adds to webhooks
+///
+/// Configured through `GITHUB_WEBHOOK_SECRET`, the secret entered for the
+/// webhooks on GitHub. Without it every webhook is refused, since anyone who
+/// knows a tag could otherwise link its timer anywhere.
+#[derive(Clone, Default)]
+pub(crate) struct GitHubWebhooks {
+    secret: Option<String>,
+}
+
+impl std::fmt::Debug for GitHubWebhooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GitHubWebhooks")
+            .field("signed", &self.secret.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl GitHubWebhooks {
+    pub(crate) fn from_env() -> Self {
+        Self {
+            secret: env::var("GITHUB_WEBHOOK_SECRET").ok(),
+        }
+    }
+
+    /// Whether a secret is configured, without which webhooks are refused
+    pub(crate) fn is_configured(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    fn mac(secret: &str) -> Hmac<Sha256> {
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length")
+    }
+
+    /// Checks the `X-Hub-Signature-256` header of a webhook against its body,
+    /// failing every check while no secret is configured
+    pub(crate) fn verify(&self, body: &[u8], signature: Option<&str>) -> bool {
+        let Some(secret) = &self.secret else {
+            return false;
+        };
+        let Some(signature) = signature
+            .and_then(|signature| signature.strip_prefix("sha256="))
+            .and_then(|signature| hex::decode(signature).ok())
+        else {
+            return false;
+        };
+
+        let mut mac = Self::mac(secret);
+        mac.update(body);
+        mac.verify_slice(&signature).is_ok()
+    }
+
+    /// Webhooks signed with `secret`, for tests
+    #[cfg(test)]
+    pub(crate) fn with_secret(secret: &str) -> Self {
+        Self {
+            secret: Some(secret.to_string()),
+        }
+    }
+
+    /// The `X-Hub-Signature-256` header GitHub would send along with `body`
+    #[cfg(test)]
+    pub(crate) fn sign(&self, body: &[u8]) -> String {
+        let mut mac = Self::mac(self.secret.as_deref().expect("Signing needs a secret"));
+        mac.update(body);
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Push {
+    #[serde(rename = "ref")]
+    git_ref: String,
+    #[serde(default)]
+    deleted: bool,
+    repository: Repository,
+}
+
+#[derive(Debug, Deserialize)]
+struct Create {
+    #[serde(rename = "ref")]
+    git_ref: String,
+    ref_type: String,
+    repository: Repository,
+}
+
+#[derive(Debug, Deserialize)]
+struct HtmlUrl {
+    html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    action: String,
+    issue: HtmlUrl,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    action: String,
+    pull_request: HtmlUrl,
+}
+
+/// Whether an issue or pull request action means someone started on it
+fn starts_work(action: &str) -> bool {
+    matches!(action, "opened" | "reopened" | "assigned")
+}
+
+fn branch_url(repository: &Repository, branch: &str) -> String {
+    format!(
+        "{}/tree/{}",
+        repository.html_url.trim_end_matches('/'),
+        branch
+    )
+}
+
+/// The link for the running timer a webhook stands for, if any
+///
+/// `event` is the `X-GitHub-Event` header. Events which don't say what is
+/// being worked on, like `ping`, have none.
+pub(crate) fn link_of(event: &str, body: &[u8]) -> Result<Option<String>> {
+    let link = match event {
+        "push" => {
+            let push: Push = serde_json::from_slice(body)?;
+            push.git_ref
+                .strip_prefix("refs/heads/")
+                .filter(|_| !push.deleted)
+                .map(|branch| branch_url(&push.repository, branch))
+        }
+        "create" => {
+            let create: Create = serde_json::from_slice(body)?;
+            (create.ref_type == "branch").then(|| branch_url(&create.repository, &create.git_ref))
+        }
+        "issues" => {
+            let issue: Issue = serde_json::from_slice(body)?;
+            starts_work(&issue.action).then_some(issue.issue.html_url)
+        }
+        "pull_request" => {
+            let pull: PullRequest = serde_json::from_slice(body)?;
+            starts_work(&pull.action).then_some(pull.pull_request.html_url)
+        }
+        _ => None,
+    };
+
+    Ok(link)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn webhooks_link_branches_and_issues() {
+        let repository = r#""repository": {"html_url": "https://github.com/bIgBV/sprite"}"#;
+        let push = format!(r#"{{"ref": "refs/heads/fix/login", {}}}"#, repository);
+        assert_eq!(
+            link_of("push", push.as_bytes()).unwrap().as_deref(),
+            Some("https://github.com/bIgBV/sprite/tree/fix/login")
+        );
+
+        let tag = format!(r#"{{"ref": "refs/tags/v1.0", {}}}"#, repository);
+        assert_eq!(link_of("push", tag.as_bytes()).unwrap(), None);
+
+        let deleted = format!(
+            r#"{{"ref": "refs/heads/old", "deleted": true, {}}}"#,
+            repository
+        );
+        assert_eq!(link_of("push", deleted.as_bytes()).unwrap(), None);
+
+        let issue = br#"{"action": "assigned", "issue": {"html_url": "https://github.com/bIgBV/sprite/issues/7"}}"#;
+        assert_eq!(
+            link_of("issues", issue).unwrap().as_deref(),
+            Some("https://github.com/bIgBV/sprite/issues/7")
+        );
+        let closed = br#"{"action": "closed", "issue": {"html_url": "https://github.com/bIgBV/sprite/issues/7"}}"#;
+        assert_eq!(link_of("issues", closed).unwrap(), None);
+
+        assert_eq!(link_of("ping", b"{}").unwrap(), None);
+        assert!(link_of("push", b"{}").is_err());
+    }
+
+    #[test]
+    fn signatures_are_checked() {
+        let body = b"{\"zen\": \"Keep it logically awesome.\"}";
+        let unconfigured = GitHubWebhooks::default();
+        assert!(!unconfigured.verify(body, None));
+        assert!(!unconfigured.verify(body, Some("sha256=")));
+
+        let webhooks = GitHubWebhooks::with_secret("It's a Secret to Everybody");
+        // The example from GitHub's documentation on validating deliveries
+        assert!(webhooks.verify(
+            b"Hello, World!",
+            Some("sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17")
+        ));
+        assert!(!webhooks.verify(
+            body,
+            Some("sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17")
+        ));
+        assert!(!webhooks.verify(body, Some("sha256=zz")));
+        assert!(!webhooks.verify(body, None));
+        assert!(webhooks.verify(body, Some(&webhooks.sign(body))));
+    }
+}
@@ -0,0 +1,546 @@
+//! Pushing finished timers to Google Calendar as events
+//!
+//! A tag connects a Google account by opening
+//! `/integrations/google/:timer_tag/connect`, which goes through Google's
+//! OAuth consent screen and back to `/integrations/google/callback`. From
+//! then on a background task adds an event for every timer the tag finishes,
+//! to the calendar picked for its project or else the account's primary one.
+
+use std::{collections::BTreeMap, env, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::{SecondsFormat, Utc};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, instrument};
+
+use crate::{
+    leases,
+    timer_store::{DataStore, GoogleAccount, GoogleTimer},
+    uid::TagId,
+    uri_base,
+};
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const API_URL: &str = "https://www.googleapis.com/calendar/v3";
+
+/// Only adding events is needed, not reading the rest of the calendar
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+
+/// How often finished timers are pushed
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The most timers pushed in one run, the rest wait for the next one
+const SYNC_BATCH: i64 = 100;
+
+/// Access tokens are refreshed this many seconds before they expire
+const EXPIRY_MARGIN: i64 = 60;
+
+/// How long the consent screen can be left open, in seconds
+const STATE_LIFETIME: i64 = 15 * 60;
+
+/// Bytes of the keyed BLAKE3 digest signing the OAuth state
+const SIGNATURE_BYTES: usize = 16;
+
+/// The OAuth client Sprite uses to talk to Google Calendar
+///
+/// Configured through `GOOGLE_CLIENT_ID` and `GOOGLE_CLIENT_SECRET` of a web
+/// application client, with `<URI_BASE>/integrations/google/callback` as its
+/// redirect URI.
+#[derive(Clone)]
+pub(crate) struct GoogleCalendar {
+    client_id: String,
+    client_secret: String,
+
+    /// Signs the OAuth state, so a callback can't connect someone else's tag
+    key: [u8; blake3::KEY_LEN],
+    client: reqwest::Client,
+    auth_url: String,
+    token_url: String,
+    api_url: String,
+}
+
+impl std::fmt::Debug for GoogleCalendar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GoogleCalendar")
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What Google's token endpoint answers with
+#[derive(Debug, Deserialize)]
+pub(crate) struct Tokens {
+    pub(crate) access_token: String,
+
+    /// Seconds until the access token expires
+    pub(crate) expires_in: i64,
+
+    /// Only given when the account is first connected
+    pub(crate) refresh_token: Option<String>,
+}
+
+/// A calendar event, as far as Sprite fills it in
+#[derive(Debug, Serialize, PartialEq)]
+struct Event {
+    summary: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    start: EventTime,
+    end: EventTime,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct EventTime {
+    #[serde(rename = "dateTime")]
+    date_time: String,
+}
+
+impl From<&GoogleTimer> for Event {
+    fn from(timer: &GoogleTimer) -> Self {
+        let time = |time: chrono::DateTime<Utc>| EventTime {
+            date_time: time.to_rfc3339_opts(SecondsFormat::Secs, true),
+        };
+
+        Self {
+            summary: timer.project.clone(),
+            description: timer.note.clone().filter(|note| !note.is_empty()),
+            start: time(timer.start_time_ms.0),
+            end: time(timer.start_time_ms.end(timer.duration_ms)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedEvent {
+    id: String,
+}
+
+impl GoogleCalendar {
+    /// Reads the OAuth client from the environment, if one is configured
+    pub(crate) fn from_env() -> Result<Option<Self>> {
+        match (
+            env::var("GOOGLE_CLIENT_ID"),
+            env::var("GOOGLE_CLIENT_SECRET"),
+        ) {
+            (Ok(client_id), Ok(client_secret)) => Ok(Some(Self::new(client_id, client_secret))),
+            (Err(_), Err(_)) => Ok(None),
+            _ => Err(anyhow!(
+                "GOOGLE_CLIENT_ID and GOOGLE_CLIENT_SECRET must be set together"
+            )),
+        }
+    }
+
+    fn new(client_id: String, client_secret: String) -> Self {
+        Self {
+            key: blake3::derive_key(
+                "sprite 2026-10-15 google oauth state",
+                client_secret.as_bytes(),
+            ),
+            client_id,
+            client_secret,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("The reqwest client should build without custom TLS config"),
+            auth_url: AUTH_URL.to_string(),
+            token_url: TOKEN_URL.to_string(),
+            api_url: API_URL.to_string(),
+        }
+    }
+
+    /// Talks to a fake Google at `base` instead of the real one
+    #[cfg(test)]
+    fn at(mut self, base: &str) -> Self {
+        self.auth_url = format!("{}/auth", base);
+        self.token_url = format!("{}/token", base);
+        self.api_url = format!("{}/calendar/v3", base);
+        self
+    }
+
+    /// Where Google sends the browser back to after the consent screen
+    fn redirect_uri() -> String {
+        format!("{}/integrations/google/callback", uri_base())
+    }
+
+    fn signature(&self, payload: &str) -> String {
+        blake3::keyed_hash(&self.key, payload.as_bytes()).as_bytes()[..SIGNATURE_BYTES]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// The OAuth state of a tag connecting its account at `now`
+    fn state(&self, uid: &TagId, now: i64) -> String {
+        let payload = format!("{}.{}", uid.as_ref(), now);
+        let signature = self.signature(&payload);
+
+        format!("{}.{}", payload, signature)
+    }
+
+    /// The tag which started connecting an account, unless the state was
+    /// tampered with or is too old
+    pub(crate) fn verify_state(&self, state: &str, now: i64) -> Option<TagId> {
+        let (payload, signature) = state.rsplit_once('.')?;
+        let (uid, issued) = payload.rsplit_once('.')?;
+        let issued: i64 = issued.parse().ok()?;
+        if !(0..=STATE_LIFETIME).contains(&(now - issued)) {
+            return None;
+        }
+
+        // Compare every byte so the time taken doesn't reveal how much matched
+        let expected = self.signature(payload);
+        let difference = expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b));
+        (difference == 0 && expected.len() == signature.len()).then(|| TagId::from(uid.to_string()))
+    }
+
+    /// Where to send a tag to connect its Google account
+    pub(crate) fn authorize_url(&self, uid: &TagId) -> Result<String> {
+        let mut url = Url::parse(&self.auth_url)?;
+        url.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &Self::redirect_uri())
+            .append_pair("response_type", "code")
+            .append_pair("scope", SCOPE)
+            // A refresh token is only handed out for offline access, and
+            // only on consent, also when the account was connected before
+            .append_pair("access_type", "offline")
+            .append_pair("prompt", "consent")
+            .append_pair("state", &self.state(uid, Utc::now().timestamp()));
+
+        Ok(url.into())
+    }
+
+    /// Trades the code of the OAuth callback for tokens
+    #[instrument(skip_all)]
+    pub(crate) async fn exchange(&self, code: &str) -> Result<Tokens> {
+        let redirect_uri = Self::redirect_uri();
+        let tokens = self
+            .client
+            .post(&self.token_url)
+            .form(&[
+                ("code", code),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+                ("redirect_uri", &redirect_uri),
+                ("grant_type", "authorization_code"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(tokens)
+    }
+
+    #[instrument(skip_all)]
+    async fn refresh(&self, refresh_token: &str) -> Result<Tokens> {
+        let tokens = self
+            .client
+            .post(&self.token_url)
+            .form(&[
+                ("refresh_token", refresh_token),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+                ("grant_type", "refresh_token"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(tokens)
+    }
+
+    /// A working access token for `account`, refreshing it if it's about to expire
+    async fn access_token(&self, store: &DataStore, account: &GoogleAccount) -> Result<String> {
+        let now = Utc::now().timestamp();
+        if account.expires_at > now + EXPIRY_MARGIN {
+            return Ok(account.access_token.clone());
+        }
+
+        let tokens = self.refresh(&account.refresh_token).await?;
+        let uid = TagId::from(account.unique_id.clone());
+        store
+            .update_google_token(&uid, &tokens.access_token, now + tokens.expires_in)
+            .await?;
+
+        Ok(tokens.access_token)
+    }
+
+    /// Adds a timer to its calendar, returning the ID of the new event
+    #[instrument(skip(self, access_token))]
+    async fn insert_event(&self, access_token: &str, timer: &GoogleTimer) -> Result<String> {
+        let mut url = Url::parse(&self.api_url)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("Invalid Google Calendar API URL"))?
+            .extend(["calendars", &timer.calendar_id, "events"]);
+
+        let event: CreatedEvent = self
+            .client
+            .post(url)
+            .bearer_auth(access_token)
+            .json(&Event::from(timer))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(event.id)
+    }
+
+    /// Pushes the timers of one tag, stopping at the first which fails so
+    /// the rest are retried in order on the next run
+    #[instrument(skip(self, store, timers), fields(count = timers.len()))]
+    async fn sync_tag(&self, store: &DataStore, uid: &TagId, timers: &[GoogleTimer]) -> Result<()> {
+        let Some(account) = store.google_account(uid).await? else {
+            return Ok(());
+        };
+        let access_token = self.access_token(store, &account).await?;
+        for timer in timers {
+            let event_id = self.insert_event(&access_token, timer).await?;
+            store.mark_google_synced(timer, &event_id).await?;
+        }
+
+        info!("Pushed timers to Google Calendar");
+        Ok(())
+    }
+
+    /// Pushes the finished timers of every connected tag which weren't yet
+    async fn sync(&self, store: &DataStore) {
+        let timers = match store.unsynced_google_timers(SYNC_BATCH).await {
+            Ok(timers) => timers,
+            Err(err) => {
+                error!(%err, "Unable to find timers to push to Google Calendar");
+                return;
+            }
+        };
+
+        let mut by_tag: BTreeMap<String, Vec<GoogleTimer>> = BTreeMap::new();
+        for timer in timers {
+            by_tag
+                .entry(timer.unique_id.clone())
+                .or_default()
+                .push(timer);
+        }
+        for (tag_id, timers) in by_tag {
+            let uid = TagId::from(tag_id);
+            if let Err(err) = self.sync_tag(store, &uid, &timers).await {
+                error!(tag_id = uid.as_ref(), %err, "Unable to push timers to Google Calendar");
+            }
+        }
+    }
+}
+
+/// Spawns the background task pushing finished timers to Google Calendar
+pub(crate) fn spawn(store: DataStore, calendar: Option<GoogleCalendar>) {
+    let Some(calendar) = calendar else {
+        info!("Google Calendar sync disabled");
+        return;
+    };
+
+    tokio::spawn(run(store, calendar));
+}
+
+#[instrument(skip_all)]
+async fn run(store: DataStore, calendar: GoogleCalendar) {
+    let mut interval = tokio::time::interval(SYNC_INTERVAL);
+    loop {
+        interval.tick().await;
+        if !leases::held(&store, "google_calendar", SYNC_INTERVAL).await {
+            continue;
+        }
+        calendar.sync(&store).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::Path, routing::post, Form, Json, Router};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{
+        time::{StartTime, TrackedDuration},
+        timer_store::ToggleOptions,
+    };
+
+    fn calendar() -> GoogleCalendar {
+        GoogleCalendar::new("client".to_string(), "secret".to_string())
+    }
+
+    #[test]
+    fn states_are_signed_and_expire() {
+        let calendar = calendar();
+        let uid = TagId::new("test-tag").unwrap();
+        let state = calendar.state(&uid, 1_000);
+        let verify = |calendar: &GoogleCalendar, state: &str, now| {
+            calendar
+                .verify_state(state, now)
+                .map(|uid| uid.as_ref().to_string())
+        };
+
+        assert_eq!(
+            verify(&calendar, &state, 1_000).as_deref(),
+            Some(uid.as_ref())
+        );
+        assert_eq!(
+            verify(&calendar, &state, 1_000 + STATE_LIFETIME).as_deref(),
+            Some(uid.as_ref())
+        );
+        assert_eq!(verify(&calendar, &state, 1_001 + STATE_LIFETIME), None);
+
+        let other = TagId::new("other-tag").unwrap();
+        let (_, signature) = state.rsplit_once('.').unwrap();
+        let forged = format!("{}.1000.{}", other.as_ref(), signature);
+        assert_eq!(verify(&calendar, &forged, 1_000), None);
+
+        // Another client secret signs differently
+        let other_client = GoogleCalendar::new("client".to_string(), "other".to_string());
+        assert_eq!(verify(&other_client, &state, 1_000), None);
+    }
+
+    #[test]
+    fn timers_become_events() {
+        let timer = GoogleTimer {
+            timer_id: 1,
+            unique_id: "test-tag".to_string(),
+            project: "writing".to_string(),
+            start_time_ms: StartTime::from_millis(1_700_000_000_500),
+            duration_ms: TrackedDuration::from_seconds(5_400),
+            note: Some(String::new()),
+            calendar_id: "primary".to_string(),
+        };
+
+        let event = serde_json::to_value(Event::from(&timer)).unwrap();
+        assert_eq!(
+            event,
+            serde_json::json!({
+                "summary": "writing",
+                "start": {"dateTime": "2023-11-14T22:13:20Z"},
+                "end": {"dateTime": "2023-11-14T23:43:20Z"},
+            })
+        );
+    }
+
+    /// A stand-in for Google's token and calendar endpoints, which records
+    /// the events added
+    async fn fake_google() -> (String, Arc<Mutex<Vec<(String, serde_json::Value)>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        let router = Router::new()
+            .route(
+                "/token",
+                post(|Form(form): Form<BTreeMap<String, String>>| async move {
+                    assert_eq!(form["grant_type"], "refresh_token");
+                    assert_eq!(form["refresh_token"], "refresh");
+                    Json(serde_json::json!({"access_token": "fresh", "expires_in": 3600}))
+                }),
+            )
+            .route(
+                "/calendar/v3/calendars/:calendar_id/events",
+                post(
+                    move |Path(calendar_id): Path<String>,
+                          headers: axum::http::HeaderMap,
+                          Json(event): Json<serde_json::Value>| async move {
+                        assert_eq!(headers["authorization"], "Bearer fresh");
+                        let mut events = recorded.lock().unwrap();
+                        events.push((calendar_id, event));
+                        Json(serde_json::json!({"id": format!("event-{}", events.len())}))
+                    },
+                ),
+            );
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(router.into_make_service()),
+        );
+
+        (base, events)
+    }
+
+    #[tokio::test]
+    async fn finished_timers_are_pushed_once() {
+        let store = DataStore::in_memory().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let writing = store.create_project(&uid, "writing").await.unwrap();
+        let reading = store.create_project(&uid, "reading").await.unwrap();
+        let now = Utc::now().timestamp();
+
+        // Timers which ended before the account was connected stay off the calendar
+        let project = store.project(writing).await.unwrap();
+        store
+            .import_timers(
+                &project,
+                [(
+                    StartTime::from_seconds(now - 90_000),
+                    TrackedDuration::from_seconds(600),
+                )],
+            )
+            .await
+            .unwrap();
+        let toggle = |project_id, at| ToggleOptions {
+            project_id: Some(project_id),
+            at,
+            ..Default::default()
+        };
+        store
+            .toggle(&uid, &toggle(writing, Some(now - 3_600)))
+            .await
+            .unwrap();
+
+        // An access token which expired makes the sync refresh it first
+        store
+            .connect_google_account(&uid, "refresh", "stale", now - 10)
+            .await
+            .unwrap();
+        store
+            .set_google_calendar(&uid, reading, Some("reading@group.calendar.google.com"))
+            .await
+            .unwrap();
+        store.toggle(&uid, &toggle(writing, None)).await.unwrap();
+        store.toggle(&uid, &toggle(reading, None)).await.unwrap();
+        store.toggle(&uid, &toggle(reading, None)).await.unwrap();
+
+        let (base, events) = fake_google().await;
+        let calendar = calendar().at(&base);
+        calendar.sync(&store).await;
+
+        let pushed: Vec<_> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(calendar_id, event)| (calendar_id.clone(), event["summary"].clone()))
+            .collect();
+        assert_eq!(
+            pushed,
+            [
+                ("primary".to_string(), serde_json::json!("writing")),
+                (
+                    "reading@group.calendar.google.com".to_string(),
+                    serde_json::json!("reading")
+                ),
+            ]
+        );
+        let account = store.google_account(&uid).await.unwrap().unwrap();
+        assert_eq!(account.access_token, "fresh");
+        assert!(account.expires_at > now);
+
+        calendar.sync(&store).await;
+        assert_eq!(events.lock().unwrap().len(), 2);
+        assert!(store.unsynced_google_timers(10).await.unwrap().is_empty());
+    }
+}
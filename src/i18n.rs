@@ -0,0 +1,155 @@
+//! Translations of the web UI
+//!
+//! The bundles live in `locales/<language>/main.ftl` and are compiled into the
+//! binary. Pages are rendered in the language set for the tag, or else the
+//! best match of the `Accept-Language` header, falling back to English.
+
+use std::collections::HashMap;
+
+use fluent_templates::{fluent_bundle::FluentValue, static_loader, LanguageIdentifier, Loader};
+use serde::Serialize;
+
+use crate::error::Error;
+
+static_loader! {
+    static LOCALES = {
+        locales: "./locales",
+        fallback_language: "en-US",
+        // The isolating marks around arguments only get in the way in HTML
+        customise: |bundle| bundle.set_use_isolating(false),
+    };
+}
+
+/// A language the web UI has been translated to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Language(LanguageIdentifier);
+
+impl Default for Language {
+    fn default() -> Self {
+        Self("en-US".parse().expect("The fallback language is valid"))
+    }
+}
+
+impl Serialize for Language {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl Language {
+    /// Picks the language of the tag if it has one, or else the most
+    /// preferred language of an `Accept-Language` header which is supported
+    pub(crate) fn negotiate(preferred: Option<&str>, accept_language: Option<&str>) -> Self {
+        let mut requested: Vec<(f32, LanguageIdentifier)> = accept_language
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let language = parts.next()?.trim().parse().ok()?;
+                let quality = match parts.find_map(|part| part.trim().strip_prefix("q=")) {
+                    Some(quality) => quality.parse().ok()?,
+                    None => 1.0,
+                };
+                Some((quality, language))
+            })
+            .filter(|(quality, _)| *quality > 0.0)
+            .collect();
+        // Stable, so ranges with the same quality keep their order
+        requested.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        preferred
+            .and_then(|language| language.parse().ok())
+            .into_iter()
+            .chain(requested.into_iter().map(|(_, language)| language))
+            .find_map(|language| Self::supported(&language))
+            .unwrap_or_default()
+    }
+
+    /// Parses a language to store as the preference of a tag
+    pub(crate) fn parse_supported(language: &str) -> anyhow::Result<Self> {
+        language
+            .parse()
+            .ok()
+            .and_then(|language| Self::supported(&language))
+            .ok_or_else(|| {
+                Error::Validation(format!("The language {:?} isn't supported", language)).into()
+            })
+    }
+
+    /// The bundled language for `requested`, matching on the language alone
+    /// so a request for `es-MX` gets the Spanish bundle
+    fn supported(requested: &LanguageIdentifier) -> Option<Self> {
+        LOCALES
+            .locales()
+            .find(|language| language.language == requested.language)
+            .cloned()
+            .map(Self)
+    }
+
+    /// The language tag, for the `lang` attribute of a page
+    pub fn code(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// Translates the message `key`
+    pub fn tr(&self, key: &str) -> String {
+        LOCALES
+            .lookup(&self.0, key)
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Translates the message `key` which has a single argument
+    pub fn tr_with(&self, key: &str, name: &str, value: impl ToString) -> String {
+        self.tr_args(key, &[(name, value.to_string())])
+    }
+
+    /// Translates the message `key` with all of its arguments
+    pub fn tr_args(&self, key: &str, args: &[(&str, String)]) -> String {
+        let args: HashMap<_, _> = args
+            .iter()
+            .map(|(name, value)| (*name, FluentValue::from(value.as_str())))
+            .collect();
+
+        LOCALES
+            .lookup_with_args(&self.0, key, &args)
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn negotiate(preferred: Option<&str>, accept_language: &str) -> String {
+        Language::negotiate(preferred, Some(accept_language)).code()
+    }
+
+    #[test]
+    fn languages_are_negotiated() {
+        assert_eq!(negotiate(None, "de-DE,de;q=0.9,en;q=0.8"), "de");
+        assert_eq!(negotiate(None, "fr-FR, es-MX;q=0.5, en;q=0.7"), "en-US");
+        assert_eq!(negotiate(None, "fr;q=1.0, es;q=0.1"), "es");
+        assert_eq!(negotiate(None, "es;q=0, fr"), "en-US");
+        assert_eq!(negotiate(None, "*"), "en-US");
+        assert_eq!(negotiate(Some("es"), "de"), "es");
+        assert_eq!(Language::negotiate(None, None).code(), "en-US");
+
+        assert!(Language::parse_supported("fr").is_err());
+        assert_eq!(Language::parse_supported("de-AT").unwrap().code(), "de");
+    }
+
+    #[test]
+    fn messages_are_translated() {
+        let german = Language::parse_supported("de").unwrap();
+        assert_eq!(german.tr("filter"), "Filtern");
+        assert_eq!(
+            german.tr_with("chart-last-days", "days", 7),
+            "Letzte 7 Tage"
+        );
+
+        assert_eq!(Language::default().tr("filter"), "Filter");
+        // Unknown messages show their key rather than an empty string
+        assert_eq!(german.tr("no-such-message"), "no-such-message");
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use tracing::{debug, instrument};
+
+use crate::time::{StartTime, TrackedDuration};
+
+/// A single event parsed from an iCalendar file
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct CalendarEvent {
+    pub(crate) summary: String,
+    pub(crate) start: DateTime<Utc>,
+    pub(crate) end: DateTime<Utc>,
+}
+
+impl CalendarEvent {
+    /// How long the event lasts
+    pub(crate) fn duration(&self) -> TrackedDuration {
+        TrackedDuration::between(StartTime(self.start), self.end)
+    }
+}
+
+/// Parses all timed events out of an iCalendar (.ics) document
+///
+/// Times without an explicit zone are interpreted in `timezone`. All-day
+/// events and events without an end are skipped since they don't map to a
+/// timer.
+#[instrument(skip(ics))]
+pub(crate) fn parse_events(ics: &str, timezone: chrono_tz::Tz) -> Result<Vec<CalendarEvent>> {
+    let mut events = Vec::new();
+    let mut current: Option<PartialEvent> = None;
+
+    for line in unfold(ics) {
+        let Some((name, params, value)) = split_property(&line) else {
+            continue;
+        };
+
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => current = Some(PartialEvent::default()),
+            ("END", "VEVENT") => {
+                let Some(event) = current.take() else {
+                    return Err(anyhow!("Unexpected END:VEVENT"));
+                };
+                match event.finish() {
+                    Some(event) => events.push(event),
+                    None => debug!("Skipping event without a start and end time"),
+                }
+            }
+            ("SUMMARY", value) => {
+                if let Some(event) = current.as_mut() {
+                    event.summary = unescape(value);
+                }
+            }
+            ("DTSTART", value) => {
+                if let Some(event) = current.as_mut() {
+                    event.start = parse_date_time(&params, value, timezone)?;
+                }
+            }
+            ("DTEND", value) => {
+                if let Some(event) = current.as_mut() {
+                    event.end = parse_date_time(&params, value, timezone)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(events)
+}
+
+#[derive(Debug, Default)]
+struct PartialEvent {
+    summary: String,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+}
+
+impl PartialEvent {
+    fn finish(self) -> Option<CalendarEvent> {
+        let (start, end) = (self.start?, self.end?);
+        if end <= start {
+            return None;
+        }
+
+        Some(CalendarEvent {
+            summary: self.summary,
+            start,
+            end,
+        })
+    }
+}
+
+/// Joins folded content lines back together
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    lines
+}
+
+/// Parameters of a content line as `(name, value)` pairs
+type Params = Vec<(String, String)>;
+
+/// Splits a content line into its name, parameters and value
+fn split_property(line: &str) -> Option<(String, Params, &str)> {
+    let (head, value) = line.split_once(':')?;
+    let mut parts = head.split(';');
+    let name = parts.next()?.to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.to_ascii_uppercase(),
+                value.trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+
+    Some((name, params, value.trim_end()))
+}
+
+/// Parses a DATE-TIME value, returning `None` for all-day DATE values
+fn parse_date_time(
+    params: &Params,
+    value: &str,
+    timezone: chrono_tz::Tz,
+) -> Result<Option<DateTime<Utc>>> {
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    if param("VALUE") == Some("DATE") || NaiveDate::parse_from_str(value, "%Y%m%d").is_ok() {
+        return Ok(None);
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")?;
+        return Ok(Some(Utc.from_utc_datetime(&time)));
+    }
+
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")?;
+    let timezone = match param("TZID") {
+        Some(tzid) => tzid
+            .parse()
+            .map_err(|err| anyhow!("Unable to parse timezone: {}", err))?,
+        None => timezone,
+    };
+
+    match timezone.from_local_datetime(&time) {
+        chrono::LocalResult::Single(time) | chrono::LocalResult::Ambiguous(time, _) => {
+            Ok(Some(time.with_timezone(&Utc)))
+        }
+        chrono::LocalResult::None => Err(anyhow!("Invalid local time {} in {}", value, timezone)),
+    }
+}
+
+/// Reverses TEXT value escaping
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(escaped) => result.push(escaped),
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+SUMMARY:Daily standup\r
+DTSTART:20240102T170000Z\r
+DTEND:20240102T171500Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Planning\\, quarterly\r
+DTSTART;TZID=America/New_York:20240103T090000\r
+DTEND;TZID=America/New_York:20240103T1\r
+ 03000\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Holiday\r
+DTSTART;VALUE=DATE:20240104\r
+DTEND;VALUE=DATE:20240105\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn parse_timed_events() {
+        let events = parse_events(CALENDAR, chrono_tz::US::Pacific).unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "Daily standup");
+        assert_eq!(events[0].duration(), TrackedDuration::from_seconds(15 * 60));
+        assert_eq!(events[1].summary, "Planning, quarterly");
+        assert_eq!(
+            events[1].start,
+            Utc.with_ymd_and_hms(2024, 1, 3, 14, 0, 0).unwrap()
+        );
+        assert_eq!(events[1].duration(), TrackedDuration::from_seconds(90 * 60));
+    }
+
+    #[test]
+    fn floating_times_use_given_timezone() {
+        let calendar = "BEGIN:VEVENT\nDTSTART:20240102T090000\nDTEND:20240102T100000\nEND:VEVENT\n";
+        let events = parse_events(calendar, chrono_tz::US::Pacific).unwrap();
+
+        assert_eq!(
+            events[0].start,
+            Utc.with_ymd_and_hms(2024, 1, 2, 17, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use std::{env, time::Duration};
+
+use tracing::{error, info, instrument};
+
+use chrono::Utc;
+
+use crate::{
+    leases,
+    timer_store::{DataStore, IDEMPOTENCY_KEY_TTL},
+};
+
+/// How often the janitor runs its periodic housekeeping
+const JANITOR_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Number of days without activity after which a tag is archived.
+///
+/// Auto-archiving is disabled unless `TAG_ARCHIVE_AFTER_DAYS` is set.
+fn archive_after_days() -> Option<i64> {
+    let days = env::var("TAG_ARCHIVE_AFTER_DAYS").ok()?;
+    match days.parse() {
+        Ok(days) => Some(days),
+        Err(err) => {
+            error!(days, %err, "Invalid TAG_ARCHIVE_AFTER_DAYS, auto-archive disabled");
+            None
+        }
+    }
+}
+
+/// Spawns the background housekeeping task
+pub(crate) fn spawn(store: DataStore) {
+    let days = archive_after_days();
+    if days.is_none() {
+        info!("Tag auto-archive disabled");
+    }
+
+    tokio::spawn(run(store, days));
+}
+
+#[instrument(skip(store))]
+async fn run(store: DataStore, archive_after_days: Option<i64>) {
+    let mut interval = tokio::time::interval(JANITOR_INTERVAL);
+    loop {
+        interval.tick().await;
+        if !leases::held(&store, "janitor", JANITOR_INTERVAL).await {
+            continue;
+        }
+
+        let cutoff = Utc::now().timestamp() - IDEMPOTENCY_KEY_TTL;
+        match store.prune_idempotency_keys(cutoff).await {
+            Ok(pruned) => info!(pruned, "Pruned old idempotency keys"),
+            Err(err) => error!(%err, "Unable to prune idempotency keys"),
+        }
+
+        let Some(days) = archive_after_days else {
+            continue;
+        };
+        match store.archive_inactive_tags(days).await {
+            Ok(archived) => info!(archived, "Archived inactive tags"),
+            Err(err) => error!(%err, "Unable to archive inactive tags"),
+        }
+    }
+}
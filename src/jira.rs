@@ -0,0 +1,313 @@
+//! Pushing finished timers to Jira as worklogs
+//!
+//! A tag connects its Jira site with the email and API token of an account,
+//! then picks an issue for each project it wants logged. A background task
+//! adds a worklog on that issue for every timer of the project it finishes.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{error, info, instrument};
+
+use crate::{
+    leases,
+    timer_store::{DataStore, JiraAccount, JiraTimer},
+    uid::TagId,
+};
+
+/// How often finished timers are pushed
+const SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The most timers pushed in one run, the rest wait for the next one
+const SYNC_BATCH: i64 = 100;
+
+/// A worklog, as far as Sprite fills it in
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Worklog {
+    /// When the work started, in the format Jira insists on
+    started: String,
+    time_spent_seconds: i64,
+
+    /// The note of the timer, in the Atlassian Document Format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<serde_json::Value>,
+}
+
+impl From<&JiraTimer> for Worklog {
+    fn from(timer: &JiraTimer) -> Self {
+        let comment = timer
+            .note
+            .as_deref()
+            .filter(|note| !note.is_empty())
+            .map(|note| {
+                json!({
+                    "type": "doc",
+                    "version": 1,
+                    "content": [{
+                        "type": "paragraph",
+                        "content": [{"type": "text", "text": note}],
+                    }],
+                })
+            });
+
+        Self {
+            started: timer
+                .start_time_ms
+                .0
+                .format("%Y-%m-%dT%H:%M:%S%.3f%z")
+                .to_string(),
+            time_spent_seconds: timer.duration_ms.seconds(),
+            comment,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedWorklog {
+    id: String,
+}
+
+/// Talks to the Jira sites tags connected
+#[derive(Debug, Clone)]
+struct Jira {
+    client: reqwest::Client,
+}
+
+impl Jira {
+    fn new() -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("The reqwest client should build without custom TLS config"),
+        }
+    }
+
+    /// Logs a timer on its issue, returning the ID of the new worklog
+    #[instrument(skip(self, account))]
+    async fn add_worklog(&self, account: &JiraAccount, timer: &JiraTimer) -> Result<String> {
+        let mut url = reqwest::Url::parse(&account.site)?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid Jira site {}", account.site))?
+            .pop_if_empty()
+            .extend(["rest", "api", "3", "issue", &timer.issue_key, "worklog"]);
+        url.query_pairs_mut().append_pair("notifyUsers", "false");
+
+        let worklog: CreatedWorklog = self
+            .client
+            .post(url)
+            .basic_auth(&account.email, Some(&account.api_token))
+            .json(&Worklog::from(timer))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(worklog.id)
+    }
+
+    /// Pushes the timers of one tag, stopping at the first which fails so
+    /// the rest are retried in order on the next run
+    #[instrument(skip(self, store, timers), fields(count = timers.len()))]
+    async fn sync_tag(&self, store: &DataStore, uid: &TagId, timers: &[JiraTimer]) -> Result<()> {
+        let Some(account) = store.jira_account(uid).await? else {
+            return Ok(());
+        };
+        for timer in timers {
+            let worklog_id = self.add_worklog(&account, timer).await?;
+            store.mark_jira_synced(timer, &worklog_id).await?;
+        }
+
+        info!("Pushed timers to Jira");
+        Ok(())
+    }
+
+    /// Pushes the finished timers of every connected tag which weren't yet
+    async fn sync(&self, store: &DataStore) {
+        let timers = match store.unsynced_jira_timers(SYNC_BATCH).await {
+            Ok(timers) => timers,
+            Err(err) => {
+                error!(%err, "Unable to find timers to push to Jira");
+                return;
+            }
+        };
+
+        let mut by_tag: BTreeMap<String, Vec<JiraTimer>> = BTreeMap::new();
+        for timer in timers {
+            by_tag
+                .entry(timer.unique_id.clone())
+                .or_default()
+                .push(timer);
+        }
+        for (tag_id, timers) in by_tag {
+            let uid = TagId::from(tag_id);
+            if let Err(err) = self.sync_tag(store, &uid, &timers).await {
+                error!(tag_id = uid.as_ref(), %err, "Unable to push timers to Jira");
+            }
+        }
+    }
+}
+
+/// Spawns the background task pushing finished timers to Jira
+pub(crate) fn spawn(store: DataStore) {
+    tokio::spawn(run(store));
+}
+
+#[instrument(skip_all)]
+async fn run(store: DataStore) {
+    let jira = Jira::new();
+    let mut interval = tokio::time::interval(SYNC_INTERVAL);
+    loop {
+        interval.tick().await;
+        if !leases::held(&store, "jira", SYNC_INTERVAL).await {
+            continue;
+        }
+        jira.sync(&store).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        extract::{Path, Query},
+        http::HeaderMap,
+        routing::post,
+        Json, Router,
+    };
+    use chrono::Utc;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{
+        time::{StartTime, TrackedDuration},
+        timer_store::ToggleOptions,
+    };
+
+    #[test]
+    fn timers_become_worklogs() {
+        let timer = JiraTimer {
+            timer_id: 1,
+            unique_id: "test-tag".to_string(),
+            issue_key: "SPRITE-12".to_string(),
+            start_time_ms: StartTime::from_millis(1_700_000_000_500),
+            duration_ms: TrackedDuration::from_seconds(5_400),
+            note: Some("chapter 3".to_string()),
+        };
+
+        let worklog = serde_json::to_value(Worklog::from(&timer)).unwrap();
+        assert_eq!(
+            worklog,
+            json!({
+                "started": "2023-11-14T22:13:20.500+0000",
+                "timeSpentSeconds": 5_400,
+                "comment": {
+                    "type": "doc",
+                    "version": 1,
+                    "content": [{
+                        "type": "paragraph",
+                        "content": [{"type": "text", "text": "chapter 3"}],
+                    }],
+                },
+            })
+        );
+    }
+
+    type Recorded = Arc<Mutex<Vec<(String, serde_json::Value)>>>;
+
+    /// A stand-in for a Jira site, which records the worklogs added
+    async fn fake_jira() -> (String, Recorded) {
+        let worklogs = Recorded::default();
+        let recorded = worklogs.clone();
+        let router = Router::new().route(
+            "/jira/rest/api/3/issue/:issue_key/worklog",
+            post(
+                move |Path(issue_key): Path<String>,
+                      Query(query): Query<BTreeMap<String, String>>,
+                      headers: HeaderMap,
+                      Json(worklog): Json<serde_json::Value>| async move {
+                    assert_eq!(query["notifyUsers"], "false");
+                    // Basic auth of "me@example.com:token"
+                    assert_eq!(
+                        headers["authorization"],
+                        "Basic bWVAZXhhbXBsZS5jb206dG9rZW4="
+                    );
+                    let mut worklogs = recorded.lock().unwrap();
+                    worklogs.push((issue_key, worklog));
+                    Json(json!({"id": format!("{}", 10_000 + worklogs.len())}))
+                },
+            ),
+        );
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}/jira/", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(router.into_make_service()),
+        );
+
+        (base, worklogs)
+    }
+
+    #[tokio::test]
+    async fn finished_timers_are_logged_once() {
+        let store = DataStore::in_memory().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let writing = store.create_project(&uid, "writing").await.unwrap();
+        let reading = store.create_project(&uid, "reading").await.unwrap();
+        let now = Utc::now().timestamp();
+        let toggle = |project_id, at| ToggleOptions {
+            project_id: Some(project_id),
+            at,
+            ..Default::default()
+        };
+
+        let (site, worklogs) = fake_jira().await;
+        store
+            .connect_jira_account(&uid, &site, "me@example.com", "token")
+            .await
+            .unwrap();
+        store
+            .set_jira_issue(&uid, writing, Some("sprite-12"))
+            .await
+            .unwrap();
+        assert!(store
+            .set_jira_issue(&uid, reading, Some("not an issue"))
+            .await
+            .is_err());
+
+        // Only timers of projects with an issue, lasting a minute or more, are logged
+        store
+            .toggle(&uid, &toggle(writing, Some(now - 600)))
+            .await
+            .unwrap();
+        store.toggle(&uid, &toggle(writing, None)).await.unwrap();
+        store.toggle(&uid, &toggle(writing, None)).await.unwrap();
+        store.toggle(&uid, &toggle(writing, None)).await.unwrap();
+        store.toggle(&uid, &toggle(reading, None)).await.unwrap();
+        store.toggle(&uid, &toggle(reading, None)).await.unwrap();
+
+        let jira = Jira::new();
+        jira.sync(&store).await;
+        let logged: Vec<_> = worklogs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(issue_key, worklog)| {
+                let minutes = worklog["timeSpentSeconds"].as_i64().unwrap() / 60;
+                (issue_key.clone(), minutes)
+            })
+            .collect();
+        assert_eq!(logged, [("SPRITE-12".to_string(), 10)]);
+
+        jira.sync(&store).await;
+        assert_eq!(worklogs.lock().unwrap().len(), 1);
+        assert!(store.unsynced_jira_timers(10).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+//! Running each background task on only one of the instances sharing a database
+//!
+//! Every instance spawns the same background tasks, so two of them behind a
+//! load balancer would send every digest and reminder twice. Before each run a
+//! task takes a lease in the database, which one instance holds at a time. The
+//! holder renews it every run and another instance takes over once it expires,
+//! like after the holder was stopped.
+
+use std::{env, sync::OnceLock, time::Duration};
+
+use chrono::Utc;
+use tracing::{debug, error, info};
+
+use crate::timer_store::DataStore;
+
+static INSTANCE_ID: OnceLock<String> = OnceLock::new();
+
+/// Names this instance as the holder of leases, from `INSTANCE_ID`
+///
+/// Falls back to a random ID, which is new with every restart.
+fn instance_id() -> &'static str {
+    INSTANCE_ID.get_or_init(|| {
+        let id = env::var("INSTANCE_ID")
+            .ok()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        info!(id, "Identified instance for background tasks");
+        id
+    })
+}
+
+/// Whether this instance should run `task`, which runs `every` so often
+///
+/// A lease outlives two runs, so a missed run doesn't hand the task over.
+/// When the database can't be asked the run is skipped, as running it twice
+/// is worse than running it late.
+pub(crate) async fn held(store: &DataStore, task: &str, every: Duration) -> bool {
+    let ttl = i64::try_from(every.as_secs().saturating_mul(2)).unwrap_or(i64::MAX);
+    let holder = instance_id();
+    match store
+        .acquire_lease(task, holder, Utc::now().timestamp(), ttl)
+        .await
+    {
+        Ok(true) => true,
+        Ok(false) => {
+            debug!(task, holder, "Another instance runs the task");
+            false
+        }
+        Err(err) => {
+            error!(task, %err, "Unable to take the lease on the task, skipping it");
+            false
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Time tracking with NFC tags
+//!
+//! The storage layer can be used without the web server: open a [`DataStore`]
+//! and toggle timers for a [`TagId`], then summarize them with the
+//! [`timer_utils`] exports and [`report`]s. [`server::serve`] runs the web
+//! server of the `sprite` binary.
+//!
+//! [`DataStore`]: timer_store::DataStore
+//! [`TagId`]: uid::TagId
+
+#![forbid(unsafe_code)]
+#![deny(elided_lifetimes_in_paths)]
+
+mod admin;
+pub mod backup;
+mod cache;
+pub mod cli;
+mod cors;
+pub mod date_format;
+mod demo;
+mod digest;
+pub mod duration;
+mod email;
+pub mod error;
+mod events;
+mod extract;
+mod forwarded;
+mod github;
+mod google_calendar;
+mod i18n;
+mod ics;
+mod janitor;
+mod jira;
+mod leases;
+pub mod load_env;
+pub mod memory_store;
+mod midnight;
+mod mqtt;
+mod ndef;
+pub mod plugins;
+mod pwa;
+mod read_only;
+mod reminders;
+pub mod report;
+mod request_id;
+mod scheduled_exports;
+pub mod server;
+mod templates;
+pub mod time;
+pub mod timer_store;
+pub mod timer_utils;
+mod tls;
+mod toggle_link;
+pub mod uid;
+mod webhooks;
+
+use std::env;
+
+/// Where the server is reached unless a request or `URI_BASE` says otherwise
+const DEFAULT_ORIGIN: &str = "http://localhost:3000";
+
+/// The URL the server is reachable at, which links on pages and in emails start with
+///
+/// While handling a request this is the scheme and host the client sent it
+/// to, see [`forwarded`]. Setting `URI_BASE` overrides that, which emails and
+/// other links made outside of a request need to point anywhere but
+/// `localhost`. An `http://` override is upgraded to `https://` for requests
+/// a trusted proxy received over HTTPS. The [`base_path`] is appended either
+/// way.
+pub fn uri_base() -> String {
+    let client = forwarded::current();
+    let origin = match env::var("URI_BASE") {
+        Ok(uri_base) => match uri_base.strip_prefix("http://") {
+            Some(rest) if client.is_some_and(|client| client.https) => {
+                format!("https://{}", rest)
+            }
+            _ => uri_base,
+        },
+        Err(_) => client
+            .and_then(|client| client.origin())
+            .unwrap_or_else(|| DEFAULT_ORIGIN.to_string()),
+    };
+
+    format!("{}{}", origin.trim_end_matches('/'), base_path())
+}
+
+/// The path prefix every route is served under, empty unless `BASE_PATH` is set
+///
+/// For running behind a reverse proxy which forwards a sub-path like
+/// `/sprite/` without stripping it. Links within the site start with this.
+pub fn base_path() -> String {
+    normalize_base_path(&env::var("BASE_PATH").unwrap_or_default())
+}
+
+/// Turns `sprite`, `/sprite/` and the like into `/sprite`, and `/` into nothing
+fn normalize_base_path(path: &str) -> String {
+    let path = path.trim().trim_matches('/');
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn base_paths_are_normalized() {
+        assert_eq!(normalize_base_path(""), "");
+        assert_eq!(normalize_base_path("/"), "");
+        assert_eq!(normalize_base_path("sprite"), "/sprite");
+        assert_eq!(normalize_base_path("/sprite/"), "/sprite");
+        assert_eq!(normalize_base_path("/apps/sprite"), "/apps/sprite");
+    }
+}
@@ -0,0 +1,324 @@
+//! Keeping tags, projects and timers in memory instead of SQLite
+//!
+//! Useful for tests and for embedding the tracking logic where a database
+//! isn't wanted. Nothing survives the process, and only the [`TimerStore`]
+//! operations are supported: there are no teams, settings or short timer
+//! policies, so timers behave like they do for a tag with the defaults. In
+//! particular, timers can't be started before the tag has a project.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, MutexGuard},
+};
+
+use anyhow::Result;
+use axum::async_trait;
+use chrono::Utc;
+
+use crate::{
+    error::Error,
+    time::TrackedDuration,
+    timer_store::{
+        event_at, no_project, validate_project_name, Device, Project, Timer, TimerStore,
+        ToggleOptions,
+    },
+    uid::TagId,
+};
+
+/// A [`TimerStore`] which keeps everything in memory
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The tags which have stored anything
+    tags: HashSet<String>,
+
+    /// Every record by ID, which is its position plus one since nothing is deleted
+    projects: Vec<Project>,
+    timers: Vec<Timer>,
+    devices: Vec<Device>,
+}
+
+impl MemoryStore {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("The memory store is never left half updated")
+    }
+}
+
+impl State {
+    fn create_project(&mut self, uid: &TagId, project_name: &str) -> i64 {
+        let tag_id = uid.as_ref();
+        self.tags.insert(tag_id.to_string());
+        for project in self.projects.iter_mut() {
+            if project.unique_id == tag_id {
+                project.is_current = false;
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        let id = self.projects.len() as i64 + 1;
+        self.projects.push(Project {
+            id,
+            name: project_name.to_string(),
+            is_current: true,
+            unique_id: tag_id.to_string(),
+            created: now,
+            updated_at: now,
+            archived: false,
+            parent_id: None,
+        });
+
+        id
+    }
+
+    /// The project a new timer of the tag starts on
+    fn project_for(&self, uid: &TagId, project_id: Option<i64>) -> Result<i64> {
+        let tag_id = uid.as_ref();
+        if let Some(project_id) = project_id {
+            return match self
+                .projects
+                .iter()
+                .find(|project| project.id == project_id)
+            {
+                Some(project) if project.unique_id == tag_id => Ok(project_id),
+                _ => Err(Error::NotFound(format!(
+                    "Project {} does not belong to {}",
+                    project_id, uid
+                ))
+                .into()),
+            };
+        }
+
+        let current = self
+            .projects
+            .iter()
+            .find(|project| project.unique_id == tag_id && project.is_current);
+        current
+            .map(|project| project.id)
+            .ok_or_else(|| no_project(uid))
+    }
+}
+
+#[async_trait]
+impl TimerStore for MemoryStore {
+    async fn resolve_tag(&self, tag: &str) -> Result<TagId> {
+        Ok(tag.to_string().into())
+    }
+
+    async fn find_tag(&self, tag: &str) -> Result<TagId> {
+        if !self.state().tags.contains(tag) {
+            return Err(Error::NotFound(format!("There is no tag {}", tag)).into());
+        }
+
+        Ok(tag.to_string().into())
+    }
+
+    async fn create_project(&self, uid: &TagId, project_name: &str) -> Result<i64> {
+        let project_name = validate_project_name(project_name)?;
+        let mut state = self.state();
+        let taken = state.projects.iter().any(|project| {
+            project.unique_id == uid.as_ref()
+                && !project.archived
+                && project.name.to_lowercase() == project_name.to_lowercase()
+        });
+        if taken {
+            return Err(Error::Conflict(format!(
+                "There already is a project named {:?}",
+                project_name
+            ))
+            .into());
+        }
+
+        Ok(state.create_project(uid, project_name))
+    }
+
+    async fn project(&self, project_id: i64) -> Result<Project> {
+        self.state()
+            .projects
+            .iter()
+            .find(|project| project.id == project_id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("There is no project {}", project_id)).into())
+    }
+
+    async fn projects_by_tag(&self, uid: &TagId) -> Result<HashMap<Project, Vec<Timer>>> {
+        let state = self.state();
+        let projects = state
+            .projects
+            .iter()
+            .filter(|project| project.unique_id == uid.as_ref())
+            .map(|project| {
+                let timers = state
+                    .timers
+                    .iter()
+                    .filter(|timer| timer.project_id == project.id)
+                    .cloned()
+                    .collect();
+                (project.clone(), timers)
+            })
+            .collect();
+
+        Ok(projects)
+    }
+
+    async fn identify_device(&self, uid: &TagId, details: &str) -> Result<Device> {
+        let mut state = self.state();
+        let now = Utc::now().timestamp();
+        let existing = state
+            .devices
+            .iter_mut()
+            .find(|device| device.unique_id == uid.as_ref() && device.details == details);
+        if let Some(device) = existing {
+            device.last_seen = now;
+            return Ok(device.clone());
+        }
+
+        let device = Device {
+            id: state.devices.len() as i64 + 1,
+            unique_id: uid.as_ref().to_string(),
+            details: details.to_string(),
+            first_seen: now,
+            last_seen: now,
+            revoked: false,
+        };
+        state.devices.push(device.clone());
+
+        Ok(device)
+    }
+
+    async fn toggle(&self, uid: &TagId, options: &ToggleOptions) -> Result<i64> {
+        if let Some(device) = options.device.as_ref().filter(|device| device.revoked) {
+            return Err(Error::Validation(format!("Device {} has been revoked", device.id)).into());
+        }
+
+        let tag_id = uid.as_ref();
+        let at = event_at(options.at)?;
+        let now = Utc::now().timestamp();
+        let mut state = self.state();
+        state.tags.insert(tag_id.to_string());
+
+        let running = state
+            .timers
+            .iter_mut()
+            .find(|timer| timer.unique_id == tag_id && timer.is_current);
+        if let Some(timer) = running {
+            if at < timer.start_time_ms.0 {
+                return Err(Error::Validation(format!(
+                    "Timer {} can't stop before it started",
+                    timer.id
+                ))
+                .into());
+            }
+            timer.stop_at(at);
+            timer.is_current = false;
+            timer.updated_at = now;
+            return Ok(timer.id);
+        }
+
+        let project_id = state.project_for(uid, options.project_id)?;
+        let id = state.timers.len() as i64 + 1;
+        state.timers.push(Timer {
+            id,
+            unique_id: tag_id.to_string(),
+            project_id,
+            start_time_ms: at.into(),
+            is_current: true,
+            duration_ms: TrackedDuration::default(),
+            note: None,
+            link: None,
+            created_at: now,
+            updated_at: now,
+            device_id: options.device.as_ref().map(|device| device.id),
+            invoiced: false,
+        });
+
+        Ok(id)
+    }
+
+    async fn find_timer(&self, timer_id: i64) -> Result<Option<Timer>> {
+        Ok(self
+            .state()
+            .timers
+            .iter()
+            .find(|timer| timer.id == timer_id)
+            .cloned())
+    }
+
+    async fn exportable_timers_by_project(&self, project_id: &i64) -> Result<Vec<Timer>> {
+        let mut timers: Vec<_> = self
+            .state()
+            .timers
+            .iter()
+            .filter(|timer| timer.project_id == *project_id && !timer.is_current)
+            .cloned()
+            .collect();
+        timers.sort_by_key(|timer| std::cmp::Reverse(timer.start_time_ms));
+
+        Ok(timers)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn timers_are_toggled_in_memory() {
+        let store = MemoryStore::default();
+        let uid = TagId::new("test-tag").unwrap();
+        assert!(store.find_tag(uid.as_ref()).await.is_err());
+
+        // Timers need a project to start on
+        let err = store.toggle(&uid, &Default::default()).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::NoProject(_))));
+        assert!(store.create_project(&uid, " ").await.is_err());
+        store.create_project(&uid, "reading").await.unwrap();
+        assert!(store.create_project(&uid, "Reading").await.is_err());
+
+        let now = Utc::now().timestamp();
+        let options = ToggleOptions {
+            at: Some(now - 60),
+            ..Default::default()
+        };
+        let timer_id = store.toggle(&uid, &options).await.unwrap();
+        let timer = store.find_timer(timer_id).await.unwrap().unwrap();
+        assert!(timer.is_current);
+        assert_eq!(
+            store.project(timer.project_id).await.unwrap().name,
+            "reading"
+        );
+        assert_eq!(
+            store.find_tag(uid.as_ref()).await.unwrap().as_ref(),
+            uid.as_ref()
+        );
+
+        let stopped = store.toggle(&uid, &Default::default()).await.unwrap();
+        assert_eq!(stopped, timer_id);
+        let timers = store
+            .exportable_timers_by_project(&timer.project_id)
+            .await
+            .unwrap();
+        assert_eq!(timers.len(), 1);
+        assert!(timers[0].duration_ms >= TrackedDuration::from_seconds(60));
+
+        // New timers start on the newest project
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let timer_id = store.toggle(&uid, &Default::default()).await.unwrap();
+        let timer = store.find_timer(timer_id).await.unwrap().unwrap();
+        assert_eq!(timer.project_id, project_id);
+
+        let other = TagId::new("other-tag").unwrap();
+        let options = ToggleOptions {
+            project_id: Some(project_id),
+            ..Default::default()
+        };
+        assert!(store.toggle(&other, &options).await.is_err());
+        assert_eq!(store.projects_by_tag(&uid).await.unwrap().len(), 2);
+    }
+}
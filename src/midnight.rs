@@ -0,0 +1,209 @@
+//! Splits running timers at local midnight, for tags which want every timer
+//! to fall on a single day
+
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use tracing::{error, info, instrument};
+
+use crate::{
+    events::{EventBus, TimerEvent},
+    leases,
+    time::StartTime,
+    timer_store::{DataStore, MidnightTimer},
+};
+
+/// How often running timers are checked for having passed midnight
+const MIDNIGHT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The first midnight after `time` in `timezone`
+///
+/// Some timezones skip midnight when switching to daylight saving time, their
+/// day starts at the first time after it instead.
+fn next_midnight(timezone: Tz, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let tomorrow = time.with_timezone(&timezone).date_naive().succ_opt()?;
+    let midnight = (0..=2)
+        .filter_map(|hour| NaiveTime::from_hms_opt(hour, 0, 0))
+        .find_map(|start| {
+            timezone
+                .from_local_datetime(&tomorrow.and_time(start))
+                .earliest()
+        })?;
+
+    Some(midnight.with_timezone(&Utc))
+}
+
+/// The midnights a timer which started at `start` has run through by `now`
+fn midnights_between(timezone: Tz, start: StartTime, now: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let mut midnights = Vec::new();
+    let mut after = start.0;
+    while let Some(midnight) = next_midnight(timezone, after).filter(|midnight| *midnight <= now) {
+        midnights.push(midnight);
+        after = midnight;
+    }
+
+    midnights
+}
+
+/// Spawns the background task splitting running timers at midnight
+pub(crate) fn spawn(store: DataStore, events: EventBus) {
+    tokio::spawn(run(store, events));
+}
+
+#[instrument(skip_all)]
+async fn run(store: DataStore, events: EventBus) {
+    let mut interval = tokio::time::interval(MIDNIGHT_INTERVAL);
+    loop {
+        interval.tick().await;
+        if !leases::held(&store, "midnight", MIDNIGHT_INTERVAL).await {
+            continue;
+        }
+        split_timers(&store, &events, Utc::now()).await;
+    }
+}
+
+async fn split_timers(store: &DataStore, events: &EventBus, now: DateTime<Utc>) {
+    let timers = match store.midnight_timers().await {
+        Ok(timers) => timers,
+        Err(err) => {
+            error!(%err, "Unable to find timers to split at midnight");
+            return;
+        }
+    };
+
+    for timer in timers {
+        if let Err(err) = split_timer(store, events, &timer, now).await {
+            error!(timer_id = timer.timer_id, %err, "Unable to split timer at midnight");
+        }
+    }
+}
+
+#[instrument(skip(store, events, timer), fields(tag_id = timer.unique_id, timer_id = timer.timer_id))]
+async fn split_timer(
+    store: &DataStore,
+    events: &EventBus,
+    timer: &MidnightTimer,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let timezone: Tz = timer
+        .timezone
+        .parse()
+        .map_err(|err| anyhow::anyhow!("Invalid timezone {:?}: {}", timer.timezone, err))?;
+    let midnights = midnights_between(timezone, timer.start_time_ms, now);
+    if midnights.is_empty() {
+        return Ok(());
+    }
+
+    // A timer which ran for days while the task was down is split at every
+    // midnight it passed, so each day gets its own timer
+    let mut timer_id = timer.timer_id;
+    for midnight in &midnights {
+        timer_id = store
+            .split_running_timer(timer_id, StartTime(*midnight))
+            .await?
+            .id;
+    }
+    events.publish(TimerEvent::Toggled {
+        unique_id: timer.unique_id.clone(),
+    });
+
+    info!(splits = midnights.len(), "Split timer at midnight");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        timer_store::{TagSettings, ToggleOptions},
+        uid::TagId,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn local(timezone: Tz, hour: u32, day: u32) -> DateTime<Utc> {
+        timezone
+            .with_ymd_and_hms(2026, 10, day, hour, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn midnights_are_local() {
+        let pacific = chrono_tz::US::Pacific;
+        let start = StartTime(local(pacific, 22, 13));
+
+        assert!(midnights_between(pacific, start, local(pacific, 23, 13)).is_empty());
+        assert_eq!(
+            midnights_between(pacific, start, local(pacific, 1, 15)),
+            [local(pacific, 0, 14), local(pacific, 0, 15)]
+        );
+
+        // Santiago skips from 23:59 to 01:00 when daylight saving time starts
+        let santiago = chrono_tz::America::Santiago;
+        let start = santiago
+            .with_ymd_and_hms(2026, 9, 5, 20, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let midnight = next_midnight(santiago, start).unwrap();
+        assert_eq!(
+            midnight.with_timezone(&santiago).naive_local().to_string(),
+            "2026-09-06 01:00:00"
+        );
+    }
+
+    #[tokio::test]
+    async fn running_timers_are_split_at_midnight() {
+        let store = DataStore::in_memory().await.unwrap();
+        let events = EventBus::new();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                split_at_midnight: true,
+                goal_timezone: "US/Pacific".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let pacific = chrono_tz::US::Pacific;
+        let options = ToggleOptions {
+            at: Some(local(pacific, 22, 13).timestamp()),
+            ..Default::default()
+        };
+        store.toggle(&uid, &options).await.unwrap();
+
+        split_timers(&store, &events, local(pacific, 2, 15)).await;
+        let mut timers: Vec<_> = store
+            .projects_by_tag(&uid)
+            .await
+            .unwrap()
+            .into_values()
+            .flatten()
+            .collect();
+        timers.sort_by_key(|timer| timer.start_time_ms);
+        let summary: Vec<_> = timers
+            .iter()
+            .map(|timer| {
+                let (start_time, duration) = timer.seconds();
+                (start_time, duration, timer.is_current)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (local(pacific, 22, 13).timestamp(), 2 * 3600, false),
+                (local(pacific, 0, 14).timestamp(), 24 * 3600, false),
+                (local(pacific, 0, 15).timestamp(), 0, true),
+            ]
+        );
+
+        // Nothing changes until the next midnight
+        split_timers(&store, &events, local(pacific, 3, 15)).await;
+        assert_eq!(store.midnight_timers().await.unwrap().len(), 1);
+        let running = &store.midnight_timers().await.unwrap()[0];
+        assert_eq!(running.timer_id, timers[2].id);
+    }
+}
@@ -0,0 +1,148 @@
+/// URI identifier codes from the NFC Forum URI Record Type Definition.
+///
+/// Longer prefixes come first so the most specific one is picked.
+const URI_PREFIXES: [(u8, &str); 4] = [
+    (0x02, "https://www."),
+    (0x01, "http://www."),
+    (0x04, "https://"),
+    (0x03, "http://"),
+];
+
+/// Message begin, message end, short record and the well-known type name format
+const SHORT_RECORD_HEADER: u8 = 0b1101_0001;
+
+/// Same as [`SHORT_RECORD_HEADER`] without the short record flag
+const LONG_RECORD_HEADER: u8 = 0b1100_0001;
+
+/// The well-known record type for URIs
+const URI_TYPE: u8 = b'U';
+
+/// A single record NDEF message holding a URI
+#[derive(Debug)]
+pub(crate) struct UriRecord {
+    /// The URI stored in the record
+    uri: String,
+
+    /// The abbreviated prefix and the code it was replaced by
+    prefix_code: u8,
+    prefix: &'static str,
+
+    /// The bytes of the encoded message
+    bytes: Vec<u8>,
+}
+
+impl UriRecord {
+    pub(crate) fn new(uri: &str) -> Self {
+        let (prefix_code, prefix) = URI_PREFIXES
+            .iter()
+            .copied()
+            .find(|(_, prefix)| uri.starts_with(prefix))
+            .unwrap_or((0x00, ""));
+
+        let mut payload = vec![prefix_code];
+        payload.extend_from_slice(&uri.as_bytes()[prefix.len()..]);
+
+        let mut bytes = Vec::with_capacity(payload.len() + 7);
+        match u8::try_from(payload.len()) {
+            Ok(length) => bytes.extend_from_slice(&[SHORT_RECORD_HEADER, 1, length]),
+            Err(_) => {
+                let length = u32::try_from(payload.len()).expect("URI too long for NDEF");
+                bytes.extend_from_slice(&[LONG_RECORD_HEADER, 1]);
+                bytes.extend_from_slice(&length.to_be_bytes());
+            }
+        }
+        bytes.push(URI_TYPE);
+        bytes.extend_from_slice(&payload);
+
+        Self {
+            uri: uri.to_string(),
+            prefix_code,
+            prefix,
+            bytes,
+        }
+    }
+
+    /// The URI stored in the record
+    pub(crate) fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// The encoded NDEF message
+    pub(crate) fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The encoded NDEF message as space separated hex bytes
+    pub(crate) fn hex(&self) -> String {
+        self.bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// A human readable breakdown of the encoded message
+    pub(crate) fn explanation(&self) -> Vec<String> {
+        let short = self.bytes[0] == SHORT_RECORD_HEADER;
+        let payload_length = self.bytes.len() - if short { 4 } else { 7 };
+        let prefix = if self.prefix.is_empty() {
+            "no abbreviation".to_string()
+        } else {
+            format!("\"{}\"", self.prefix)
+        };
+
+        vec![
+            format!(
+                "{:02X}: record header (first and only record, {} record, NFC Forum well-known type)",
+                self.bytes[0],
+                if short { "short" } else { "long" }
+            ),
+            "01: type length of 1 byte".to_string(),
+            format!("payload length of {} bytes", payload_length),
+            format!("{:02X}: record type \"U\" (URI)", URI_TYPE),
+            format!("{:02X}: URI prefix code for {}", self.prefix_code, prefix),
+            format!(
+                "remaining bytes: \"{}\" encoded as UTF-8",
+                &self.uri[self.prefix.len()..]
+            ),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn encode_https_uri() {
+        let record = UriRecord::new("https://sprite.fly.dev/timer/abc");
+        let mut expected = vec![0xD1, 0x01, 25, b'U', 0x04];
+        expected.extend_from_slice(b"sprite.fly.dev/timer/abc");
+
+        assert_eq!(record.bytes(), expected.as_slice());
+        assert_eq!(&record.hex()[..14], "D1 01 19 55 04");
+    }
+
+    #[test]
+    fn encode_prefers_longest_prefix() {
+        let record = UriRecord::new("http://www.example.com");
+
+        assert_eq!(record.bytes()[4], 0x01);
+        assert_eq!(&record.bytes()[5..], b"example.com");
+    }
+
+    #[test]
+    fn encode_long_uri() {
+        let uri = format!("https://example.com/{}", "a".repeat(300));
+        let record = UriRecord::new(&uri);
+        let payload_length = uri.len() - "https://".len() + 1;
+
+        assert_eq!(record.bytes()[0], 0xC1);
+        assert_eq!(
+            &record.bytes()[2..6],
+            (payload_length as u32).to_be_bytes().as_slice()
+        );
+        assert_eq!(record.bytes().len(), payload_length + 7);
+    }
+}
@@ -0,0 +1,347 @@
+//! Experimental WASM plugins which hook into timer events
+//!
+//! Plugins are WebAssembly modules loaded from the directory in
+//! `SPRITE_PLUGIN_DIR` when sprite is built with the `plugins` feature. A
+//! plugin exports its `memory`, an `alloc(len: i32) -> i32` function and any
+//! of the following hooks, each taking a UTF-8 JSON document as `(ptr, len)`
+//! and returning a JSON document packed as `(ptr << 32) | len`:
+//!
+//! * `on_stop` receives the stopped timer and returns its new note (or null)
+//! * `on_export_row` receives an export row object and returns the row to write
+//!
+//! Plugins run without any imports, with a bounded amount of fuel and memory,
+//! in a fresh instance for every call. A failing plugin is logged and skipped
+//! so it can never break toggling or exporting.
+
+#[cfg(test)]
+use std::time::Duration;
+use std::{env, path::PathBuf};
+#[cfg(feature = "plugins")]
+use std::{path::Path, sync::Arc};
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::timer_store::Timer;
+
+/// The number of instructions (roughly) a single hook call may execute
+#[cfg(feature = "plugins")]
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+/// The most memory a plugin instance may grow to
+#[cfg(feature = "plugins")]
+const MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+/// The plugins loaded at startup
+#[derive(Debug, Clone, Default)]
+pub struct Plugins {
+    #[cfg(feature = "plugins")]
+    plugins: Arc<Vec<wasm::Plugin>>,
+    /// How long each `on_stop` call stalls, standing in for a slow plugin
+    #[cfg(test)]
+    stall: Option<Duration>,
+}
+
+impl Plugins {
+    /// Loads every `.wasm` module in `SPRITE_PLUGIN_DIR`
+    pub fn from_env() -> Result<Self> {
+        match env::var_os("SPRITE_PLUGIN_DIR") {
+            Some(dir) => Self::load(PathBuf::from(dir)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    #[cfg(feature = "plugins")]
+    fn load(dir: PathBuf) -> Result<Self> {
+        let engine = wasm::engine()?;
+        let mut plugins = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("wasm") {
+                continue;
+            }
+
+            tracing::info!(path = %path.display(), "Loading plugin");
+            plugins.push(wasm::Plugin::from_file(&engine, &path)?);
+        }
+
+        Ok(Self {
+            plugins: Arc::new(plugins),
+            #[cfg(test)]
+            stall: None,
+        })
+    }
+
+    #[cfg(not(feature = "plugins"))]
+    fn load(dir: PathBuf) -> Result<Self> {
+        warn!(dir = %dir.display(), "Built without the plugins feature, ignoring SPRITE_PLUGIN_DIR");
+        Ok(Self::default())
+    }
+
+    /// Whether any plugins are loaded
+    pub(crate) fn is_empty(&self) -> bool {
+        #[cfg(test)]
+        if self.stall.is_some() {
+            return false;
+        }
+
+        #[cfg(feature = "plugins")]
+        return self.plugins.is_empty();
+
+        #[cfg(not(feature = "plugins"))]
+        true
+    }
+
+    /// Runs the `on_stop` hooks, returning the note the stopped timer should have
+    pub(crate) fn on_stop(&self, timer: &Timer) -> Option<String> {
+        #[cfg(test)]
+        if let Some(stall) = self.stall {
+            std::thread::sleep(stall);
+        }
+
+        let note = timer.note.clone();
+
+        #[cfg(feature = "plugins")]
+        let mut note = note;
+
+        #[cfg(feature = "plugins")]
+        for plugin in self.plugins.iter() {
+            let mut input = match serde_json::to_value(timer) {
+                Ok(input) => input,
+                Err(err) => {
+                    warn!(%err, "Unable to serialize timer for plugins");
+                    break;
+                }
+            };
+            input["note"] = note.clone().into();
+
+            match plugin.call("on_stop", &input) {
+                Ok(Some(Value::String(new_note))) => note = Some(new_note),
+                Ok(Some(Value::Null)) => note = None,
+                Ok(Some(output)) => {
+                    warn!(plugin = %plugin, %output, "on_stop returned a non-string")
+                }
+                Ok(None) => {}
+                Err(err) => warn!(plugin = %plugin, %err, "on_stop failed"),
+            }
+        }
+
+        note
+    }
+
+    /// Runs the `on_export_row` hooks over a single export row
+    pub(crate) fn on_export_row(&self, row: Map<String, Value>) -> Map<String, Value> {
+        #[cfg(feature = "plugins")]
+        let mut row = row;
+
+        #[cfg(feature = "plugins")]
+        for plugin in self.plugins.iter() {
+            match plugin.call("on_export_row", &Value::Object(row.clone())) {
+                Ok(Some(Value::Object(new_row))) => row = new_row,
+                Ok(Some(output)) => {
+                    warn!(plugin = %plugin, %output, "on_export_row returned a non-object")
+                }
+                Ok(None) => {}
+                Err(err) => warn!(plugin = %plugin, %err, "on_export_row failed"),
+            }
+        }
+
+        row
+    }
+
+    #[cfg(all(test, feature = "plugins"))]
+    fn from_wat(wat: &str) -> Result<Self> {
+        let engine = wasm::engine()?;
+        let plugin = wasm::Plugin::new(&engine, "test".into(), wat.as_bytes())?;
+
+        Ok(Self {
+            plugins: Arc::new(vec![plugin]),
+            ..Self::default()
+        })
+    }
+
+    /// Plugins which take `stall` for every `on_stop` call and change nothing
+    #[cfg(test)]
+    pub(crate) fn stalling(stall: Duration) -> Self {
+        Self {
+            #[cfg(feature = "plugins")]
+            plugins: Arc::default(),
+            stall: Some(stall),
+        }
+    }
+}
+
+#[cfg(feature = "plugins")]
+mod wasm {
+    use std::fmt::{self, Display};
+
+    use anyhow::{anyhow, Result};
+    use serde_json::Value;
+    use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+    use super::{Path, FUEL_PER_CALL, MAX_MEMORY_BYTES};
+
+    pub(super) fn engine() -> Result<Engine> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+
+        Engine::new(&config)
+    }
+
+    /// A compiled plugin module
+    pub(super) struct Plugin {
+        name: String,
+        engine: Engine,
+        module: Module,
+    }
+
+    impl Plugin {
+        pub(super) fn from_file(engine: &Engine, path: &Path) -> Result<Self> {
+            let name = path.display().to_string();
+            Self::new(engine, name, &std::fs::read(path)?)
+        }
+
+        pub(super) fn new(engine: &Engine, name: String, bytes: &[u8]) -> Result<Self> {
+            let module = Module::new(engine, bytes)?;
+            if module.imports().len() > 0 {
+                return Err(anyhow!("Plugin {} must not import anything", name));
+            }
+
+            Ok(Self {
+                name,
+                engine: engine.clone(),
+                module,
+            })
+        }
+
+        /// Calls a hook with a JSON input, returning `None` if the plugin doesn't export it
+        pub(super) fn call(&self, hook: &str, input: &Value) -> Result<Option<Value>> {
+            if self.module.get_export(hook).is_none() {
+                return Ok(None);
+            }
+
+            let limits = StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY_BYTES)
+                .build();
+            let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+            store.limiter(|limits| limits);
+            store.add_fuel(FUEL_PER_CALL)?;
+
+            let instance = Instance::new(&mut store, &self.module, &[])?;
+            let memory = instance
+                .get_memory(&mut store, "memory")
+                .ok_or_else(|| anyhow!("Plugin {} does not export its memory", self.name))?;
+            let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+            let hook = instance.get_typed_func::<(i32, i32), i64>(&mut store, hook)?;
+
+            let input = serde_json::to_vec(input)?;
+            let length = i32::try_from(input.len())?;
+            let pointer = alloc.call(&mut store, length)?;
+            memory.write(&mut store, usize::try_from(pointer)?, &input)?;
+
+            let packed = hook.call(&mut store, (pointer, length))?;
+            let pointer = usize::try_from(packed >> 32)?;
+            let length = usize::try_from(packed & 0xFFFF_FFFF)?;
+            let output = memory
+                .data(&store)
+                .get(pointer..pointer + length)
+                .ok_or_else(|| anyhow!("Plugin {} returned an out of bounds result", self.name))?;
+
+            Ok(Some(serde_json::from_slice(output)?))
+        }
+    }
+
+    impl Display for Plugin {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.name)
+        }
+    }
+
+    impl fmt::Debug for Plugin {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Plugin").field("name", &self.name).finish()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "plugins"))]
+mod test {
+    use super::*;
+    use crate::{timer_store::DataStore, uid::TagId};
+    use pretty_assertions::assert_eq;
+
+    /// Replaces every note with "rewritten" and adds a column to export rows
+    const REWRITER: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (data (i32.const 0) "\"rewritten\"")
+  (data (i32.const 16) "{\"billable\":\"yes\"}")
+  (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+  (func (export "on_stop") (param i32 i32) (result i64) (i64.const 11))
+  (func (export "on_export_row") (param i32 i32) (result i64)
+    (i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const 18))))
+"#;
+
+    const SPINNER: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 0))
+  (func (export "on_stop") (param i32 i32) (result i64) (loop (br 0)) (i64.const 0)))
+"#;
+
+    #[test]
+    fn hooks_transform_values() {
+        let plugins = Plugins::from_wat(REWRITER).unwrap();
+        let timer = Timer::default();
+
+        assert_eq!(plugins.on_stop(&timer), Some("rewritten".to_string()));
+
+        let row = plugins.on_export_row(Map::new());
+        assert_eq!(row.get("billable"), Some(&Value::from("yes")));
+    }
+
+    #[tokio::test]
+    async fn stopped_timers_get_the_note_from_plugins() {
+        let store = DataStore::in_memory()
+            .await
+            .unwrap()
+            .with_plugins(Plugins::from_wat(REWRITER).unwrap());
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+
+        let started = store.toggle_current(&uid).await.unwrap();
+        assert_eq!(store.find_timer(started).await.unwrap().unwrap().note, None);
+        let stopped = store.toggle_current(&uid).await.unwrap();
+
+        // The plugins run in the background once the stop is committed
+        let mut note = None;
+        for _ in 0..100 {
+            note = store.find_timer(stopped).await.unwrap().unwrap().note;
+            if note.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(note, Some("rewritten".to_string()));
+    }
+
+    #[test]
+    fn runaway_plugin_is_stopped() {
+        let plugins = Plugins::from_wat(SPINNER).unwrap();
+        let timer = Timer {
+            note: Some("original".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(plugins.on_stop(&timer), Some("original".to_string()));
+    }
+
+    #[test]
+    fn plugins_cannot_import() {
+        let engine = wasm::engine().unwrap();
+        let wat = r#"(module (import "env" "read" (func)))"#;
+
+        assert!(wasm::Plugin::new(&engine, "imports".into(), wat.as_bytes()).is_err());
+    }
+}
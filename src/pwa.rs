@@ -0,0 +1,93 @@
+//! Installing the timer page as a progressive web app
+//!
+//! The service worker and icon are compiled into the binary so the app works
+//! without the generated assets of [`crate::cache::ASSET_DIR`].
+
+use axum::{
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::base_path;
+
+const SERVICE_WORKER: &str = include_str!("../assets/pwa/sw.js");
+const ICON: &str = include_str!("../assets/pwa/icon.svg");
+
+/// The web app manifest of a tag's timer page
+#[derive(Debug, Serialize)]
+pub(crate) struct Manifest {
+    name: String,
+    short_name: &'static str,
+    start_url: String,
+    scope: String,
+    display: &'static str,
+    background_color: &'static str,
+    theme_color: &'static str,
+    icons: [Icon; 1],
+}
+
+#[derive(Debug, Serialize)]
+struct Icon {
+    src: String,
+    sizes: &'static str,
+    #[serde(rename = "type")]
+    mime_type: &'static str,
+}
+
+impl Manifest {
+    /// The manifest opening the timer page of `tag_name` when the app is launched
+    pub(crate) fn new(tag_name: &str) -> Self {
+        Self {
+            name: format!("Soot Sprite - {}", tag_name),
+            short_name: "Soot Sprite",
+            start_url: format!("{}/timer/{}", base_path(), tag_name),
+            scope: format!("{}/", base_path()),
+            display: "standalone",
+            background_color: "#ffffff",
+            theme_color: "#212529",
+            icons: [Icon {
+                src: format!("{}/pwa/icon.svg", base_path()),
+                sizes: "any",
+                mime_type: "image/svg+xml",
+            }],
+        }
+    }
+}
+
+impl IntoResponse for Manifest {
+    fn into_response(self) -> Response {
+        let mut response = axum::Json(self).into_response();
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/manifest+json"),
+        );
+        response
+    }
+}
+
+/// The service worker queueing toggles while offline
+///
+/// Always revalidated, so a deploy with a new worker is picked up right away.
+pub(crate) async fn service_worker() -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/javascript"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        SERVICE_WORKER,
+    )
+        .into_response()
+}
+
+/// The icon of the installed app
+pub(crate) async fn icon() -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "image/svg+xml"),
+            (header::CACHE_CONTROL, "public, max-age=86400"),
+        ],
+        ICON,
+    )
+        .into_response()
+}
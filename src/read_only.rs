@@ -0,0 +1,70 @@
+//! Serving a database without changing it, like a replica browsing a backup
+//!
+//! Setting `READ_ONLY` to `true` makes every route which changes data answer
+//! with a 503 and keeps the background tasks which write from running. Pages
+//! show a banner so nobody wonders why their taps go nowhere.
+
+use std::env;
+
+use axum::{
+    extract::State,
+    http::{header, Method, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::debug;
+
+use crate::error::{AppError, Error, PageError};
+
+/// Whether `READ_ONLY` is set to `true` or `1`
+pub(crate) fn enabled() -> bool {
+    env::var("READ_ONLY").is_ok_and(|value| matches!(value.trim(), "true" | "1"))
+}
+
+/// Middleware refusing requests which change data, which is all but `GET`,
+/// `HEAD` and `OPTIONS` ones
+pub(crate) async fn guard<B>(
+    State(read_only): State<bool>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let safe = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    if read_only && !safe {
+        return refusal(&request);
+    }
+
+    next.run(request).await
+}
+
+/// Middleware refusing every request, for the few `GET` routes which change data
+pub(crate) async fn refuse<B>(
+    State(read_only): State<bool>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if read_only {
+        return refusal(&request);
+    }
+
+    next.run(request).await
+}
+
+/// An error page for browsers, JSON for everyone else
+fn refusal<B>(request: &Request<B>) -> Response {
+    debug!(method = %request.method(), uri = %request.uri(), "Refused change while read-only");
+    let err = Error::Unavailable("This server is read-only, changes are disabled".to_string());
+    let browser = request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+
+    if browser {
+        PageError::from(err).into_response()
+    } else {
+        AppError::from(err).into_response()
+    }
+}
@@ -0,0 +1,559 @@
+//! Reminders to start a timer when none is running during work hours, and
+//! alerts for timers which have run so long they were probably forgotten or
+//! projects running through their budget, and celebrations of daily goals
+
+use std::{str::FromStr, time::Duration};
+
+use anyhow::Result;
+use askama::Template;
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Serialize;
+use tracing::{error, info, instrument};
+
+use crate::{
+    email::Mailer,
+    error::Error,
+    events::{EventBus, TimerEvent},
+    leases,
+    templates::{BudgetAlert, LongTimerAlert, Reminder},
+    time::TrackedDuration,
+    timer_store::{DailyGoal, DataStore, IdleTag, LongTimer, ProjectBudget},
+    uid::TagId,
+    webhooks::{Payload, Webhooks},
+};
+
+/// How often idle tags and running timers are checked
+const REMINDER_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The hours a tag wants reminders during, like `Mon-Fri 09:00-17:00 Europe/Berlin`
+///
+/// Days are a comma separated list of days or ranges of days, and the
+/// timezone defaults to US/Pacific.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WorkSchedule {
+    /// Whether each day, starting from Monday, is a work day
+    days: [bool; 7],
+    start: NaiveTime,
+    end: NaiveTime,
+    timezone: Tz,
+}
+
+impl FromStr for WorkSchedule {
+    type Err = Error;
+
+    fn from_str(schedule: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            Error::Validation(format!("Invalid schedule {:?}: {}", schedule, reason))
+        };
+
+        let mut parts = schedule.split_whitespace();
+        let (Some(days), Some(hours)) = (parts.next(), parts.next()) else {
+            return Err(invalid("expected days and hours, like Mon-Fri 09:00-17:00"));
+        };
+        let timezone = match parts.next() {
+            Some(timezone) => timezone.parse().map_err(|_| invalid("unknown timezone"))?,
+            None => chrono_tz::US::Pacific,
+        };
+        if parts.next().is_some() {
+            return Err(invalid("unexpected text after the timezone"));
+        }
+
+        let mut work_days = [false; 7];
+        for range in days.split(',') {
+            let (first, last) = range.split_once('-').unwrap_or((range, range));
+            let parse_day = |day: &str| {
+                day.parse::<Weekday>()
+                    .map_err(|_| invalid(&format!("unknown day {:?}", day)))
+            };
+            let (mut day, last) = (parse_day(first)?, parse_day(last)?);
+            work_days[day.num_days_from_monday() as usize] = true;
+            while day != last {
+                day = day.succ();
+                work_days[day.num_days_from_monday() as usize] = true;
+            }
+        }
+
+        let parse_time = |time: &str| {
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|_| invalid(&format!("invalid time {:?}", time)))
+        };
+        let (start, end) = hours
+            .split_once('-')
+            .ok_or_else(|| invalid("expected hours like 09:00-17:00"))?;
+        let (start, end) = (parse_time(start)?, parse_time(end)?);
+        if start >= end {
+            return Err(invalid("work hours must end after they start"));
+        }
+
+        Ok(Self {
+            days: work_days,
+            start,
+            end,
+            timezone,
+        })
+    }
+}
+
+impl WorkSchedule {
+    /// When the work hours `now` falls in started, if it falls in any
+    fn started_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local = now.with_timezone(&self.timezone);
+        let weekday = local.date_naive().weekday();
+        let time = local.time();
+        if !self.days[weekday.num_days_from_monday() as usize]
+            || time < self.start
+            || time >= self.end
+        {
+            return None;
+        }
+
+        let started = self
+            .timezone
+            .from_local_datetime(&local.date_naive().and_time(self.start))
+            .earliest()?;
+        Some(started.with_timezone(&Utc))
+    }
+}
+
+/// How long an idle tag has gone without a timer, if it's due for a reminder
+///
+/// Time outside the work hours doesn't count, and a tag is reminded once per
+/// stretch without a running timer.
+fn idle_minutes(tag: &IdleTag, schedule: &WorkSchedule, now: DateTime<Utc>) -> Option<i64> {
+    let started = schedule.started_at(now)?.timestamp();
+    let idle_since = tag
+        .last_stopped
+        .map_or(started, |stopped| stopped.seconds().max(started));
+    let idle_minutes = (now.timestamp() - idle_since) / 60;
+
+    let reminded = tag
+        .reminded_at
+        .is_some_and(|reminded_at| reminded_at >= idle_since);
+    (idle_minutes >= tag.reminder_after && !reminded).then_some(idle_minutes)
+}
+
+/// When the day `now` falls in started in `timezone`
+fn day_started_at(timezone: Tz, now: DateTime<Utc>) -> i64 {
+    let local = now.with_timezone(&timezone);
+    // Some timezones skip midnight when switching to daylight saving time
+    timezone
+        .from_local_datetime(&local.date_naive().and_time(NaiveTime::MIN))
+        .earliest()
+        .map_or(
+            now.timestamp() - i64::from(local.num_seconds_from_midnight()),
+            |midnight| midnight.timestamp(),
+        )
+}
+
+/// What's posted to a tag's reminder webhook
+///
+/// `text` makes it show up in chat tools with incoming webhooks, like Slack.
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    text: &'a str,
+    unique_id: &'a str,
+    kind: &'static str,
+    minutes: i64,
+}
+
+/// Sends reminders and alerts by email, to webhooks and to the event stream
+#[derive(Debug)]
+struct Notifier {
+    mailer: Option<Mailer>,
+    webhooks: Webhooks,
+    events: EventBus,
+}
+
+impl Notifier {
+    #[instrument(skip(self, tag), fields(tag_id = tag.unique_id))]
+    async fn remind(&self, tag: &IdleTag, idle_minutes: i64) -> Result<()> {
+        let uid = TagId::from(tag.unique_id.clone());
+        let reminder = Reminder::new(&uid, idle_minutes);
+        let subject = reminder.subject();
+
+        if let (Some(mailer), Some(email)) = (&self.mailer, &tag.email) {
+            mailer
+                .send_text(email, &subject, reminder.render()?)
+                .await?;
+        }
+        if let Some(webhook) = &tag.reminder_webhook {
+            self.post(webhook, &subject, &tag.unique_id, "idle", idle_minutes)?;
+        }
+
+        info!(idle_minutes, "Sent reminder");
+        Ok(())
+    }
+
+    #[instrument(skip(self, timer), fields(tag_id = timer.unique_id, timer_id = timer.timer_id))]
+    async fn alert(&self, timer: &LongTimer, minutes: i64) -> Result<()> {
+        self.events.publish(TimerEvent::LongRunning {
+            unique_id: timer.unique_id.clone(),
+            timer_id: timer.timer_id,
+            minutes,
+        });
+
+        let uid = TagId::from(timer.unique_id.clone());
+        let alert = LongTimerAlert::new(&uid, &timer.project, minutes);
+        let subject = alert.subject();
+
+        if let (Some(mailer), Some(email)) = (&self.mailer, &timer.email) {
+            mailer.send_text(email, &subject, alert.render()?).await?;
+        }
+        if let Some(webhook) = &timer.reminder_webhook {
+            self.post(webhook, &subject, &timer.unique_id, "long_running", minutes)?;
+        }
+
+        info!(minutes, "Sent long running timer alert");
+        Ok(())
+    }
+
+    #[instrument(skip(self, budget), fields(tag_id = budget.unique_id, project_id = budget.project_id))]
+    async fn budget(&self, budget: &ProjectBudget, threshold: i64) -> Result<()> {
+        self.events.publish(TimerEvent::BudgetReached {
+            unique_id: budget.unique_id.clone(),
+            project_id: budget.project_id,
+            percent: threshold,
+        });
+
+        let alert = BudgetAlert::new(budget, threshold);
+        let subject = alert.subject();
+
+        if let (Some(mailer), Some(email)) = (&self.mailer, &budget.email) {
+            mailer.send_text(email, &subject, alert.render()?).await?;
+        }
+        if let Some(webhook) = &budget.reminder_webhook {
+            let minutes = budget.consumed.0.num_minutes();
+            self.post(webhook, &subject, &budget.unique_id, "budget", minutes)?;
+        }
+
+        info!(threshold, "Sent budget alert");
+        Ok(())
+    }
+
+    #[instrument(skip(self, goal), fields(tag_id = goal.unique_id))]
+    async fn goal(&self, goal: &DailyGoal, minutes: i64) -> Result<()> {
+        self.events.publish(TimerEvent::GoalReached {
+            unique_id: goal.unique_id.clone(),
+            goal: goal.minutes,
+            minutes,
+        });
+
+        if let Some(webhook) = &goal.webhook {
+            let text = format!(
+                "Reached the daily goal of {}h {}m",
+                goal.minutes / 60,
+                goal.minutes % 60
+            );
+            self.post(webhook, &text, &goal.unique_id, "goal", minutes)?;
+        }
+
+        info!(minutes, "Celebrated daily goal");
+        Ok(())
+    }
+
+    /// Posts to a webhook in the background, see [`Webhooks::deliver`]
+    fn post(
+        &self,
+        webhook: &str,
+        text: &str,
+        unique_id: &str,
+        kind: &'static str,
+        minutes: i64,
+    ) -> Result<()> {
+        let payload = WebhookPayload {
+            text,
+            unique_id,
+            kind,
+            minutes,
+        };
+        self.webhooks.deliver(
+            &TagId::from(unique_id.to_string()),
+            webhook,
+            Payload::json(kind, &payload)?,
+        );
+        Ok(())
+    }
+}
+
+/// Ensures a webhook URL can be posted to
+pub(crate) fn validate_webhook(url: &str) -> Result<()> {
+    let valid = reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+    if !valid {
+        return Err(Error::Validation(format!("Invalid webhook URL {:?}", url)).into());
+    }
+
+    Ok(())
+}
+
+/// Spawns the background task reminding idle tags and alerting about long timers
+pub(crate) fn spawn(
+    store: DataStore,
+    mailer: Option<Mailer>,
+    events: EventBus,
+    webhooks: Webhooks,
+) {
+    let notifier = Notifier {
+        mailer,
+        events,
+        webhooks,
+    };
+
+    tokio::spawn(run(store, notifier));
+}
+
+#[instrument(skip_all)]
+async fn run(store: DataStore, notifier: Notifier) {
+    let mut interval = tokio::time::interval(REMINDER_INTERVAL);
+    loop {
+        interval.tick().await;
+        if !leases::held(&store, "reminders", REMINDER_INTERVAL).await {
+            continue;
+        }
+        let now = Utc::now();
+        remind_idle_tags(&store, &notifier, now).await;
+        alert_long_timers(&store, &notifier, now).await;
+        alert_budgets(&store, &notifier, now).await;
+        celebrate_goals(&store, &notifier, now).await;
+    }
+}
+
+async fn remind_idle_tags(store: &DataStore, notifier: &Notifier, now: DateTime<Utc>) {
+    let tags = match store.idle_tags().await {
+        Ok(tags) => tags,
+        Err(err) => {
+            error!(%err, "Unable to find idle tags");
+            return;
+        }
+    };
+
+    for tag in tags {
+        let schedule = match tag.reminder_schedule.parse::<WorkSchedule>() {
+            Ok(schedule) => schedule,
+            Err(err) => {
+                error!(tag_id = tag.unique_id, %err, "Skipping invalid reminder schedule");
+                continue;
+            }
+        };
+        let Some(idle_minutes) = idle_minutes(&tag, &schedule, now) else {
+            continue;
+        };
+
+        // Marked even when sending fails, a broken webhook shouldn't be retried every minute
+        if let Err(err) = notifier.remind(&tag, idle_minutes).await {
+            error!(tag_id = tag.unique_id, %err, "Unable to send reminder");
+        }
+        let uid = TagId::from(tag.unique_id);
+        if let Err(err) = store.mark_reminded(&uid, now.timestamp()).await {
+            error!(%uid, %err, "Unable to record reminder");
+        }
+    }
+}
+
+async fn alert_long_timers(store: &DataStore, notifier: &Notifier, now: DateTime<Utc>) {
+    let timers = match store.long_running_timers(now.timestamp()).await {
+        Ok(timers) => timers,
+        Err(err) => {
+            error!(%err, "Unable to find long running timers");
+            return;
+        }
+    };
+
+    for timer in timers {
+        let minutes = timer.start_time.elapsed(now).0.num_minutes();
+        // Like reminders, a failed alert isn't retried
+        if let Err(err) = notifier.alert(&timer, minutes).await {
+            error!(timer_id = timer.timer_id, %err, "Unable to send long running timer alert");
+        }
+        if let Err(err) = store
+            .mark_long_timer_alerted(timer.timer_id, now.timestamp())
+            .await
+        {
+            error!(timer_id = timer.timer_id, %err, "Unable to record long running timer alert");
+        }
+    }
+}
+
+async fn alert_budgets(store: &DataStore, notifier: &Notifier, now: DateTime<Utc>) {
+    let budgets = match store.budgets_to_alert(now.timestamp()).await {
+        Ok(budgets) => budgets,
+        Err(err) => {
+            error!(%err, "Unable to find budgets to alert");
+            return;
+        }
+    };
+
+    for budget in budgets {
+        let Some(threshold) = budget.due_threshold() else {
+            continue;
+        };
+        // Passing 100% right away only sends that alert, not the one for 80% as well
+        if let Err(err) = notifier.budget(&budget, threshold).await {
+            error!(project_id = budget.project_id, %err, "Unable to send budget alert");
+        }
+        if let Err(err) = store
+            .mark_budget_alerted(budget.project_id, threshold)
+            .await
+        {
+            error!(project_id = budget.project_id, %err, "Unable to record budget alert");
+        }
+    }
+}
+
+async fn celebrate_goals(store: &DataStore, notifier: &Notifier, now: DateTime<Utc>) {
+    let goals = match store.daily_goals().await {
+        Ok(goals) => goals,
+        Err(err) => {
+            error!(%err, "Unable to find daily goals");
+            return;
+        }
+    };
+
+    for goal in goals {
+        let timezone = match goal.timezone.parse::<Tz>() {
+            Ok(timezone) => timezone,
+            Err(err) => {
+                error!(tag_id = goal.unique_id, %err, "Skipping goal with an invalid timezone");
+                continue;
+            }
+        };
+        let day_started = day_started_at(timezone, now);
+        if goal
+            .reached_at
+            .is_some_and(|reached_at| reached_at >= day_started)
+        {
+            continue;
+        }
+
+        let uid = TagId::from(goal.unique_id.clone());
+        let tracked = match store.tracked_since(&uid, day_started, now).await {
+            Ok(tracked) => tracked,
+            Err(err) => {
+                error!(%uid, %err, "Unable to sum up today's time");
+                continue;
+            }
+        };
+        if tracked < TrackedDuration::from_seconds(goal.minutes * 60) {
+            continue;
+        }
+
+        // Like the alerts, a failed celebration isn't retried
+        if let Err(err) = notifier.goal(&goal, tracked.0.num_minutes()).await {
+            error!(%uid, %err, "Unable to celebrate daily goal");
+        }
+        if let Err(err) = store.mark_goal_reached(&uid, now.timestamp()).await {
+            error!(%uid, %err, "Unable to record daily goal");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::time::StartTime;
+    use pretty_assertions::assert_eq;
+
+    fn berlin(d: u32, h: u32, m: u32) -> DateTime<Utc> {
+        // 2026-10-12 is a Monday
+        chrono_tz::Europe::Berlin
+            .with_ymd_and_hms(2026, 10, d, h, m, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn idle_tag(last_stopped: Option<i64>, reminded_at: Option<i64>) -> IdleTag {
+        let last_stopped = last_stopped.map(StartTime::from_seconds);
+        IdleTag {
+            unique_id: "test-tag".to_string(),
+            email: Some("me@example.com".to_string()),
+            reminder_webhook: None,
+            reminder_schedule: "Mon-Fri 09:00-17:00 Europe/Berlin".to_string(),
+            reminder_after: 15,
+            last_stopped,
+            reminded_at,
+        }
+    }
+
+    #[test]
+    fn schedules_are_parsed() {
+        let schedule: WorkSchedule = "Mon-Wed,Fri 09:00-17:30 Europe/Berlin".parse().unwrap();
+        assert_eq!(schedule.days, [true, true, true, false, true, false, false]);
+        assert_eq!(schedule.start, NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        assert_eq!(schedule.end, NaiveTime::from_hms_opt(17, 30, 0).unwrap());
+        assert_eq!(schedule.timezone, chrono_tz::Europe::Berlin);
+
+        let wrapping: WorkSchedule = "Sat-Mon 10:00-12:00".parse().unwrap();
+        assert_eq!(
+            wrapping.days,
+            [true, false, false, false, false, true, true]
+        );
+        assert_eq!(wrapping.timezone, chrono_tz::US::Pacific);
+
+        for invalid in [
+            "",
+            "Mon-Fri",
+            "Someday 09:00-17:00",
+            "Mon-Fri 17:00-09:00",
+            "Mon-Fri 9-5",
+            "Mon-Fri 09:00-17:00 Mars/Olympus",
+        ] {
+            assert!(invalid.parse::<WorkSchedule>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn days_start_at_local_midnight() {
+        let midnight = berlin(13, 0, 0).timestamp();
+        assert_eq!(
+            day_started_at(chrono_tz::Europe::Berlin, berlin(13, 0, 0)),
+            midnight
+        );
+        assert_eq!(
+            day_started_at(chrono_tz::Europe::Berlin, berlin(13, 23, 59)),
+            midnight
+        );
+        // Still the evening before in California
+        assert_eq!(
+            day_started_at(chrono_tz::US::Pacific, berlin(13, 8, 0)),
+            chrono_tz::US::Pacific
+                .with_ymd_and_hms(2026, 10, 12, 0, 0, 0)
+                .unwrap()
+                .timestamp()
+        );
+    }
+
+    #[test]
+    fn reminders_are_sent_once_per_idle_stretch() {
+        let schedule: WorkSchedule = "Mon-Fri 09:00-17:00 Europe/Berlin".parse().unwrap();
+
+        // Idle time before work hours doesn't count
+        assert_eq!(
+            idle_minutes(&idle_tag(None, None), &schedule, berlin(12, 9, 10)),
+            None
+        );
+        assert_eq!(
+            idle_minutes(&idle_tag(None, None), &schedule, berlin(12, 9, 20)),
+            Some(20)
+        );
+
+        // Nor does time outside the work days and hours
+        assert_eq!(
+            idle_minutes(&idle_tag(None, None), &schedule, berlin(17, 10, 0)),
+            None
+        );
+        assert_eq!(
+            idle_minutes(&idle_tag(None, None), &schedule, berlin(12, 17, 30)),
+            None
+        );
+
+        let stopped = berlin(12, 11, 0).timestamp();
+        let tag = idle_tag(Some(stopped), None);
+        assert_eq!(idle_minutes(&tag, &schedule, berlin(12, 11, 14)), None);
+        assert_eq!(idle_minutes(&tag, &schedule, berlin(12, 11, 15)), Some(15));
+
+        // Reminded during this stretch already, but not during the next one
+        let tag = idle_tag(Some(stopped), Some(berlin(12, 11, 15).timestamp()));
+        assert_eq!(idle_minutes(&tag, &schedule, berlin(12, 12, 0)), None);
+        assert_eq!(idle_minutes(&tag, &schedule, berlin(13, 9, 30)), Some(30));
+    }
+}
@@ -0,0 +1,123 @@
+//! Reports summarizing the time tracked by a tag
+
+use chrono::{Datelike, NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use serde::Serialize;
+
+use crate::{error::Error, timer_store::TimeBucket};
+
+/// The time tracked on every day of a year, for an activity graph
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Heatmap {
+    pub year: i32,
+    pub timezone: Tz,
+    pub days: Vec<HeatmapDay>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct HeatmapDay {
+    pub date: NaiveDate,
+
+    /// The total duration of the timers started on this day in seconds
+    pub seconds: i64,
+}
+
+impl Heatmap {
+    /// The range of timestamps covering `year` in `timezone`, to fetch buckets for
+    pub fn range(year: i32, timezone: Tz) -> anyhow::Result<(i64, i64)> {
+        if !(1970..=9999).contains(&year) {
+            return Err(Error::Validation(format!("The year {} is out of range", year)).into());
+        }
+
+        let start_of = |year| {
+            timezone
+                .with_ymd_and_hms(year, 1, 1, 0, 0, 0)
+                .earliest()
+                .map(|start| start.timestamp())
+                .ok_or_else(|| {
+                    anyhow::anyhow!("No midnight on January 1st {} in {}", year, timezone)
+                })
+        };
+
+        Ok((start_of(year)?, start_of(year + 1)?))
+    }
+
+    /// Folds the buckets into the local days of `year`, including days without any time
+    pub fn new(year: i32, timezone: Tz, buckets: &[TimeBucket]) -> Self {
+        let mut days: Vec<_> = NaiveDate::from_ymd_opt(year, 1, 1)
+            .into_iter()
+            .flat_map(|first| first.iter_days())
+            .take_while(|date| date.year() == year)
+            .map(|date| HeatmapDay { date, seconds: 0 })
+            .collect();
+
+        for bucket in buckets {
+            let Some(start) = timezone.timestamp_opt(bucket.start, 0).single() else {
+                continue;
+            };
+            if start.year() != year {
+                continue;
+            }
+            if let Some(day) = days.get_mut(start.ordinal0() as usize) {
+                day.seconds += bucket.tracked.seconds();
+            }
+        }
+
+        Self {
+            year,
+            timezone,
+            days,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::time::TrackedDuration;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn buckets_are_folded_into_local_days() {
+        let timezone = chrono_tz::US::Pacific;
+        let (start, end) = Heatmap::range(2024, timezone).unwrap();
+        // 2024-01-01T00:00 in US/Pacific is 08:00 UTC
+        assert_eq!(start, 1_704_096_000);
+
+        let buckets = [
+            // The first minutes of the year
+            TimeBucket {
+                start,
+                tracked: TrackedDuration::from_seconds(60),
+            },
+            TimeBucket {
+                start: start + 900,
+                tracked: TrackedDuration::from_seconds(30),
+            },
+            // 2024-03-10 23:45, right before midnight on the day DST starts
+            TimeBucket {
+                start: 1_710_139_500,
+                tracked: TrackedDuration::from_seconds(600),
+            },
+            // The last minutes of the year
+            TimeBucket {
+                start: end - 900,
+                tracked: TrackedDuration::from_seconds(120),
+            },
+        ];
+        let heatmap = Heatmap::new(2024, timezone, &buckets);
+
+        assert_eq!(heatmap.days.len(), 366);
+        assert_eq!(
+            heatmap.days[0],
+            HeatmapDay {
+                date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                seconds: 90,
+            }
+        );
+        let dst = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        assert_eq!(heatmap.days[dst.ordinal0() as usize].seconds, 600);
+        assert_eq!(heatmap.days[365].seconds, 120);
+        assert_eq!(heatmap.days.iter().map(|day| day.seconds).sum::<i64>(), 810);
+    }
+}
@@ -0,0 +1,45 @@
+//! Request IDs for correlating responses with the logs
+//!
+//! Every request gets an `x-request-id`, either the one the client sent or a
+//! new UUID. It's echoed back in the response headers, recorded on the request
+//! span and included in error responses.
+
+use axum::{http::Request, middleware::Next, response::Response};
+use tower_http::request_id::RequestId;
+use tracing::{info_span, Span};
+
+tokio::task_local! {
+    static CURRENT: String;
+}
+
+/// The ID of the request currently being handled
+pub(crate) fn current() -> Option<String> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+fn request_id<B>(request: &Request<B>) -> Option<&str> {
+    request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|id| id.header_value().to_str().ok())
+}
+
+/// Creates the span every request is handled in
+pub(crate) fn span<B>(request: &Request<B>) -> Span {
+    info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id = request_id(request).unwrap_or_default(),
+        client_ip = tracing::field::Empty,
+    )
+}
+
+/// Middleware making the request ID available through [`current`]
+pub(crate) async fn scope<B>(request: Request<B>, next: Next<B>) -> Response {
+    let Some(id) = request_id(&request).map(str::to_string) else {
+        return next.run(request).await;
+    };
+
+    CURRENT.scope(id, next.run(request)).await
+}
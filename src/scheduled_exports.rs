@@ -0,0 +1,252 @@
+//! Delivering the CSV export of a project at the end of every week or month,
+//! so invoicing doesn't depend on someone remembering to download it
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use askama::Template;
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use tracing::{error, info, instrument};
+
+use crate::{
+    email::Mailer,
+    leases,
+    plugins::Plugins,
+    templates::{from_render_timezone, ExportDelivery},
+    timer_store::{DataStore, ExportSchedule, ScheduledExport},
+    timer_utils::export_period,
+    uid::TagId,
+    webhooks::{Payload, Webhooks},
+};
+
+/// How often the scheduler checks for exports which are due
+const EXPORT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The latest period of `schedule` which ended by `now`, from midnight to midnight
+fn latest_period(
+    schedule: ExportSchedule,
+    now: DateTime<Tz>,
+) -> Option<(DateTime<Tz>, DateTime<Tz>)> {
+    let today = now.date_naive();
+    let (start, end) = match schedule {
+        ExportSchedule::Weekly => {
+            let monday =
+                today - chrono::Duration::days(today.weekday().num_days_from_monday().into());
+            (monday - chrono::Duration::weeks(1), monday)
+        }
+        ExportSchedule::Monthly => {
+            let first = today.with_day(1)?;
+            (first.checked_sub_months(Months::new(1))?, first)
+        }
+    };
+
+    let midnight = |date: NaiveDate| {
+        now.timezone()
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()
+    };
+    Some((midnight(start)?, midnight(end)?))
+}
+
+/// The period an export should be delivered for at `now`, unless it was already
+///
+/// Exports start with the first period which ends after they were scheduled.
+fn due_period(
+    export: &ScheduledExport,
+    now: DateTime<Utc>,
+) -> Option<(DateTime<Tz>, DateTime<Tz>)> {
+    let timezone = from_render_timezone(&export.timezone).ok()?;
+    let (start, end) = latest_period(export.schedule, now.with_timezone(&timezone))?;
+    let delivered = export
+        .last_sent
+        .is_some_and(|last_sent| last_sent >= start.timestamp());
+
+    (end.timestamp() > export.created_at && !delivered).then_some((start, end))
+}
+
+/// Spawns the background task delivering scheduled exports
+pub(crate) fn spawn(
+    store: DataStore,
+    mailer: Option<Mailer>,
+    webhooks: Webhooks,
+    plugins: Plugins,
+) {
+    tokio::spawn(run(store, mailer, webhooks, plugins));
+}
+
+#[instrument(skip_all)]
+async fn run(store: DataStore, mailer: Option<Mailer>, webhooks: Webhooks, plugins: Plugins) {
+    let mut interval = tokio::time::interval(EXPORT_INTERVAL);
+    loop {
+        interval.tick().await;
+        if !leases::held(&store, "scheduled_exports", EXPORT_INTERVAL).await {
+            continue;
+        }
+        let exports = match store.all_scheduled_exports().await {
+            Ok(exports) => exports,
+            Err(err) => {
+                error!(%err, "Unable to find scheduled exports");
+                continue;
+            }
+        };
+
+        let now = Utc::now();
+        for export in exports {
+            let Some((start, end)) = due_period(&export, now) else {
+                continue;
+            };
+
+            let delivery = Delivery {
+                store: &store,
+                mailer: mailer.as_ref(),
+                webhooks: &webhooks,
+                plugins: &plugins,
+            };
+            match delivery.deliver(&export, start, end).await {
+                Ok(()) => {
+                    info!(export_id = export.id, %start, "Delivered scheduled export");
+                    if let Err(err) = store.mark_export_sent(export.id, start.timestamp()).await {
+                        error!(export_id = export.id, %err, "Unable to record scheduled export");
+                    }
+                }
+                // Left unmarked so the next run retries. Failed webhooks are
+                // retried by their own queue instead, and count as delivered.
+                Err(err) => {
+                    error!(export_id = export.id, %err, "Unable to deliver scheduled export")
+                }
+            }
+        }
+    }
+}
+
+struct Delivery<'a> {
+    store: &'a DataStore,
+    mailer: Option<&'a Mailer>,
+    webhooks: &'a Webhooks,
+    plugins: &'a Plugins,
+}
+
+impl Delivery<'_> {
+    #[instrument(skip(self, export), fields(export_id = export.id))]
+    async fn deliver(
+        &self,
+        export: &ScheduledExport,
+        start: DateTime<Tz>,
+        end: DateTime<Tz>,
+    ) -> Result<()> {
+        let project = self.store.project(export.project_id).await?;
+        let csv = export_period(
+            self.store,
+            &project,
+            &export.timezone,
+            (start.timestamp(), end.timestamp()),
+            self.plugins,
+        )
+        .await?;
+
+        let name: String = project
+            .name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect();
+        let filename = format!("{}-{}.csv", name, start.date_naive());
+
+        if let Some(email) = &export.email {
+            let mailer = self
+                .mailer
+                .ok_or_else(|| anyhow!("Email is disabled, unable to send to {}", email))?;
+            let delivery = ExportDelivery::new(&project, start.date_naive(), end.date_naive());
+            mailer
+                .send_attachment(
+                    email,
+                    &delivery.subject(),
+                    delivery.render()?,
+                    &filename,
+                    "text/csv",
+                    csv.clone(),
+                )
+                .await?;
+        }
+
+        if let Some(webhook) = &export.webhook {
+            let payload = Payload {
+                kind: "export".to_string(),
+                content_type: "text/csv".to_string(),
+                filename: Some(filename),
+                body: csv,
+            };
+            self.webhooks
+                .deliver(&TagId::from(export.unique_id.clone()), webhook, payload);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn berlin(y: i32, m: u32, d: u32, h: u32) -> DateTime<Tz> {
+        chrono_tz::Europe::Berlin
+            .with_ymd_and_hms(y, m, d, h, 0, 0)
+            .unwrap()
+    }
+
+    fn export(
+        schedule: ExportSchedule,
+        created_at: i64,
+        last_sent: Option<i64>,
+    ) -> ScheduledExport {
+        ScheduledExport {
+            id: 1,
+            project_id: 1,
+            unique_id: "test-tag".to_string(),
+            schedule,
+            timezone: "Europe/Berlin".to_string(),
+            email: Some("billing@example.com".to_string()),
+            webhook: None,
+            created_at,
+            last_sent,
+        }
+    }
+
+    #[test]
+    fn periods_end_at_midnight() {
+        // 2026-10-15 is a Thursday
+        let now = berlin(2026, 10, 15, 12);
+        assert_eq!(
+            latest_period(ExportSchedule::Weekly, now),
+            Some((berlin(2026, 10, 5, 0), berlin(2026, 10, 12, 0)))
+        );
+        assert_eq!(
+            latest_period(ExportSchedule::Monthly, now),
+            Some((berlin(2026, 9, 1, 0), berlin(2026, 10, 1, 0)))
+        );
+        assert_eq!(
+            latest_period(ExportSchedule::Monthly, berlin(2026, 1, 1, 0)),
+            Some((berlin(2025, 12, 1, 0), berlin(2026, 1, 1, 0)))
+        );
+    }
+
+    #[test]
+    fn exports_are_due_once_per_period() {
+        let now = berlin(2026, 10, 15, 12).with_timezone(&Utc);
+        let september = berlin(2026, 9, 1, 0);
+
+        let due = due_period(&export(ExportSchedule::Monthly, 0, None), now);
+        assert_eq!(due.map(|(start, _)| start), Some(september));
+
+        let sent = export(ExportSchedule::Monthly, 0, Some(september.timestamp()));
+        assert_eq!(due_period(&sent, now), None);
+
+        // Scheduled after the period ended, so the first delivery is for October
+        let created_at = berlin(2026, 10, 2, 0).timestamp();
+        assert_eq!(
+            due_period(&export(ExportSchedule::Monthly, created_at, None), now),
+            None
+        );
+    }
+}
@@ -0,0 +1,5309 @@
+//! The web server, its routes and their handlers
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::{
+    admin::AdminToken,
+    backup::{self, Backups},
+    base_path, cache, cors,
+    date_format::{Clock, DateOrder},
+    digest,
+    duration::{DurationFormat, DurationRounding},
+    email,
+    error::{AppError, Error, PageError},
+    events::{EventBus, TimerEvent},
+    extract::{
+        owner_cookie_name, AcceptLanguage, Admin, ApiTag, OwnerTag, PageOwnerTag, PagePath,
+        PageQuery, SharedTag, WantsJson,
+    },
+    forwarded::{self, Forwarding, TrustedProxies},
+    github::{self, GitHubWebhooks},
+    google_calendar::{self, GoogleCalendar},
+    i18n, ics, janitor, jira, midnight,
+    mqtt::{self, MqttConfig},
+    ndef,
+    plugins::Plugins,
+    pwa, read_only, reminders, report, request_id, scheduled_exports, templates,
+    time::StartTime,
+    timer_store::{
+        AccountDeletion, ActivityEntry, DataStore, DatabaseConfig, ExportSchedule, FailedWebhook,
+        LabelHours, NewToken, Project, ProjectHours, ProjectQuery, ProjectStats, ProjectTemplate,
+        Role, ScheduledExport, SearchResult, ShortTimerPolicy, Streaks, SyncEvent, SyncedToggle,
+        TagActivity, TagSettings, Team, TeamReport, Theme, Timer, TimerCursor, TimerOperation,
+        TimerPage, TimerStore, Timesheet, TimesheetStatus, ToggleOptions, Token, TokenKind,
+    },
+    timer_utils::{
+        self, export_account, export_project, retain_labelled, ExportFilter, LabelMatch,
+    },
+    tls::TlsConfig,
+    toggle_link::ToggleLinks,
+    uid::{self, TagId},
+    uri_base,
+    webhooks::{self, Webhooks},
+};
+use anyhow::Result;
+use askama::Template;
+use axum::{
+    debug_handler,
+    extract::{FromRef, Path, Query, State},
+    http::{self, header, HeaderMap, StatusCode, Uri},
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        AppendHeaders, IntoResponse, Redirect, Response,
+    },
+    routing::{delete, get, post},
+    Form, Json, Router,
+};
+use chrono::{Datelike, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use tower::ServiceBuilder;
+use tower_http::{
+    compression::{
+        predicate::{DefaultPredicate, NotForContentType, Predicate},
+        CompressionLayer,
+    },
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    services::ServeDir,
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::{debug, info, instrument, warn};
+
+/// Runs the web server
+pub async fn serve() -> Result<()> {
+    let plugins = Plugins::from_env()?;
+    let backups = Backups::from_env()?;
+    let mqtt = MqttConfig::from_env()?;
+    let toggle_links = ToggleLinks::from_env()?;
+    let google = GoogleCalendar::from_env()?;
+    let github = GitHubWebhooks::from_env();
+    let admin = AdminToken::from_env()?;
+    let tls = match TlsConfig::from_env()? {
+        Some(tls) => Some(tls.load().await?),
+        None => None,
+    };
+    let config = DatabaseConfig::from_env()?;
+    let timer_store = DataStore::new(&config, plugins.clone(), backups.as_ref()).await?;
+    cache::hash_assets();
+    let events = EventBus::new();
+    let webhooks = Webhooks::new(timer_store.clone());
+    let read_only = read_only::enabled();
+    if read_only {
+        // Every background task writes to the database
+        warn!("Read-only mode, changes and background tasks are disabled");
+    } else {
+        janitor::spawn(timer_store.clone());
+        webhooks::spawn(webhooks.clone());
+        backup::spawn(timer_store.clone(), backups.clone());
+        let mailer = email::Mailer::from_env()?;
+        digest::spawn(timer_store.clone(), mailer.clone());
+        scheduled_exports::spawn(
+            timer_store.clone(),
+            mailer.clone(),
+            webhooks.clone(),
+            plugins.clone(),
+        );
+        reminders::spawn(
+            timer_store.clone(),
+            mailer,
+            events.clone(),
+            webhooks.clone(),
+        );
+        midnight::spawn(timer_store.clone(), events.clone());
+        google_calendar::spawn(timer_store.clone(), google.clone());
+        jira::spawn(timer_store.clone());
+        mqtt::spawn(timer_store.clone(), mqtt, events.clone());
+    }
+    let state = App {
+        store: Arc::new(timer_store.clone()),
+        timer_store,
+        events,
+        plugins,
+        backups,
+        toggle_links,
+        google,
+        github,
+        admin,
+        webhooks,
+        read_only,
+    };
+    let app = build_router(state);
+
+    // run our app with hyper, listening globally on port 3000
+    let listener = SocketAddr::from_str("0.0.0.0:3000")?;
+    match tls {
+        Some(tls) => {
+            tracing::info!("listening on {} with TLS", listener);
+            axum_server::bind_rustls(listener, tls)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await?;
+        }
+        None => {
+            tracing::info!("listening on {}", listener);
+            axum::Server::bind(&listener)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+                .unwrap();
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds the routes of the web server along with its middleware
+///
+/// Everything is nested under the [`base_path`], if one is configured.
+pub(crate) fn build_router(state: App) -> Router {
+    let routes = Router::new()
+        // `GET /` goes to `root`
+        .route("/timer/:timer_tag", get(timers))
+        .route("/timer/:timer_tag/:timezone", get(timers_with_tz))
+        .route("/timer/:timer_tag/events", get(tag_events))
+        .route("/timer/:timer_tag/week/:date", get(week_page))
+        .route("/timer/:timer_tag/activity", get(activity_page))
+        .route(
+            "/timer/:timer_tag/fragment/projects",
+            get(projects_fragment),
+        )
+        .route(
+            "/timer/:timer_tag/:timezone/fragment/projects",
+            get(projects_fragment_with_tz),
+        )
+        .route("/timer/toggle", post(toggle_timer))
+        .route("/timer/sync", post(sync_toggles))
+        .route("/api/v1/sync", post(sync_batch))
+        .route("/timer/:timer_tag/manifest.webmanifest", get(manifest))
+        .route("/timer/:timer_tag/stop_at/:timer_id", post(stop_timer_at))
+        .route(
+            "/timer/:timer_tag/merge/:timer_id/:other_id",
+            post(merge_timers),
+        )
+        .route("/timer/:timer_tag/split/:timer_id", post(split_timer))
+        .route("/timers/:timer_tag/reassign", post(reassign_timers))
+        .route("/tag/register", post(register_tag))
+        .route("/tag/:timer_tag/login", get(login_page).post(login))
+        .route("/export/all", get(export_all))
+        .route("/export/:project_id/:timezone", get(export))
+        .route("/timer/:timer_tag/toggle-link", post(toggle_link))
+        .route("/t/:token/toggle", get(toggle_from_link))
+        .route("/integrations/github/:timer_tag", post(github_webhook))
+        .route("/integrations/jira/:timer_tag", post(connect_jira))
+        .route(
+            "/integrations/jira/:timer_tag/disconnect",
+            post(disconnect_jira),
+        )
+        .route(
+            "/integrations/jira/:timer_tag/issue/:project_id",
+            post(set_jira_issue),
+        )
+        .route(
+            "/integrations/google/:timer_tag/connect",
+            get(connect_google),
+        )
+        .route(
+            "/integrations/google/callback",
+            get(google_callback).layer(middleware::from_fn_with_state(
+                state.read_only,
+                read_only::refuse,
+            )),
+        )
+        .route(
+            "/integrations/google/:timer_tag/disconnect",
+            post(disconnect_google),
+        )
+        .route(
+            "/integrations/google/:timer_tag/calendar/:project_id",
+            post(set_google_calendar),
+        )
+        .route("/timer/:timer_tag/ndef", get(ndef_record))
+        .route("/timer/:timer_tag/ndef/explain", get(explain_ndef_record))
+        .route("/timer/:timer_tag/toggle", post(toggle_from_page))
+        .route("/timer/:timer_tag/preset", post(create_preset))
+        .route("/timer/:timer_tag/preset/:preset_id", post(apply_preset))
+        .route("/timer/:timer_tag/label/:timer_id", post(add_label))
+        .route(
+            "/timer/:timer_tag/label/:timer_id/remove",
+            post(remove_label),
+        )
+        .route("/timer/:timer_tag/link/:timer_id", post(set_timer_link))
+        .route("/project/:tag/:project_id", get(project_page))
+        .route("/project/:tag/create", post(create_project))
+        .route("/project/:tag/search", get(search_projects))
+        .route(
+            "/project/:tag/templates",
+            get(project_templates).post(create_project_template),
+        )
+        .route(
+            "/project/:tag/templates/:template_id/remove",
+            post(remove_project_template),
+        )
+        .route("/project/:tag/archive/:project_id", post(archive_project))
+        .route("/project/:tag/budget/:project_id", post(set_project_budget))
+        .route("/project/:tag/clone/:project_id", post(clone_project))
+        .route("/project/:tag/invoiced/:project_id", post(set_invoiced))
+        .route("/project/:tag/parent/:project_id", post(set_project_parent))
+        .route("/project/:tag/exports", get(scheduled_exports))
+        .route(
+            "/project/:tag/exports/:project_id",
+            post(create_scheduled_export),
+        )
+        .route(
+            "/project/:tag/exports/:export_id/remove",
+            post(remove_scheduled_export),
+        )
+        .route(
+            "/project/:tag/import/:project_id/:timezone",
+            post(import_calendar),
+        )
+        .route("/search/:timer_tag", get(search))
+        .route("/report/:timer_tag/heatmap", get(heatmap))
+        .route("/report/:timer_tag/clients", get(client_hours))
+        .route("/report/:timer_tag/labels", get(label_hours))
+        .route("/report/:timer_tag/heatmap/view", get(heatmap_page))
+        .route("/settings/:timer_tag", get(settings).post(update_settings))
+        .route("/settings/:timer_tag/theme", post(set_theme))
+        .route(
+            "/settings/:timer_tag/webhook-secret",
+            post(rotate_webhook_secret),
+        )
+        .route(
+            "/settings/:timer_tag/webhook-secret/remove",
+            post(remove_webhook_secret),
+        )
+        .route(
+            "/share/:timer_tag",
+            get(share_links).post(create_share_link),
+        )
+        .route(
+            "/share/:timer_tag/:token_id/revoke",
+            post(revoke_share_link),
+        )
+        .route(
+            "/api-tokens/:timer_tag",
+            get(api_tokens).post(create_api_token),
+        )
+        .route(
+            "/api-tokens/:timer_tag/:token_id/revoke",
+            post(revoke_api_token),
+        )
+        .route("/account/:timer_tag", delete(delete_account))
+        .route(
+            "/account/:timer_tag/deletion",
+            post(request_account_deletion),
+        )
+        .route("/api/v1/homeassistant/sensor", get(home_assistant_sensor))
+        .route("/api/v1/homeassistant/toggle", post(home_assistant_toggle))
+        .route("/api/v1/project/:project_id/stats", get(project_stats))
+        .route("/api/v1/project/:project_id/timers", get(project_timers))
+        .route("/api/v1/streaks", get(streaks))
+        .route("/api/v1/activity", get(activity))
+        .route("/api/v1/projects", post(api_create_project))
+        .route("/api/v1/timers/bulk", post(bulk_timers))
+        .route("/api/v1/timers/:timer_id/link", post(api_set_timer_link))
+        .route("/shared/:token", get(shared_timers))
+        .route("/shared/:token/:timezone", get(shared_timers_with_tz))
+        .route(
+            "/shared/:token/export/:timezone/:project_id",
+            get(shared_export),
+        )
+        .route("/team/:timer_tag", post(create_team))
+        .route("/team/:team_id/:timer_tag", get(team_report))
+        .route("/team/:team_id/:timer_tag/members", post(set_team_member))
+        .route(
+            "/team/:team_id/:timer_tag/members/:member_id/remove",
+            post(remove_team_member),
+        )
+        .route(
+            "/team/:team_id/:timer_tag/projects",
+            post(create_team_project),
+        )
+        .route(
+            "/team/:team_id/:timer_tag/timesheets",
+            get(timesheets).post(submit_timesheet),
+        )
+        .route(
+            "/team/:team_id/:timer_tag/timesheets/:timesheet_id/review",
+            post(review_timesheet),
+        )
+        .route("/links/:timer_tag", get(links).post(create_link))
+        .route("/links/:timer_tag/:slug/remove", post(remove_link))
+        .route("/s/:slug", get(follow_link))
+        .route("/devices/:timer_tag", get(devices))
+        .route("/devices/:timer_tag/:device_id/revoke", post(revoke_device))
+        .route("/wall/:group_id", get(wall))
+        .route("/wall/:group_id/fragment/cards", get(wall_cards))
+        .route("/wall/:group_id/events", get(wall_events))
+        .route("/wall/:group_id/:tag", post(add_to_wall))
+        .route("/admin/tags/inactive/:days", get(inactive_tags))
+        .route("/admin/backup", post(backup_now))
+        .route("/admin/webhooks/failed", get(failed_webhooks))
+        .route(
+            "/admin/webhooks/failed/redeliver",
+            post(redeliver_failed_webhooks),
+        )
+        .route(
+            "/admin/webhooks/failed/:id/redeliver",
+            post(redeliver_failed_webhook),
+        )
+        .route("/sw.js", get(pwa::service_worker))
+        .route("/pwa/icon.svg", get(pwa::icon))
+        .nest_service(
+            "/assets",
+            ServiceBuilder::new()
+                .layer(middleware::from_fn(cache::asset_headers))
+                .service(ServeDir::new(cache::ASSET_DIR)),
+        );
+    let routes = match base_path() {
+        base if base.is_empty() => routes,
+        base => Router::new().nest(&base, routes),
+    };
+
+    let read_only = state.read_only;
+    routes.fallback(not_found).with_state(state).layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(request_id::span)
+                    .on_response(DefaultOnResponse::new().level(tracing::Level::INFO)),
+            )
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(cors::layer())
+            // Pages get large with long histories, but server-sent events
+            // must not be buffered by the encoder
+            .layer(CompressionLayer::new().compress_when(
+                DefaultPredicate::new().and(NotForContentType::const_new("text/event-stream")),
+            ))
+            .layer(middleware::from_fn(request_id::scope))
+            .layer(middleware::from_fn_with_state(
+                Forwarding::new(
+                    TrustedProxies::from_env(),
+                    matches!(TlsConfig::from_env(), Ok(Some(_))),
+                ),
+                forwarded::scope,
+            ))
+            .layer(middleware::from_fn_with_state(read_only, read_only::guard)),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct App {
+    pub(crate) timer_store: DataStore,
+
+    /// The same store as `timer_store`, for handlers which only track time
+    ///
+    /// Those take it as `State<Arc<dyn TimerStore>>`, so tests can call them
+    /// with a [`MemoryStore`][crate::memory_store::MemoryStore] instead.
+    pub(crate) store: Arc<dyn TimerStore>,
+    pub(crate) events: EventBus,
+    pub(crate) plugins: Plugins,
+    pub(crate) backups: Option<Backups>,
+    pub(crate) toggle_links: Option<ToggleLinks>,
+
+    /// Pushes finished timers to Google Calendar, when configured
+    pub(crate) google: Option<GoogleCalendar>,
+
+    /// Checks webhooks linking the running timer to GitHub branches and issues
+    pub(crate) github: GitHubWebhooks,
+
+    /// Guards the `/admin` routes
+    pub(crate) admin: AdminToken,
+
+    /// Posts to webhooks, and redelivers the ones which kept failing
+    pub(crate) webhooks: Webhooks,
+
+    /// Refuses changes, see [`read_only`]
+    pub(crate) read_only: bool,
+}
+
+impl FromRef<App> for Arc<dyn TimerStore> {
+    fn from_ref(app: &App) -> Self {
+        app.store.clone()
+    }
+}
+
+impl FromRef<App> for EventBus {
+    fn from_ref(app: &App) -> Self {
+        app.events.clone()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectForm {
+    name: String,
+
+    /// The [`ProjectTemplate`] to start from, none when left out or empty
+    template_id: Option<String>,
+}
+
+impl ProjectForm {
+    fn template_id(&self) -> Result<Option<i64>> {
+        self.template_id
+            .as_deref()
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                id.parse()
+                    .map_err(|_| Error::Validation(format!("Invalid project template {:?}", id)))
+            })
+            .transpose()
+            .map_err(Into::into)
+    }
+}
+
+/// Creates a project from the form of the timer page
+///
+/// A rejected name renders the timer page again with the error on the form.
+#[debug_handler]
+async fn create_project(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Path(timer_tag): Path<String>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+    Form(project): Form<ProjectForm>,
+) -> Result<Response, PageError> {
+    info!(timer_tag, "Creating new project for timer_tag");
+    let created = match project.template_id() {
+        Ok(Some(template_id)) => {
+            app.timer_store
+                .create_project_from_template(&tag, &project.name, template_id)
+                .await
+        }
+        Ok(None) => app.store.create_project(&tag, &project.name).await,
+        Err(err) => Err(err),
+    };
+    let err = match created {
+        Ok(_) => {
+            return Ok(
+                Redirect::to(&format!("{}/timer/{}", base_path(), tag.as_ref())).into_response(),
+            )
+        }
+        Err(err) => err,
+    };
+
+    let message = match err.downcast_ref::<Error>() {
+        Some(Error::Validation(message) | Error::Conflict(message)) => message.clone(),
+        _ => return Err(err.into()),
+    };
+    debug!(timer_tag, message, "Rejected project name");
+    let page = render_timers(
+        app,
+        timer_tag,
+        None,
+        ViewParams::default(),
+        accept_language,
+        Some((project.name, message)),
+    )
+    .await?;
+
+    Ok((StatusCode::UNPROCESSABLE_ENTITY, page).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiProjectForm {
+    name: String,
+    template_id: Option<i64>,
+}
+
+/// Creates a project for the token's tag, from a template if one is picked
+#[instrument(skip(app, uid))]
+#[debug_handler]
+async fn api_create_project(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+    Json(form): Json<ApiProjectForm>,
+) -> Result<(StatusCode, Json<Project>), AppError> {
+    let id = match form.template_id {
+        Some(template_id) => {
+            app.timer_store
+                .create_project_from_template(&uid, &form.name, template_id)
+                .await?
+        }
+        None => app.timer_store.create_project(&uid, &form.name).await?,
+    };
+
+    Ok((
+        StatusCode::CREATED,
+        Json(app.timer_store.project(id).await?),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiLink {
+    /// `null` removes the link
+    link: Option<String>,
+}
+
+/// Links one of the token's timers to the issue, commit or branch it was spent on
+#[instrument(skip(app, uid))]
+#[debug_handler]
+async fn api_set_timer_link(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+    Path(timer_id): Path<i64>,
+    Json(form): Json<ApiLink>,
+) -> Result<Json<Timer>, AppError> {
+    let timer = app
+        .timer_store
+        .set_timer_link(&uid, timer_id, form.link.as_deref())
+        .await?;
+
+    Ok(Json(timer))
+}
+
+/// Links the running timer of a tag to what a GitHub webhook says is being
+/// worked on, answering with the timer it linked
+#[instrument(skip(app, headers, body))]
+#[debug_handler]
+async fn github_webhook(
+    State(app): State<App>,
+    Path(timer_tag): Path<String>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<Json<Option<Timer>>, AppError> {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    if !app.github.is_configured() {
+        return Err(Error::Forbidden("GitHub webhooks aren't configured".to_string()).into());
+    }
+    if !app.github.verify(&body, header("x-hub-signature-256")) {
+        return Err(Error::Forbidden("The webhook signature doesn't match".to_string()).into());
+    }
+    let event = header("x-github-event").unwrap_or_default();
+    let link = github::link_of(event, &body)
+        .map_err(|err| Error::Validation(format!("Unexpected {} webhook: {}", event, err)))?;
+    let Some(link) = link else {
+        return Ok(Json(None));
+    };
+
+    let tag = app.timer_store.resolve_tag(&timer_tag).await?;
+    let timer = app.timer_store.link_running_timer(&tag, &link).await?;
+
+    Ok(Json(timer))
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkTimers {
+    operations: Vec<TimerOperation>,
+}
+
+#[derive(Debug, Serialize)]
+struct BulkTimersResult {
+    /// Whether the changes were kept, which only happens if all succeeded
+    applied: bool,
+
+    /// The outcome of every operation, in the order they were sent
+    results: Vec<BulkTimerResult>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BulkTimerStatus {
+    Created,
+    Updated,
+    Deleted,
+
+    /// The operation would have succeeded but another one failed
+    RolledBack,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+struct BulkTimerResult {
+    status: BulkTimerStatus,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timer: Option<Timer>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<BulkTimerError>,
+}
+
+#[derive(Debug, Serialize)]
+struct BulkTimerError {
+    error: &'static str,
+    message: String,
+}
+
+/// Creates, updates and deletes many finished timers of the token's tag at once
+///
+/// The operations are applied in a single transaction, either all of them or,
+/// if any fails, none, in which case the response is a 422 listing what went
+/// wrong with each one.
+#[instrument(skip(app, uid, bulk), fields(count = bulk.operations.len()))]
+#[debug_handler]
+async fn bulk_timers(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+    Json(bulk): Json<BulkTimers>,
+) -> Result<(StatusCode, Json<BulkTimersResult>), AppError> {
+    if bulk.operations.len() > MAX_SYNC_TOGGLES {
+        return Err(Error::Validation(format!(
+            "At most {} timers can be changed at once",
+            MAX_SYNC_TOGGLES
+        ))
+        .into());
+    }
+
+    let now = Utc::now().timestamp();
+    let (applied, outcomes) = app
+        .timer_store
+        .bulk_timers(&uid, &bulk.operations, now)
+        .await?;
+
+    let mut results = Vec::with_capacity(outcomes.len());
+    for (operation, outcome) in bulk.operations.iter().zip(outcomes) {
+        let result = match outcome {
+            Ok(timer) => BulkTimerResult {
+                status: match operation {
+                    _ if !applied => BulkTimerStatus::RolledBack,
+                    TimerOperation::Create { .. } => BulkTimerStatus::Created,
+                    TimerOperation::Update { .. } => BulkTimerStatus::Updated,
+                    TimerOperation::Delete { .. } => BulkTimerStatus::Deleted,
+                },
+                timer: timer.filter(|_| applied),
+                error: None,
+            },
+            Err(err) => {
+                let err = AppError::from(err);
+                // Only the client's mistakes are reported per operation
+                if let AppError::Internal(_) = err {
+                    return Err(err);
+                }
+                BulkTimerResult {
+                    status: BulkTimerStatus::Failed,
+                    timer: None,
+                    error: Some(BulkTimerError {
+                        error: err.kind(),
+                        message: err.message(),
+                    }),
+                }
+            }
+        };
+        results.push(result);
+    }
+
+    let status = if applied {
+        app.events.publish(TimerEvent::Toggled {
+            unique_id: uid.as_ref().to_string(),
+        });
+        StatusCode::OK
+    } else {
+        StatusCode::UNPROCESSABLE_ENTITY
+    };
+    Ok((status, Json(BulkTimersResult { applied, results })))
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectTemplateForm {
+    name: String,
+
+    /// The budget of new projects in hours, no budget when left out
+    budget_hours: Option<f64>,
+
+    /// The project new projects are nested under, like their client
+    parent_id: Option<i64>,
+}
+
+/// Lists the project templates of a tag
+#[debug_handler]
+async fn project_templates(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+) -> Result<Json<Vec<ProjectTemplate>>, AppError> {
+    Ok(Json(app.timer_store.project_templates(&tag).await?))
+}
+
+/// Saves a template new projects of a tag can start from
+#[debug_handler]
+async fn create_project_template(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Json(form): Json<ProjectTemplateForm>,
+) -> Result<(StatusCode, Json<ProjectTemplate>), AppError> {
+    let budget = form
+        .budget_hours
+        .map(|hours| (hours * 3600.0).round() as i64);
+    let template = app
+        .timer_store
+        .create_project_template(&tag, &form.name, budget, form.parent_id)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+/// Removes a project template, the projects created from it stay as they are
+#[debug_handler]
+async fn remove_project_template(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, template_id)): Path<(String, i64)>,
+) -> Result<StatusCode, AppError> {
+    app.timer_store
+        .remove_project_template(&tag, template_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The largest page [`search_projects`] will return
+const MAX_PROJECTS_PER_PAGE: i64 = 200;
+
+#[derive(Debug, Deserialize)]
+struct ProjectSearchParams {
+    /// Only include projects whose name contains this
+    q: Option<String>,
+
+    /// Include archived projects instead of active ones
+    #[serde(default)]
+    archived: bool,
+
+    /// The 1-based page to return
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectSearchResult {
+    projects: Vec<Project>,
+    page: i64,
+    per_page: i64,
+    total: i64,
+}
+
+/// Searches the projects of a tag by name, one page at a time
+#[debug_handler]
+async fn search_projects(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Query(params): Query<ProjectSearchParams>,
+) -> Result<Json<ProjectSearchResult>, AppError> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params
+        .per_page
+        .unwrap_or(50)
+        .clamp(1, MAX_PROJECTS_PER_PAGE);
+
+    let query = ProjectQuery {
+        name: params.q.filter(|q| !q.trim().is_empty()),
+        archived: Some(params.archived),
+        limit: per_page,
+        offset: (page - 1) * per_page,
+    };
+    let (projects, total) = app.timer_store.search_projects(&tag, &query).await?;
+
+    Ok(Json(ProjectSearchResult {
+        projects,
+        page,
+        per_page,
+        total,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct ArchiveForm {
+    archived: bool,
+}
+
+/// Archives or restores a project
+#[debug_handler]
+async fn archive_project(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Path((_, project_id)): Path<(String, i64)>,
+    Form(form): Form<ArchiveForm>,
+) -> Result<Redirect, PageError> {
+    info!(%tag, project_id, form.archived, "Archiving project");
+    app.timer_store
+        .set_project_archived(&tag, project_id, form.archived)
+        .await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+/// Starts a new project like an existing one, without its timers
+#[debug_handler]
+async fn clone_project(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Path((_, project_id)): Path<(String, i64)>,
+) -> Result<Redirect, PageError> {
+    app.timer_store.clone_project(&tag, project_id).await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+struct BudgetForm {
+    /// The budget in hours, no budget when left out
+    hours: Option<f64>,
+}
+
+/// Sets or removes the hour budget of a project
+#[debug_handler]
+async fn set_project_budget(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, project_id)): Path<(String, i64)>,
+    Json(form): Json<BudgetForm>,
+) -> Result<StatusCode, AppError> {
+    let budget = form.hours.map(|hours| (hours * 3600.0).round() as i64);
+    app.timer_store
+        .set_project_budget(&tag, project_id, budget)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+struct InvoicedForm {
+    /// The first day of the range
+    from: NaiveDate,
+
+    /// The last day of the range, included
+    to: NaiveDate,
+
+    /// Where days start at midnight, US/Pacific when left out
+    timezone: Option<String>,
+
+    /// Whether the timers were invoiced, defaults to `true` while `false` undoes marking them
+    invoiced: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct InvoicedResult {
+    updated: u64,
+}
+
+/// Marks the finished timers of a project in a range of days as invoiced, or not
+#[debug_handler]
+async fn set_invoiced(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, project_id)): Path<(String, i64)>,
+    Json(form): Json<InvoicedForm>,
+) -> Result<Json<InvoicedResult>, AppError> {
+    let timezone = match form.timezone.as_deref() {
+        Some(timezone) => templates::from_render_timezone(timezone)?,
+        None => chrono_tz::US::Pacific,
+    };
+    let range = (
+        midnight(timezone, form.from)?,
+        midnight(timezone, form.to + chrono::Duration::days(1))?,
+    );
+
+    let updated = app
+        .timer_store
+        .set_invoiced(&tag, project_id, range, form.invoiced.unwrap_or(true))
+        .await?;
+
+    Ok(Json(InvoicedResult { updated }))
+}
+
+#[derive(Debug, Deserialize)]
+struct ParentForm {
+    /// The project to nest under, moves the project to the top level when left out
+    parent_id: Option<i64>,
+}
+
+/// Nests a project under another one, like a task under its project or a project under its client
+#[debug_handler]
+async fn set_project_parent(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, project_id)): Path<(String, i64)>,
+    Json(form): Json<ParentForm>,
+) -> Result<Json<Project>, AppError> {
+    let project = app
+        .timer_store
+        .set_project_parent(&tag, project_id, form.parent_id)
+        .await?;
+
+    Ok(Json(project))
+}
+
+#[derive(Debug, Deserialize)]
+struct ScheduledExportForm {
+    schedule: ExportSchedule,
+
+    /// Where periods start at midnight, US/Pacific when left out
+    timezone: Option<String>,
+    email: Option<String>,
+    webhook: Option<String>,
+}
+
+/// Lists the scheduled exports of a tag's projects
+#[debug_handler]
+async fn scheduled_exports(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+) -> Result<Json<Vec<ScheduledExport>>, AppError> {
+    Ok(Json(app.timer_store.scheduled_exports(&tag).await?))
+}
+
+/// Schedules the CSV export of a project to be emailed or posted at the end of every period
+#[debug_handler]
+async fn create_scheduled_export(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, project_id)): Path<(String, i64)>,
+    Json(form): Json<ScheduledExportForm>,
+) -> Result<(StatusCode, Json<ScheduledExport>), AppError> {
+    let timezone = match form.timezone.as_deref() {
+        Some(timezone) => templates::from_render_timezone(timezone)?,
+        None => chrono_tz::US::Pacific,
+    };
+    let email = form
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|email| !email.is_empty());
+    if let Some(email) = email {
+        email::validate_address(email)?;
+    }
+    let webhook = form
+        .webhook
+        .as_deref()
+        .map(str::trim)
+        .filter(|webhook| !webhook.is_empty());
+    if let Some(webhook) = webhook {
+        reminders::validate_webhook(webhook)?;
+    }
+
+    let export = app
+        .timer_store
+        .create_scheduled_export(
+            &tag,
+            project_id,
+            form.schedule,
+            timezone.name(),
+            (email, webhook),
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(export)))
+}
+
+/// Stops delivering a scheduled export
+#[debug_handler]
+async fn remove_scheduled_export(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, export_id)): Path<(String, i64)>,
+) -> Result<StatusCode, AppError> {
+    app.timer_store
+        .remove_scheduled_export(&tag, export_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportParams {
+    /// Only import events whose summary contains this keyword
+    keyword: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportResult {
+    imported: u64,
+}
+
+/// Imports the events of an uploaded .ics calendar as timers into one of the
+/// tag's projects
+#[debug_handler]
+async fn import_calendar(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, project_id, timezone)): Path<(String, i64, String)>,
+    Query(params): Query<ImportParams>,
+    calendar: String,
+) -> Result<Json<ImportResult>, AppError> {
+    info!(%tag, project_id, ?params, "Importing calendar");
+    let project = app.timer_store.owned_project(&tag, project_id).await?;
+    let timezone = templates::from_render_timezone(&timezone)?;
+    let keyword = params.keyword.map(|keyword| keyword.to_lowercase());
+
+    let events = ics::parse_events(&calendar, timezone)
+        .map_err(|err| Error::Validation(format!("Unable to parse calendar: {}", err)))?
+        .into_iter()
+        .filter(|event| match &keyword {
+            Some(keyword) => event.summary.to_lowercase().contains(keyword),
+            None => true,
+        })
+        .map(|event| (StartTime(event.start), event.duration()));
+
+    let imported = app.timer_store.import_timers(&project, events).await?;
+
+    Ok(Json(ImportResult { imported }))
+}
+
+/// Reports all tags which haven't been used in the given number of days
+#[debug_handler]
+async fn inactive_tags(
+    State(app): State<App>,
+    _: Admin,
+    Path(days): Path<i64>,
+) -> Result<Json<Vec<TagActivity>>, AppError> {
+    let tags = app.timer_store.inactive_tags(days).await?;
+
+    Ok(Json(tags))
+}
+
+#[derive(Debug, Serialize)]
+struct BackupResult {
+    key: String,
+}
+
+/// Takes a backup of the database right away
+#[debug_handler]
+async fn backup_now(State(app): State<App>, _: Admin) -> Result<Json<BackupResult>, AppError> {
+    let Some(backups) = app.backups else {
+        return Err(Error::NotFound("Backups are not configured".to_string()).into());
+    };
+    let key = backups.backup(&app.timer_store).await?;
+
+    Ok(Json(BackupResult { key }))
+}
+
+#[derive(Debug, Deserialize)]
+struct RedeliverParams {
+    /// Only redeliver webhooks to this URL, like the receiver which was fixed
+    url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Redelivery {
+    delivered: usize,
+    failed: usize,
+}
+
+/// Lists the webhook deliveries which kept failing, oldest first
+#[debug_handler]
+async fn failed_webhooks(
+    State(app): State<App>,
+    _: Admin,
+) -> Result<Json<Vec<FailedWebhook>>, AppError> {
+    Ok(Json(app.timer_store.failed_webhooks().await?))
+}
+
+/// Redelivers one failed webhook
+#[debug_handler]
+async fn redeliver_failed_webhook(
+    State(app): State<App>,
+    _: Admin,
+    Path(id): Path<i64>,
+) -> Result<Json<Redelivery>, AppError> {
+    let delivered = app.webhooks.redeliver(id).await?;
+
+    Ok(Json(Redelivery {
+        delivered: usize::from(delivered),
+        failed: usize::from(!delivered),
+    }))
+}
+
+/// Redelivers every failed webhook, or those to one URL
+#[debug_handler]
+async fn redeliver_failed_webhooks(
+    State(app): State<App>,
+    _: Admin,
+    Query(params): Query<RedeliverParams>,
+) -> Result<Json<Redelivery>, AppError> {
+    let mut redelivery = Redelivery {
+        delivered: 0,
+        failed: 0,
+    };
+    for webhook in app.timer_store.failed_webhooks().await? {
+        if params.url.as_ref().is_some_and(|url| *url != webhook.url) {
+            continue;
+        }
+        if app.webhooks.redeliver(webhook.id).await? {
+            redelivery.delivered += 1;
+        } else {
+            redelivery.failed += 1;
+        }
+    }
+    info!(?redelivery, "Redelivered failed webhooks");
+
+    Ok(Json(redelivery))
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookSecretForm {
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct WebhookSecret {
+    url: String,
+    secret: String,
+}
+
+/// Makes a new secret to sign a tag's webhooks to a URL with, replacing the previous one
+///
+/// The receiver verifies the `X-Sprite-Signature` header with it, see [`crate::webhooks`].
+#[debug_handler]
+async fn rotate_webhook_secret(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Json(form): Json<WebhookSecretForm>,
+) -> Result<Json<WebhookSecret>, AppError> {
+    let url = form.url.trim();
+    reminders::validate_webhook(url)?;
+    let secret = app.timer_store.rotate_webhook_secret(&tag, url).await?;
+
+    Ok(Json(WebhookSecret {
+        url: url.to_string(),
+        secret,
+    }))
+}
+
+/// Stops signing a tag's webhooks to a URL
+#[debug_handler]
+async fn remove_webhook_secret(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Json(form): Json<WebhookSecretForm>,
+) -> Result<StatusCode, AppError> {
+    app.timer_store
+        .remove_webhook_secret(&tag, form.url.trim())
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+struct ThemeForm {
+    theme: Theme,
+}
+
+/// Switches the color scheme of the tag's pages
+#[debug_handler]
+async fn set_theme(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Form(form): Form<ThemeForm>,
+) -> Result<Redirect, PageError> {
+    info!(%tag, ?form, "Setting theme");
+    let mut settings = app.timer_store.settings(&tag).await?;
+    settings.theme = form.theme;
+    app.timer_store.update_settings(&settings).await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+struct PresetForm {
+    project_id: i64,
+    name: String,
+    minutes: i64,
+    note: Option<String>,
+}
+
+/// Saves a new preset for the tag
+#[debug_handler]
+async fn create_preset(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Form(preset): Form<PresetForm>,
+) -> Result<Redirect, PageError> {
+    info!(%tag, ?preset, "Creating new preset");
+    let note = preset.note.as_deref().filter(|note| !note.is_empty());
+    let _ = app
+        .timer_store
+        .create_preset(
+            &tag,
+            preset.project_id,
+            &preset.name,
+            preset.minutes * 60,
+            note,
+        )
+        .await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+/// Adds a finished timer ending now based on a preset
+#[debug_handler]
+async fn apply_preset(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Path((_, preset_id)): Path<(String, i64)>,
+) -> Result<Redirect, PageError> {
+    info!(%tag, preset_id, "Applying preset");
+    let id = app.timer_store.apply_preset(&tag, preset_id).await?;
+    debug!(id, "Created timer from preset");
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    /// A comma separated list of columns, like `start,end,duration`
+    columns: Option<String>,
+    duration: Option<DurationFormat>,
+
+    /// Also export the timers which were invoiced already
+    #[serde(default)]
+    invoiced: bool,
+
+    /// Only export the timers carrying these comma separated labels
+    labels: Option<String>,
+
+    /// Whether timers need `any` of the labels, the default, or `all` of them
+    #[serde(default)]
+    label_match: LabelMatch,
+}
+
+impl ExportParams {
+    /// Which of the project's timers the export has
+    fn filter(&self) -> ExportFilter {
+        let filter = ExportFilter {
+            invoiced: self.invoiced,
+            ..Default::default()
+        };
+        match self.labels.as_deref() {
+            Some(labels) => filter.with_labels(labels, self.label_match),
+            None => filter,
+        }
+    }
+}
+
+/// Export all finished timers for a tag as a CSV file
+#[debug_handler]
+async fn export(
+    State(app): State<App>,
+    PagePath((timezone, project_id)): PagePath<(String, i64)>,
+    PageQuery(params): PageQuery<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, PageError> {
+    // Fails for a missing project rather than exporting an empty file
+    let project = app.timer_store.project(project_id).await?;
+    let csv = export_project(
+        &app.timer_store,
+        &project,
+        &timezone,
+        params.columns.as_deref(),
+        params.duration,
+        &params.filter(),
+        &app.plugins,
+    )
+    .await?;
+
+    Ok(cache::conditional(&headers, "text/csv", csv))
+}
+
+/// Exports all data of the token's tag as a ZIP archive
+#[instrument(skip(app, uid))]
+#[debug_handler]
+async fn export_all(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+    Query(params): Query<StatsParams>,
+) -> Result<Response, AppError> {
+    let timezone = templates::to_render_timezone(&params.timezone()?);
+    let archive = export_account(&app.timer_store, &uid, &timezone, &app.plugins).await?;
+    let disposition = format!(
+        "attachment; filename=\"sprite-{}.zip\"",
+        Utc::now().format("%Y-%m-%d")
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+// Renders the main timer page for a given tag, or its timers as JSON when asked for
+#[instrument(skip(app))]
+#[debug_handler]
+async fn timers(
+    State(app): State<App>,
+    PagePath(timer_tag): PagePath<String>,
+    PageQuery(params): PageQuery<ViewParams>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+    WantsJson(wants_json): WantsJson,
+) -> Response {
+    if wants_json {
+        return timers_json(app, timer_tag, params).await.into_response();
+    }
+
+    render_timers(app, timer_tag, None, params, accept_language, None)
+        .await
+        .into_response()
+}
+
+#[instrument(skip(app))]
+#[debug_handler]
+async fn timers_with_tz(
+    State(app): State<App>,
+    PagePath((timer_tag, timezone)): PagePath<(String, String)>,
+    PageQuery(params): PageQuery<ViewParams>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+    WantsJson(wants_json): WantsJson,
+) -> Response {
+    if wants_json {
+        return timers_json(app, timer_tag, params).await.into_response();
+    }
+
+    render_timers(
+        app,
+        timer_tag,
+        Some(timezone),
+        params,
+        accept_language,
+        None,
+    )
+    .await
+    .into_response()
+}
+
+/// A project with its timers, as the timer page lists them
+#[derive(Debug, Serialize)]
+struct ProjectTimers {
+    project: Project,
+
+    /// Oldest first
+    timers: Vec<Timer>,
+}
+
+/// The projects of a tag with their timers, for scripts reading the timer page
+#[instrument(skip(app))]
+async fn timers_json(
+    app: App,
+    timer_tag: String,
+    params: ViewParams,
+) -> Result<Json<Vec<ProjectTimers>>, AppError> {
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+    let mut projects = app.timer_store.projects_by_tag(&tag).await?;
+    if let Some(label) = params.label() {
+        let labels = app.timer_store.labels_by_tag(&tag).await?;
+        retain_labelled(&mut projects, &labels, label);
+    }
+
+    let mut projects: Vec<_> = projects
+        .into_iter()
+        .map(|(project, mut timers)| {
+            timers.sort_by_key(|timer| (timer.start_time_ms, timer.id));
+            ProjectTimers { project, timers }
+        })
+        .collect();
+    projects.sort_by_key(|entry| entry.project.id);
+
+    Ok(Json(projects))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ViewParams {
+    /// Only show timers with this label
+    label: Option<String>,
+
+    /// Overrides the duration format of the tag
+    duration: Option<DurationFormat>,
+}
+
+impl ViewParams {
+    fn label(&self) -> Option<&str> {
+        self.label
+            .as_deref()
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+    }
+}
+
+#[instrument(skip(app))]
+async fn render_timers(
+    app: App,
+    timer_tag: String,
+    timezone: Option<String>,
+    params: ViewParams,
+    accept_language: Option<String>,
+    project_error: Option<(String, String)>,
+) -> Result<Response, PageError> {
+    debug!(timer_tag, "Rendering timers");
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+    let mut timers = app.timer_store.projects_by_tag(&tag).await?;
+    let labels = app.timer_store.labels_by_tag(&tag).await?;
+    let presets = app.timer_store.presets(&tag).await?;
+    let settings = app.timer_store.settings(&tag).await?;
+    let project_templates = app.timer_store.project_templates(&tag).await?;
+
+    if let Some(label) = params.label() {
+        retain_labelled(&mut timers, &labels, label);
+    }
+
+    let label_filter = params.label().map(str::to_string);
+    let locale = templates::Locale::new(&settings, params.duration, accept_language.as_deref());
+    let budgets = app
+        .timer_store
+        .project_budgets(&tag, Utc::now().timestamp())
+        .await?;
+    let page_timezone = match timezone.as_deref() {
+        Some(timezone) => templates::from_render_timezone(timezone)?,
+        None => chrono_tz::US::Pacific,
+    };
+    let streaks = tag_streaks(&app, &tag, &settings, page_timezone).await?;
+    let rendered_page = templates::render_timers(
+        tag,
+        timezone,
+        timers,
+        &labels,
+        presets,
+        label_filter,
+        locale,
+    )?
+    .with_budgets(budgets)
+    .with_streaks(streaks)
+    .with_project_templates(project_templates)
+    .with_theme(settings.theme);
+    let rendered_page = match project_error {
+        Some((name, message)) => rendered_page.with_project_error(name, message),
+        None => rendered_page,
+    };
+    Ok(into_response(&rendered_page))
+}
+
+/// Renders only the project sections of the timer page
+#[instrument(skip(app))]
+#[debug_handler]
+async fn projects_fragment(
+    State(app): State<App>,
+    PagePath(timer_tag): PagePath<String>,
+    PageQuery(params): PageQuery<ViewParams>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+) -> Result<impl IntoResponse, PageError> {
+    render_projects_fragment(app, timer_tag, None, params, accept_language).await
+}
+
+#[instrument(skip(app))]
+#[debug_handler]
+async fn projects_fragment_with_tz(
+    State(app): State<App>,
+    PagePath((timer_tag, timezone)): PagePath<(String, String)>,
+    PageQuery(params): PageQuery<ViewParams>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+) -> Result<impl IntoResponse, PageError> {
+    render_projects_fragment(app, timer_tag, Some(timezone), params, accept_language).await
+}
+
+#[instrument(skip(app))]
+async fn render_projects_fragment(
+    app: App,
+    timer_tag: String,
+    timezone: Option<String>,
+    params: ViewParams,
+    accept_language: Option<String>,
+) -> Result<Response, PageError> {
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+    let mut timers = app.timer_store.projects_by_tag(&tag).await?;
+    let labels = app.timer_store.labels_by_tag(&tag).await?;
+    let settings = app.timer_store.settings(&tag).await?;
+
+    if let Some(label) = params.label() {
+        retain_labelled(&mut timers, &labels, label);
+    }
+
+    let locale = templates::Locale::new(&settings, params.duration, accept_language.as_deref());
+    let budgets = app
+        .timer_store
+        .project_budgets(&tag, Utc::now().timestamp())
+        .await?;
+    let fragment = templates::ProjectsFragment::new(
+        tag.as_ref().to_string(),
+        timers,
+        &labels,
+        timezone,
+        locale,
+    )?
+    .with_budgets(budgets);
+    Ok(into_response(&fragment))
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelForm {
+    label: String,
+}
+
+/// Attaches a label to a timer
+#[debug_handler]
+async fn add_label(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Path((_, timer_id)): Path<(String, i64)>,
+    Form(form): Form<LabelForm>,
+) -> Result<Redirect, PageError> {
+    app.timer_store
+        .add_timer_label(&tag, timer_id, &form.label)
+        .await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+struct TimerLinkForm {
+    /// Left empty to remove the link
+    #[serde(default)]
+    link: String,
+}
+
+/// Links a timer to the issue, commit or branch it was spent on
+#[debug_handler]
+async fn set_timer_link(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Path((_, timer_id)): Path<(String, i64)>,
+    Form(form): Form<TimerLinkForm>,
+) -> Result<Redirect, PageError> {
+    let link = Some(form.link.trim()).filter(|link| !link.is_empty());
+    app.timer_store.set_timer_link(&tag, timer_id, link).await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+/// Detaches a label from a timer
+#[debug_handler]
+async fn remove_label(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Path((_, timer_id)): Path<(String, i64)>,
+    Form(form): Form<LabelForm>,
+) -> Result<Redirect, PageError> {
+    app.timer_store
+        .remove_timer_label(&tag, timer_id, &form.label)
+        .await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    q: String,
+}
+
+/// Searches the timer notes and project names of a tag
+#[debug_handler]
+async fn search(
+    State(app): State<App>,
+    Path(timer_tag): Path<String>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<SearchResult>>, AppError> {
+    let tag = app.timer_store.resolve_tag(&timer_tag).await?;
+    let results = app.timer_store.search(&tag, &params.q).await?;
+
+    Ok(Json(results))
+}
+
+#[derive(Debug, Deserialize)]
+struct HeatmapParams {
+    /// Defaults to the current year
+    year: Option<i32>,
+
+    /// The timezone days are counted in, like `US-Pacific`
+    timezone: Option<String>,
+
+    /// Overrides the duration format of the tag
+    duration: Option<DurationFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HoursParams {
+    /// The first day of the range
+    from: NaiveDate,
+
+    /// The last day of the range, included
+    to: NaiveDate,
+
+    /// Where days start at midnight, US/Pacific when left out
+    timezone: Option<String>,
+}
+
+impl HoursParams {
+    /// The unix epochs the range of days starts and ends at
+    fn range(&self) -> Result<(i64, i64)> {
+        let timezone = match self.timezone.as_deref() {
+            Some(timezone) => templates::from_render_timezone(timezone)?,
+            None => chrono_tz::US::Pacific,
+        };
+        let from = midnight(timezone, self.from)?;
+        let to = midnight(timezone, self.to + chrono::Duration::days(1))?;
+
+        Ok((from, to))
+    }
+}
+
+/// Gets the hours tracked in a range of days, rolled up into top level projects like clients
+#[instrument(skip(app))]
+#[debug_handler]
+async fn client_hours(
+    State(app): State<App>,
+    Path(timer_tag): Path<String>,
+    Query(params): Query<HoursParams>,
+) -> Result<Json<Vec<ProjectHours>>, AppError> {
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+    let (from, to) = params.range()?;
+
+    Ok(Json(app.timer_store.hours_by_client(&tag, from, to).await?))
+}
+
+/// Gets the hours tracked in a range of days per label
+///
+/// A timer counts towards every label it carries.
+#[instrument(skip(app))]
+#[debug_handler]
+async fn label_hours(
+    State(app): State<App>,
+    Path(timer_tag): Path<String>,
+    Query(params): Query<HoursParams>,
+) -> Result<Json<Vec<LabelHours>>, AppError> {
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+    let (from, to) = params.range()?;
+
+    Ok(Json(app.timer_store.hours_by_label(&tag, from, to).await?))
+}
+
+async fn build_heatmap(
+    app: &App,
+    tag: &TagId,
+    params: HeatmapParams,
+) -> Result<report::Heatmap, AppError> {
+    let timezone = match params.timezone {
+        Some(timezone) => templates::from_render_timezone(&timezone)?,
+        None => chrono_tz::US::Pacific,
+    };
+    let year = params
+        .year
+        .unwrap_or_else(|| Utc::now().with_timezone(&timezone).year());
+
+    let (from, to) = report::Heatmap::range(year, timezone)?;
+    let buckets = app.timer_store.time_buckets(tag, from, to).await?;
+
+    Ok(report::Heatmap::new(year, timezone, &buckets))
+}
+
+/// Gets the time tracked on every day of a year
+#[instrument(skip(app))]
+#[debug_handler]
+async fn heatmap(
+    State(app): State<App>,
+    Path(timer_tag): Path<String>,
+    Query(params): Query<HeatmapParams>,
+) -> Result<Json<report::Heatmap>, AppError> {
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+
+    Ok(Json(build_heatmap(&app, &tag, params).await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct WeekParams {
+    /// The timezone days start at midnight in, like `US-Pacific`
+    timezone: Option<String>,
+
+    /// Overrides the duration format of the tag
+    duration: Option<DurationFormat>,
+}
+
+/// Renders the timers of the week around `date`, grouped by the day they started on
+#[instrument(skip(app))]
+#[debug_handler]
+async fn week_page(
+    State(app): State<App>,
+    PagePath((timer_tag, date)): PagePath<(String, NaiveDate)>,
+    PageQuery(params): PageQuery<WeekParams>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+) -> Result<impl IntoResponse, PageError> {
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+    let settings = app.timer_store.settings(&tag).await?;
+    let timezone = match params.timezone.as_deref() {
+        Some(timezone) => templates::from_render_timezone(timezone)?,
+        None => chrono_tz::US::Pacific,
+    };
+
+    let monday = date - chrono::Duration::days(date.weekday().num_days_from_monday().into());
+    let days = monday
+        .iter_days()
+        .take(7)
+        .map(|day| {
+            let next = day + chrono::Duration::days(1);
+            Ok((day, midnight(timezone, day)?, midnight(timezone, next)?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let days = app
+        .timer_store
+        .timers_by_day(&tag, &days, Utc::now())
+        .await?;
+
+    let locale = templates::Locale::new(&settings, params.duration, accept_language.as_deref());
+    let page = templates::WeekPage::new(
+        tag.as_ref().to_string(),
+        monday,
+        days,
+        timezone,
+        locale,
+        settings.theme,
+    );
+    Ok(into_response(&page))
+}
+
+/// The most entries of the activity log shown at once
+const MAX_ACTIVITY: i64 = 200;
+
+#[derive(Debug, Deserialize)]
+struct ActivityParams {
+    /// How many of the latest entries to show, 20 by default
+    limit: Option<i64>,
+
+    /// The timezone times are shown in, like `US-Pacific`
+    timezone: Option<String>,
+
+    /// Overrides the duration format of the tag
+    duration: Option<DurationFormat>,
+}
+
+impl ActivityParams {
+    fn limit(&self) -> i64 {
+        self.limit.unwrap_or(20).clamp(1, MAX_ACTIVITY)
+    }
+}
+
+/// Renders what happened to a tag lately, like timers being started and stopped
+#[instrument(skip(app))]
+#[debug_handler]
+async fn activity_page(
+    State(app): State<App>,
+    PagePath(timer_tag): PagePath<String>,
+    PageQuery(params): PageQuery<ActivityParams>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+) -> Result<impl IntoResponse, PageError> {
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+    let settings = app.timer_store.settings(&tag).await?;
+    let timezone = match params.timezone.as_deref() {
+        Some(timezone) => templates::from_render_timezone(timezone)?,
+        None => chrono_tz::US::Pacific,
+    };
+    let entries = app.timer_store.activity(&tag, params.limit()).await?;
+
+    let locale = templates::Locale::new(&settings, params.duration, accept_language.as_deref());
+    let page = templates::ActivityPage::new(
+        tag.as_ref().to_string(),
+        entries,
+        Utc::now().timestamp(),
+        timezone,
+        locale,
+        settings.theme,
+    );
+    Ok(into_response(&page))
+}
+
+/// Gets the latest entries of the token's activity log, newest first
+#[instrument(skip(app, uid))]
+#[debug_handler]
+async fn activity(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+    Query(params): Query<ActivityParams>,
+) -> Result<Json<Vec<ActivityEntry>>, AppError> {
+    Ok(Json(app.timer_store.activity(&uid, params.limit()).await?))
+}
+
+/// Renders everything about a single project of the tag: its timers, stats and budget
+#[instrument(skip(app))]
+#[debug_handler]
+async fn project_page(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    PagePath((_, project_id)): PagePath<(String, i64)>,
+    PageQuery(params): PageQuery<WeekParams>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+) -> Result<impl IntoResponse, PageError> {
+    let project = app.timer_store.owned_project(&tag, project_id).await?;
+    let settings = app.timer_store.settings(&tag).await?;
+    let timezone = match params.timezone.as_deref() {
+        Some(timezone) => templates::from_render_timezone(timezone)?,
+        None => chrono_tz::US::Pacific,
+    };
+
+    let timers = app
+        .timer_store
+        .projects_by_tag(&tag)
+        .await?
+        .remove(&project)
+        .unwrap_or_default();
+    let labels = app.timer_store.labels_by_tag(&tag).await?;
+    let stats = app.timer_store.project_stats(project_id, timezone).await?;
+    let budget = app
+        .timer_store
+        .project_budgets(&tag, Utc::now().timestamp())
+        .await?
+        .remove(&project_id);
+
+    let locale = templates::Locale::new(&settings, params.duration, accept_language.as_deref());
+    let page = templates::ProjectPage::new(
+        project,
+        timers,
+        &labels,
+        stats,
+        budget,
+        timezone,
+        locale,
+        settings.theme,
+    );
+    Ok(into_response(&page))
+}
+
+/// Renders the time tracked on every day of a year as an activity graph
+#[instrument(skip(app))]
+#[debug_handler]
+async fn heatmap_page(
+    State(app): State<App>,
+    PagePath(timer_tag): PagePath<String>,
+    PageQuery(params): PageQuery<HeatmapParams>,
+) -> Result<impl IntoResponse, PageError> {
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+    let settings = app.timer_store.settings(&tag).await?;
+    let duration_format = settings.duration_display(params.duration);
+    let heatmap = build_heatmap(&app, &tag, params).await?;
+
+    let page = templates::HeatmapPage::new(tag.as_ref().to_string(), heatmap, duration_format);
+    Ok(into_response(&page))
+}
+
+/// Gets the settings of a tag
+#[instrument(skip(app))]
+#[debug_handler]
+async fn settings(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+) -> Result<Json<TagSettings>, AppError> {
+    Ok(Json(app.timer_store.settings(&tag).await?))
+}
+
+/// Settings to change, anything left out keeps its current value
+#[derive(Debug, Deserialize)]
+struct SettingsUpdate {
+    short_timer_threshold: Option<i64>,
+    short_timer_policy: Option<ShortTimerPolicy>,
+    concurrent_timers: Option<bool>,
+    default_project: Option<bool>,
+
+    /// Where digests are emailed to, an empty address removes it
+    email: Option<String>,
+    weekly_digest: Option<bool>,
+
+    /// When to send reminders, an empty schedule turns them off
+    reminder_schedule: Option<String>,
+    reminder_after: Option<i64>,
+
+    /// Where reminders are posted to, an empty URL removes it
+    reminder_webhook: Option<String>,
+
+    /// Minutes before a running timer is alerted about, 0 turns alerts off
+    long_timer_after: Option<i64>,
+
+    /// Minutes a day needs to count towards a streak
+    streak_minutes: Option<i64>,
+
+    /// Minutes to track every day, 0 removes the goal
+    daily_goal: Option<i64>,
+    goal_timezone: Option<String>,
+    goal_webhook: Option<bool>,
+
+    /// The default columns of exports, an empty list restores the built-in ones
+    export_columns: Option<String>,
+    duration_format: Option<DurationFormat>,
+    duration_rounding: Option<DurationRounding>,
+
+    /// Whether durations under an hour are written with their seconds
+    duration_seconds: Option<bool>,
+
+    /// Whether running timers are split at midnight in the goal timezone
+    split_at_midnight: Option<bool>,
+    date_order: Option<DateOrder>,
+    clock: Option<Clock>,
+
+    /// The language of the web UI, an empty one negotiates it with the browser
+    language: Option<String>,
+    theme: Option<Theme>,
+}
+
+/// Updates the settings of a tag
+#[instrument(skip(app))]
+#[debug_handler]
+async fn update_settings(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Json(update): Json<SettingsUpdate>,
+) -> Result<Json<TagSettings>, AppError> {
+    let mut settings = app.timer_store.settings(&tag).await?;
+
+    if let Some(threshold) = update.short_timer_threshold {
+        settings.short_timer_threshold = threshold;
+    }
+    if let Some(policy) = update.short_timer_policy {
+        settings.short_timer_policy = policy;
+    }
+    if let Some(concurrent_timers) = update.concurrent_timers {
+        settings.concurrent_timers = concurrent_timers;
+    }
+    if let Some(default_project) = update.default_project {
+        settings.default_project = default_project;
+    }
+    if let Some(email) = update.email {
+        let email = email.trim();
+        settings.email = if email.is_empty() {
+            None
+        } else {
+            email::validate_address(email)?;
+            Some(email.to_string())
+        };
+    }
+    if let Some(weekly_digest) = update.weekly_digest {
+        settings.weekly_digest = weekly_digest;
+    }
+    if let Some(schedule) = update.reminder_schedule {
+        let schedule = schedule.trim();
+        settings.reminder_schedule = if schedule.is_empty() {
+            None
+        } else {
+            schedule.parse::<reminders::WorkSchedule>()?;
+            Some(schedule.to_string())
+        };
+    }
+    if let Some(after) = update.reminder_after {
+        if after <= 0 {
+            return Err(Error::Validation("Reminders need at least a minute".to_string()).into());
+        }
+        settings.reminder_after = after;
+    }
+    if let Some(webhook) = update.reminder_webhook {
+        let webhook = webhook.trim();
+        settings.reminder_webhook = if webhook.is_empty() {
+            None
+        } else {
+            reminders::validate_webhook(webhook)?;
+            Some(webhook.to_string())
+        };
+    }
+    if let Some(after) = update.long_timer_after {
+        if after < 0 {
+            return Err(
+                Error::Validation("The long timer limit can't be negative".to_string()).into(),
+            );
+        }
+        settings.long_timer_after = (after > 0).then_some(after);
+    }
+    if let Some(minutes) = update.streak_minutes {
+        if minutes <= 0 {
+            return Err(
+                Error::Validation("A streak needs at least a minute a day".to_string()).into(),
+            );
+        }
+        settings.streak_minutes = minutes;
+    }
+    if let Some(goal) = update.daily_goal {
+        if goal < 0 {
+            return Err(Error::Validation("The daily goal can't be negative".to_string()).into());
+        }
+        settings.daily_goal = (goal > 0).then_some(goal);
+    }
+    if let Some(timezone) = update.goal_timezone {
+        if timezone.parse::<chrono_tz::Tz>().is_err() {
+            return Err(Error::Validation(format!("Unknown timezone {:?}", timezone)).into());
+        }
+        settings.goal_timezone = timezone;
+    }
+    if let Some(goal_webhook) = update.goal_webhook {
+        settings.goal_webhook = goal_webhook;
+    }
+    if let Some(columns) = update.export_columns {
+        let columns = columns.trim();
+        settings.export_columns = if columns.is_empty() {
+            None
+        } else {
+            timer_utils::parse_columns(columns)?;
+            Some(columns.to_string())
+        };
+    }
+    if let Some(duration_format) = update.duration_format {
+        settings.duration_format = duration_format;
+    }
+    if let Some(duration_rounding) = update.duration_rounding {
+        settings.duration_rounding = duration_rounding;
+    }
+    if let Some(duration_seconds) = update.duration_seconds {
+        settings.duration_seconds = duration_seconds;
+    }
+    if let Some(split_at_midnight) = update.split_at_midnight {
+        settings.split_at_midnight = split_at_midnight;
+    }
+    if let Some(date_order) = update.date_order {
+        settings.date_order = date_order;
+    }
+    if let Some(clock) = update.clock {
+        settings.clock = clock;
+    }
+    if let Some(theme) = update.theme {
+        settings.theme = theme;
+    }
+    if let Some(language) = update.language {
+        let language = language.trim();
+        settings.language = if language.is_empty() {
+            None
+        } else {
+            Some(i18n::Language::parse_supported(language)?.code())
+        };
+    }
+
+    app.timer_store.update_settings(&settings).await?;
+    Ok(Json(app.timer_store.settings(&tag).await?))
+}
+
+/// Renders the short links leading to the timer page of a tag
+#[instrument(skip(app))]
+#[debug_handler]
+async fn links(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+) -> Result<impl IntoResponse, PageError> {
+    let slugs = app.timer_store.slugs(&tag).await?;
+    let settings = app.timer_store.settings(&tag).await?;
+
+    let page = templates::LinksPage::new(tag.as_ref().to_string(), slugs, settings.date_format());
+    Ok(into_response(&page))
+}
+
+#[derive(Debug, Deserialize)]
+struct LinkForm {
+    /// The slug to use, a random one is picked when empty
+    #[serde(default)]
+    slug: String,
+}
+
+/// Creates a short link to the timer page of a tag
+#[debug_handler]
+async fn create_link(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Form(form): Form<LinkForm>,
+) -> Result<Redirect, PageError> {
+    let slug = Some(form.slug.trim()).filter(|slug| !slug.is_empty());
+    app.timer_store.create_slug(&tag, slug).await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/links/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+/// Removes a short link, freeing its slug
+#[debug_handler]
+async fn remove_link(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    PagePath((_, slug)): PagePath<(String, String)>,
+) -> Result<Redirect, PageError> {
+    app.timer_store.remove_slug(&tag, &slug).await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/links/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+/// Follows a short link to the timer page it leads to
+#[debug_handler]
+async fn follow_link(
+    State(app): State<App>,
+    PagePath(slug): PagePath<String>,
+) -> Result<Redirect, PageError> {
+    let tag = app.timer_store.slug_tag(&slug).await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+/// Renders the devices which have toggled timers for a tag
+#[instrument(skip(app))]
+#[debug_handler]
+async fn devices(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+) -> Result<impl IntoResponse, PageError> {
+    let devices = app.timer_store.devices(&tag).await?;
+    let settings = app.timer_store.settings(&tag).await?;
+
+    let page =
+        templates::DevicesPage::new(tag.as_ref().to_string(), devices, settings.date_format());
+    Ok(into_response(&page))
+}
+
+/// Revokes a device so it can no longer toggle timers
+#[debug_handler]
+async fn revoke_device(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Path((_, device_id)): Path<(String, i64)>,
+) -> Result<Redirect, PageError> {
+    info!(%tag, device_id, "Revoking device");
+    app.timer_store.revoke_device(&tag, device_id).await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/devices/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+#[derive(Debug, Serialize)]
+struct ShareLink {
+    token_id: i64,
+
+    /// The link itself, only known right after it was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    created_at: i64,
+    revoked: bool,
+}
+
+impl From<Token> for ShareLink {
+    fn from(share: Token) -> Self {
+        Self {
+            token_id: share.token_id,
+            url: None,
+            created_at: share.created_at,
+            revoked: share.revoked,
+        }
+    }
+}
+
+/// Creates a read-only share link for a tag
+#[debug_handler]
+async fn create_share_link(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+) -> Result<Json<ShareLink>, AppError> {
+    let share = app.timer_store.create_token(&tag, TokenKind::Share).await?;
+
+    Ok(Json(ShareLink {
+        url: Some(format!("{}/shared/{}", uri_base(), share.token)),
+        ..share.info.into()
+    }))
+}
+
+/// Lists the share links of a tag, including revoked ones
+#[debug_handler]
+async fn share_links(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+) -> Result<Json<Vec<ShareLink>>, AppError> {
+    let shares = app.timer_store.tokens(&tag, TokenKind::Share).await?;
+
+    Ok(Json(shares.into_iter().map(ShareLink::from).collect()))
+}
+
+/// Revokes a share link so it no longer grants access
+#[debug_handler]
+async fn revoke_share_link(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, token_id)): Path<(String, i64)>,
+) -> Result<StatusCode, AppError> {
+    app.timer_store
+        .revoke_token(&tag, TokenKind::Share, token_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Creates an API token for integrations like Home Assistant
+///
+/// The token is part of the response this one time, only a hash is kept.
+#[debug_handler]
+async fn create_api_token(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+) -> Result<Json<NewToken>, AppError> {
+    Ok(Json(
+        app.timer_store.create_token(&tag, TokenKind::Api).await?,
+    ))
+}
+
+/// Lists the API tokens of a tag, including revoked ones
+#[debug_handler]
+async fn api_tokens(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+) -> Result<Json<Vec<Token>>, AppError> {
+    Ok(Json(app.timer_store.tokens(&tag, TokenKind::Api).await?))
+}
+
+/// Revokes an API token so it's no longer accepted
+#[debug_handler]
+async fn revoke_api_token(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, token_id)): Path<(String, i64)>,
+) -> Result<StatusCode, AppError> {
+    app.timer_store
+        .revoke_token(&tag, TokenKind::Api, token_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Issues the token confirming that a tag's account should be deleted
+///
+/// Only the owner key grants this, API tokens given to integrations don't.
+#[instrument(skip_all)]
+#[debug_handler]
+async fn request_account_deletion(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+) -> Result<Json<AccountDeletion>, AppError> {
+    Ok(Json(
+        app.timer_store
+            .request_account_deletion(&tag, Utc::now().timestamp())
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountDeletionParams {
+    /// The token from [`request_account_deletion`]
+    confirmation: String,
+}
+
+/// Permanently deletes all data of a tag
+#[instrument(skip_all)]
+#[debug_handler]
+async fn delete_account(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Query(params): Query<AccountDeletionParams>,
+) -> Result<StatusCode, AppError> {
+    let deleted = app
+        .timer_store
+        .delete_account(&tag, &params.confirmation, Utc::now().timestamp())
+        .await?;
+    info!(%tag, deleted, "Deleted account");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The state of a tag's timer, shaped for a Home Assistant RESTful sensor
+///
+/// `state` works as is for a binary sensor, the rest are meant for attributes.
+#[derive(Debug, Serialize)]
+struct HomeAssistantState {
+    /// `on` while a timer is running, `off` otherwise
+    state: &'static str,
+
+    /// The project being tracked, or the current project when idle
+    project: Option<String>,
+
+    /// When the running timer was started as a unix epoch in UTC
+    started_at: Option<i64>,
+
+    /// How long the running timer has been going for
+    elapsed_seconds: i64,
+
+    /// [`HomeAssistantState::elapsed_seconds`] in the tag's duration format
+    elapsed: String,
+}
+
+impl HomeAssistantState {
+    async fn of(app: &App, uid: &uid::TagId) -> Result<Self> {
+        let status = app.timer_store.tag_status(uid).await?;
+        let settings = app.timer_store.settings(uid).await?;
+        let elapsed_seconds = status
+            .start_time
+            .map_or(0, |start| (Utc::now().timestamp() - start).max(0));
+
+        Ok(Self {
+            state: if status.start_time.is_some() {
+                "on"
+            } else {
+                "off"
+            },
+            project: status.project_name,
+            started_at: status.start_time,
+            elapsed_seconds,
+            elapsed: settings.duration_display(None).format(elapsed_seconds),
+        })
+    }
+}
+
+/// Reports whether the timer of the token's tag is running, and for how long
+#[instrument(skip_all)]
+#[debug_handler]
+async fn home_assistant_sensor(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+) -> Result<Json<HomeAssistantState>, AppError> {
+    Ok(Json(HomeAssistantState::of(&app, &uid).await?))
+}
+
+/// Toggles the timer of the token's tag, for a Home Assistant RESTful command
+#[instrument(skip_all)]
+#[debug_handler]
+async fn home_assistant_toggle(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+) -> Result<Json<HomeAssistantState>, AppError> {
+    let device = app
+        .timer_store
+        .identify_device(&uid, "Home Assistant")
+        .await?;
+    let options = ToggleOptions {
+        device: Some(device),
+        ..Default::default()
+    };
+    let id = app.timer_store.toggle(&uid, &options).await?;
+    debug!(id, message = "Toggled timer from Home Assistant");
+    app.events.publish(TimerEvent::Toggled {
+        unique_id: uid.as_ref().to_string(),
+    });
+
+    Ok(Json(HomeAssistantState::of(&app, &uid).await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct StatsParams {
+    /// The timezone days are counted in, US/Pacific when left out
+    timezone: Option<String>,
+}
+
+impl StatsParams {
+    fn timezone(&self) -> Result<chrono_tz::Tz> {
+        match self.timezone.as_deref() {
+            Some(timezone) => templates::from_render_timezone(timezone),
+            None => Ok(chrono_tz::US::Pacific),
+        }
+    }
+}
+
+/// Summarizes the finished timers of one of the token's projects, for a stats card
+#[instrument(skip(app, uid))]
+#[debug_handler]
+async fn project_stats(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+    Path(project_id): Path<i64>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<ProjectStats>, AppError> {
+    let project = app.timer_store.owned_project(&uid, project_id).await?;
+    let timezone = params.timezone()?;
+
+    Ok(Json(
+        app.timer_store.project_stats(project.id, timezone).await?,
+    ))
+}
+
+/// The most timers a page of `/api/v1/project/:project_id/timers` can have
+const MAX_TIMERS_PAGE: u32 = 500;
+
+#[derive(Debug, Deserialize)]
+struct TimersPageParams {
+    /// The `next` cursor of the previous page
+    after: Option<String>,
+    limit: Option<u32>,
+}
+
+/// Lists the finished timers of one of the token's projects, newest first, a page at a time
+#[instrument(skip(app, uid))]
+#[debug_handler]
+async fn project_timers(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+    Path(project_id): Path<i64>,
+    Query(params): Query<TimersPageParams>,
+) -> Result<Json<TimerPage>, AppError> {
+    let project = app.timer_store.owned_project(&uid, project_id).await?;
+    let after = params
+        .after
+        .as_deref()
+        .map(str::parse::<TimerCursor>)
+        .transpose()?;
+    let limit = params.limit.unwrap_or(100).clamp(1, MAX_TIMERS_PAGE);
+    let everything = ExportFilter {
+        invoiced: true,
+        ..Default::default()
+    };
+
+    Ok(Json(
+        app.timer_store
+            .exportable_timers_page(project.id, after, limit, &everything)
+            .await?,
+    ))
+}
+
+/// Gets the token's streaks of days with enough time tracked
+#[instrument(skip(app, uid))]
+#[debug_handler]
+async fn streaks(
+    State(app): State<App>,
+    ApiTag(uid): ApiTag,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<Streaks>, AppError> {
+    let settings = app.timer_store.settings(&uid).await?;
+
+    Ok(Json(
+        tag_streaks(&app, &uid, &settings, params.timezone()?).await?,
+    ))
+}
+
+/// The streaks of a tag, with days counted in `timezone`
+async fn tag_streaks(
+    app: &App,
+    uid: &TagId,
+    settings: &TagSettings,
+    timezone: chrono_tz::Tz,
+) -> Result<Streaks> {
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+
+    app.timer_store
+        .streaks(uid, settings.streak_minutes, timezone, today)
+        .await
+}
+
+/// Renders the read-only view of a tag behind a share link
+#[instrument(skip_all)]
+#[debug_handler]
+async fn shared_timers(
+    State(app): State<App>,
+    shared: SharedTag,
+    PageQuery(params): PageQuery<DurationParams>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+) -> Result<impl IntoResponse, PageError> {
+    render_shared_timers(app, shared, None, params, accept_language).await
+}
+
+#[instrument(skip_all)]
+#[debug_handler]
+async fn shared_timers_with_tz(
+    State(app): State<App>,
+    shared: SharedTag,
+    PagePath((_, timezone)): PagePath<(String, String)>,
+    PageQuery(params): PageQuery<DurationParams>,
+    AcceptLanguage(accept_language): AcceptLanguage,
+) -> Result<impl IntoResponse, PageError> {
+    render_shared_timers(app, shared, Some(timezone), params, accept_language).await
+}
+
+#[derive(Debug, Deserialize)]
+struct DurationParams {
+    /// Overrides the duration format of the tag
+    duration: Option<DurationFormat>,
+}
+
+async fn render_shared_timers(
+    app: App,
+    shared: SharedTag,
+    timezone: Option<String>,
+    params: DurationParams,
+    accept_language: Option<String>,
+) -> Result<Response, PageError> {
+    let timers = app.timer_store.projects_by_tag(&shared.uid).await?;
+    let labels = app.timer_store.labels_by_tag(&shared.uid).await?;
+    let settings = app.timer_store.settings(&shared.uid).await?;
+
+    let locale = templates::Locale::new(&settings, params.duration, accept_language.as_deref());
+    let page = templates::SharedPage::new(shared.token, timers, &labels, timezone, locale)?;
+    Ok(into_response(&page))
+}
+
+/// Exports a project of a tag behind a share link
+#[instrument(skip_all)]
+#[debug_handler]
+async fn shared_export(
+    State(app): State<App>,
+    shared: SharedTag,
+    PagePath((_, timezone, project_id)): PagePath<(String, String, i64)>,
+    PageQuery(params): PageQuery<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, PageError> {
+    let project = app
+        .timer_store
+        .owned_project(&shared.uid, project_id)
+        .await?;
+    let csv = export_project(
+        &app.timer_store,
+        &project,
+        &timezone,
+        params.columns.as_deref(),
+        params.duration,
+        &params.filter(),
+        &app.plugins,
+    )
+    .await?;
+
+    Ok(cache::conditional(&headers, "text/csv", csv))
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamForm {
+    name: String,
+}
+
+/// Creates a team owned by the tag
+#[debug_handler]
+async fn create_team(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Json(form): Json<TeamForm>,
+) -> Result<Json<Team>, AppError> {
+    let team = app.timer_store.create_team(&tag, &form.name).await?;
+
+    Ok(Json(team))
+}
+
+/// Reports the members, projects and timers of a team to one of its members
+#[debug_handler]
+async fn team_report(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((team_id, _)): Path<(i64, String)>,
+) -> Result<Json<TeamReport>, AppError> {
+    let report = app.timer_store.team_report(&tag, team_id).await?;
+
+    Ok(Json(report))
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamMemberForm {
+    /// The tag (ID or alias) of the member
+    member: String,
+    role: Role,
+}
+
+/// Adds a member to a team or changes their role
+#[debug_handler]
+async fn set_team_member(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((team_id, _)): Path<(i64, String)>,
+    Json(form): Json<TeamMemberForm>,
+) -> Result<StatusCode, AppError> {
+    let member = app.timer_store.resolve_tag(&form.member).await?;
+    app.timer_store
+        .set_team_member(&tag, team_id, &member, form.role)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Removes a member from a team
+#[debug_handler]
+async fn remove_team_member(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((team_id, _, member_id)): Path<(i64, String, i64)>,
+) -> Result<StatusCode, AppError> {
+    app.timer_store
+        .remove_team_member(&tag, team_id, member_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Creates a project which every member of the team can track time on
+#[debug_handler]
+async fn create_team_project(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((team_id, _)): Path<(i64, String)>,
+    Json(form): Json<ProjectForm>,
+) -> Result<Json<Project>, AppError> {
+    let project_id = app
+        .timer_store
+        .create_team_project(&tag, team_id, &form.name)
+        .await?;
+
+    Ok(Json(app.timer_store.project(project_id).await?))
+}
+
+/// The start of a day in `timezone`, as a unix epoch in UTC
+fn midnight(timezone: chrono_tz::Tz, date: NaiveDate) -> Result<i64, Error> {
+    timezone
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+        .map(|midnight| midnight.timestamp())
+        .ok_or_else(|| Error::Validation(format!("There is no midnight on {}", date)))
+}
+
+#[derive(Debug, Deserialize)]
+struct TimesheetForm {
+    /// The Monday the week starts on
+    week: NaiveDate,
+
+    /// Where the week starts at midnight, US/Pacific when left out
+    timezone: Option<String>,
+}
+
+/// Lists the timesheets of a team, every member's for owners and their own for everyone else
+#[debug_handler]
+async fn timesheets(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((team_id, _)): Path<(i64, String)>,
+) -> Result<Json<Vec<Timesheet>>, AppError> {
+    Ok(Json(app.timer_store.timesheets(&tag, team_id).await?))
+}
+
+/// Submits the member's timers on the team's projects in a week for approval
+#[debug_handler]
+async fn submit_timesheet(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((team_id, _)): Path<(i64, String)>,
+    Json(form): Json<TimesheetForm>,
+) -> Result<(StatusCode, Json<Timesheet>), AppError> {
+    if form.week.weekday() != Weekday::Mon {
+        return Err(Error::Validation(format!(
+            "Weeks start on a Monday, not {}",
+            form.week.weekday()
+        ))
+        .into());
+    }
+    let timezone = match form.timezone.as_deref() {
+        Some(timezone) => templates::from_render_timezone(timezone)?,
+        None => chrono_tz::US::Pacific,
+    };
+    let week = (
+        midnight(timezone, form.week)?,
+        midnight(timezone, form.week + chrono::Duration::weeks(1))?,
+    );
+
+    let timesheet = app
+        .timer_store
+        .submit_timesheet(&tag, team_id, week)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(timesheet)))
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewForm {
+    /// Either `approved` or `rejected`
+    status: TimesheetStatus,
+    comment: Option<String>,
+}
+
+/// Approves or rejects a submitted timesheet
+#[debug_handler]
+async fn review_timesheet(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((team_id, _, timesheet_id)): Path<(i64, String, i64)>,
+    Json(form): Json<ReviewForm>,
+) -> Result<Json<Timesheet>, AppError> {
+    let timesheet = app
+        .timer_store
+        .review_timesheet(
+            &tag,
+            team_id,
+            timesheet_id,
+            form.status,
+            form.comment.as_deref(),
+        )
+        .await?;
+
+    Ok(Json(timesheet))
+}
+
+/// Renders live status cards for every tag in a group
+#[instrument(skip(app))]
+#[debug_handler]
+async fn wall(
+    State(app): State<App>,
+    PagePath(group_id): PagePath<String>,
+) -> Result<impl IntoResponse, PageError> {
+    let statuses = app.timer_store.group_status(&group_id).await?;
+
+    Ok(into_response(&templates::WallPage::new(group_id, statuses)))
+}
+
+/// Renders only the status cards of the wall
+#[instrument(skip(app))]
+#[debug_handler]
+async fn wall_cards(
+    State(app): State<App>,
+    PagePath(group_id): PagePath<String>,
+) -> Result<impl IntoResponse, PageError> {
+    let statuses = app.timer_store.group_status(&group_id).await?;
+
+    Ok(into_response(&templates::WallCards::new(statuses)))
+}
+
+/// Streams an event whenever a tag in the group is toggled
+#[instrument(skip(app))]
+#[debug_handler]
+async fn wall_events(
+    State(app): State<App>,
+    Path(group_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, serde_json::Error>>>, AppError> {
+    let members: HashSet<String> = app
+        .timer_store
+        .group_status(&group_id)
+        .await?
+        .into_iter()
+        .map(|status| status.unique_id)
+        .collect();
+
+    let stream = BroadcastStream::new(app.events.subscribe())
+        // Lagging subscribers just miss a refresh
+        .filter_map(|event| event.ok())
+        .filter(move |event| members.contains(event.unique_id()))
+        .map(|event| Event::default().json_data(event));
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Streams the events of a single tag, like toggles and long running timer alerts
+#[instrument(skip(app))]
+#[debug_handler]
+async fn tag_events(
+    State(app): State<App>,
+    Path(timer_tag): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, serde_json::Error>>>, AppError> {
+    let tag = app.timer_store.resolve_tag(&timer_tag).await?;
+
+    let stream = BroadcastStream::new(app.events.subscribe())
+        .filter_map(|event| event.ok())
+        .filter(move |event| event.unique_id() == tag.as_ref())
+        .map(|event| Event::default().json_data(event));
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Adds a tag to a wall display group
+#[debug_handler]
+async fn add_to_wall(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Path((group_id, _)): Path<(String, String)>,
+) -> Result<Redirect, PageError> {
+    info!(group_id, %tag, "Adding tag to group");
+    app.timer_store.add_group_member(&group_id, &tag).await?;
+
+    Ok(Redirect::to(&format!("{}/wall/{}", base_path(), group_id)))
+}
+
+/// How long a toggle link ignores being opened again, in seconds
+const TOGGLE_LINK_REPLAY_WINDOW: i64 = 10;
+
+#[derive(Debug, Serialize)]
+struct ToggleLink {
+    url: String,
+}
+
+/// Gets a new link which leads to toggling a tag's timer, for writing onto an
+/// NFC tag
+///
+/// The links handed out before stop working.
+#[instrument(skip_all)]
+#[debug_handler]
+async fn toggle_link(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+) -> Result<Json<ToggleLink>, AppError> {
+    let links = app
+        .toggle_links
+        .as_ref()
+        .ok_or_else(|| Error::NotFound("Toggle links aren't enabled".to_string()))?;
+    let generation = app.timer_store.rotate_toggle_link(&tag).await?;
+
+    Ok(Json(ToggleLink {
+        url: format!("{}/t/{}/toggle", uri_base(), links.sign(&tag, generation)),
+    }))
+}
+
+/// The tag a toggle link was signed for, as long as it's of the current
+/// generation
+async fn toggle_link_tag(app: &App, token: &str) -> Result<TagId> {
+    let invalid = || Error::NotFound("This toggle link isn't valid".to_string());
+    let (uid, generation) = app
+        .toggle_links
+        .as_ref()
+        .and_then(|links| links.verify(token))
+        .ok_or_else(invalid)?;
+    if generation != app.timer_store.toggle_link_generation(&uid).await? {
+        return Err(invalid().into());
+    }
+
+    Ok(uid)
+}
+
+/// Toggles the timer of the tag a link was signed for, then shows its timer page
+///
+/// Opening the link again within [`TOGGLE_LINK_REPLAY_WINDOW`] only shows the
+/// timer page, so a link preview or a reader firing twice toggles once.
+#[instrument(skip_all)]
+#[debug_handler]
+async fn toggle_from_link(
+    State(app): State<App>,
+    PagePath(token): PagePath<String>,
+    headers: HeaderMap,
+) -> Result<Redirect, PageError> {
+    let uid = toggle_link_tag(&app, &token).await?;
+
+    if app
+        .timer_store
+        .claim_toggle_link(&uid, TOGGLE_LINK_REPLAY_WINDOW)
+        .await?
+    {
+        let details = headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("Toggle link");
+        let device = app.timer_store.identify_device(&uid, details).await?;
+        let options = ToggleOptions {
+            device: Some(device),
+            ..Default::default()
+        };
+        let id = app.timer_store.toggle(&uid, &options).await?;
+        debug!(id, message = "Toggled timer from link");
+        app.events.publish(TimerEvent::Toggled {
+            unique_id: uid.as_ref().to_string(),
+        });
+    }
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        uid.as_ref()
+    )))
+}
+
+/// The Google Calendar client, or an error explaining it isn't configured
+fn google_calendar(app: &App) -> Result<&GoogleCalendar, Error> {
+    app.google
+        .as_ref()
+        .ok_or_else(|| Error::Unavailable("Google Calendar isn't configured".to_string()))
+}
+
+/// Sends a tag to Google's consent screen to connect its calendar
+#[instrument(skip(app))]
+#[debug_handler]
+async fn connect_google(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+) -> Result<Redirect, PageError> {
+    let calendar = google_calendar(&app)?;
+
+    Ok(Redirect::to(&calendar.authorize_url(&tag)?))
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleCallback {
+    code: Option<String>,
+    state: String,
+    error: Option<String>,
+}
+
+/// Where Google sends a tag back to after it agreed, or refused, to connect
+/// its calendar
+#[instrument(skip_all)]
+#[debug_handler]
+async fn google_callback(
+    State(app): State<App>,
+    PageQuery(callback): PageQuery<GoogleCallback>,
+) -> Result<Redirect, PageError> {
+    let calendar = google_calendar(&app)?;
+    let now = Utc::now().timestamp();
+    let uid = calendar
+        .verify_state(&callback.state, now)
+        .ok_or_else(|| Error::Forbidden("This Google sign-in has expired".to_string()))?;
+    if let Some(error) = callback.error {
+        return Err(Error::Forbidden(format!("Google refused the connection: {}", error)).into());
+    }
+    let code = callback
+        .code
+        .ok_or_else(|| Error::Validation("Google didn't send a code".to_string()))?;
+
+    let tokens = calendar.exchange(&code).await?;
+    let refresh_token = tokens
+        .refresh_token
+        .ok_or_else(|| Error::Unavailable("Google didn't allow access while away".to_string()))?;
+    app.timer_store
+        .connect_google_account(
+            &uid,
+            &refresh_token,
+            &tokens.access_token,
+            now + tokens.expires_in,
+        )
+        .await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        uid.as_ref()
+    )))
+}
+
+/// Stops pushing a tag's timers to Google Calendar
+#[instrument(skip(app))]
+#[debug_handler]
+async fn disconnect_google(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+) -> Result<Redirect, PageError> {
+    app.timer_store.disconnect_google_account(&tag).await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleCalendarForm {
+    /// Left empty to go back to the account's primary calendar
+    #[serde(default)]
+    calendar_id: String,
+}
+
+/// Picks the Google calendar a project's timers are pushed to
+#[instrument(skip(app))]
+#[debug_handler]
+async fn set_google_calendar(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    PagePath((_, project_id)): PagePath<(String, i64)>,
+    Form(form): Form<GoogleCalendarForm>,
+) -> Result<Redirect, PageError> {
+    let calendar_id = Some(form.calendar_id.trim()).filter(|id| !id.is_empty());
+    app.timer_store
+        .set_google_calendar(&tag, project_id, calendar_id)
+        .await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+#[derive(Deserialize)]
+struct JiraForm {
+    /// Like `https://example.atlassian.net`
+    site: String,
+    email: String,
+    api_token: String,
+}
+
+/// Connects a tag to the Jira site its timers are logged on
+#[instrument(skip(app, form))]
+#[debug_handler]
+async fn connect_jira(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Form(form): Form<JiraForm>,
+) -> Result<Redirect, PageError> {
+    app.timer_store
+        .connect_jira_account(&tag, &form.site, &form.email, &form.api_token)
+        .await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+/// Stops logging a tag's timers in Jira
+#[instrument(skip(app))]
+#[debug_handler]
+async fn disconnect_jira(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+) -> Result<Redirect, PageError> {
+    app.timer_store.disconnect_jira_account(&tag).await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+struct JiraIssueForm {
+    /// Left empty to stop logging the project
+    #[serde(default)]
+    issue_key: String,
+}
+
+/// Picks the Jira issue a project's timers are logged on
+#[instrument(skip(app))]
+#[debug_handler]
+async fn set_jira_issue(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    PagePath((_, project_id)): PagePath<(String, i64)>,
+    Form(form): Form<JiraIssueForm>,
+) -> Result<Redirect, PageError> {
+    let issue_key = Some(form.issue_key.trim()).filter(|key| !key.is_empty());
+    app.timer_store
+        .set_jira_issue(&tag, project_id, issue_key)
+        .await?;
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        tag.as_ref()
+    )))
+}
+
+/// Toggles a tag's timer from the start/stop button of its timer page
+///
+/// Takes a plain form post, so the page works without JavaScript. Only the
+/// owner's browser can press it, since it's signed in with the owner cookie.
+#[instrument(skip(store, events, headers))]
+#[debug_handler(state = App)]
+async fn toggle_from_page(
+    State(store): State<Arc<dyn TimerStore>>,
+    State(events): State<EventBus>,
+    PageOwnerTag(uid): PageOwnerTag,
+    headers: HeaderMap,
+) -> Result<Redirect, PageError> {
+    let details = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("Timer page");
+    let device = store.identify_device(&uid, details).await?;
+    let options = ToggleOptions {
+        device: Some(device),
+        ..Default::default()
+    };
+    let id = store.toggle(&uid, &options).await?;
+    debug!(id, message = "Toggled timer from page");
+    events.publish(TimerEvent::Toggled {
+        unique_id: uid.as_ref().to_string(),
+    });
+
+    Ok(Redirect::to(&format!(
+        "{}/timer/{}",
+        base_path(),
+        uid.as_ref()
+    )))
+}
+
+/// The NDEF record pointing at the timer page of a tag
+///
+/// Uses the newest short link of the tag if it has one, since it takes up
+/// less space on the tag.
+async fn timer_page_record(app: &App, tag: &uid::TagId) -> Result<ndef::UriRecord> {
+    let uri = match app.timer_store.slugs(tag).await?.first() {
+        Some(slug) => format!("{}/s/{}", uri_base(), slug.slug),
+        None => format!("{}/timer/{}", uri_base(), tag.as_ref()),
+    };
+
+    Ok(ndef::UriRecord::new(&uri))
+}
+
+/// Downloads the NDEF message to write onto an NFC tag
+#[instrument(skip(app))]
+#[debug_handler]
+async fn ndef_record(
+    State(app): State<App>,
+    Path(timer_tag): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let tag = app.timer_store.resolve_tag(&timer_tag).await?;
+    let record = timer_page_record(&app, &tag).await?;
+    let disposition = format!("attachment; filename=\"{}.ndef\"", timer_tag);
+
+    let headers = AppendHeaders([
+        (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+        (header::CONTENT_DISPOSITION, disposition),
+    ]);
+
+    Ok((headers, record.bytes().to_vec()))
+}
+
+#[derive(Debug, Serialize)]
+struct NdefExplanation {
+    uri: String,
+    hex: String,
+    explanation: Vec<String>,
+}
+
+/// Explains the NDEF message to write onto an NFC tag byte by byte
+#[instrument(skip(app))]
+#[debug_handler]
+async fn explain_ndef_record(
+    State(app): State<App>,
+    Path(timer_tag): Path<String>,
+) -> Result<Json<NdefExplanation>, AppError> {
+    let tag = app.timer_store.resolve_tag(&timer_tag).await?;
+    let record = timer_page_record(&app, &tag).await?;
+
+    Ok(Json(NdefExplanation {
+        uri: record.uri().to_string(),
+        hex: record.hex(),
+        explanation: record.explanation(),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+struct UserContent {
+    uid: TagId,
+    url: String,
+
+    /// What the toggle did, for readers to give feedback on
+    action: ToggleAction,
+
+    /// The project of the timer, unless it was discarded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+
+    /// When the timer started, as a unix epoch in UTC
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_time: Option<i64>,
+
+    /// The seconds tracked by a stopped timer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ToggleAction {
+    Started,
+    Stopped,
+
+    /// The timer was shorter than the short timer threshold and thrown away
+    Discarded,
+}
+
+#[derive(Debug, Deserialize)]
+struct Toggle {
+    #[serde(rename = "device-details")]
+    pub device_details: String,
+
+    #[serde(rename = "timer-tag")]
+    pub timer_tag: String,
+
+    /// Toggle a timer for this project instead of the current one
+    #[serde(rename = "project-id")]
+    pub project_id: Option<i64>,
+
+    /// When the tag was tapped as a unix epoch in UTC, for delayed or replayed toggles
+    #[serde(rename = "event-time", alias = "event_time")]
+    pub event_time: Option<i64>,
+}
+
+/// Header carrying the client generated key which makes replayed toggles idempotent
+pub(crate) const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+#[derive(Debug, Deserialize)]
+struct Registration {
+    #[serde(rename = "timer-tag")]
+    pub timer_tag: String,
+
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RegisteredTag {
+    uid: TagId,
+    alias: Option<String>,
+    url: String,
+
+    /// Proves ownership of the tag, only part of this response
+    owner_key: String,
+}
+
+/// The `Set-Cookie` header signing a browser in as the owner of a tag
+fn owner_cookie(uid: &TagId, key: &str) -> (header::HeaderName, String) {
+    let secure = if uri_base().starts_with("https://") {
+        "; Secure"
+    } else {
+        ""
+    };
+    let cookie = format!(
+        "{}={}; Path={}/; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        owner_cookie_name(uid),
+        key,
+        base_path(),
+        365 * 24 * 60 * 60,
+        secure
+    );
+
+    (header::SET_COOKIE, cookie)
+}
+
+/// Registers a tag, optionally with a human friendly alias to use in URLs
+///
+/// Knowing the raw tag proves to hold it, so registering issues a new owner
+/// key for the tag, replacing the previous one.
+#[instrument(skip(app))]
+#[debug_handler]
+async fn register_tag(
+    State(app): State<App>,
+    Json(registration): Json<Registration>,
+) -> Result<impl IntoResponse, AppError> {
+    let uid = uid::TagId::new(&registration.timer_tag)?;
+    let legacy = uid::TagId::legacy(&registration.timer_tag);
+    app.timer_store.migrate_legacy_tag(&legacy, &uid).await?;
+    let alias = registration.alias;
+    app.timer_store.register_tag(&uid, alias.as_deref()).await?;
+    let owner_key = app.timer_store.issue_owner_key(&uid).await?;
+
+    let url = format!(
+        "{}/timer/{}",
+        uri_base(),
+        alias.as_deref().unwrap_or(uid.as_ref())
+    );
+    Ok((
+        AppendHeaders([owner_cookie(&uid, &owner_key)]),
+        Json(RegisteredTag {
+            uid,
+            alias,
+            url,
+            owner_key,
+        }),
+    ))
+}
+
+/// The page signing a browser in as the owner of a tag
+#[debug_handler]
+async fn login_page(
+    State(app): State<App>,
+    PagePath(timer_tag): PagePath<String>,
+) -> Result<impl IntoResponse, PageError> {
+    let tag = app.timer_store.resolve_tag(&timer_tag).await?;
+
+    Ok(into_response(&templates::LoginPage::new(
+        tag.as_ref().to_string(),
+    )))
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    owner_key: String,
+}
+
+/// Signs a browser in as the owner of a tag, keeping its owner key in a cookie
+#[debug_handler]
+async fn login(
+    State(app): State<App>,
+    PagePath(timer_tag): PagePath<String>,
+    Form(form): Form<LoginForm>,
+) -> Result<impl IntoResponse, PageError> {
+    let tag = app.timer_store.resolve_tag(&timer_tag).await?;
+    let owner = app.timer_store.owner_tag(form.owner_key.trim()).await?;
+    if owner.as_ref() != tag.as_ref() {
+        return Err(Error::Forbidden("This owner key isn't valid".to_string()).into());
+    }
+
+    Ok((
+        AppendHeaders([owner_cookie(&tag, form.owner_key.trim())]),
+        Redirect::to(&format!("{}/timer/{}", base_path(), tag.as_ref())),
+    ))
+}
+
+/// Toggles the current timer for the given tag
+#[instrument(skip(app))]
+#[debug_handler]
+async fn toggle_timer(
+    State(app): State<App>,
+    headers: HeaderMap,
+    Json(toggle): Json<Toggle>,
+) -> Result<Json<UserContent>, AppError> {
+    info!(tag = ?toggle, "Toggle timer");
+    let key = headers
+        .get(IDEMPOTENCY_KEY)
+        .map(|key| key.to_str())
+        .transpose()?;
+    let (uid, timer_id) = apply_toggle(&app, &toggle, key).await?;
+
+    let alias = app.timer_store.tag_alias(&uid).await?;
+    let url = format!(
+        "{}/timer/{}",
+        uri_base(),
+        alias.as_deref().unwrap_or(uid.as_ref())
+    );
+
+    // Short timers may have been discarded when they were stopped
+    let Some(timer) = app.timer_store.find_timer(timer_id).await? else {
+        return Ok(Json(UserContent {
+            uid,
+            url,
+            action: ToggleAction::Discarded,
+            project: None,
+            start_time: None,
+            duration: None,
+        }));
+    };
+    let project = app.timer_store.project(timer.project_id).await?;
+    let (start_time, duration) = timer.seconds();
+
+    Ok(Json(UserContent {
+        uid,
+        url,
+        action: if timer.is_current {
+            ToggleAction::Started
+        } else {
+            ToggleAction::Stopped
+        },
+        project: Some(project.name),
+        start_time: Some(start_time),
+        duration: (!timer.is_current).then_some(duration),
+    }))
+}
+
+/// Toggles a timer for the tag of `toggle`, at most once per idempotency key
+async fn apply_toggle(app: &App, toggle: &Toggle, key: Option<&str>) -> Result<(TagId, i64)> {
+    let timer_tag = &toggle.timer_tag;
+
+    let uid = uid::TagId::new(timer_tag)?;
+    let legacy = uid::TagId::legacy(timer_tag);
+    app.timer_store.migrate_legacy_tag(&legacy, &uid).await?;
+
+    let device = app
+        .timer_store
+        .identify_device(&uid, &toggle.device_details)
+        .await?;
+    let options = ToggleOptions {
+        device: Some(device),
+        project_id: toggle.project_id,
+        at: toggle.event_time,
+    };
+    let (id, replayed) = match key {
+        Some(key) => app.timer_store.toggle_once(&uid, key, &options).await?,
+        None => (app.timer_store.toggle(&uid, &options).await?, false),
+    };
+
+    debug!(id, replayed, message = "Toggled timer");
+    if !replayed {
+        app.events.publish(TimerEvent::Toggled {
+            unique_id: uid.as_ref().to_string(),
+        });
+    }
+
+    Ok((uid, id))
+}
+
+/// The most toggles a single sync request can carry
+const MAX_SYNC_TOGGLES: usize = 500;
+
+#[derive(Debug, Deserialize)]
+struct SyncRequest {
+    /// The queued toggles, oldest first
+    toggles: Vec<QueuedToggle>,
+}
+
+/// A toggle recorded while the device was offline
+#[derive(Debug, Deserialize)]
+struct QueuedToggle {
+    #[serde(flatten)]
+    toggle: Toggle,
+
+    /// Makes a toggle which was synced before a no-op
+    #[serde(rename = "idempotency-key")]
+    key: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SyncResult {
+    #[serde(rename = "idempotency-key")]
+    key: String,
+
+    /// The timer which was toggled, unless the toggle failed
+    timer_id: Option<i64>,
+    error: Option<String>,
+}
+
+/// Replays the toggles a device queued while it was offline, in order
+///
+/// A failing toggle doesn't stop the ones after it, so one bad toggle can't
+/// wedge the queue of a device. Every toggle gets a result.
+#[instrument(skip(app, sync))]
+#[debug_handler]
+async fn sync_toggles(
+    State(app): State<App>,
+    Json(sync): Json<SyncRequest>,
+) -> Result<Json<Vec<SyncResult>>, AppError> {
+    if sync.toggles.len() > MAX_SYNC_TOGGLES {
+        return Err(Error::Validation(format!(
+            "At most {} toggles can be synced at once",
+            MAX_SYNC_TOGGLES
+        ))
+        .into());
+    }
+
+    info!(count = sync.toggles.len(), "Syncing queued toggles");
+    let mut results = Vec::with_capacity(sync.toggles.len());
+    for queued in sync.toggles {
+        let result = apply_toggle(&app, &queued.toggle, Some(&queued.key)).await;
+        if let Err(err) = &result {
+            warn!(key = queued.key, %err, "Unable to sync toggle");
+        }
+
+        results.push(SyncResult {
+            key: queued.key,
+            timer_id: result.as_ref().ok().map(|(_, id)| *id),
+            error: result.err().map(|err| err.to_string()),
+        });
+    }
+
+    Ok(Json(results))
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchSync {
+    #[serde(rename = "device-details")]
+    device_details: String,
+
+    /// The toggles recorded by the device, oldest first
+    events: Vec<BatchEvent>,
+}
+
+/// A tap recorded by a device while it couldn't reach the server
+#[derive(Debug, Deserialize)]
+struct BatchEvent {
+    #[serde(rename = "timer-tag")]
+    timer_tag: String,
+
+    #[serde(rename = "idempotency-key")]
+    key: String,
+
+    /// When the tag was tapped as a unix epoch in UTC
+    #[serde(rename = "event-time")]
+    event_time: i64,
+
+    #[serde(rename = "project-id")]
+    project_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchSyncResult {
+    /// The outcome of every event, in the order they were sent
+    results: Vec<SyncedToggle>,
+
+    /// The state of the timers after the batch was applied
+    timers: Vec<Timer>,
+}
+
+/// Applies the toggles recorded by an offline reader in a single transaction
+///
+/// Unlike `/timer/sync`, which does its best with every toggle, a batch is
+/// applied all or nothing, so a reader can keep its events and resend the
+/// whole batch after a failure. Events which were synced before are skipped.
+#[instrument(skip(app, batch), fields(count = batch.events.len()))]
+#[debug_handler]
+async fn sync_batch(
+    State(app): State<App>,
+    Json(batch): Json<BatchSync>,
+) -> Result<Json<BatchSyncResult>, AppError> {
+    if batch.events.len() > MAX_SYNC_TOGGLES {
+        return Err(Error::Validation(format!(
+            "At most {} toggles can be synced at once",
+            MAX_SYNC_TOGGLES
+        ))
+        .into());
+    }
+    if batch
+        .events
+        .windows(2)
+        .any(|pair| pair[1].event_time < pair[0].event_time)
+    {
+        return Err(Error::Validation("Events must be ordered by their event-time".into()).into());
+    }
+
+    let mut devices = HashMap::new();
+    let mut events = Vec::with_capacity(batch.events.len());
+    for event in batch.events {
+        let uid = uid::TagId::new(&event.timer_tag)?;
+        // Tags are resolved once, outside of the transaction applying the batch
+        if !devices.contains_key(uid.as_ref()) {
+            let legacy = uid::TagId::legacy(&event.timer_tag);
+            app.timer_store.migrate_legacy_tag(&legacy, &uid).await?;
+            let device = app
+                .timer_store
+                .identify_device(&uid, &batch.device_details)
+                .await?;
+            devices.insert(uid.as_ref().to_string(), device);
+        }
+
+        events.push(SyncEvent {
+            options: ToggleOptions {
+                device: devices.get(uid.as_ref()).cloned(),
+                project_id: event.project_id,
+                at: Some(event.event_time),
+            },
+            uid,
+            key: event.key,
+        });
+    }
+
+    let (results, timers) = app.timer_store.sync(&events).await?;
+    info!(
+        applied = results.iter().filter(|toggle| !toggle.replayed).count(),
+        "Synced batch of toggles"
+    );
+
+    let toggled: HashSet<&str> = events
+        .iter()
+        .zip(&results)
+        .filter(|(_, toggle)| !toggle.replayed)
+        .map(|(event, _)| event.uid.as_ref())
+        .collect();
+    for unique_id in toggled {
+        app.events.publish(TimerEvent::Toggled {
+            unique_id: unique_id.to_string(),
+        });
+    }
+
+    Ok(Json(BatchSyncResult { results, timers }))
+}
+
+/// Serves the web app manifest of a tag's timer page
+#[instrument(skip(app))]
+#[debug_handler]
+async fn manifest(
+    State(app): State<App>,
+    PagePath(timer_tag): PagePath<String>,
+) -> Result<pwa::Manifest, PageError> {
+    let tag = app.timer_store.find_tag(&timer_tag).await?;
+
+    Ok(pwa::Manifest::new(tag.as_ref()))
+}
+
+#[derive(Debug, Deserialize)]
+struct StopAt {
+    /// When the timer should have stopped as a unix epoch in UTC
+    #[serde(rename = "stop-at", alias = "stop_at")]
+    pub stop_at: i64,
+}
+
+/// Stops a forgotten timer retroactively
+#[debug_handler]
+async fn stop_timer_at(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, timer_id)): Path<(String, i64)>,
+    Json(stop): Json<StopAt>,
+) -> Result<Json<Option<Timer>>, AppError> {
+    info!(%tag, timer_id, ?stop, "Stopping timer retroactively");
+    let timer = app
+        .timer_store
+        .stop_timer_at(&tag, timer_id, stop.stop_at)
+        .await?;
+
+    app.events.publish(TimerEvent::Toggled {
+        unique_id: tag.as_ref().to_string(),
+    });
+
+    Ok(Json(timer))
+}
+
+/// Merges two back to back timers, like the ones left by an accidental double toggle
+#[debug_handler]
+async fn merge_timers(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, timer_id, other_id)): Path<(String, i64, i64)>,
+) -> Result<Json<Timer>, AppError> {
+    info!(%tag, timer_id, other_id, "Merging timers");
+    let timer = app
+        .timer_store
+        .merge_timers(&tag, timer_id, other_id)
+        .await?;
+
+    app.events.publish(TimerEvent::Toggled {
+        unique_id: tag.as_ref().to_string(),
+    });
+
+    Ok(Json(timer))
+}
+
+#[derive(Debug, Deserialize)]
+struct SplitAt {
+    /// Where to split the timer as a unix epoch in UTC
+    #[serde(rename = "split-at", alias = "split_at")]
+    pub split_at: i64,
+
+    /// The project the part after `split_at` moves to, the timer's own when left out
+    #[serde(rename = "project-id", alias = "project_id")]
+    pub project_id: Option<i64>,
+}
+
+/// Splits a finished timer in two, optionally moving the second part to another project
+#[debug_handler]
+async fn split_timer(
+    State(app): State<App>,
+    OwnerTag(tag): OwnerTag,
+    Path((_, timer_id)): Path<(String, i64)>,
+    Json(split): Json<SplitAt>,
+) -> Result<Json<[Timer; 2]>, AppError> {
+    info!(%tag, timer_id, ?split, "Splitting timer");
+    let (first, second) = app
+        .timer_store
+        .split_timer(&tag, timer_id, split.split_at, split.project_id)
+        .await?;
+
+    app.events.publish(TimerEvent::Toggled {
+        unique_id: tag.as_ref().to_string(),
+    });
+
+    Ok(Json([first, second]))
+}
+
+#[derive(Debug, Deserialize)]
+struct ReassignTimers {
+    #[serde(rename = "timer-ids", alias = "timer_ids")]
+    pub timer_ids: Vec<i64>,
+
+    /// The project the timers move to
+    #[serde(rename = "project-id", alias = "project_id")]
+    pub project_id: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct ReassignResult {
+    reassigned: u64,
+}
+
+/// Moves timers tracked under the wrong project to the right one
+#[debug_handler]
+async fn reassign_timers(
+    State(app): State<App>,
+    PageOwnerTag(tag): PageOwnerTag,
+    Json(reassign): Json<ReassignTimers>,
+) -> Result<Json<ReassignResult>, AppError> {
+    if reassign.timer_ids.is_empty() {
+        return Err(Error::Validation("Select at least one timer to move".to_string()).into());
+    }
+
+    let reassigned = app
+        .timer_store
+        .reassign_timers(&tag, &reassign.timer_ids, reassign.project_id)
+        .await?;
+
+    app.events.publish(TimerEvent::Toggled {
+        unique_id: tag.as_ref().to_string(),
+    });
+
+    Ok(Json(ReassignResult { reassigned }))
+}
+
+/// Renders the error page for any route which doesn't exist
+async fn not_found(uri: Uri) -> PageError {
+    Error::NotFound(format!("There is nothing at {}", uri.path())).into()
+}
+
+pub(crate) fn into_response<T: Template>(t: &T) -> Response {
+    match t.render() {
+        Ok(body) => {
+            let headers = [(
+                http::header::CONTENT_TYPE,
+                http::HeaderValue::from_static(T::MIME_TYPE),
+            )];
+
+            (headers, body).into_response()
+        }
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{memory_store::MemoryStore, time::in_seconds, webhooks::Payload};
+    use axum::{body::Body, http::Request};
+    use pretty_assertions::assert_eq;
+    use tower::ServiceExt;
+
+    /// The web server backed by an empty in-memory database
+    async fn test_app() -> (Router, DataStore) {
+        test_app_with(false).await
+    }
+
+    async fn test_app_with(read_only: bool) -> (Router, DataStore) {
+        let timer_store = DataStore::in_memory().await.unwrap();
+        let state = App {
+            read_only,
+            ..test_state(&timer_store)
+        };
+
+        (build_router(state), timer_store)
+    }
+
+    /// The state of an app without any of the optional integrations configured
+    fn test_state(timer_store: &DataStore) -> App {
+        App {
+            store: Arc::new(timer_store.clone()),
+            timer_store: timer_store.clone(),
+            events: EventBus::new(),
+            plugins: Plugins::default(),
+            backups: None,
+            toggle_links: None,
+            google: None,
+            github: GitHubWebhooks::default(),
+            admin: AdminToken::default(),
+            webhooks: Webhooks::new(timer_store.clone()),
+            read_only: false,
+        }
+    }
+
+    async fn body_text(response: Response) -> String {
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn github_webhooks_link_the_running_timer() {
+        let store = DataStore::in_memory().await.unwrap();
+        let github = GitHubWebhooks::with_secret("It's a Secret to Everybody");
+        let app = build_router(App {
+            github: github.clone(),
+            ..test_state(&store)
+        });
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+        let unsigned = |event: &str| {
+            Request::post(format!("/integrations/github/{}", uid.as_ref()))
+                .header(header::CONTENT_TYPE, "application/json")
+                .header("x-github-event", event)
+        };
+        let webhook = |event: &str, body: &'static str| {
+            unsigned(event)
+                .header("x-hub-signature-256", github.sign(body.as_bytes()))
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let push = r#"{"ref": "refs/heads/fix-login", "repository": {"html_url": "https://github.com/bIgBV/sprite"}}"#;
+
+        // Unsigned webhooks are refused, as are all of them without a secret
+        let response = app
+            .clone()
+            .oneshot(unsigned("push").body(Body::from(push)).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let (unconfigured, _) = test_app().await;
+        let response = unconfigured.oneshot(webhook("push", push)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Nothing is running yet
+        let response = app.clone().oneshot(webhook("push", push)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, "null");
+
+        store.toggle(&uid, &ToggleOptions::default()).await.unwrap();
+        let response = app.clone().oneshot(webhook("push", push)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let timer: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(
+            timer["link"],
+            "https://github.com/bIgBV/sprite/tree/fix-login"
+        );
+
+        let response = app.clone().oneshot(webhook("ping", "{}")).await.unwrap();
+        assert_eq!(body_text(response).await, "null");
+
+        // Links are set from the timer page as well, but only to web pages
+        let timer_id = timer["id"].as_i64().unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let link = |link: &str| {
+            Request::post(format!("/timer/{}/link/{}", uid.as_ref(), timer_id))
+                .header(header::AUTHORIZATION, format!("Bearer {}", owner_key))
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(format!("link={}", link)))
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(link("javascript:alert(1)"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app.clone().oneshot(link("")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let running = store.find_timer(timer_id).await.unwrap().unwrap();
+        assert_eq!(running.link, None);
+    }
+
+    #[tokio::test]
+    async fn timers_are_toggled_over_http() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let toggle = || {
+            Request::post("/timer/toggle")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    r#"{"timer-tag": "test-tag", "device-details": "test-reader"}"#,
+                ))
+                .unwrap()
+        };
+
+        // Until the tag has a project, the reader is asked to create one
+        let response = app.clone().oneshot(toggle()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let content: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(content["error"], "no_project");
+
+        store.create_project(&uid, "writing").await.unwrap();
+        let response = app.clone().oneshot(toggle()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let content: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(content["uid"], uid.as_ref());
+        assert_eq!(content["action"], "started");
+        assert_eq!(content["project"], "writing");
+        assert!(content["start_time"].is_i64());
+        assert!(content.get("duration").is_none());
+
+        // Links on the page point to the host the page was requested from
+        let request = Request::get(format!("/timer/{}", uid.as_ref()))
+            .header(header::HOST, "sprite.test:8080")
+            .body(Body::empty())
+            .unwrap();
+        let page = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(page.status(), StatusCode::OK);
+        let page = body_text(page).await;
+        assert!(page.contains("Stop timer"));
+        assert!(page.contains(&format!("http://sprite.test:8080/timer/{}/", uid.as_ref())));
+
+        let response = app.clone().oneshot(toggle()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let content: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(content["action"], "stopped");
+        assert_eq!(content["project"], "writing");
+        assert_eq!(content["duration"], 0);
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        assert!(projects.values().flatten().all(|timer| !timer.is_current));
+
+        // Readers hear about short timers being thrown away
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                short_timer_threshold: 30,
+                short_timer_policy: ShortTimerPolicy::Discard,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        app.clone().oneshot(toggle()).await.unwrap();
+        let response = app.oneshot(toggle()).await.unwrap();
+        let content: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(content["action"], "discarded");
+        assert!(content.get("project").is_none());
+    }
+
+    /// Accepts connections without ever answering, like a receiver which hangs
+    fn hanging_receiver() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        format!("http://{}/hook", address)
+    }
+
+    #[tokio::test]
+    async fn toggles_never_wait_for_integrations() {
+        let stall = std::time::Duration::from_secs(2);
+        let store = DataStore::in_memory()
+            .await
+            .unwrap()
+            .with_plugins(Plugins::stalling(stall));
+        let webhooks = Webhooks::new(store.clone());
+        let app = build_router(App {
+            webhooks: webhooks.clone(),
+            ..test_state(&store)
+        });
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+
+        // Every webhook of the tag hangs, with deliveries already waiting on it
+        let webhook = hanging_receiver();
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                reminder_webhook: Some(webhook.clone()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        for kind in ["idle", "long_running", "budget", "goal"] {
+            let payload = Payload::json(kind, &serde_json::json!({ "kind": kind })).unwrap();
+            webhooks.deliver(&uid, &webhook, payload);
+        }
+
+        let toggle = || {
+            Request::post("/timer/toggle")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    r#"{"timer-tag": "test-tag", "device-details": "test-reader"}"#,
+                ))
+                .unwrap()
+        };
+        for action in ["started", "stopped", "started", "stopped"] {
+            let started = std::time::Instant::now();
+            let response = app.clone().oneshot(toggle()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let content: serde_json::Value =
+                serde_json::from_str(&body_text(response).await).unwrap();
+            assert_eq!(content["action"], action);
+            assert!(
+                started.elapsed() < stall / 2,
+                "Toggling took {:?}",
+                started.elapsed()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn projects_are_created_over_http() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let create = |name: &str, key: &str| {
+            Request::post(format!("/project/{}/create", uid.as_ref()))
+                .header(header::AUTHORIZATION, format!("Bearer {}", key))
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(format!("name={}", name)))
+                .unwrap()
+        };
+
+        // Knowing the tag isn't enough to create projects for it
+        let response = app
+            .clone()
+            .oneshot(create("writing", "not-the-key"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(store.projects_by_tag(&uid).await.unwrap().is_empty());
+
+        let response = app
+            .clone()
+            .oneshot(create("writing", &owner_key))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            format!("/timer/{}", uid.as_ref())
+        );
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        let names: Vec<_> = projects
+            .keys()
+            .map(|project| project.name.as_str())
+            .collect();
+        assert_eq!(names, ["writing"]);
+
+        // Rejected names render the form again with the error
+        for (name, error) in [
+            ("+++", "must be between 1 and 80 characters"),
+            ("+Writing+", "already is a project named"),
+        ] {
+            let response = app.clone().oneshot(create(name, &owner_key)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+            let page = body_text(response).await;
+            assert!(page.contains("is-invalid"));
+            assert!(page.contains(error));
+        }
+        assert_eq!(store.projects_by_tag(&uid).await.unwrap().len(), 1);
+
+        let response = app.oneshot(get("/timer/unknown-tag")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn themes_are_switched_over_http() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let page = || get(&format!("/timer/{}", uid.as_ref()));
+
+        let response = app.clone().oneshot(page()).await.unwrap();
+        assert!(body_text(response)
+            .await
+            .contains(r#"data-bs-theme="light""#));
+
+        let request = Request::post(format!("/settings/{}/theme", uid.as_ref()))
+            .header(header::AUTHORIZATION, format!("Bearer {}", owner_key))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from("theme=dark"))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(store.settings(&uid).await.unwrap().theme, Theme::Dark);
+
+        let response = app.clone().oneshot(page()).await.unwrap();
+        assert!(body_text(response)
+            .await
+            .contains(r#"data-bs-theme="dark""#));
+
+        let request = Request::post(format!("/settings/{}/theme", uid.as_ref()))
+            .header(header::AUTHORIZATION, format!("Bearer {}", owner_key))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from("theme=sepia"))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert!(response.status().is_client_error());
+    }
+
+    #[tokio::test]
+    async fn weeks_are_shown_by_day() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let writing = store.create_project(&uid, "writing").await.unwrap();
+        let project = store.project(writing).await.unwrap();
+        let start = chrono_tz::US::Pacific
+            .with_ymd_and_hms(2026, 10, 13, 10, 0, 0)
+            .unwrap()
+            .timestamp();
+        store
+            .import_timers(&project, [(start, 90 * 60)].map(in_seconds))
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(get(&format!("/timer/{}/week/2026-10-15", uid.as_ref())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let page = body_text(response).await;
+        assert!(page.contains("Week of 2026-10-12"));
+        assert!(page.contains("Tue, 2026-10-13"));
+        assert!(page.contains("<td>10:00</td>"));
+        assert!(page.contains("<td>11:30</td>"));
+        assert!(page.contains("/week/2026-10-05?timezone=US-Pacific"));
+        assert!(page.contains("/week/2026-10-19?timezone=US-Pacific"));
+        assert_eq!(page.matches("Nothing tracked").count(), 6);
+
+        let response = app
+            .oneshot(get(&format!("/timer/{}/week/yesterday", uid.as_ref())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn activity_is_shown_in_words() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+        let options = ToggleOptions {
+            at: Some(Utc::now().timestamp() - 2 * 3600),
+            ..Default::default()
+        };
+        store.toggle(&uid, &options).await.unwrap();
+        store.toggle_current(&uid).await.unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(get(&format!("/timer/{}/activity", uid.as_ref())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let page = body_text(response).await;
+        assert!(page.contains("Stopped writing after 2:00"));
+        assert!(page.contains("Created project writing"));
+        assert!(page.contains("Started writing"));
+        assert!(page.contains("2 h ago"));
+        assert!(page.contains("just now"));
+
+        let response = app
+            .oneshot(get(&format!("/timer/{}/activity?limit=1", uid.as_ref())))
+            .await
+            .unwrap();
+        let page = body_text(response).await;
+        assert!(page.contains("Stopped writing"));
+        assert!(!page.contains("Started writing"));
+    }
+
+    #[tokio::test]
+    async fn the_timer_page_is_negotiated() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(
+                &project,
+                [(1_700_000_000, 5_400), (1_600_000_000, 60)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+        let request = |uri: String, accept: &str| {
+            Request::get(uri)
+                .header(header::ACCEPT, accept)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        for uri in [
+            format!("/timer/{}", uid.as_ref()),
+            format!("/timer/{}/US-Pacific", uid.as_ref()),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(request(uri, "application/json"))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let content: serde_json::Value =
+                serde_json::from_str(&body_text(response).await).unwrap();
+            assert_eq!(content[0]["project"]["name"], "writing");
+            assert_eq!(content[0]["timers"][0]["start_time"], 1_600_000_000);
+            assert_eq!(content[0]["timers"][1]["duration"], 5_400);
+        }
+
+        // Browsers accept anything, but HTML comes first
+        let response = app
+            .clone()
+            .oneshot(request(
+                format!("/timer/{}", uid.as_ref()),
+                "text/html,application/json;q=0.9,*/*;q=0.8",
+            ))
+            .await
+            .unwrap();
+        assert!(body_text(response).await.starts_with("<!DOCTYPE html>"));
+
+        let response = app
+            .oneshot(request(
+                "/timer/unknown-tag".to_string(),
+                "application/json",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let content: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert!(content["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn projects_have_their_own_page() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(
+                &project,
+                [(1_700_000_000, 5_400), (1_700_100_000, 1_800)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+        store
+            .set_project_budget(&uid, project_id, Some(10 * 3600))
+            .await
+            .unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let page = |path: String, key: Option<&str>| {
+            let request = Request::get(path);
+            let request = match key {
+                Some(key) => request.header(
+                    header::COOKIE,
+                    format!("{}={}", owner_cookie_name(&uid), key),
+                ),
+                None => request,
+            };
+            request.body(Body::empty()).unwrap()
+        };
+        let path = format!(
+            "/project/{}/{}?timezone=US-Pacific",
+            uid.as_ref(),
+            project_id
+        );
+
+        // Only the owner sees it, and the tag isn't part of the page
+        let response = app.clone().oneshot(page(path.clone(), None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(page(path.clone(), Some(&owner_key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let page_text = body_text(response).await;
+        assert!(page_text.contains("<title>writing</title>"));
+        assert!(!page_text.contains(uid.as_ref()));
+        assert!(page_text.contains("<dd class=\"col-sm-8\">2</dd>"));
+        assert!(page_text.contains("<dd class=\"col-sm-8\">2:00</dd>"));
+        assert!(page_text.contains("<dd class=\"col-sm-8\">1:30</dd>"));
+        assert!(page_text.contains(&format!(
+            "/export/US-Pacific/{}?duration=decimal",
+            project_id
+        )));
+        assert!(page_text.contains("value=\"10\""));
+
+        // The main page links to it
+        let response = app
+            .clone()
+            .oneshot(get(&format!("/timer/{}", uid.as_ref())))
+            .await
+            .unwrap();
+        let main_page = body_text(response).await;
+        assert!(main_page.contains(&path));
+
+        // Other tags' projects aren't found, even by their owner
+        let other = TagId::new("other-tag").unwrap();
+        let other_project = store.create_project(&other, "reading").await.unwrap();
+        let response = app
+            .oneshot(page(
+                format!("/project/{}/{}", uid.as_ref(), other_project),
+                Some(&owner_key),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn admin_routes_need_the_admin_token() {
+        let store = DataStore::in_memory().await.unwrap();
+        let app = build_router(App {
+            admin: AdminToken::new("correct horse battery staple"),
+            ..test_state(&store)
+        });
+        let inactive = |days: &str, token: Option<&str>| {
+            let request = Request::get(format!("/admin/tags/inactive/{}", days));
+            let request = match token {
+                Some(token) => request.header(header::AUTHORIZATION, format!("Bearer {}", token)),
+                None => request,
+            };
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(inactive("30", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(inactive("30", Some("correct horse")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(inactive("30", Some("correct horse battery staple")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Spans too long to represent are rejected rather than overflowing
+        let response = app
+            .clone()
+            .oneshot(inactive(
+                &i64::MAX.to_string(),
+                Some("correct horse battery staple"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Without a configured token, nobody gets in
+        let (unconfigured, _) = test_app().await;
+        let response = unconfigured
+            .oneshot(inactive("30", Some("")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Backups are only started by the admin too
+        let backup = |token: Option<&str>| {
+            let request = Request::post("/admin/backup");
+            let request = match token {
+                Some(token) => request.header(header::AUTHORIZATION, format!("Bearer {}", token)),
+                None => request,
+            };
+            request.body(Body::empty()).unwrap()
+        };
+        let response = app.clone().oneshot(backup(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .oneshot(backup(Some("correct horse battery staple")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn projects_are_exported_over_http() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(&project, [(1_700_000_000, 5_400)].map(in_seconds))
+            .await
+            .unwrap();
+
+        let uri = format!(
+            "/export/US-Pacific/{}?columns=project,duration&duration=decimal",
+            project_id
+        );
+        let response = app.clone().oneshot(get(&uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv");
+        assert_eq!(
+            body_text(response).await,
+            "project,duration\nwriting,1.50\n"
+        );
+
+        let response = app.oneshot(get("/export/US-Pacific/404")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn exports_are_filtered_by_labels() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        let starts = [1_700_000_000, 1_700_010_000, 1_700_020_000];
+        store
+            .import_timers(
+                &project,
+                [(starts[0], 3_600), (starts[1], 1_800), (starts[2], 900)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+        let timers = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap();
+        let id = |start: i64| {
+            timers
+                .iter()
+                .find(|timer| timer.start_time_ms.seconds() == start)
+                .unwrap()
+                .id
+        };
+        for (start, label) in [
+            (starts[0], "meeting"),
+            (starts[1], "meeting"),
+            (starts[1], "billable"),
+        ] {
+            store.add_timer_label(&uid, id(start), label).await.unwrap();
+        }
+
+        let export = |query: &str| {
+            let uri = format!(
+                "/export/US-Pacific/{}?columns=duration&duration=decimal&{}",
+                project_id, query
+            );
+            let app = app.clone();
+            async move { body_text(app.oneshot(get(&uri)).await.unwrap()).await }
+        };
+        assert_eq!(export("").await, "duration\n0.25\n0.50\n1.00\n");
+        assert_eq!(export("labels=meeting").await, "duration\n0.50\n1.00\n");
+        // Any of the labels by default, all of them when asked for
+        assert_eq!(export("labels=billable,missing").await, "duration\n0.50\n");
+        assert_eq!(
+            export("labels=meeting,billable&label_match=all").await,
+            "duration\n0.50\n"
+        );
+        assert_eq!(
+            export("labels=meeting,missing&label_match=all").await,
+            "duration\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn team_routes_need_the_owner_key() {
+        let (app, store) = test_app().await;
+        let owner = TagId::new("owner-tag").unwrap();
+        let viewer = TagId::new("viewer-tag").unwrap();
+        let team = store.create_team(&owner, "Agency").await.unwrap();
+        store
+            .set_team_member(&owner, team.id, &viewer, Role::Viewer)
+            .await
+            .unwrap();
+        let owner_key = store.issue_owner_key(&owner).await.unwrap();
+        let viewer_key = store.issue_owner_key(&viewer).await.unwrap();
+        let promote = |key: &str| {
+            Request::post(format!("/team/{}/{}/members", team.id, owner.as_ref()))
+                .header(header::AUTHORIZATION, format!("Bearer {}", key))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"member": "viewer-tag", "role": "owner"}"#))
+                .unwrap()
+        };
+
+        // Knowing the owner's tag doesn't let a viewer act as the owner
+        let response = app.clone().oneshot(promote(&viewer_key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(get(&format!("/team/{}/{}", team.id, viewer.as_ref())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.clone().oneshot(promote(&owner_key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let request = Request::get(format!("/team/{}/{}", team.id, viewer.as_ref()))
+            .header(header::AUTHORIZATION, format!("Bearer {}", viewer_key))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let report = body_text(response).await;
+        assert!(!report.contains(owner.as_ref()));
+        assert!(report.contains(r#""role":"owner""#));
+    }
+
+    #[tokio::test]
+    async fn timers_are_toggled_from_the_page_by_the_owner() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let press = |cookie: Option<String>| {
+            let request = Request::post(format!("/timer/{}/toggle", uid.as_ref()));
+            match cookie {
+                Some(cookie) => request.header(header::COOKIE, cookie),
+                None => request,
+            }
+            .body(Body::empty())
+            .unwrap()
+        };
+
+        // Anyone who saw the timer page could otherwise start and stop timers
+        let response = app.clone().oneshot(press(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        assert!(projects.values().flatten().all(|timer| !timer.is_current));
+
+        let cookie = format!("{}={}", owner_cookie_name(&uid), owner_key);
+        let response = app.oneshot(press(Some(cookie))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        assert!(projects.values().flatten().any(|timer| timer.is_current));
+    }
+
+    #[tokio::test]
+    async fn calendars_are_imported_into_the_owners_projects() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let other_project = store.create_project(&other, "reading").await.unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let import = |project_id: i64, key: Option<&str>| {
+            let request = Request::post(format!(
+                "/project/{}/import/{}/UTC",
+                uid.as_ref(),
+                project_id
+            ));
+            match key {
+                Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {}", key)),
+                None => request,
+            }
+            .body(Body::from(
+                "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:Standup\r\n\
+                 DTSTART:20240102T170000Z\r\nDTEND:20240102T171500Z\r\n\
+                 END:VEVENT\r\nEND:VCALENDAR\r\n",
+            ))
+            .unwrap()
+        };
+
+        let response = app.clone().oneshot(import(project_id, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // The owner key of one tag doesn't reach into another tag's projects
+        let response = app
+            .clone()
+            .oneshot(import(other_project, Some(&owner_key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(store
+            .projects_by_tag(&other)
+            .await
+            .unwrap()
+            .values()
+            .all(Vec::is_empty));
+
+        let response = app
+            .oneshot(import(project_id, Some(&owner_key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, r#"{"imported":1}"#);
+    }
+
+    #[tokio::test]
+    async fn devices_are_listed_only_for_the_owner() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        store.identify_device(&uid, "test-reader").await.unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let page = |cookie: Option<String>| {
+            let request = Request::get(format!("/devices/{}", uid.as_ref()));
+            match cookie {
+                Some(cookie) => request.header(header::COOKIE, cookie),
+                None => request,
+            }
+            .body(Body::empty())
+            .unwrap()
+        };
+
+        let response = app.clone().oneshot(page(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(!body_text(response).await.contains("test-reader"));
+
+        let cookie = format!("{}={}", owner_cookie_name(&uid), owner_key);
+        let response = app.oneshot(page(Some(cookie))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(body_text(response).await.contains("test-reader"));
+    }
+
+    #[tokio::test]
+    async fn devices_are_revoked_by_the_owner() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let device = store.identify_device(&uid, "test-reader").await.unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let revoke = |cookie: Option<String>| {
+            let request = Request::post(format!("/devices/{}/{}/revoke", uid.as_ref(), device.id));
+            match cookie {
+                Some(cookie) => request.header(header::COOKIE, cookie),
+                None => request,
+            }
+            .body(Body::empty())
+            .unwrap()
+        };
+
+        let response = app.clone().oneshot(revoke(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(!store.devices(&uid).await.unwrap()[0].revoked);
+
+        let cookie = format!("{}={}", owner_cookie_name(&uid), owner_key);
+        let response = app.oneshot(revoke(Some(cookie))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert!(store.devices(&uid).await.unwrap()[0].revoked);
+    }
+
+    #[tokio::test]
+    async fn scheduled_exports_need_the_owner_key() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let schedule = |key: Option<&str>| {
+            let request =
+                Request::post(format!("/project/{}/exports/{}", uid.as_ref(), project_id))
+                    .header(header::CONTENT_TYPE, "application/json");
+            match key {
+                Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {}", key)),
+                None => request,
+            }
+            .body(Body::from(
+                r#"{"schedule": "weekly", "webhook": "https://example.com/hook"}"#,
+            ))
+            .unwrap()
+        };
+
+        // Knowing the tag isn't enough to send its timers elsewhere
+        let response = app.clone().oneshot(schedule(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(store.scheduled_exports(&uid).await.unwrap().is_empty());
+
+        let response = app.oneshot(schedule(Some(&owner_key))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(store.scheduled_exports(&uid).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn forgotten_timers_are_stopped_by_the_owner() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+        let started = Utc::now().timestamp() - 3600;
+        let options = ToggleOptions {
+            at: Some(started),
+            ..Default::default()
+        };
+        let timer_id = store.toggle(&uid, &options).await.unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let stop = |key: Option<&str>| {
+            let request = Request::post(format!("/timer/{}/stop_at/{}", uid.as_ref(), timer_id))
+                .header(header::CONTENT_TYPE, "application/json");
+            match key {
+                Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {}", key)),
+                None => request,
+            }
+            .body(Body::from(format!(r#"{{"stop-at": {}}}"#, started + 600)))
+            .unwrap()
+        };
+
+        let response = app.clone().oneshot(stop(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let timer = store.find_timer(timer_id).await.unwrap().unwrap();
+        assert!(timer.is_current);
+
+        let response = app.oneshot(stop(Some(&owner_key))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let timer = store.find_timer(timer_id).await.unwrap().unwrap();
+        assert!(!timer.is_current);
+        assert_eq!(timer.seconds(), (started, 600));
+    }
+
+    #[tokio::test]
+    async fn timers_are_merged_by_the_owner() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+        let started = Utc::now().timestamp() - 3600;
+        let mut timer_ids = Vec::new();
+        for at in [started, started + 600, started + 700, started + 1600] {
+            let options = ToggleOptions {
+                at: Some(at),
+                ..Default::default()
+            };
+            timer_ids.push(store.toggle(&uid, &options).await.unwrap());
+        }
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let merge = |key: Option<&str>| {
+            let request = Request::post(format!(
+                "/timer/{}/merge/{}/{}",
+                uid.as_ref(),
+                timer_ids[0],
+                timer_ids[2]
+            ));
+            match key {
+                Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {}", key)),
+                None => request,
+            }
+            .body(Body::empty())
+            .unwrap()
+        };
+
+        let response = app.clone().oneshot(merge(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(store.find_timer(timer_ids[2]).await.unwrap().is_some());
+
+        let response = app.oneshot(merge(Some(&owner_key))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(store.find_timer(timer_ids[2]).await.unwrap().is_none());
+        let timer = store.find_timer(timer_ids[0]).await.unwrap().unwrap();
+        assert_eq!(timer.seconds(), (started, 1600));
+    }
+
+    #[tokio::test]
+    async fn timers_are_split_by_the_owner() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+        let started = Utc::now().timestamp() - 3600;
+        let mut timer_ids = Vec::new();
+        for at in [started, started + 1200] {
+            let options = ToggleOptions {
+                at: Some(at),
+                ..Default::default()
+            };
+            timer_ids.push(store.toggle(&uid, &options).await.unwrap());
+        }
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let split = |key: Option<&str>| {
+            let request = Request::post(format!("/timer/{}/split/{}", uid.as_ref(), timer_ids[0]))
+                .header(header::CONTENT_TYPE, "application/json");
+            match key {
+                Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {}", key)),
+                None => request,
+            }
+            .body(Body::from(format!(r#"{{"split-at": {}}}"#, started + 600)))
+            .unwrap()
+        };
+
+        let response = app.clone().oneshot(split(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let timer = store.find_timer(timer_ids[0]).await.unwrap().unwrap();
+        assert_eq!(timer.seconds(), (started, 1200));
+
+        let response = app.oneshot(split(Some(&owner_key))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let timers: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(timers.as_array().unwrap().len(), 2);
+        let timer = store.find_timer(timer_ids[0]).await.unwrap().unwrap();
+        assert_eq!(timer.seconds(), (started, 600));
+    }
+
+    #[tokio::test]
+    async fn timers_are_reassigned_by_the_owner() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let writing = store.create_project(&uid, "writing").await.unwrap();
+        let reading = store.create_project(&uid, "reading").await.unwrap();
+        let options = ToggleOptions {
+            project_id: Some(writing),
+            ..Default::default()
+        };
+        let timer_id = store.toggle(&uid, &options).await.unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let reassign = |cookie: Option<String>| {
+            let request = Request::post(format!("/timers/{}/reassign", uid.as_ref()))
+                .header(header::CONTENT_TYPE, "application/json");
+            match cookie {
+                Some(cookie) => request.header(header::COOKIE, cookie),
+                None => request,
+            }
+            .body(Body::from(format!(
+                r#"{{"timer-ids": [{}], "project-id": {}}}"#,
+                timer_id, reading
+            )))
+            .unwrap()
+        };
+
+        let response = app.clone().oneshot(reassign(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let timer = store.find_timer(timer_id).await.unwrap().unwrap();
+        assert_eq!(timer.project_id, writing);
+
+        let cookie = format!("{}={}", owner_cookie_name(&uid), owner_key);
+        let response = app.oneshot(reassign(Some(cookie))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, r#"{"reassigned":1}"#);
+        let timer = store.find_timer(timer_id).await.unwrap().unwrap();
+        assert_eq!(timer.project_id, reading);
+    }
+
+    #[tokio::test]
+    async fn webhook_secrets_need_the_owner_key() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let url = "https://example.com/hook";
+        let secret = |path: &str, key: Option<&str>| {
+            let request = Request::post(format!("/settings/{}/{}", uid.as_ref(), path))
+                .header(header::CONTENT_TYPE, "application/json");
+            match key {
+                Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {}", key)),
+                None => request,
+            }
+            .body(Body::from(format!(r#"{{"url": "{}"}}"#, url)))
+            .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(secret("webhook-secret", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(store.webhook_secret(&uid, url).await.unwrap(), None);
+
+        let response = app
+            .clone()
+            .oneshot(secret("webhook-secret", Some(&owner_key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let created: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(created["url"], url);
+        let stored = store.webhook_secret(&uid, url).await.unwrap();
+        assert_eq!(created["secret"].as_str(), stored.as_deref());
+
+        // Rotating replaces the secret
+        let response = app
+            .clone()
+            .oneshot(secret("webhook-secret", Some(&owner_key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let rotated = store.webhook_secret(&uid, url).await.unwrap();
+        assert!(rotated.is_some());
+        assert_ne!(rotated, stored);
+
+        let response = app
+            .clone()
+            .oneshot(secret("webhook-secret/remove", Some(&owner_key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(store.webhook_secret(&uid, url).await.unwrap(), None);
+        let response = app
+            .oneshot(secret("webhook-secret/remove", Some(&owner_key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn failed_webhooks_are_redelivered_by_the_admin() {
+        let store = DataStore::in_memory().await.unwrap();
+        let app = build_router(App {
+            admin: AdminToken::new("correct horse battery staple"),
+            ..test_state(&store)
+        });
+        // Nothing listens on the port, so redelivering fails again
+        let webhook = FailedWebhook {
+            id: 0,
+            unique_id: "test-tag".to_string(),
+            url: "http://127.0.0.1:1/hook".to_string(),
+            kind: "export".to_string(),
+            content_type: "text/csv".to_string(),
+            filename: Some("writing.csv".to_string()),
+            body: b"project,duration\n".to_vec(),
+            status: Some(502),
+            response: "Bad Gateway".to_string(),
+            attempts: 4,
+            failed_at: 1_700_000_000,
+            next_attempt_at: None,
+        };
+        let id = store.record_failed_webhook(&webhook).await.unwrap();
+        let admin = |request: http::request::Builder, token: Option<&str>| {
+            match token {
+                Some(token) => request.header(header::AUTHORIZATION, format!("Bearer {}", token)),
+                None => request,
+            }
+            .body(Body::empty())
+            .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(admin(Request::get("/admin/webhooks/failed"), None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(admin(
+                Request::get("/admin/webhooks/failed"),
+                Some("correct horse battery staple"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let listed: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(listed[0]["id"], id);
+        assert_eq!(listed[0]["status"], 502);
+        assert_eq!(listed[0]["response"], "Bad Gateway");
+        assert!(listed[0].get("body").is_none());
+
+        let redeliver = format!("/admin/webhooks/failed/{}/redeliver", id);
+        let response = app
+            .clone()
+            .oneshot(admin(Request::post(&redeliver), None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(admin(
+                Request::post(&redeliver),
+                Some("correct horse battery staple"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, r#"{"delivered":0,"failed":1}"#);
+        let failed = store.failed_webhook(id).await.unwrap();
+        assert_eq!(failed.status, None);
+        assert_eq!(failed.attempts, 5);
+
+        // Bulk redelivery can be narrowed to the receiver which was fixed
+        let response = app
+            .clone()
+            .oneshot(admin(
+                Request::post("/admin/webhooks/failed/redeliver?url=https://example.com/other"),
+                Some("correct horse battery staple"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(body_text(response).await, r#"{"delivered":0,"failed":0}"#);
+        let response = app
+            .oneshot(admin(
+                Request::post("/admin/webhooks/failed/redeliver"),
+                Some("correct horse battery staple"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(body_text(response).await, r#"{"delivered":0,"failed":1}"#);
+        assert_eq!(store.failed_webhook(id).await.unwrap().attempts, 6);
+    }
+
+    #[tokio::test]
+    async fn api_tokens_need_the_owner_key() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let uri = format!("/api-tokens/{}", uid.as_ref());
+        let with_key = |key: &str| {
+            Request::post(&uri)
+                .header(header::AUTHORIZATION, format!("Bearer {}", key))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let registration = Request::post("/tag/register")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"timer-tag": "test-tag"}"#))
+            .unwrap();
+        let response = app.clone().oneshot(registration).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookie = response.headers()[header::SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(cookie.contains("HttpOnly; SameSite=Lax"));
+        let registered: serde_json::Value =
+            serde_json::from_str(&body_text(response).await).unwrap();
+        let owner_key = registered["owner_key"].as_str().unwrap().to_string();
+
+        let response = app
+            .clone()
+            .oneshot(Request::post(&uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.clone().oneshot(get(&uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Another tag's key, or an API token, doesn't prove ownership
+        let other = store
+            .issue_owner_key(&TagId::new("other-tag").unwrap())
+            .await
+            .unwrap();
+        let response = app.clone().oneshot(with_key(&other)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let api_token = store
+            .create_token(&uid, TokenKind::Api)
+            .await
+            .unwrap()
+            .token;
+        let response = app.clone().oneshot(with_key(&api_token)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.clone().oneshot(with_key(&owner_key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let created: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(created["token"].as_str().unwrap().len(), 32);
+
+        // The secret is never listed again, and the cookie works like the header
+        let request = Request::get(&uri)
+            .header(header::COOKIE, cookie.split(';').next().unwrap())
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let listed: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(listed.as_array().unwrap().len(), 2);
+        assert!(listed[0].get("token").is_none());
+    }
+
+    #[tokio::test]
+    async fn accounts_are_deleted_only_by_their_owner() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let deletion = format!("/account/{}/deletion", uid.as_ref());
+        let request = |key: Option<&str>| {
+            let request = Request::post(&deletion);
+            match key {
+                Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {}", key)),
+                None => request,
+            }
+            .body(Body::empty())
+            .unwrap()
+        };
+
+        // Neither anonymous callers nor API tokens, which anyone could once
+        // mint, reach the deletion
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post(format!("/api-tokens/{}", uid.as_ref()))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let api_token = store
+            .create_token(&uid, TokenKind::Api)
+            .await
+            .unwrap()
+            .token;
+        let response = app
+            .clone()
+            .oneshot(request(Some(&api_token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::delete(format!("/account/{}?confirmation=x", uid.as_ref()))
+                    .header(header::AUTHORIZATION, format!("Bearer {}", api_token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(store.project(project_id).await.is_ok());
+
+        let owner_key = store.issue_owner_key(&uid).await.unwrap();
+        let response = app
+            .clone()
+            .oneshot(request(Some(&owner_key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let issued: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        let confirmation = issued["token"].as_str().unwrap();
+        let response = app
+            .oneshot(
+                Request::delete(format!(
+                    "/account/{}?confirmation={}",
+                    uid.as_ref(),
+                    confirmation
+                ))
+                .header(header::AUTHORIZATION, format!("Bearer {}", owner_key))
+                .body(Body::empty())
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(store.project(project_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn toggle_links_toggle_when_opened() {
+        let timer_store = DataStore::in_memory().await.unwrap();
+        let app = build_router(App {
+            toggle_links: Some(ToggleLinks::new("a secret which is long enough")),
+            ..test_state(&timer_store)
+        });
+        let uid = TagId::new("test-tag").unwrap();
+        timer_store.create_project(&uid, "writing").await.unwrap();
+        let owner_key = timer_store.issue_owner_key(&uid).await.unwrap();
+        let mint = |key: Option<&str>| {
+            let request = Request::post(format!("/timer/{}/toggle-link", uid.as_ref()));
+            match key {
+                Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {}", key)),
+                None => request,
+            }
+            .body(Body::empty())
+            .unwrap()
+        };
+        let running = || async {
+            timer_store
+                .tag_status(&uid)
+                .await
+                .unwrap()
+                .start_time
+                .is_some()
+        };
+
+        let response = app.clone().oneshot(mint(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.clone().oneshot(mint(Some(&owner_key))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let link: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        let path = link["url"]
+            .as_str()
+            .unwrap()
+            .split_once("/t/")
+            .map(|(_, token)| format!("/t/{}", token))
+            .unwrap();
+
+        // Opening the link toggles, and leads to the timer page
+        assert!(!running().await);
+        let response = app.clone().oneshot(get(&path)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            format!("/timer/{}", uid.as_ref())
+        );
+        assert!(running().await);
+
+        // Opening it again right away doesn't toggle back
+        let response = app.clone().oneshot(get(&path)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert!(running().await);
+
+        // A new link invalidates the old one
+        let response = app.clone().oneshot(mint(Some(&owner_key))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(get(&path)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(running().await);
+    }
+
+    #[tokio::test]
+    async fn accounts_are_exported_as_zip() {
+        let (app, store) = test_app().await;
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "Über uns").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(&project, [(1_700_000_000, 5_400)].map(in_seconds))
+            .await
+            .unwrap();
+        let token = store
+            .create_token(&uid, TokenKind::Api)
+            .await
+            .unwrap()
+            .token;
+
+        let response = app.clone().oneshot(get("/export/all")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let request = Request::get("/export/all?timezone=Europe-Berlin")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/zip");
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(body)).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+        let csv_name = format!("projects/{}-Über-uns.csv", project_id);
+        assert_eq!(names, ["account.json", csv_name.as_str()]);
+
+        let csv = std::io::read_to_string(zip.by_name(&csv_name).unwrap()).unwrap();
+        assert_eq!(
+            csv.lines().next().unwrap(),
+            "start_time,end_time,duration,project,client,note,labels,device"
+        );
+        // The timer started 2023-11-14 22:13 UTC
+        assert!(csv.contains("\n2023-11-14 23:13,2023-11-15 00:43,1:30,Über uns,"));
+
+        let account: serde_json::Value =
+            serde_json::from_reader(zip.by_name("account.json").unwrap()).unwrap();
+        assert_eq!(account["unique_id"], uid.as_ref());
+        assert_eq!(account["projects"][0]["name"], "Über uns");
+        assert_eq!(account["settings"]["streak_minutes"], 30);
+    }
+
+    #[tokio::test]
+    async fn read_only_servers_refuse_changes() {
+        let (app, store) = test_app_with(true).await;
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(get(&format!("/timer/{}", uid.as_ref())))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::post("/timer/toggle")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"timer-tag": "test-tag"}"#))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let content: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+        assert_eq!(content["error"], "unavailable");
+
+        // Browsers get a page
+        let request = Request::post("/t/some-token/toggle")
+            .header(header::ACCEPT, "text/html,*/*")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body_text(response).await.contains("read-only"));
+
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        assert!(projects.values().all(Vec::is_empty));
+    }
+
+    #[tokio::test]
+    async fn the_timer_page_toggles_in_memory() {
+        let store: Arc<dyn TimerStore> = Arc::new(MemoryStore::default());
+        let events = EventBus::new();
+        let mut toggles = events.subscribe();
+        let uid = TagId::new("test-tag").unwrap();
+        let timer_tag = uid.as_ref().to_string();
+
+        store.create_project(&uid, "writing").await.unwrap();
+
+        let redirect = toggle_from_page(
+            State(store.clone()),
+            State(events.clone()),
+            PageOwnerTag(uid.clone()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        assert_eq!(
+            redirect.headers()[header::LOCATION],
+            format!("/timer/{}", timer_tag)
+        );
+        assert!(matches!(
+            toggles.try_recv(),
+            Ok(TimerEvent::Toggled { unique_id }) if unique_id == timer_tag
+        ));
+
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        let (project, timers) = projects.iter().next().unwrap();
+        assert_eq!(project.name, "writing");
+        assert_eq!(timers.len(), 1);
+        assert!(timers[0].is_current);
+    }
+}
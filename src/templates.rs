@@ -2,16 +2,25 @@ use std::collections::HashMap;
 
 use anyhow::{anyhow, Result};
 use askama::Template;
+use axum::http::StatusCode;
 
-#[allow(unused_imports)]
-use askama_axum;
-
-use chrono::TimeZone;
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
 use serde::Serialize;
 use tracing::{debug, instrument};
 
 use crate::{
-    timer_store::{Project, Timer},
+    base_path,
+    date_format::DateFormat,
+    duration::{DurationDisplay, DurationFormat},
+    error::Error,
+    i18n::Language,
+    report::Heatmap,
+    time::{StartTime, TrackedDuration},
+    timer_store::{
+        ActivityEntry, ActivityKind, Device, Preset, Project, ProjectBudget, ProjectHours,
+        ProjectStats, ProjectTemplate, Slug, Streaks, TagSettings, TagStatus, Theme, Timer,
+        TimerDay, TimerLabels,
+    },
     uid::TagId,
     uri_base,
 };
@@ -23,6 +32,49 @@ pub(crate) static DEFAULT_TIMEZONES: [chrono_tz::Tz; 4] = [
     chrono_tz::US::Eastern,
 ];
 
+/// The language of a page and how its durations and timestamps are written
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Locale {
+    pub language: Language,
+    pub duration: DurationDisplay,
+    pub date: DateFormat,
+}
+
+impl Locale {
+    /// The locale of a tag, unless the request picks a duration format
+    pub(crate) fn new(
+        settings: &TagSettings,
+        duration: Option<DurationFormat>,
+        accept_language: Option<&str>,
+    ) -> Self {
+        Self {
+            language: Language::negotiate(settings.language.as_deref(), accept_language),
+            duration: settings.duration_display(duration),
+            date: settings.date_format(),
+        }
+    }
+
+    /// Translates the message `key` into the language of the page
+    pub fn tr(&self, key: &str) -> String {
+        self.language.tr(key)
+    }
+
+    /// Translates the message `key` which has a single argument
+    pub fn tr_with(&self, key: &str, name: &str, value: impl ToString) -> String {
+        self.language.tr_with(key, name, value)
+    }
+
+    /// How long ago `at` was as of `now`, both unix epochs, like "5 min ago"
+    pub fn ago(&self, at: i64, now: i64) -> String {
+        match (now - at).max(0) {
+            seconds if seconds < 60 => self.tr("ago-now"),
+            seconds if seconds < 3600 => self.tr_with("ago-minutes", "count", seconds / 60),
+            seconds if seconds < 86400 => self.tr_with("ago-hours", "count", seconds / 3600),
+            seconds => self.tr_with("ago-days", "count", seconds / 86400),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Template)]
 #[template(path = "index.html")]
 pub struct MainPage {
@@ -31,67 +83,1050 @@ pub struct MainPage {
     timezones: Vec<String>,
     uri_base: String,
     projects: Vec<ProjectSection>,
+    presets: Vec<Preset>,
+    label_filter: Option<String>,
+    locale: Locale,
+
+    /// The time tracked over the last week and month, for the bar charts
+    charts: Vec<ChartPeriod>,
+
+    /// The timer which is running, if any
+    running: Option<RunningTimer>,
+
+    /// Why the submitted project couldn't be created, shown on the project form
+    project_error: Option<ProjectFormError>,
+
+    /// The days in a row with enough time tracked, shown while there's a streak
+    streaks: Option<Streaks>,
+
+    /// The templates new projects can start from
+    project_templates: Vec<ProjectTemplate>,
+
+    /// The color scheme the page is shown in
+    theme: Theme,
+}
+
+/// A rejected submission of the project form of the [`MainPage`]
+#[derive(Debug, Serialize)]
+struct ProjectFormError {
+    /// The submitted name, so it can be corrected rather than typed again
+    name: String,
+    message: String,
+}
+
+/// The timer a tag is tracking right now, shown at the top of the [`MainPage`]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+struct RunningTimer {
+    /// The name of the project the timer belongs to
+    project: String,
+
+    /// When the timer was started, as a unix epoch in UTC
+    started_at: i64,
+
+    /// How long the timer has been running in seconds, as of rendering the page
+    elapsed: i64,
+}
+
+impl RunningTimer {
+    /// Finds the running timer among the timers of a tag's projects
+    fn find(projects: &HashMap<Project, Vec<Timer>>, now: chrono::DateTime<Utc>) -> Option<Self> {
+        projects.iter().find_map(|(project, timers)| {
+            let timer = timers.iter().find(|timer| timer.is_current)?;
+            Some(Self {
+                project: project.name.clone(),
+                started_at: timer.start_time_ms.seconds(),
+                elapsed: timer.elapsed(now).seconds(),
+            })
+        })
+    }
+}
+
+/// The read-only view of a tag's timers behind a share link
+#[derive(Debug, Serialize, Template)]
+#[template(path = "shared.html")]
+pub struct SharedPage {
+    current_timezone: String,
+    token: String,
+    timezones: Vec<String>,
+    uri_base: String,
+    projects: Vec<ProjectSection>,
+    locale: Locale,
+}
+
+/// Just the project sections of the [`MainPage`], for refreshing them in place
+#[derive(Debug, Serialize, Template)]
+#[template(path = "fragments/projects.html")]
+pub struct ProjectsFragment {
+    current_timezone: String,
+    projects: Vec<ProjectSection>,
+    locale: Locale,
+}
+
+/// Live status cards for every tag in a group
+#[derive(Debug, Serialize, Template)]
+#[template(path = "wall.html")]
+pub struct WallPage {
+    group_id: String,
+    cards: Vec<StatusCard>,
+}
+
+/// Just the status cards of the [`WallPage`], for refreshing them in place
+#[derive(Debug, Serialize, Template)]
+#[template(path = "fragments/wall_cards.html")]
+pub struct WallCards {
+    cards: Vec<StatusCard>,
+}
+
+/// The devices which have toggled timers for a tag
+#[derive(Debug, Serialize, Template)]
+#[template(path = "devices.html")]
+pub struct DevicesPage {
+    tag_name: String,
+    devices: Vec<Device>,
+    date_format: DateFormat,
+}
+
+impl DevicesPage {
+    pub(crate) fn new(tag_name: String, devices: Vec<Device>, date_format: DateFormat) -> Self {
+        Self {
+            tag_name,
+            devices,
+            date_format,
+        }
+    }
+}
+
+/// Signs a browser in as the owner of a tag with its owner key
+#[derive(Debug, Serialize, Template)]
+#[template(path = "login.html")]
+pub struct LoginPage {
+    tag_name: String,
+}
+
+impl LoginPage {
+    pub(crate) fn new(tag_name: String) -> Self {
+        Self { tag_name }
+    }
+}
+
+/// The short links leading to the timer page of a tag
+#[derive(Debug, Serialize, Template)]
+#[template(path = "links.html")]
+pub struct LinksPage {
+    tag_name: String,
+    slugs: Vec<Slug>,
+    uri_base: String,
+    date_format: DateFormat,
+}
+
+impl LinksPage {
+    pub(crate) fn new(tag_name: String, slugs: Vec<Slug>, date_format: DateFormat) -> Self {
+        Self {
+            tag_name,
+            slugs,
+            uri_base: uri_base(),
+            date_format,
+        }
+    }
+}
+
+/// A day in the grid of the [`HeatmapPage`]
+#[derive(Debug, Serialize)]
+struct HeatmapCell {
+    date: String,
+    seconds: i64,
+
+    /// How busy the day was from 0 to 4, relative to the busiest day of the year
+    level: i64,
+}
+
+/// A GitHub-style activity graph of the time tracked per day of a year
+#[derive(Debug, Serialize, Template)]
+#[template(path = "heatmap.html")]
+pub struct HeatmapPage {
+    tag_name: String,
+    year: i32,
+    timezone: String,
+    total: i64,
+    duration_format: DurationDisplay,
+
+    /// Columns of Monday to Sunday, padded with `None` outside the year
+    weeks: Vec<Vec<Option<HeatmapCell>>>,
+}
+
+impl HeatmapPage {
+    pub(crate) fn new(
+        tag_name: String,
+        heatmap: Heatmap,
+        duration_format: DurationDisplay,
+    ) -> Self {
+        let busiest = heatmap
+            .days
+            .iter()
+            .map(|day| day.seconds)
+            .max()
+            .unwrap_or(0);
+        let padding = heatmap
+            .days
+            .first()
+            .map_or(0, |day| day.date.weekday().num_days_from_monday() as usize);
+
+        let cells: Vec<_> = std::iter::repeat_with(|| None)
+            .take(padding)
+            .chain(heatmap.days.iter().map(|day| {
+                Some(HeatmapCell {
+                    date: day.date.format("%a, %b %-d %Y").to_string(),
+                    seconds: day.seconds,
+                    level: match day.seconds {
+                        0 => 0,
+                        // Any time at all shows up as at least level 1
+                        seconds => (seconds * 4 + busiest - 1) / busiest,
+                    },
+                })
+            }))
+            .collect();
+        let weeks = cells
+            .into_iter()
+            .fold(Vec::<Vec<_>>::new(), |mut weeks, cell| {
+                match weeks.last_mut() {
+                    Some(week) if week.len() < 7 => week.push(cell),
+                    _ => weeks.push(vec![cell]),
+                }
+                weeks
+            });
+
+        Self {
+            tag_name,
+            year: heatmap.year,
+            timezone: heatmap.timezone.name().replace('/', "-"),
+            total: heatmap.days.iter().map(|day| day.seconds).sum(),
+            duration_format,
+            weeks,
+        }
+    }
+}
+
+/// The latest entries of a tag's activity log, described in words
+#[derive(Debug, Serialize, Template)]
+#[template(path = "activity.html")]
+pub struct ActivityPage {
+    tag_name: String,
+    timezone: String,
+
+    /// Newest first
+    entries: Vec<ActivityItem>,
+    locale: Locale,
+    theme: Theme,
+}
+
+/// An entry of the [`ActivityPage`]
+#[derive(Debug, Serialize)]
+struct ActivityItem {
+    description: String,
+
+    /// How long ago it happened, like "2 h ago"
+    ago: String,
+
+    /// When it happened, for hovering over the [`ActivityItem::ago`]
+    at: String,
+}
+
+impl ActivityPage {
+    pub(crate) fn new(
+        tag_name: String,
+        entries: Vec<ActivityEntry>,
+        now: i64,
+        timezone: chrono_tz::Tz,
+        locale: Locale,
+        theme: Theme,
+    ) -> Self {
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let key = match entry.kind {
+                    ActivityKind::Started => "activity-started",
+                    ActivityKind::Stopped => "activity-stopped",
+                    ActivityKind::Discarded => "activity-discarded",
+                    ActivityKind::Created => "activity-created",
+                };
+                let duration = locale.duration.format(entry.duration.unwrap_or_default());
+                ActivityItem {
+                    description: locale
+                        .language
+                        .tr_args(key, &[("project", entry.project), ("duration", duration)]),
+                    ago: locale.ago(entry.at, now),
+                    at: format_time(&entry.at, timezone, &locale.date.long()).unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        Self {
+            tag_name,
+            timezone: to_render_timezone(&timezone),
+            entries,
+            locale,
+            theme,
+        }
+    }
+}
+
+/// Everything about a single project: its timers, stats and budget
+#[derive(Debug, Serialize, Template)]
+#[template(path = "project.html")]
+pub struct ProjectPage {
+    current_timezone: String,
+    project: Project,
+    stats: ProjectStats,
+
+    /// Just the one project, so its timers are listed like on the [`MainPage`]
+    projects: Vec<ProjectSection>,
+    locale: Locale,
+    theme: Theme,
+}
+
+impl ProjectPage {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        project: Project,
+        timers: Vec<Timer>,
+        labels: &TimerLabels,
+        stats: ProjectStats,
+        budget: Option<ProjectBudget>,
+        current_timezone: chrono_tz::Tz,
+        locale: Locale,
+        theme: Theme,
+    ) -> Self {
+        let mut projects = project_sections(
+            HashMap::from([(project.clone(), timers)]),
+            labels,
+            &current_timezone,
+            &Viewer::ProjectPage,
+        );
+        attach_budgets(
+            &mut projects,
+            budget.into_iter().map(|b| (b.project_id, b)).collect(),
+        );
+
+        Self {
+            current_timezone: to_render_timezone(&current_timezone),
+            project,
+            stats,
+            projects,
+            locale,
+            theme,
+        }
+    }
+
+    /// The budget of the project in hours, empty if it has none
+    fn budget_hours(&self) -> String {
+        self.projects
+            .first()
+            .and_then(|project| project.budget.as_ref())
+            .map(|budget| (budget.budget as f64 / 3600.0).to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// The timers of a tag in one week, grouped by the local day they started on
+#[derive(Debug, Serialize, Template)]
+#[template(path = "week.html")]
+pub struct WeekPage {
+    tag_name: String,
+    timezone: String,
+
+    /// The Monday the week starts on
+    monday: NaiveDate,
+    total: i64,
+
+    /// Monday to Sunday
+    days: Vec<WeekDay>,
+    locale: Locale,
+    theme: Theme,
+}
+
+/// A day of the [`WeekPage`]
+#[derive(Debug, Serialize)]
+struct WeekDay {
+    label: String,
+    total: i64,
+    entries: Vec<WeekEntry>,
+}
+
+#[derive(Debug, Serialize)]
+struct WeekEntry {
+    project: String,
+    start: String,
+
+    /// When the timer stopped, unless it's still running
+    end: Option<String>,
+    duration: i64,
+    note: String,
+}
+
+impl WeekPage {
+    pub(crate) fn new(
+        tag_name: String,
+        monday: NaiveDate,
+        days: Vec<TimerDay>,
+        timezone: chrono_tz::Tz,
+        locale: Locale,
+        theme: Theme,
+    ) -> Self {
+        let time = |at: DateTime<Utc>| {
+            at.with_timezone(&timezone)
+                .format(locale.date.time())
+                .to_string()
+        };
+        let day_label = format!("%a, {}", locale.date.date());
+
+        let days: Vec<_> = days
+            .into_iter()
+            .map(|day| WeekDay {
+                label: day.date.format(&day_label).to_string(),
+                total: day.total.seconds(),
+                entries: day
+                    .entries
+                    .into_iter()
+                    .map(|entry| WeekEntry {
+                        start: time(entry.start_time.0),
+                        end: (!entry.is_current)
+                            .then(|| time(entry.start_time.end(entry.duration))),
+                        project: entry.project,
+                        duration: entry.duration.seconds(),
+                        note: entry.note.unwrap_or_default(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            tag_name,
+            timezone: to_render_timezone(&timezone),
+            monday,
+            total: days.iter().map(|day| day.total).sum(),
+            days,
+            locale,
+            theme,
+        }
+    }
+
+    /// The Monday of the week before
+    fn previous(&self) -> NaiveDate {
+        self.monday - chrono::Duration::days(7)
+    }
+
+    /// The Monday of the week after
+    fn next(&self) -> NaiveDate {
+        self.monday + chrono::Duration::days(7)
+    }
+}
+
+/// The weekly summary email, sent by [`crate::digest`]
+#[derive(Debug, Template)]
+#[template(path = "email/weekly_digest.txt")]
+pub struct WeeklyDigest {
+    week: String,
+    projects: Vec<ProjectHours>,
+    total: i64,
+    duration_format: DurationDisplay,
+    link: String,
+}
+
+impl WeeklyDigest {
+    pub(crate) fn new(
+        uid: &TagId,
+        week_start: chrono::DateTime<chrono_tz::Tz>,
+        projects: Vec<ProjectHours>,
+        duration_format: DurationDisplay,
+    ) -> Self {
+        let week_end = week_start + chrono::Duration::days(6);
+        Self {
+            week: format!(
+                "{} to {}",
+                week_start.format("%a, %b %-d"),
+                week_end.format("%a, %b %-d %Y")
+            ),
+            total: projects
+                .iter()
+                .map(|project| project.duration)
+                .sum::<TrackedDuration>()
+                .seconds(),
+            projects,
+            duration_format,
+            link: format!("{}/timer/{}", uri_base(), uid.as_ref()),
+        }
+    }
+
+    /// The subject line of the email
+    pub(crate) fn subject(&self) -> String {
+        format!("Your week in Soot Sprite: {}", self.week)
+    }
+
+    /// The HTML alternative of the email
+    pub(crate) fn html(&self) -> WeeklyDigestHtml<'_> {
+        WeeklyDigestHtml { digest: self }
+    }
+}
+
+/// The HTML alternative of the [`WeeklyDigest`]
+#[derive(Debug, Template)]
+#[template(path = "email/weekly_digest.html")]
+pub struct WeeklyDigestHtml<'a> {
+    digest: &'a WeeklyDigest,
+}
+
+/// The email reminding a tag to start a timer, sent by [`crate::reminders`]
+#[derive(Debug, Template)]
+#[template(path = "email/reminder.txt")]
+pub struct Reminder {
+    idle_minutes: i64,
+    link: String,
+}
+
+impl Reminder {
+    pub(crate) fn new(uid: &TagId, idle_minutes: i64) -> Self {
+        Self {
+            idle_minutes,
+            link: format!("{}/timer/{}", uri_base(), uid.as_ref()),
+        }
+    }
+
+    /// The subject line of the email, also posted to the webhook
+    pub(crate) fn subject(&self) -> String {
+        format!("No timer running for {} minutes", self.idle_minutes)
+    }
+}
+
+/// The email alerting a tag that its timer has been running for a long time
+#[derive(Debug, Template)]
+#[template(path = "email/long_timer.txt")]
+pub struct LongTimerAlert {
+    project: String,
+    minutes: i64,
+    link: String,
+}
+
+impl LongTimerAlert {
+    pub(crate) fn new(uid: &TagId, project: &str, minutes: i64) -> Self {
+        Self {
+            project: project.to_string(),
+            minutes,
+            link: format!("{}/timer/{}", uri_base(), uid.as_ref()),
+        }
+    }
+
+    /// The subject line of the email, also posted to the webhook
+    pub(crate) fn subject(&self) -> String {
+        format!(
+            "Your {} timer has been running for {}h {}m",
+            self.project,
+            self.minutes / 60,
+            self.minutes % 60
+        )
+    }
+}
+
+/// The email alerting a tag that a project reached a threshold of its budget
+#[derive(Debug, Template)]
+#[template(path = "email/budget.txt")]
+pub struct BudgetAlert {
+    project: String,
+    percent: i64,
+    consumed: i64,
+    budget: i64,
+    link: String,
+}
+
+impl BudgetAlert {
+    pub(crate) fn new(budget: &ProjectBudget, threshold: i64) -> Self {
+        Self {
+            project: budget.project.clone(),
+            percent: threshold,
+            consumed: budget.consumed.seconds(),
+            budget: budget.budget,
+            link: format!("{}/timer/{}", uri_base(), budget.unique_id),
+        }
+    }
+
+    /// The subject line of the email, also posted to the webhook
+    pub(crate) fn subject(&self) -> String {
+        format!("{} has used {}% of its budget", self.project, self.percent)
+    }
+}
+
+/// The email delivering a [`ScheduledExport`][crate::timer_store::ScheduledExport]
+#[derive(Debug, Template)]
+#[template(path = "email/export.txt")]
+pub struct ExportDelivery {
+    project: String,
+    from: String,
+    to: String,
+    link: String,
+}
+
+impl ExportDelivery {
+    pub(crate) fn new(project: &Project, from: NaiveDate, to: NaiveDate) -> Self {
+        Self {
+            project: project.name.clone(),
+            from: from.to_string(),
+            to: to.to_string(),
+            link: format!("{}/timer/{}", uri_base(), project.unique_id),
+        }
+    }
+
+    pub(crate) fn subject(&self) -> String {
+        format!(
+            "Timers of {} from {} to {}",
+            self.project, self.from, self.to
+        )
+    }
+}
+
+/// Shown when a page can't be rendered
+#[derive(Debug, Serialize, Template)]
+#[template(path = "error.html")]
+pub struct ErrorPage {
+    status: u16,
+    reason: String,
+    message: String,
+    request_id: Option<String>,
+}
+
+impl ErrorPage {
+    pub(crate) fn new(status: StatusCode, message: String, request_id: Option<String>) -> Self {
+        Self {
+            status: status.as_u16(),
+            reason: status.canonical_reason().unwrap_or("Error").to_string(),
+            message,
+            request_id,
+        }
+    }
+}
+
+/// Data structure for holding the status of a single tag on the wall
+#[derive(Debug, Serialize)]
+struct StatusCard {
+    tag_name: String,
+    project_name: String,
+    running: bool,
+    elapsed: i64,
+}
+
+/// The periods the [`MainPage`] charts the tracked time for, in days
+const CHART_PERIODS: [i64; 2] = [7, 30];
+
+/// The time tracked over the last few days, per day and per project
+#[derive(Debug, Serialize, PartialEq, Eq)]
+struct ChartPeriod {
+    days: i64,
+    total: i64,
+
+    /// Every day of the period, oldest first, including days without any time
+    per_day: Vec<ChartBar>,
+
+    /// The projects with time in the period, most time first
+    per_project: Vec<ChartBar>,
+}
+
+/// A single bar of a chart
+#[derive(Debug, Serialize, PartialEq, Eq)]
+struct ChartBar {
+    label: String,
+    seconds: i64,
+
+    /// How long the bar is relative to the longest one in its chart
+    percent: i64,
+}
+
+impl ChartBar {
+    fn scaled(bars: Vec<(String, i64)>) -> Vec<Self> {
+        let longest = bars.iter().map(|(_, seconds)| *seconds).max().unwrap_or(0);
+        bars.into_iter()
+            .map(|(label, seconds)| ChartBar {
+                percent: if longest > 0 {
+                    seconds * 100 / longest
+                } else {
+                    0
+                },
+                label,
+                seconds,
+            })
+            .collect()
+    }
+}
+
+/// Sums up finished timers, given as `(project, started, tracked)`, for
+/// each of the [`CHART_PERIODS`] ending today
+///
+/// Timers count towards the local day they started on.
+fn chart_periods(
+    timers: &[(&str, StartTime, TrackedDuration)],
+    timezone: &chrono_tz::Tz,
+    now: chrono::DateTime<Utc>,
+) -> Vec<ChartPeriod> {
+    let today = now.with_timezone(timezone).date_naive();
+
+    CHART_PERIODS
+        .iter()
+        .map(|&days| {
+            let first_day = today - chrono::Duration::days(days - 1);
+            let mut per_day = vec![TrackedDuration::default(); days as usize];
+            let mut per_project: HashMap<&str, TrackedDuration> = HashMap::new();
+
+            for &(project, started, tracked) in timers {
+                let started = started.0.with_timezone(timezone);
+                let day = (started.date_naive() - first_day).num_days();
+                if !(0..days).contains(&day) {
+                    continue;
+                }
+                per_day[day as usize] += tracked;
+                *per_project.entry(project).or_default() += tracked;
+            }
+
+            let day_format = if days <= 7 { "%a" } else { "%-d" };
+            let per_day = per_day
+                .into_iter()
+                .zip(first_day.iter_days())
+                .map(|(tracked, date)| (date.format(day_format).to_string(), tracked.seconds()))
+                .collect();
+            let mut per_project: Vec<_> = per_project
+                .into_iter()
+                .map(|(project, tracked)| (project.to_string(), tracked.seconds()))
+                .collect();
+            per_project.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+            ChartPeriod {
+                days,
+                total: per_project.iter().map(|(_, seconds)| seconds).sum(),
+                per_day: ChartBar::scaled(per_day),
+                per_project: ChartBar::scaled(per_project),
+            }
+        })
+        .collect()
+}
+
+/// Who a page is rendered for, which decides where its links point
+#[derive(Debug)]
+enum Viewer {
+    /// The owner of the tag, who can change everything
+    Owner(String),
+
+    /// Someone with the token of a read-only share link
+    Shared(String),
+
+    /// The owner on the page of one of their projects, whose URL already names
+    /// the tag, so its links are relative rather than repeating the tag
+    ProjectPage,
 }
 
 /// Data structure for holding information related to a project
 #[derive(Debug, Serialize)]
 struct ProjectSection {
+    id: i64,
     name: String,
     timers: Vec<Timer>,
+    labels: TimerLabels,
     download_link: String,
+
+    /// Where labels are added and removed, unless the page is read-only
+    label_link: Option<String>,
+
+    /// Where timers are linked to issues and commits, unless the page is read-only
+    link_form: Option<String>,
+
+    /// Where the project is cloned, unless the page is read-only
+    clone_link: Option<String>,
+
+    /// The page of the project, unless the page is read-only
+    page_link: Option<String>,
+
+    /// The hour budget of the project, if it has one
+    budget: Option<ProjectBudget>,
+}
+
+impl ProjectSection {
+    /// The labels attached to one of the timers of this project
+    fn labels_of(&self, timer: &Timer) -> &[String] {
+        self.labels
+            .get(&timer.id())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
 }
 
 impl MainPage {
     pub(crate) fn new(
         tag_name: String,
         projects: HashMap<Project, Vec<Timer>>,
+        labels: &TimerLabels,
+        presets: Vec<Preset>,
         timezone: Option<String>,
+        label_filter: Option<String>,
+        locale: Locale,
     ) -> Result<Self> {
-        let current_timezone: chrono_tz::Tz = if let Some(timezone) = timezone {
-            from_render_timezone(&timezone)?
-        } else {
-            chrono_tz::US::Pacific
-        };
-
-        let mut project_sections = Vec::new();
-        for (project, timers) in projects {
-            let link = format!(
-                "{}/export/{}/{}",
-                uri_base(),
-                to_render_timezone(&current_timezone),
-                project.id,
-            );
-
-            project_sections.push(ProjectSection {
-                name: project.name,
-                timers,
-                download_link: link,
-            });
-        }
-
-        let timezones = DEFAULT_TIMEZONES
+        let current_timezone = current_timezone(timezone)?;
+        let finished_timers: Vec<_> = projects
             .iter()
-            .filter(|tz| **tz != current_timezone)
-            .map(|tz| format!("{}", to_render_timezone(tz)))
+            .flat_map(|(project, timers)| {
+                timers
+                    .iter()
+                    .filter(|timer| !timer.is_current)
+                    .map(|timer| {
+                        (
+                            project.name.as_str(),
+                            timer.start_time_ms,
+                            timer.duration_ms,
+                        )
+                    })
+            })
             .collect();
+        let now = Utc::now();
+        let charts = chart_periods(&finished_timers, &current_timezone, now);
+        let running = RunningTimer::find(&projects, now);
+        let viewer = Viewer::Owner(tag_name.clone());
+        let project_sections = project_sections(projects, labels, &current_timezone, &viewer);
 
         Ok(Self {
             tag_name,
-            current_timezone: format!("{}", to_render_timezone(&current_timezone)),
-            timezones,
+            current_timezone: to_render_timezone(&current_timezone),
+            timezones: other_timezones(&current_timezone),
             uri_base: uri_base(),
             projects: project_sections,
+            presets,
+            label_filter,
+            locale,
+            charts,
+            running,
+            project_error: None,
+            streaks: None,
+            project_templates: Vec::new(),
+            theme: Theme::default(),
         })
     }
 }
 
+impl SharedPage {
+    pub(crate) fn new(
+        token: String,
+        projects: HashMap<Project, Vec<Timer>>,
+        labels: &TimerLabels,
+        timezone: Option<String>,
+        locale: Locale,
+    ) -> Result<Self> {
+        let current_timezone = current_timezone(timezone)?;
+        let viewer = Viewer::Shared(token.clone());
+
+        Ok(Self {
+            projects: project_sections(projects, labels, &current_timezone, &viewer),
+            token,
+            current_timezone: to_render_timezone(&current_timezone),
+            timezones: other_timezones(&current_timezone),
+            uri_base: uri_base(),
+            locale,
+        })
+    }
+}
+
+impl ProjectsFragment {
+    pub(crate) fn new(
+        tag_name: String,
+        projects: HashMap<Project, Vec<Timer>>,
+        labels: &TimerLabels,
+        timezone: Option<String>,
+        locale: Locale,
+    ) -> Result<Self> {
+        let current_timezone = current_timezone(timezone)?;
+        let viewer = Viewer::Owner(tag_name);
+
+        Ok(Self {
+            projects: project_sections(projects, labels, &current_timezone, &viewer),
+            current_timezone: to_render_timezone(&current_timezone),
+            locale,
+        })
+    }
+}
+
+impl MainPage {
+    /// Shows the budgets of the projects which have one
+    pub(crate) fn with_budgets(mut self, budgets: HashMap<i64, ProjectBudget>) -> Self {
+        attach_budgets(&mut self.projects, budgets);
+        self
+    }
+
+    /// Offers starting new projects from the tag's templates
+    pub(crate) fn with_project_templates(
+        mut self,
+        project_templates: Vec<ProjectTemplate>,
+    ) -> Self {
+        self.project_templates = project_templates;
+        self
+    }
+
+    /// Shows the page in the tag's color scheme
+    pub(crate) fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Shows the tag's current streak
+    pub(crate) fn with_streaks(mut self, streaks: Streaks) -> Self {
+        self.streaks = Some(streaks);
+        self
+    }
+
+    /// Shows why a submitted project name was rejected on the project form
+    pub(crate) fn with_project_error(mut self, name: String, message: String) -> Self {
+        self.project_error = Some(ProjectFormError { name, message });
+        self
+    }
+}
+
+impl ProjectsFragment {
+    /// Shows the budgets of the projects which have one
+    pub(crate) fn with_budgets(mut self, budgets: HashMap<i64, ProjectBudget>) -> Self {
+        attach_budgets(&mut self.projects, budgets);
+        self
+    }
+}
+
+fn attach_budgets(sections: &mut [ProjectSection], mut budgets: HashMap<i64, ProjectBudget>) {
+    for section in sections {
+        section.budget = budgets.remove(&section.id);
+    }
+}
+
+impl WallPage {
+    pub(crate) fn new(group_id: String, statuses: Vec<TagStatus>) -> Self {
+        Self {
+            group_id,
+            cards: status_cards(statuses),
+        }
+    }
+}
+
+impl WallCards {
+    pub(crate) fn new(statuses: Vec<TagStatus>) -> Self {
+        Self {
+            cards: status_cards(statuses),
+        }
+    }
+}
+
+fn status_cards(statuses: Vec<TagStatus>) -> Vec<StatusCard> {
+    let now = Utc::now().timestamp();
+    statuses
+        .into_iter()
+        .map(|status| StatusCard {
+            tag_name: status.unique_id,
+            project_name: status.project_name.unwrap_or_default(),
+            running: status.start_time.is_some(),
+            elapsed: status.start_time.map_or(0, |start| now - start),
+        })
+        .collect()
+}
+
+/// Parses the requested timezone, defaulting to US/Pacific
+fn current_timezone(timezone: Option<String>) -> Result<chrono_tz::Tz> {
+    match timezone {
+        Some(timezone) => from_render_timezone(&timezone),
+        None => Ok(chrono_tz::US::Pacific),
+    }
+}
+
+/// The timezones to offer switching to
+fn other_timezones(current_timezone: &chrono_tz::Tz) -> Vec<String> {
+    DEFAULT_TIMEZONES
+        .iter()
+        .filter(|tz| *tz != current_timezone)
+        .map(to_render_timezone)
+        .collect()
+}
+
+fn project_sections(
+    projects: HashMap<Project, Vec<Timer>>,
+    labels: &TimerLabels,
+    timezone: &chrono_tz::Tz,
+    viewer: &Viewer,
+) -> Vec<ProjectSection> {
+    let export_base = match viewer {
+        Viewer::Owner(_) | Viewer::ProjectPage => format!("{}/export", uri_base()),
+        Viewer::Shared(token) => format!("{}/shared/{}/export", uri_base(), token),
+    };
+    let label_link = match viewer {
+        Viewer::Owner(tag_name) => Some(format!("{}/timer/{}/label", base_path(), tag_name)),
+        Viewer::Shared(_) | Viewer::ProjectPage => None,
+    };
+    let link_form = match viewer {
+        Viewer::Owner(tag_name) => Some(format!("{}/timer/{}/link", base_path(), tag_name)),
+        Viewer::Shared(_) | Viewer::ProjectPage => None,
+    };
+    let clone_base = match viewer {
+        Viewer::Owner(tag_name) => Some(format!("{}/project/{}/clone", base_path(), tag_name)),
+        Viewer::ProjectPage => Some("clone".to_string()),
+        Viewer::Shared(_) => None,
+    };
+
+    projects
+        .into_iter()
+        .map(|(project, timers)| {
+            let link = format!(
+                "{}/{}/{}",
+                export_base,
+                to_render_timezone(timezone),
+                project.id,
+            );
+
+            let labels = timers
+                .iter()
+                .filter_map(|timer| Some((timer.id(), labels.get(&timer.id())?.clone())))
+                .collect();
+
+            ProjectSection {
+                id: project.id,
+                name: project.name,
+                timers,
+                labels,
+                download_link: link,
+                label_link: label_link.clone(),
+                link_form: link_form.clone(),
+                clone_link: clone_base
+                    .as_ref()
+                    .map(|base| format!("{}/{}", base, project.id)),
+                page_link: match viewer {
+                    Viewer::Owner(tag_name) => Some(format!(
+                        "{}/project/{}/{}?timezone={}",
+                        base_path(),
+                        tag_name,
+                        project.id,
+                        to_render_timezone(timezone)
+                    )),
+                    Viewer::Shared(_) | Viewer::ProjectPage => None,
+                },
+                budget: None,
+            }
+        })
+        .collect()
+}
+
 #[instrument(skip(projects))]
 pub fn render_timers(
     tag: TagId,
     timezone: Option<String>,
     projects: HashMap<Project, Vec<Timer>>,
+    labels: &TimerLabels,
+    presets: Vec<Preset>,
+    label_filter: Option<String>,
+    locale: Locale,
 ) -> anyhow::Result<MainPage> {
-    let page = MainPage::new(tag.as_ref().to_string(), projects, timezone)?;
+    let page = MainPage::new(
+        tag.as_ref().to_string(),
+        projects,
+        labels,
+        presets,
+        timezone,
+        label_filter,
+        locale,
+    )?;
 
     debug!("Rendering timers for {} tag", page.tag_name);
     Ok(page)
@@ -107,54 +1142,60 @@ pub(crate) fn to_render_timezone(timezone: &chrono_tz::Tz) -> String {
 pub(crate) fn from_render_timezone(timezone: &str) -> Result<chrono_tz::Tz> {
     let zone = timezone.replace("-", "/");
     zone.parse()
-        .map_err(|err| anyhow!("Unable to parse timezone: {}", err))
+        .map_err(|err| Error::Validation(format!("Unable to parse timezone: {}", err)).into())
 }
 
 mod filters {
-    use std::{fmt::Display, num::ParseIntError};
-
-    use crate::timer_store::Timer;
+    use std::borrow::Borrow;
 
-    use super::TimerPart;
+    use crate::{
+        date_format::DateFormat, duration::DurationDisplay, time::TrackedDuration,
+        timer_store::Timer,
+    };
 
-    pub fn end_time_to_human_time(timer: &Timer, timezone: &str) -> askama::Result<String> {
-        to_human_date(&(timer.start_time + timer.duration), timezone)
+    pub fn end_time_to_human_time(
+        timer: &Timer,
+        timezone: &str,
+        format: impl Borrow<DateFormat>,
+    ) -> askama::Result<String> {
+        to_human_date(&timer.ended_at().timestamp(), timezone, format)
     }
 
-    pub fn to_human_date(timestamp: &i64, timezone: &str) -> askama::Result<String> {
+    pub fn to_human_date(
+        timestamp: &i64,
+        timezone: &str,
+        format: impl Borrow<DateFormat>,
+    ) -> askama::Result<String> {
         let timezone: chrono_tz::Tz = super::from_render_timezone(timezone)
             .map_err(|err| askama::Error::Custom(err.into()))?;
-        let formatted_time = super::format_time(&timestamp, timezone, "%a, %F %H:%M")
+        let formatted_time = super::format_time(timestamp, timezone, &format.borrow().long())
             .map_err(|err| askama::Error::Custom(err.into()))?;
 
         Ok(formatted_time)
     }
 
-    /// Extracts the parts of time from a given timetamp
+    /// How many seconds a timer has been running as of rendering the page
     ///
-    /// Mainly used to get the hours and minutes for a timer.
-    pub fn extract_timer_values<T: Display>(time: T, part: &str) -> askama::Result<String> {
-        let time = time
-            .to_string()
-            .parse()
-            .map_err(|err: ParseIntError| askama::Error::Custom(Box::new(err)))?; // Inference trips over itself without type
-        let part = match part {
-            "hours" => TimerPart::Hour,
-            "minutes" => TimerPart::Min,
-            _ => panic!("Unexpected argument"),
-        };
-
-        let Ok(part) = super::extract_timer(part, time) else {
-            return Err(askama::Error::Custom("Unable to extract timer part".into()));
-        };
+    /// Finished timers have the duration they were stopped with.
+    pub fn elapsed(timer: &Timer) -> askama::Result<i64> {
+        Ok(timer.elapsed(chrono::Utc::now()).seconds())
+    }
 
-        Ok(format!("{}", part))
+    /// Writes a duration in seconds in the format picked for the page
+    pub fn format_duration(
+        seconds: &i64,
+        display: impl Into<DurationDisplay>,
+    ) -> askama::Result<String> {
+        Ok(display.into().format(*seconds))
     }
-}
 
-pub enum TimerPart {
-    Hour,
-    Min,
+    /// Writes a tracked duration in the format picked for the page
+    pub fn format_tracked(
+        tracked: &TrackedDuration,
+        display: impl Into<DurationDisplay>,
+    ) -> askama::Result<String> {
+        format_duration(&tracked.seconds(), display)
+    }
 }
 
 #[instrument]
@@ -168,28 +1209,6 @@ pub fn format_time(time: &i64, timezone: chrono_tz::Tz, fmt_string: &str) -> Res
     }
 }
 
-/// Extracts the minute and hour parts of the duration.
-///
-/// Duration is stored in minute resolution
-pub fn extract_timer(part: TimerPart, time: i64) -> Result<i64> {
-    let minute = 60;
-    let hour = 60 * minute;
-    match part {
-        // Round minutes to the nearest minute
-        TimerPart::Min => {
-            // Round down to only the minutes portion
-            let minutes = if time > hour { time % hour } else { time };
-
-            Ok(if minutes > minute {
-                minutes / minute
-            } else {
-                0
-            })
-        }
-        TimerPart::Hour => Ok(if time > hour { time / hour } else { 0 }),
-    }
-}
-
 #[cfg(test)]
 mod test {
 
@@ -197,40 +1216,111 @@ mod test {
     use pretty_assertions::assert_eq;
 
     #[test]
-    fn extract_timer_under_hour() {
-        let result = extract_timer(TimerPart::Min, 45);
+    fn charts_sum_time_per_local_day_and_project() {
+        let timezone = chrono_tz::US::Pacific;
+        // Thursday 2026-10-15 at noon in US/Pacific
+        let now = timezone
+            .with_ymd_and_hms(2026, 10, 15, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let hours_ago = |hours: i64| StartTime::from(now - chrono::Duration::hours(hours));
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 0);
+        let timers = [
+            (
+                "writing",
+                hours_ago(2),
+                TrackedDuration::from_seconds(3_600),
+            ),
+            (
+                "writing",
+                hours_ago(3),
+                TrackedDuration::from_seconds(1_800),
+            ),
+            // 23:00 on Wednesday
+            ("reading", hours_ago(13), TrackedDuration::from_seconds(900)),
+            // Eight days ago only counts towards the month
+            (
+                "reading",
+                hours_ago(8 * 24),
+                TrackedDuration::from_seconds(7_200),
+            ),
+            // Before the month
+            (
+                "writing",
+                hours_ago(31 * 24),
+                TrackedDuration::from_seconds(60),
+            ),
+        ];
+        let charts = chart_periods(&timers, &timezone, now);
 
-        let result = extract_timer(TimerPart::Min, 45 * 60);
+        let week = &charts[0];
+        assert_eq!(week.days, 7);
+        assert_eq!(week.total, 6_300);
+        assert_eq!(week.per_day.len(), 7);
+        assert_eq!(
+            week.per_day[5..],
+            [
+                ChartBar {
+                    label: "Wed".to_string(),
+                    seconds: 900,
+                    percent: 16,
+                },
+                ChartBar {
+                    label: "Thu".to_string(),
+                    seconds: 5_400,
+                    percent: 100,
+                },
+            ]
+        );
+        assert_eq!(
+            week.per_project
+                .iter()
+                .map(|bar| (bar.label.as_str(), bar.seconds))
+                .collect::<Vec<_>>(),
+            vec![("writing", 5_400), ("reading", 900)]
+        );
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 45);
+        let month = &charts[1];
+        assert_eq!(month.per_day.len(), 30);
+        assert_eq!(month.total, 13_500);
+        assert_eq!(month.per_project[0].label, "reading");
+        assert_eq!(month.per_project[0].percent, 100);
     }
 
     #[test]
-    fn extract_timer_over_hour() {
-        let time = ((2 * 60) + 35) * 60;
-        let result = extract_timer(TimerPart::Min, time);
-
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 35);
-
-        let result = extract_timer(TimerPart::Hour, time);
-        assert_eq!(result.unwrap(), 2);
-    }
-
-    #[test]
-    fn extract_timer_long_duration() {
-        let time = 74242;
-
-        let result = extract_timer(TimerPart::Min, time);
+    fn running_timers_show_their_elapsed_time() {
+        let project = Project {
+            id: 1,
+            name: "writing".to_string(),
+            ..Default::default()
+        };
+        let started = Utc::now() - chrono::Duration::minutes(90);
+        let timer = Timer {
+            id: 1,
+            project_id: 1,
+            is_current: true,
+            start_time_ms: started.into(),
+            ..Default::default()
+        };
+        let projects = HashMap::from([(project, vec![timer])]);
 
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), 37);
+        let fragment = ProjectsFragment::new(
+            "test-tag".to_string(),
+            projects,
+            &TimerLabels::new(),
+            None,
+            Locale::default(),
+        )
+        .unwrap();
+        let html = fragment.render().unwrap();
 
-        let result = extract_timer(TimerPart::Hour, time);
-        assert_eq!(result.unwrap(), 20);
+        // Rendering may straddle a second
+        assert!(
+            html.contains(r#"data-elapsed="5400""#) || html.contains(r#"data-elapsed="5401""#),
+            "{}",
+            html
+        );
+        assert!(html.contains(r#"<span id="currentHours" class="time">01</span>"#));
+        assert!(html.contains(r#"<span id="currentMinutes" class="time">30</span>"#));
     }
 }
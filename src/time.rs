@@ -0,0 +1,290 @@
+//! Typed instants and durations of timers
+//!
+//! Both are stored as milliseconds in the database and written as
+//! milliseconds in JSON, but in between they are plain chrono values so the
+//! math on them doesn't depend on remembering the unit of an `i64`.
+
+use std::{
+    iter::Sum,
+    ops::{Add, AddAssign},
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Serialize, Serializer};
+use sqlx::{
+    encode::IsNull,
+    error::BoxDynError,
+    sqlite::{SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef},
+    Decode, Encode, Sqlite, Type,
+};
+
+/// When a timer started, in UTC
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StartTime(pub DateTime<Utc>);
+
+impl StartTime {
+    /// The start at a unix epoch in milliseconds
+    ///
+    /// Epochs chrono can't represent fall back to the unix epoch itself.
+    pub fn from_millis(millis: i64) -> Self {
+        Self(
+            Utc.timestamp_millis_opt(millis)
+                .single()
+                .unwrap_or_default(),
+        )
+    }
+
+    /// The start at a unix epoch in whole seconds, like clients send them
+    pub fn from_seconds(seconds: i64) -> Self {
+        Self::from_millis(seconds.saturating_mul(1000))
+    }
+
+    /// The start as a unix epoch in milliseconds
+    pub fn millis(self) -> i64 {
+        self.0.timestamp_millis()
+    }
+
+    /// The whole second the timer started in, as a unix epoch
+    pub fn seconds(self) -> i64 {
+        self.0.timestamp()
+    }
+
+    /// How long a timer started at this time has been running at `now`
+    ///
+    /// Never negative, even if the clock went backwards in between.
+    pub fn elapsed(self, now: DateTime<Utc>) -> TrackedDuration {
+        TrackedDuration::between(self, now)
+    }
+
+    /// When a timer starting at this time ends after `tracked`
+    pub fn end(self, tracked: TrackedDuration) -> DateTime<Utc> {
+        self.0 + tracked.0
+    }
+}
+
+/// The `start_time` and `duration` columns of a timer, which keep it in whole
+/// seconds next to the milliseconds
+///
+/// The duration is the difference of the whole seconds of the start and end,
+/// so `start_time + duration` is the second the timer ended in.
+pub fn second_columns(start: StartTime, tracked: TrackedDuration) -> (i64, i64) {
+    let start_time = start.seconds();
+    (start_time, start.end(tracked).timestamp() - start_time)
+}
+
+/// A timer's start and duration written in whole seconds, as the tests do
+#[cfg(test)]
+pub fn in_seconds((start, tracked): (i64, i64)) -> (StartTime, TrackedDuration) {
+    (
+        StartTime::from_seconds(start),
+        TrackedDuration::from_seconds(tracked),
+    )
+}
+
+impl From<DateTime<Utc>> for StartTime {
+    fn from(time: DateTime<Utc>) -> Self {
+        Self(time)
+    }
+}
+
+/// A start read from the database, where it's kept in milliseconds
+impl From<i64> for StartTime {
+    fn from(millis: i64) -> Self {
+        Self::from_millis(millis)
+    }
+}
+
+/// How long a timer lasted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrackedDuration(pub chrono::Duration);
+
+impl Default for TrackedDuration {
+    fn default() -> Self {
+        Self(chrono::Duration::zero())
+    }
+}
+
+impl TrackedDuration {
+    /// A duration of `millis` milliseconds
+    pub fn from_millis(millis: i64) -> Self {
+        Self(chrono::Duration::milliseconds(millis))
+    }
+
+    /// A duration of `seconds` seconds
+    pub fn from_seconds(seconds: i64) -> Self {
+        Self(chrono::Duration::seconds(seconds))
+    }
+
+    /// The time from `start` until `end`, or nothing if `end` comes first
+    pub fn between(start: StartTime, end: DateTime<Utc>) -> Self {
+        Self((end - start.0).max(chrono::Duration::zero()))
+    }
+
+    /// The duration in milliseconds
+    pub fn millis(self) -> i64 {
+        self.0.num_milliseconds()
+    }
+
+    /// The duration in whole seconds, dropping the leftover milliseconds
+    pub fn seconds(self) -> i64 {
+        self.0.num_seconds()
+    }
+
+    /// Whether no time was tracked
+    pub fn is_zero(self) -> bool {
+        self.0 == chrono::Duration::zero()
+    }
+}
+
+/// A duration read from the database, where it's kept in milliseconds
+impl From<i64> for TrackedDuration {
+    fn from(millis: i64) -> Self {
+        Self::from_millis(millis)
+    }
+}
+
+impl Add for TrackedDuration {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl AddAssign for TrackedDuration {
+    fn add_assign(&mut self, other: Self) {
+        self.0 = self.0 + other.0;
+    }
+}
+
+impl Sum for TrackedDuration {
+    fn sum<I: Iterator<Item = Self>>(durations: I) -> Self {
+        durations.fold(Self::default(), Add::add)
+    }
+}
+
+/// Serializes a [`TrackedDuration`] in whole seconds, for JSON fields which
+/// have always been in seconds
+pub fn as_seconds<S: Serializer>(
+    tracked: &TrackedDuration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(tracked.seconds())
+}
+
+/// Serializes a [`StartTime`] as a unix epoch in whole seconds, for JSON
+/// fields which have always been in seconds
+pub fn as_epoch_seconds<S: Serializer>(
+    start: &StartTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(start.seconds())
+}
+
+impl Serialize for StartTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.millis())
+    }
+}
+
+impl Serialize for TrackedDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.millis())
+    }
+}
+
+impl Type<Sqlite> for StartTime {
+    fn type_info() -> SqliteTypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <i64 as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for StartTime {
+    fn encode_by_ref(&self, args: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        <i64 as Encode<'q, Sqlite>>::encode(self.millis(), args)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for StartTime {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Self::from_millis(<i64 as Decode<'r, Sqlite>>::decode(
+            value,
+        )?))
+    }
+}
+
+impl Type<Sqlite> for TrackedDuration {
+    fn type_info() -> SqliteTypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <i64 as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for TrackedDuration {
+    fn encode_by_ref(&self, args: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        <i64 as Encode<'q, Sqlite>>::encode(self.millis(), args)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for TrackedDuration {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Self::from_millis(<i64 as Decode<'r, Sqlite>>::decode(
+            value,
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sub_second_precision_survives() {
+        let start = StartTime::from_millis(1_000_999);
+        assert_eq!(start.seconds(), 1_000);
+        assert_eq!(start.millis(), 1_000_999);
+
+        let tracked = start.elapsed(Utc.timestamp_millis_opt(1_002_500).unwrap());
+        assert_eq!(tracked, TrackedDuration::from_millis(1_501));
+        assert_eq!(tracked.seconds(), 1);
+        assert_eq!(start.end(tracked).timestamp_millis(), 1_002_500);
+
+        // Clocks going backwards don't make for negative time
+        assert!(start
+            .elapsed(Utc.timestamp_millis_opt(0).unwrap())
+            .is_zero());
+
+        let total: TrackedDuration = [tracked, TrackedDuration::from_seconds(2)]
+            .into_iter()
+            .sum();
+        assert_eq!(total.millis(), 3_501);
+        assert_eq!(serde_json::to_string(&total).unwrap(), "3501");
+    }
+
+    #[tokio::test]
+    async fn values_round_trip_through_sqlite() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let start = StartTime::from_millis(1_700_000_000_123);
+        let tracked = TrackedDuration::from_millis(90_500);
+
+        let (millis, decoded_start, decoded_tracked): (i64, StartTime, TrackedDuration) =
+            sqlx::query_as("SELECT ?1, ?1, ?2")
+                .bind(start)
+                .bind(tracked)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        assert_eq!(millis, 1_700_000_000_123);
+        assert_eq!(decoded_start, start);
+        assert_eq!(decoded_tracked, tracked);
+    }
+}
@@ -1,49 +1,320 @@
 use std::{
-    collections::HashMap,
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
     env,
     fmt::Display,
     hash::Hash,
-    time::{Duration, SystemTime},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
 
-use chrono::Utc;
-use serde::Serialize;
-use sqlx::SqlitePool;
-use tracing::{debug, error, info, instrument};
+use axum::async_trait;
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
+    SqliteConnection, SqlitePool,
+};
+use tracing::{debug, error, info, instrument, warn};
 
-use crate::uid::TagId;
+use crate::{
+    backup::Backups,
+    date_format::{Clock, DateFormat, DateOrder},
+    duration::{DurationDisplay, DurationFormat, DurationRounding},
+    error::Error,
+    plugins::Plugins,
+    time::{second_columns, StartTime, TrackedDuration},
+    timer_utils::{ExportFilter, LabelMatch},
+    uid::TagId,
+};
 
 #[derive(Debug, Clone)]
-pub(crate) struct DataStore {
+pub struct DataStore {
     pool: SqlitePool,
+    plugins: Plugins,
+    projects_cache: ProjectsCache,
+}
+
+/// How long a tag's projects stay cached without being asked for
+const PROJECTS_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// The most tags whose projects are cached at once
+const PROJECTS_CACHE_CAPACITY: usize = 1024;
+
+/// Recently loaded [`DataStore::projects_by_tag`] maps, by tag
+///
+/// Every change to the projects and timers of a tag bumps its version in
+/// `TAG_VERSIONS`, through triggers, so a cached map is used only as long as
+/// the version it was loaded at is the current one. That holds for changes
+/// made by other instances sharing the database too.
+#[derive(Debug, Clone, Default)]
+struct ProjectsCache(Arc<Mutex<HashMap<String, CachedProjects>>>);
+
+#[derive(Debug)]
+struct CachedProjects {
+    version: i64,
+    used_at: Instant,
+    projects: HashMap<Project, Vec<Timer>>,
+}
+
+impl ProjectsCache {
+    fn get(&self, tag: &str, version: i64) -> Option<HashMap<Project, Vec<Timer>>> {
+        let mut cache = self.0.lock().expect("Projects cache poisoned");
+        let cached = cache.get_mut(tag)?;
+        if cached.version != version || cached.used_at.elapsed() > PROJECTS_CACHE_TTL {
+            cache.remove(tag);
+            return None;
+        }
+
+        cached.used_at = Instant::now();
+        Some(cached.projects.clone())
+    }
+
+    fn insert(&self, tag: &str, version: i64, projects: HashMap<Project, Vec<Timer>>) {
+        let mut cache = self.0.lock().expect("Projects cache poisoned");
+        if cache.len() >= PROJECTS_CACHE_CAPACITY {
+            cache.retain(|_, cached| cached.used_at.elapsed() <= PROJECTS_CACHE_TTL);
+        }
+        if cache.len() >= PROJECTS_CACHE_CAPACITY {
+            let least_recent = cache
+                .iter()
+                .min_by_key(|(_, cached)| cached.used_at)
+                .map(|(tag, _)| tag.clone());
+            if let Some(tag) = least_recent {
+                cache.remove(&tag);
+            }
+        }
+
+        cache.insert(
+            tag.to_string(),
+            CachedProjects {
+                version,
+                used_at: Instant::now(),
+                projects,
+            },
+        );
+    }
+}
+
+/// Connection settings for the SQLite database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseConfig {
+    /// The database to connect to, from `DATABASE_URL`
+    pub url: String,
+
+    /// The most connections kept in the pool, from `DATABASE_MAX_CONNECTIONS`
+    pub max_connections: u32,
+
+    /// How long a connection waits for a lock held by another one, from
+    /// `DATABASE_BUSY_TIMEOUT_MS`
+    pub busy_timeout: Duration,
+
+    /// How long to keep retrying when the database can't be opened on
+    /// startup, from `DATABASE_CONNECT_TIMEOUT_SECS`
+    pub connect_timeout: Duration,
+}
+
+impl DatabaseConfig {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            url: env::var("DATABASE_URL")?,
+            max_connections: env_or("DATABASE_MAX_CONNECTIONS", 8),
+            busy_timeout: Duration::from_millis(env_or("DATABASE_BUSY_TIMEOUT_MS", 5_000)),
+            connect_timeout: Duration::from_secs(env_or("DATABASE_CONNECT_TIMEOUT_SECS", 30)),
+        })
+    }
+
+    /// The path of the database file, `None` for in-memory databases
+    pub fn file_path(&self) -> Option<PathBuf> {
+        let path = self
+            .url
+            .strip_prefix("sqlite://")
+            .or_else(|| self.url.strip_prefix("sqlite:"))?;
+        let path = path.split('?').next().unwrap_or_default();
+
+        match path {
+            "" | ":memory:" => None,
+            path => Some(PathBuf::from(path)),
+        }
+    }
+
+    /// Connection options tuned for concurrent toggles
+    ///
+    /// WAL lets readers proceed while a toggle writes and the busy timeout
+    /// makes concurrent writers wait instead of failing with `database is
+    /// locked`.
+    fn connect_options(&self) -> Result<SqliteConnectOptions> {
+        Ok(SqliteConnectOptions::from_str(&self.url)?
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(self.busy_timeout)
+            .foreign_keys(true))
+    }
+}
+
+/// How long to wait before the given retry of opening the database
+///
+/// Doubles with every attempt, starting at 100ms and capped at 5s.
+fn connect_backoff(attempt: u32) -> Duration {
+    Duration::from_millis(100)
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(Duration::from_secs(5))
+}
+
+/// Parses an environment variable, falling back to `default` if it's unset or invalid
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    let Ok(value) = env::var(name) else {
+        return default;
+    };
+
+    value.parse().unwrap_or_else(|_| {
+        warn!(name, value, "Invalid value, using the default");
+        default
+    })
 }
 
 /// A Timer object
-#[derive(Debug, sqlx::FromRow, Default, Serialize, PartialEq, Eq)]
-#[sqlx]
+#[derive(Debug, Clone, sqlx::FromRow, Default, Serialize, PartialEq, Eq)]
+#[serde(into = "TimerJson")]
 pub struct Timer {
     /// The ID of this timer
-    id: i64,
+    pub(crate) id: i64,
 
     /// The TagId this timer is associated with
-    pub(crate) unique_id: String,
+    pub unique_id: String,
 
     /// The project this timer is associated with
-    pub(crate) project_id: i64,
+    pub project_id: i64,
+
+    /// If this is the current timer associated with the [Timer::unique_id]
+    pub is_current: bool,
+
+    /// A free-form note describing this timer
+    pub note: Option<String>,
+
+    /// The issue, commit or branch this timer was spent on
+    pub link: Option<String>,
+
+    /// When this timer was created
+    pub created_at: i64,
+
+    /// When this timer was last modified
+    pub updated_at: i64,
+
+    /// The device which started this timer, if known
+    pub device_id: Option<i64>,
+
+    /// Whether the timer was billed already, which keeps it out of exports
+    pub invoiced: bool,
 
     /// When the timer was started
-    pub(crate) start_time: i64,
+    pub start_time_ms: StartTime,
 
-    /// If this is the current timer associated with the [Timer::unique_id]
-    pub(crate) is_current: bool,
+    /// How long the timer lasted, nothing while the timer is running
+    pub duration_ms: TrackedDuration,
+}
 
-    /// The duration for which this timer lasted.
-    ///
-    /// This value is only valid for timers for which `is_current` == false
-    #[sqlx(default)]
-    pub(crate) duration: i64,
+/// A [`Timer`] as written in JSON
+///
+/// Clients read the start and duration in whole seconds from before timers
+/// were kept to the millisecond, so those are written next to the
+/// milliseconds.
+#[derive(Serialize)]
+struct TimerJson {
+    id: i64,
+    unique_id: String,
+    project_id: i64,
+    start_time: i64,
+    is_current: bool,
+    duration: i64,
+    note: Option<String>,
+    link: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+    device_id: Option<i64>,
+    invoiced: bool,
+    start_time_ms: StartTime,
+    duration_ms: TrackedDuration,
+}
+
+impl From<Timer> for TimerJson {
+    fn from(timer: Timer) -> Self {
+        let (start_time, duration) = timer.seconds();
+        Self {
+            id: timer.id,
+            unique_id: timer.unique_id,
+            project_id: timer.project_id,
+            start_time,
+            is_current: timer.is_current,
+            duration,
+            note: timer.note,
+            link: timer.link,
+            created_at: timer.created_at,
+            updated_at: timer.updated_at,
+            device_id: timer.device_id,
+            invoiced: timer.invoiced,
+            start_time_ms: timer.start_time_ms,
+            duration_ms: timer.duration_ms,
+        }
+    }
+}
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// How far ahead of the server clock a client reported toggle time may be
+const MAX_CLOCK_SKEW_SECONDS: i64 = 5 * 60;
+
+/// How long after the fact a client may report a toggle
+const MAX_EVENT_AGE_SECONDS: i64 = 7 * SECONDS_PER_DAY;
+
+/// How long the idempotency key of a toggle is kept for replays to be
+/// recognized, a day longer than a client may hold on to the toggle
+pub const IDEMPOTENCY_KEY_TTL: i64 = MAX_EVENT_AGE_SECONDS + SECONDS_PER_DAY;
+
+/// The time `days` days before `now`, rejecting spans too long to represent
+fn days_before(now: i64, days: i64) -> Result<i64> {
+    days.checked_mul(SECONDS_PER_DAY)
+        .and_then(|seconds| now.checked_sub(seconds))
+        .ok_or_else(|| Error::Validation(format!("{} days is too long", days)).into())
+}
+
+/// Checks a client reported toggle time against the server clock
+///
+/// Times slightly in the future are clamped to now, since they are most
+/// likely clock drift. Anything further off is rejected.
+pub(crate) fn event_time(at: Option<i64>) -> Result<i64> {
+    let now = Utc::now().timestamp();
+    let Some(at) = at else {
+        return Ok(now);
+    };
+
+    if at > now + MAX_CLOCK_SKEW_SECONDS {
+        return Err(Error::Validation(format!("Event time {} is in the future", at)).into());
+    }
+    if at < now - MAX_EVENT_AGE_SECONDS {
+        return Err(Error::Validation(format!("Event time {} is too far in the past", at)).into());
+    }
+
+    Ok(at.min(now))
+}
+
+/// Like [`event_time`], in milliseconds
+///
+/// Clients only report seconds, toggles happening now get the milliseconds
+/// of the server clock.
+pub(crate) fn event_at(at: Option<i64>) -> Result<DateTime<Utc>> {
+    match at {
+        Some(_) => Ok(Utc
+            .timestamp_opt(event_time(at)?, 0)
+            .single()
+            .unwrap_or_default()),
+        None => Ok(Utc::now()),
+    }
 }
 
 #[derive(Debug)]
@@ -52,7 +323,7 @@ enum IsCurrent {
     No = 0,
 }
 
-#[derive(Debug, sqlx::FromRow, Default, Serialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, sqlx::FromRow, Default, Serialize, PartialEq, Eq, Hash)]
 #[sqlx]
 pub struct Project {
     /// The ID of the Project
@@ -68,452 +339,9974 @@ pub struct Project {
     pub unique_id: String,
 
     /// When this project was created
-    created: i64,
+    pub created: i64,
+
+    /// When this project was last modified
+    pub updated_at: i64,
+
+    /// Whether this project has been archived
+    pub archived: bool,
+
+    /// The project this one is nested under, like the client of a project
+    pub parent_id: Option<i64>,
 }
 
-impl Display for Project {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
-    }
+/// Activity information for a single [`TagId`][crate::uid::TagId]
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct TagActivity {
+    /// The TagId this activity is associated with
+    pub unique_id: String,
+
+    /// When the tag was last used, as a unix epoch in UTC
+    pub last_seen: i64,
+
+    /// Whether the tag has been archived due to inactivity
+    pub archived: bool,
 }
 
-impl Timer {
-    pub fn end_time(&self) -> i64 {
-        self.start_time + self.duration
-    }
+/// A saved preset for adding a finished timer in one step
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct Preset {
+    /// The ID of the preset
+    pub id: i64,
+
+    /// The TagId this preset is associated with
+    pub unique_id: String,
+
+    /// The project timers created from this preset belong to
+    pub project_id: i64,
+
+    /// The name of the preset
+    pub name: String,
+
+    /// The duration of timers created from this preset in seconds
+    pub duration: i64,
+
+    /// The note attached to timers created from this preset
+    pub note: Option<String>,
 }
 
-impl DataStore {
-    pub(crate) async fn new() -> Result<Self> {
-        let pool = SqlitePool::connect(&env::var("DATABASE_URL")?).await?;
-        sqlx::migrate!().run(&pool).await?;
-        Ok(DataStore { pool })
-    }
+/// Everything a dashboard shows about one tag, see [`DataStore::dashboard`]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct TagDashboard {
+    pub unique_id: String,
 
-    #[cfg(test)]
-    async fn new_test(pool: SqlitePool) -> Result<Self> {
-        Ok(DataStore { pool })
-    }
+    /// Newest first
+    pub projects: Vec<DashboardProject>,
+}
 
-    /// Toggles the current timer for the given UID
-    #[instrument(skip(self))]
-    pub async fn toggle_current(&self, uid: &TagId) -> Result<i64> {
-        if let Ok(mut timer) = self.current_timer(uid).await {
-            // We already have an existing timer
-            let timer_id = timer.id;
-            debug!(?timer, "Ending current timer");
-            let timer_duration = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?
-                - Duration::from_secs(timer.start_time.try_into()?);
-            timer.duration = timer_duration.as_secs().try_into()?;
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct DashboardProject {
+    pub project: Project,
 
-            if !self.update_timer(timer).await? {
-                error!(?timer_id, "Error updating timer");
-                return Err(anyhow::anyhow!("Unable to update timer"));
-            }
+    /// When the oldest running timer of the project was started
+    pub running_since: Option<i64>,
 
-            Ok(timer_id)
-        } else {
-            debug!(tag_id = uid.as_ref(), "Creating new timer");
-            // The start_time field has defaults to the current unix epoch
-            self.create_timer(uid).await
-        }
-    }
+    /// Seconds tracked on the project since the start of the period
+    pub tracked: i64,
+}
 
-    #[instrument(skip(self))]
-    /// Get the current project associated with the [`TagId`][crate::uid::TagId]
-    ///
-    /// Every project is associated with a **single** [`TagId`][crate::uid::TagId]
-    async fn current_project(&self, uid: &TagId) -> Result<Project> {
-        let tag_id = uid.as_ref();
-        info!(tag_id, "Getting current project");
+/// The timers of a tag started on one day, see [`DataStore::timers_by_day`]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct TimerDay {
+    pub date: NaiveDate,
 
-        let result = sqlx::query_as!(
-            Project,
-            r#"
-SELECT * FROM PROJECTS
-WHERE unique_id = ?1 AND is_current = ?2"#,
-            tag_id,
-            IsCurrent::Yes as i64
-        )
-        .fetch_one(&self.pool)
-        .await?;
+    /// Oldest first
+    pub entries: Vec<DayEntry>,
 
-        Ok(result)
+    /// Time tracked by the entries, running ones up to now
+    pub total: TrackedDuration,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct DayEntry {
+    pub timer_id: i64,
+    pub project: String,
+    pub start_time: StartTime,
+
+    /// Time tracked by the timer, up to now while it's running
+    pub duration: TrackedDuration,
+    pub is_current: bool,
+    pub note: Option<String>,
+}
+
+/// What happened in an [`ActivityEntry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+    /// A timer was started
+    Started,
+
+    /// A timer was stopped
+    Stopped,
+
+    /// A running timer was thrown away, like a short one
+    Discarded,
+
+    /// A project was created
+    Created,
+}
+
+/// An entry of a tag's activity log, see [`DataStore::activity`]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ActivityEntry {
+    pub id: i64,
+    pub kind: ActivityKind,
+
+    /// The name the project had at the time
+    pub project: String,
+
+    /// When it happened, as a unix epoch in UTC
+    pub at: i64,
+
+    /// The seconds tracked by a stopped or discarded timer
+    pub duration: Option<i64>,
+}
+
+/// The live status of a tag, as shown on a wall display
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct TagStatus {
+    /// The TagId this status is associated with
+    pub unique_id: String,
+
+    /// The project being tracked, or the current project when idle
+    pub project_name: Option<String>,
+
+    /// When the running timer was started, if there is one
+    pub start_time: Option<i64>,
+}
+
+/// What a [`Token`] grants to whoever holds its secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Read-only access to a tag's timers through a share link
+    Share,
+
+    /// A bearer token which lets an integration read and toggle a tag's timer
+    Api,
+}
+
+impl TokenKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            TokenKind::Share => "share",
+            TokenKind::Api => "api",
+        }
     }
 
-    /// Gets all projects associated with [`TagId`][crate::uid::TagId]
-    #[instrument(skip(self))]
-    async fn get_projects(&self, uid: &TagId) -> Result<Vec<Project>> {
-        let tag_id = uid.as_ref();
-        info!(tag_id, "Getting projects");
-        let result = sqlx::query_as!(
-            Project,
-            "SELECT * FROM PROJECTS WHERE unique_id = ?1",
-            tag_id
-        )
-        .fetch_all(&self.pool)
-        .await?;
-        Ok(result)
+    fn name(self) -> &'static str {
+        match self {
+            TokenKind::Share => "share link",
+            TokenKind::Api => "API token",
+        }
     }
+}
 
-    /// Creates a new project with the associated tag.
-    ///
-    /// If a project already exists, it ensures that the `is_current` status is handled properly.
-    #[instrument(skip(self))]
-    pub async fn create_project(&self, uid: &TagId, project_name: &str) -> Result<i64> {
-        let tag_id = uid.as_ref();
-        info!(tag_id, "Creating new project");
+/// A share link or API token of a tag
+///
+/// Only a hash of the secret is stored, so it's known only when the token is
+/// created, see [`NewToken`].
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct Token {
+    pub token_id: i64,
 
-        // Update the existing current project if necessary
-        match self.current_project(uid).await {
-            Ok(p) => {
-                sqlx::query!(
-                    r#"
-UPDATE projects
-SET is_current = ?1
-WHERE id = ?2;
-                    "#,
-                    IsCurrent::No as i64,
-                    p.id
-                )
-                .execute(&self.pool)
-                .await?;
-            }
-            Err(_) => {}
-        };
+    /// The TagId the token grants access to
+    pub unique_id: String,
 
-        let now = Utc::now().timestamp();
-        let id = sqlx::query!(
-            r#"
-INSERT INTO PROJECTS (unique_id, is_current, name, created)
-VALUES (?1, ?2, ?3, ?4)"#,
-            tag_id,
-            IsCurrent::Yes as i64,
-            project_name,
-            now
-        )
-        .execute(&self.pool)
-        .await?
-        .last_insert_rowid();
+    /// When the token was created, as a unix epoch in UTC
+    pub created_at: i64,
 
-        Ok(id)
-    }
+    /// Revoked tokens are no longer accepted
+    pub revoked: bool,
+}
 
-    #[cfg(test)]
-    async fn get_timer(&self, timer_id: i64) -> Result<Timer> {
-        Ok(sqlx::query_as!(
-            Timer,
-            r#"
-SELECT * FROM TIMERS
-WHERE id = ?1"#,
-            timer_id,
-        )
-        .fetch_one(&self.pool)
-        .await?)
+/// A token which was just created, along with its secret
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct NewToken {
+    /// The secret, part of a share URL or sent in the `Authorization` header
+    pub token: String,
+
+    #[serde(flatten)]
+    pub info: Token,
+}
+
+/// A short slug which redirects to the timer page of a tag
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct Slug {
+    /// The path segment after `/s/`
+    pub slug: String,
+
+    /// The TagId whose timer page the slug leads to
+    pub unique_id: String,
+
+    /// When the slug was created, as a unix epoch in UTC
+    pub created_at: i64,
+}
+
+/// A phone or NFC reader which toggles timers for a tag
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct Device {
+    /// The ID of the device
+    pub id: i64,
+
+    /// The TagId this device toggles timers for
+    pub unique_id: String,
+
+    /// The details the device reports about itself
+    pub details: String,
+
+    /// When the device was first used, as a unix epoch in UTC
+    pub first_seen: i64,
+
+    /// When the device was last used, as a unix epoch in UTC
+    pub last_seen: i64,
+
+    /// Revoked devices can no longer toggle timers
+    pub revoked: bool,
+}
+
+/// What a member may do in a team, each role can do everything the ones before it can
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum Role {
+    /// Sees the projects and timers of the team
+    Viewer,
+
+    /// Also tracks time on the projects of the team
+    Member,
+
+    /// Also manages the members and projects of the team
+    Owner,
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let role = match self {
+            Role::Viewer => "viewer",
+            Role::Member => "member",
+            Role::Owner => "owner",
+        };
+        write!(f, "{}", role)
     }
+}
 
-    /// Returns a map of projects->timers associated with given [`TagId`][crate::uid::TagId]
-    pub(crate) async fn projects_by_tag(
-        &self,
-        timer_tag: &TagId,
-    ) -> Result<HashMap<Project, Vec<Timer>>> {
-        let tag = timer_tag.as_ref();
-        info!(tag, "Generating project->timer map");
-        struct JoinResult {
-            project_name: String,
-            project_id: i64,
-            created: i64,
-            unique_id: String,
-            project_is_current: bool,
-            timer_id: Option<i64>,
-            start_time: Option<i64>,
-            timer_is_current: Option<bool>,
-            duration: Option<i64>,
-        }
+/// A group of tags sharing a set of projects
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct Team {
+    /// The ID of the team
+    pub id: i64,
+
+    /// The name of the team
+    pub name: String,
+
+    /// When the team was created, as a unix epoch in UTC
+    pub created_at: i64,
+}
+
+/// A tag which belongs to a [`Team`]
+///
+/// Members are only known by an ID within the team, since their tag grants
+/// access to their timers.
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct TeamMember {
+    /// The ID of the member within the team
+    pub id: i64,
+
+    /// What the member may do in the team
+    pub role: Role,
+
+    /// When the member joined, as a unix epoch in UTC
+    pub joined_at: i64,
+}
+
+/// A timer on a [`TeamProject`], telling which member tracked it by their
+/// [`TeamMember::id`]
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct TeamTimer {
+    pub id: i64,
+    pub project_id: i64,
+
+    /// The member who tracked the timer, nothing once they left the team
+    pub member_id: Option<i64>,
+
+    /// When the timer was started, written as a unix epoch in UTC
+    #[serde(serialize_with = "crate::time::as_epoch_seconds")]
+    pub start_time: StartTime,
+    pub is_current: bool,
+
+    /// How long the timer ran, only valid once it's no longer current
+    #[serde(serialize_with = "crate::time::as_seconds")]
+    pub duration: TrackedDuration,
+    pub note: Option<String>,
+    pub link: Option<String>,
+    pub invoiced: bool,
+}
+
+/// A project of a [`Team`] with the timers of all members
+///
+/// Only what members need of the [`Project`], which would name the tag of the
+/// owner who created it.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct TeamProject {
+    pub id: i64,
+    pub name: String,
+    pub archived: bool,
+
+    /// The timers of every member, most recent first
+    pub timers: Vec<TeamTimer>,
+}
+
+/// Everything a member can see of a [`Team`], from [`DataStore::team_report`]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct TeamReport {
+    pub team: Team,
+
+    /// The role of the member the report is for
+    pub role: Role,
+
+    pub members: Vec<TeamMember>,
+    pub projects: Vec<TeamProject>,
+}
+
+/// Where a [`Timesheet`] is in its review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum TimesheetStatus {
+    /// Waiting for an owner of the team to review it
+    Submitted,
+
+    /// Accepted by an owner, its timers can no longer be changed
+    Approved,
+
+    /// Sent back by an owner, the member can submit it again
+    Rejected,
+}
+
+/// The week of timers a member tracked on the projects of a [`Team`]
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct Timesheet {
+    pub id: i64,
+    pub team_id: i64,
+
+    /// The [`TeamMember::id`] of the member, nothing once they left the team
+    pub member_id: Option<i64>,
+
+    /// Midnight on the Monday the week starts, as a unix epoch in UTC
+    pub week_start: i64,
+
+    /// Midnight on the Monday after, as a unix epoch in UTC
+    pub week_end: i64,
+    pub status: TimesheetStatus,
+
+    /// Left by the owner reviewing the timesheet
+    pub comment: Option<String>,
+    pub submitted_at: i64,
+
+    /// The [`TeamMember::id`] of the owner who reviewed the timesheet
+    pub reviewed_by: Option<i64>,
+    pub reviewed_at: Option<i64>,
+}
+
+/// A tag which wants the weekly digest, from [`DataStore::digest_recipients`]
+#[derive(Debug, sqlx::FromRow, PartialEq, Eq)]
+pub struct DigestRecipient {
+    /// The TagId the digest is for
+    pub unique_id: String,
+
+    /// Where the digest is sent to
+    pub email: String,
+}
+
+/// A tag with reminders set up and no running timer
+#[derive(Debug, sqlx::FromRow, PartialEq, Eq)]
+pub struct IdleTag {
+    /// The TagId which has no running timer
+    pub unique_id: String,
+
+    /// Where the reminder is emailed to
+    pub email: Option<String>,
+
+    /// Where the reminder is posted to
+    pub reminder_webhook: Option<String>,
+
+    /// When to send reminders, see [`crate::reminders::WorkSchedule`]
+    pub reminder_schedule: String,
+
+    /// How many minutes without a running timer before a reminder is sent
+    pub reminder_after: i64,
+
+    /// When the tag's last timer stopped, if it ever had one
+    pub last_stopped: Option<StartTime>,
+
+    /// When the tag was last reminded, if ever
+    pub reminded_at: Option<i64>,
+}
+
+/// A running timer of a tag with [`TagSettings::split_at_midnight`]
+#[derive(Debug, sqlx::FromRow, PartialEq, Eq)]
+pub struct MidnightTimer {
+    /// The ID of the running timer
+    pub timer_id: i64,
+
+    /// The TagId the timer belongs to
+    pub unique_id: String,
+    pub start_time_ms: StartTime,
+
+    /// The [`TagSettings::goal_timezone`] of the tag
+    pub timezone: String,
+}
+
+/// A running timer which has passed its tag's [`TagSettings::long_timer_after`]
+#[derive(Debug, sqlx::FromRow, PartialEq, Eq)]
+pub struct LongTimer {
+    /// The ID of the running timer
+    pub timer_id: i64,
+
+    /// The TagId the timer belongs to
+    pub unique_id: String,
+
+    /// The name of the timer's project
+    pub project: String,
+
+    /// When the timer started
+    pub start_time: StartTime,
+
+    /// Where the alert is emailed to
+    pub email: Option<String>,
+
+    /// Where the alert is posted to
+    pub reminder_webhook: Option<String>,
+}
+
+/// A tag which set itself a [`TagSettings::daily_goal`]
+#[derive(Debug, sqlx::FromRow, PartialEq, Eq)]
+pub struct DailyGoal {
+    /// The TagId with the goal
+    pub unique_id: String,
+
+    /// How many minutes the tag wants to track every day
+    pub minutes: i64,
+
+    /// The timezone the days of the goal start in
+    pub timezone: String,
+
+    /// Where reaching the goal is posted to, if the tag wants it to be
+    pub webhook: Option<String>,
+
+    /// When the tag last reached its goal, if ever
+    pub reached_at: Option<i64>,
+}
+
+/// Where a page of [`DataStore::exportable_timers_page`] ends
+///
+/// Written as `<start_time_ms>:<id>` of the last timer on the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerCursor {
+    pub start_time_ms: i64,
+    pub id: i64,
+}
+
+impl TimerCursor {
+    fn of(timer: &Timer) -> Self {
+        Self {
+            start_time_ms: timer.start_time_ms.millis(),
+            id: timer.id,
+        }
+    }
+}
+
+impl Display for TimerCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.start_time_ms, self.id)
+    }
+}
+
+impl FromStr for TimerCursor {
+    type Err = Error;
+
+    fn from_str(cursor: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Validation(format!("Invalid cursor {}", cursor));
+        let (start_time_ms, id) = cursor.split_once(':').ok_or_else(invalid)?;
+
+        Ok(Self {
+            start_time_ms: start_time_ms.parse().map_err(|_| invalid())?,
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Finished timers of a project along with where the next page starts
+#[derive(Debug, Serialize)]
+pub struct TimerPage {
+    pub timers: Vec<Timer>,
+
+    /// `None` on the last page
+    #[serde(serialize_with = "serialize_cursor")]
+    pub next: Option<TimerCursor>,
+}
+
+fn serialize_cursor<S: serde::Serializer>(
+    cursor: &Option<TimerCursor>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match cursor {
+        Some(cursor) => serializer.collect_str(cursor),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// A change to the finished timers of a tag, see [`DataStore::bulk_timers`]
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum TimerOperation {
+    Create {
+        project_id: i64,
+        start_time: i64,
+        /// In seconds
+        duration: i64,
+        note: Option<String>,
+    },
+
+    /// Changes the fields which are given, an empty note removes it
+    Update {
+        timer_id: i64,
+        project_id: Option<i64>,
+        start_time: Option<i64>,
+        duration: Option<i64>,
+        note: Option<String>,
+    },
+
+    Delete {
+        timer_id: i64,
+    },
+}
+
+/// Settings new projects of a tag can start out with
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct ProjectTemplate {
+    pub id: i64,
+
+    /// The TagId the template belongs to
+    pub unique_id: String,
+    pub name: String,
+
+    /// The hour budget of new projects in seconds, if they get one
+    pub budget: Option<i64>,
+
+    /// The project new projects are nested under, like their client
+    pub parent_id: Option<i64>,
+
+    /// When the template was created, as a unix epoch in UTC
+    pub created_at: i64,
+}
+
+/// The confirmation a tag needs to delete its account, see [`DataStore::delete_account`]
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct AccountDeletion {
+    /// The secret to send along with the deletion
+    pub token: String,
+
+    /// When the token stops being accepted, as a unix epoch in UTC
+    pub expires_at: i64,
+}
+
+/// The percentages of a budget which trigger an alert once reached
+const BUDGET_THRESHOLDS: [i64; 2] = [80, 100];
+
+/// The hour budget of a project and how much of it was used
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct ProjectBudget {
+    pub project_id: i64,
+
+    /// The TagId the project belongs to
+    pub unique_id: String,
+
+    /// The name of the project
+    pub project: String,
+
+    /// The budgeted time in seconds
+    pub budget: i64,
+
+    /// The time tracked on the project, including the running timer
+    #[serde(serialize_with = "crate::time::as_seconds")]
+    pub consumed: TrackedDuration,
+
+    /// The highest of the [`BUDGET_THRESHOLDS`] an alert was sent for, or 0
+    pub alerted: i64,
+
+    /// Where alerts are emailed to
+    #[serde(skip)]
+    pub email: Option<String>,
+
+    /// Where alerts are posted to
+    #[serde(skip)]
+    pub reminder_webhook: Option<String>,
+}
+
+impl ProjectBudget {
+    /// How much of the budget was used, in percent
+    pub fn percent(&self) -> i64 {
+        self.consumed.seconds() * 100 / self.budget
+    }
+
+    /// The highest threshold which was reached but not alerted yet, if any
+    pub fn due_threshold(&self) -> Option<i64> {
+        BUDGET_THRESHOLDS
+            .into_iter()
+            .filter(|threshold| *threshold > self.alerted && self.percent() >= *threshold)
+            .max()
+    }
+}
+
+/// How often a [`ScheduledExport`] is delivered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum ExportSchedule {
+    /// Every Monday, covering the week before
+    Weekly,
+
+    /// On the first of every month, covering the month before
+    Monthly,
+}
+
+/// A CSV export of a project which is delivered at the end of every period
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct ScheduledExport {
+    pub id: i64,
+    pub project_id: i64,
+
+    /// The TagId the project belongs to
+    pub unique_id: String,
+    pub schedule: ExportSchedule,
+
+    /// The timezone periods start at midnight in
+    pub timezone: String,
+
+    /// Where the export is emailed to
+    pub email: Option<String>,
+
+    /// Where the export is posted to
+    pub webhook: Option<String>,
+    pub created_at: i64,
+
+    /// The start of the latest period which was delivered
+    pub last_sent: Option<i64>,
+}
+
+/// A webhook delivery which kept failing, see [`crate::webhooks`]
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct FailedWebhook {
+    pub id: i64,
+
+    /// The TagId the webhook was sent for
+    pub unique_id: String,
+    pub url: String,
+
+    /// What the webhook was about, like `budget` or `export`
+    pub kind: String,
+    pub content_type: String,
+
+    /// Sent as an attachment with this file name, like a CSV export
+    pub filename: Option<String>,
+    #[serde(skip)]
+    pub body: Vec<u8>,
+
+    /// The HTTP status of the last attempt, unless it got no response
+    pub status: Option<i64>,
+
+    /// The body of the last response, or why there was none
+    pub response: String,
+    pub attempts: i64,
+    pub failed_at: i64,
+
+    /// When the delivery is retried next, unless it's left for an admin to redeliver
+    pub next_attempt_at: Option<i64>,
+}
+
+/// A Google account a tag pushes its finished timers to, see [`crate::google_calendar`]
+#[derive(Clone, sqlx::FromRow, PartialEq, Eq)]
+pub struct GoogleAccount {
+    /// The TagId which connected the account
+    pub unique_id: String,
+    pub refresh_token: String,
+    pub access_token: String,
+
+    /// When the access token stops working, as a unix epoch in UTC
+    pub expires_at: i64,
+
+    /// The calendar timers go to unless their project has its own
+    pub calendar_id: String,
+
+    /// When the account was connected, timers which ended before aren't pushed
+    pub created_at: i64,
+}
+
+/// Leaves the tokens out of logs
+impl std::fmt::Debug for GoogleAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GoogleAccount")
+            .field("unique_id", &self.unique_id)
+            .field("expires_at", &self.expires_at)
+            .field("calendar_id", &self.calendar_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A finished timer which wasn't pushed to Google Calendar yet
+#[derive(Debug, Clone, sqlx::FromRow, PartialEq, Eq)]
+pub struct GoogleTimer {
+    pub timer_id: i64,
+
+    /// The TagId the timer belongs to
+    pub unique_id: String,
+
+    /// The name of the timer's project
+    pub project: String,
+    pub start_time_ms: StartTime,
+    pub duration_ms: TrackedDuration,
+    pub note: Option<String>,
+
+    /// The calendar of the project, or else the one of the account
+    pub calendar_id: String,
+}
+
+/// A Jira site a tag pushes its finished timers to, see [`crate::jira`]
+#[derive(Clone, sqlx::FromRow, PartialEq, Eq)]
+pub struct JiraAccount {
+    /// The TagId which connected the site
+    pub unique_id: String,
+
+    /// Where the site is, without a trailing slash
+    pub site: String,
+
+    /// The Atlassian account the API token belongs to
+    pub email: String,
+    pub api_token: String,
+
+    /// When the site was connected, timers which ended before aren't pushed
+    pub created_at: i64,
+}
+
+/// Leaves the API token out of logs
+impl std::fmt::Debug for JiraAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JiraAccount")
+            .field("unique_id", &self.unique_id)
+            .field("site", &self.site)
+            .field("email", &self.email)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A finished timer which wasn't logged in Jira yet
+#[derive(Debug, Clone, sqlx::FromRow, PartialEq, Eq)]
+pub struct JiraTimer {
+    pub timer_id: i64,
+
+    /// The TagId the timer belongs to
+    pub unique_id: String,
+
+    /// The issue the timer's project is logged on
+    pub issue_key: String,
+    pub start_time_ms: StartTime,
+    pub duration_ms: TrackedDuration,
+    pub note: Option<String>,
+}
+
+/// Jira only takes worklogs of at least a minute
+const MIN_WORKLOG_SECONDS: i64 = 60;
+
+/// The time tracked by the timers started within a [`TIME_BUCKET`] seconds long bucket
+#[derive(Debug, sqlx::FromRow, PartialEq, Eq)]
+pub struct TimeBucket {
+    /// When the bucket starts
+    pub start: i64,
+
+    /// The total duration of the timers started in the bucket
+    pub tracked: TrackedDuration,
+}
+
+/// The time tracked on a project over some period
+#[derive(Debug, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct ProjectHours {
+    /// The name of the project
+    pub name: String,
+
+    /// The total duration of the project's timers
+    #[serde(serialize_with = "crate::time::as_seconds")]
+    pub duration: TrackedDuration,
+}
+
+/// The time tracked on timers carrying a label over some period
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct LabelHours {
+    /// The name of the label
+    pub label: String,
+
+    /// The total duration of the labelled timers
+    #[serde(serialize_with = "crate::time::as_seconds")]
+    pub duration: TrackedDuration,
+}
+
+/// How the time of a project's finished timers adds up
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ProjectStats {
+    pub project_id: i64,
+
+    /// How many timers were tracked
+    pub sessions: i64,
+
+    /// The durations of the timers in seconds
+    pub total_seconds: i64,
+    pub average_session_seconds: i64,
+    pub longest_session_seconds: i64,
+
+    /// The day of the week with the most time tracked, by when timers started
+    pub busiest_weekday: Option<Weekday>,
+
+    /// When the first timer started and the last one stopped, as unix epochs in UTC
+    pub first_activity: Option<i64>,
+    pub last_activity: Option<i64>,
+}
+
+/// Runs of consecutive days on which a tag tracked enough time
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Streaks {
+    /// How many minutes a day needs to count
+    pub minutes: i64,
+
+    /// The days in a row up to today, or up to yesterday until today counts too
+    pub current: i64,
+    pub longest: i64,
+}
+
+/// The timers of a single project which matched a [`DataStore::search`]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SearchResult {
+    /// The project the timers belong to
+    pub project: Project,
+
+    /// The matching timers, most recent first
+    pub timers: Vec<Timer>,
+}
+
+/// Filters for [`DataStore::search_projects`]
+#[derive(Debug, Default)]
+pub struct ProjectQuery {
+    /// Only include projects whose name contains this
+    pub name: Option<String>,
+
+    /// Only include projects which are (or aren't) archived
+    pub archived: Option<bool>,
+
+    /// The number of projects to return
+    pub limit: i64,
+
+    /// The number of matching projects to skip
+    pub offset: i64,
+}
+
+/// Optional parameters for [`DataStore::toggle`]
+#[derive(Debug, Default)]
+pub struct ToggleOptions {
+    /// The device performing the toggle
+    pub device: Option<Device>,
+
+    /// The project to toggle a timer for instead of the current project
+    pub project_id: Option<i64>,
+
+    /// When the toggle happened as a unix epoch in UTC, defaults to now
+    ///
+    /// Must lie within the last week, see [`event_time`].
+    pub at: Option<i64>,
+}
+
+/// A toggle in a batch given to [`DataStore::sync`]
+#[derive(Debug)]
+pub struct SyncEvent {
+    pub uid: TagId,
+
+    /// Makes an event which was synced before a no-op
+    pub key: String,
+    pub options: ToggleOptions,
+}
+
+/// What became of a [`SyncEvent`]
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct SyncedToggle {
+    #[serde(rename = "idempotency-key")]
+    pub key: String,
+
+    /// The timer which was toggled
+    pub timer_id: i64,
+
+    /// Whether the event had been synced before and was skipped
+    pub replayed: bool,
+}
+
+/// What to do with timers shorter than the configured threshold when they are stopped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ShortTimerPolicy {
+    /// Keep the timer as is
+    #[default]
+    Keep,
+
+    /// Delete the timer
+    Discard,
+
+    /// Fold the timer into the previous timer of the same project
+    Merge,
+}
+
+/// The color scheme of the web UI
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Dark text on a white page
+    #[default]
+    Light,
+
+    /// Light text on a dark page
+    Dark,
+
+    /// Whichever the browser prefers
+    Auto,
+}
+
+/// Settings associated with a single [`TagId`][crate::uid::TagId]
+#[derive(Debug, Clone, sqlx::FromRow, Serialize, PartialEq, Eq)]
+pub struct TagSettings {
+    /// The TagId these settings are associated with
+    pub unique_id: String,
+
+    /// Timers shorter than this many seconds are handled by the
+    /// [`TagSettings::short_timer_policy`]
+    pub short_timer_threshold: i64,
+
+    /// What to do with short timers when they are stopped
+    pub short_timer_policy: ShortTimerPolicy,
+
+    /// Whether every project can have its own running timer
+    pub concurrent_timers: bool,
+
+    /// Whether starting a timer without any project creates a "new-project" for it,
+    /// instead of asking for a project to be created first
+    pub default_project: bool,
+
+    /// Where emails for this tag are sent to
+    pub email: Option<String>,
+
+    /// Whether a summary of the hours per project is emailed every week
+    pub weekly_digest: bool,
+
+    /// When to remind this tag to start a timer, see [`crate::reminders::WorkSchedule`]
+    pub reminder_schedule: Option<String>,
+
+    /// How many minutes without a running timer before a reminder is sent
+    pub reminder_after: i64,
+
+    /// A URL reminders are posted to, in addition to the email address
+    pub reminder_webhook: Option<String>,
+
+    /// How many minutes a timer can run before an alert is sent, if at all
+    pub long_timer_after: Option<i64>,
+
+    /// How many minutes a day needs to count towards a streak, see [`Streaks`]
+    pub streak_minutes: i64,
+
+    /// How many minutes the tag wants to track every day, if it has a goal
+    pub daily_goal: Option<i64>,
+
+    /// The timezone the days of the tag start in, for the
+    /// [`TagSettings::daily_goal`] and [`TagSettings::split_at_midnight`]
+    pub goal_timezone: String,
+
+    /// Whether reaching the daily goal is also posted to the reminder webhook
+    pub goal_webhook: bool,
+
+    /// The columns exports have unless the request picks them, see
+    /// [`crate::timer_utils::ExportColumn`]
+    pub export_columns: Option<String>,
+
+    /// How durations are written unless the request picks a format
+    pub duration_format: DurationFormat,
+
+    /// How durations are rounded to their format
+    pub duration_rounding: DurationRounding,
+
+    /// Whether durations under an hour are written with their seconds
+    pub duration_seconds: bool,
+
+    /// Whether running timers are split in two at midnight, so daily totals
+    /// only count the time of their own day
+    pub split_at_midnight: bool,
+
+    /// The order dates are written in on pages and in exports
+    pub date_order: DateOrder,
+
+    /// Whether times are written on a 24 or 12 hour clock
+    pub clock: Clock,
+
+    /// The language of the web UI, instead of the one the browser asks for
+    pub language: Option<String>,
+
+    /// The color scheme of the web UI
+    pub theme: Theme,
+
+    /// When these settings were first stored
+    pub created_at: i64,
+
+    /// When these settings were last modified
+    pub updated_at: i64,
+}
+
+impl Default for TagSettings {
+    fn default() -> Self {
+        Self {
+            unique_id: String::new(),
+            short_timer_threshold: 0,
+            short_timer_policy: ShortTimerPolicy::default(),
+            concurrent_timers: false,
+            default_project: false,
+            email: None,
+            weekly_digest: false,
+            reminder_schedule: None,
+            reminder_after: 15,
+            reminder_webhook: None,
+            long_timer_after: None,
+            streak_minutes: 30,
+            daily_goal: None,
+            goal_timezone: "US/Pacific".to_string(),
+            goal_webhook: false,
+            export_columns: None,
+            duration_format: DurationFormat::default(),
+            duration_rounding: DurationRounding::default(),
+            duration_seconds: false,
+            split_at_midnight: false,
+            date_order: DateOrder::default(),
+            clock: Clock::default(),
+            language: None,
+            theme: Theme::default(),
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+}
+
+impl TagSettings {
+    /// How the timestamps of this tag are written
+    pub fn date_format(&self) -> DateFormat {
+        DateFormat::new(self.date_order, self.clock)
+    }
+
+    /// How the durations of this tag are written, in `format` if given
+    pub fn duration_display(&self, format: Option<DurationFormat>) -> DurationDisplay {
+        DurationDisplay::new(
+            format.unwrap_or(self.duration_format),
+            self.duration_rounding,
+        )
+        .with_seconds(self.duration_seconds)
+    }
+}
+
+impl Display for Project {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// How many seconds a [`TimeBucket`] spans
+///
+/// Every timezone's offset from UTC is a multiple of 15 minutes, so buckets
+/// never straddle midnight in any of them.
+pub const TIME_BUCKET: i64 = 15 * 60;
+
+/// [`TIME_BUCKET`] in milliseconds, to group the `*_ms` columns by
+const TIME_BUCKET_MS: i64 = TIME_BUCKET * 1000;
+
+/// Every table which stores data keyed by a [`TagId`][crate::uid::TagId]
+const TAG_TABLES: [&str; 32] = [
+    "TAGS",
+    "SETTINGS",
+    "GROUP_MEMBERS",
+    "PROJECTS",
+    "TIMERS",
+    "PRESETS",
+    "DEVICES",
+    "LABELS",
+    "IDEMPOTENCY_KEYS",
+    "TOKENS",
+    "OWNER_KEYS",
+    "TOGGLE_LINKS",
+    "SLUGS",
+    "PROJECT_BUDGETS",
+    "SCHEDULED_EXPORTS",
+    "TEAM_MEMBERS",
+    "TIMESHEETS",
+    "DIGESTS_SENT",
+    "REMINDERS",
+    "GOALS_REACHED",
+    "ACCOUNT_DELETIONS",
+    "PROJECT_TEMPLATES",
+    "TAG_VERSIONS",
+    "ACTIVITY_LOG",
+    "GOOGLE_ACCOUNTS",
+    "GOOGLE_CALENDARS",
+    "GOOGLE_EVENTS",
+    "JIRA_ACCOUNTS",
+    "JIRA_ISSUES",
+    "JIRA_WORKLOGS",
+    "WEBHOOK_SECRETS",
+    "FAILED_WEBHOOKS",
+];
+
+/// The queries run on every page load and toggle, which must not scan a table
+///
+/// Checked by [`DataStore::check_query_plans`], so keep them in line with the
+/// queries they stand for.
+const HOT_QUERIES: [(&str, &str); 5] = [
+    (
+        "projects by tag",
+        "SELECT * FROM PROJECTS p LEFT JOIN TIMERS t ON p.id = t.project_id
+        WHERE p.unique_id = ?1 ORDER BY p.created DESC",
+    ),
+    (
+        "current timer",
+        "SELECT * FROM TIMERS WHERE unique_id = ?1 AND is_current = ?2",
+    ),
+    (
+        "timers of a project",
+        "SELECT * FROM TIMERS WHERE project_id = ?1 AND is_current = ?2
+        ORDER BY start_time_ms DESC",
+    ),
+    (
+        "timers of a period",
+        "SELECT * FROM TIMERS
+        WHERE unique_id = ?1 AND is_current = 0 AND start_time_ms >= ?2 AND start_time_ms < ?3",
+    ),
+    (
+        "active projects",
+        "SELECT * FROM PROJECTS WHERE unique_id = ?1 AND archived = 0",
+    ),
+];
+
+/// How long a tag has to confirm deleting its account, in seconds
+const ACCOUNT_DELETION_TTL: i64 = 10 * 60;
+
+/// Route segments which can't be used as aliases since they'd shadow other routes
+const RESERVED_ALIASES: [&str; 1] = ["toggle"];
+
+/// Hashes a token or key, which is stored in place of the secret itself
+///
+/// The secrets are random, so a fast hash without a salt is enough.
+fn hash_secret(secret: &str) -> String {
+    blake3::hash(secret.as_bytes()).to_hex().to_string()
+}
+
+/// Characters of generated slugs, leaving out ones which are easily confused like `0` and `o`
+const SLUG_ALPHABET: &[u8] = b"23456789abcdefghjkmnpqrstuvwxyz";
+
+/// Length of generated slugs
+const SLUG_LENGTH: usize = 6;
+
+/// Generates a random slug
+fn random_slug() -> String {
+    uuid::Uuid::new_v4().as_bytes()[..SLUG_LENGTH]
+        .iter()
+        .map(|byte| SLUG_ALPHABET[*byte as usize % SLUG_ALPHABET.len()] as char)
+        .collect()
+}
+
+/// Ensures a chosen slug is short and safe to use as a URL path segment
+fn validate_slug(slug: &str) -> Result<()> {
+    if slug.len() < 3 || slug.len() > 32 {
+        return Err(
+            Error::Validation("Slug must be between 3 and 32 characters".to_string()).into(),
+        );
+    }
+
+    if !slug
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        return Err(Error::Validation(
+            "Slug may only contain lowercase letters, digits and '-'".to_string(),
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Ensures an alias is safe to use as a single URL path segment
+fn validate_alias(alias: &str) -> Result<()> {
+    if alias.is_empty() || alias.len() > 64 {
+        return Err(
+            Error::Validation("Alias must be between 1 and 64 characters".to_string()).into(),
+        );
+    }
+
+    if !alias
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(Error::Validation(
+            "Alias may only contain letters, digits, '-' and '_'".to_string(),
+        )
+        .into());
+    }
+
+    if RESERVED_ALIASES.contains(&alias) {
+        return Err(Error::Validation(format!("Alias {:?} is reserved", alias)).into());
+    }
+
+    if TagId::has_shape(alias) {
+        return Err(Error::Validation(format!(
+            "Alias {:?} looks like a tag ID, which it would shadow",
+            alias
+        ))
+        .into());
+    }
+
+    Ok(())
+}
+
+/// The names of the labels attached to each timer, keyed by timer ID
+pub type TimerLabels = HashMap<i64, Vec<String>>;
+
+/// Ensures a label is non-empty and can be written into a single export column
+fn validate_label(label: &str) -> Result<&str> {
+    let label = label.trim();
+    if label.is_empty() || label.len() > 64 {
+        return Err(
+            Error::Validation("Label must be between 1 and 64 characters".to_string()).into(),
+        );
+    }
+
+    if label.contains(';') {
+        return Err(Error::Validation("Label may not contain ';'".to_string()).into());
+    }
+
+    Ok(label)
+}
+
+/// Checks the URL linked to a timer, only web links are allowed so the
+/// timer page can't be made to run scripts
+fn validate_link(link: &str) -> Result<&str> {
+    let link = link.trim();
+    if link.len() > 2048 {
+        return Err(Error::Validation("Link may be at most 2048 characters".to_string()).into());
+    }
+
+    match reqwest::Url::parse(link) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(link),
+        _ => Err(Error::Validation(format!("{:?} isn't a web link", link)).into()),
+    }
+}
+
+/// Checks the address of a Jira site, dropping any trailing slash
+fn validate_jira_site(site: &str) -> Result<&str> {
+    let site = site.trim().trim_end_matches('/');
+    match reqwest::Url::parse(site) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => Ok(site),
+        _ => Err(Error::Validation(format!("{:?} isn't the address of a Jira site", site)).into()),
+    }
+}
+
+/// Checks an issue key like `SPRITE-12`, which Jira compares in upper case
+fn validate_issue_key(issue_key: &str) -> Result<String> {
+    let issue_key = issue_key.trim().to_uppercase();
+    let valid = issue_key.split_once('-').is_some_and(|(project, number)| {
+        project.starts_with(|c: char| c.is_ascii_alphabetic())
+            && project
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !number.is_empty()
+            && number.chars().all(|c| c.is_ascii_digit())
+    });
+    if !valid {
+        return Err(Error::Validation(format!("{:?} isn't a Jira issue key", issue_key)).into());
+    }
+
+    Ok(issue_key)
+}
+
+/// The error for starting a timer when the tag has no project to put it on
+pub(crate) fn no_project(uid: &TagId) -> anyhow::Error {
+    Error::NoProject(format!(
+        "Create a project first, {} has no project to start a timer on",
+        uid.as_ref()
+    ))
+    .into()
+}
+
+/// Ensures a project name is non-empty and short enough to show in a page heading
+pub(crate) fn validate_project_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 80 {
+        return Err(Error::Validation(
+            "Project name must be between 1 and 80 characters".to_string(),
+        )
+        .into());
+    }
+
+    Ok(name)
+}
+
+/// The name of the `n`th copy of a project, shortened to stay a valid project name
+fn copy_name(name: &str, n: usize) -> String {
+    let suffix = match n {
+        1 => " (copy)".to_string(),
+        n => format!(" (copy {})", n),
+    };
+    let kept = 80 - suffix.chars().count();
+    let name: String = name.chars().take(kept).collect();
+
+    format!("{}{}", name.trim_end(), suffix)
+}
+
+/// Ensures a finished timer has a positive duration and ended by `now`
+fn validate_finished_timer(start: StartTime, tracked: TrackedDuration, now: i64) -> Result<()> {
+    if tracked <= TrackedDuration::default() {
+        return Err(Error::Validation("A timer's duration must be positive".to_string()).into());
+    }
+    if start.millis() < 0 || start.end(tracked).timestamp() > now {
+        return Err(Error::Validation("A timer must end in the past".to_string()).into());
+    }
+
+    Ok(())
+}
+
+/// Turns free text into an FTS5 query matching every word as a prefix
+///
+/// Each word is quoted so that user input can never be parsed as FTS5 syntax.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+impl Timer {
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// The whole second the timer ended in, as a unix epoch
+    pub fn end_time(&self) -> i64 {
+        self.ended_at().timestamp()
+    }
+
+    /// The start and duration in whole seconds, see [`second_columns`]
+    pub(crate) fn seconds(&self) -> (i64, i64) {
+        second_columns(self.start_time_ms, self.duration_ms)
+    }
+
+    /// How long the timer has been running at `now`, or how long it lasted
+    /// once it stopped
+    pub fn elapsed(&self, now: DateTime<Utc>) -> TrackedDuration {
+        if self.is_current {
+            self.start_time_ms.elapsed(now)
+        } else {
+            self.duration_ms
+        }
+    }
+
+    /// When the timer ended, to the millisecond
+    pub fn ended_at(&self) -> DateTime<Utc> {
+        self.start_time_ms.end(self.duration_ms)
+    }
+
+    /// Sets the duration from when the timer stopped
+    pub(crate) fn stop_at(&mut self, end: DateTime<Utc>) {
+        self.duration_ms = TrackedDuration::between(self.start_time_ms, end);
+    }
+}
+
+impl DataStore {
+    /// Connects to the database and applies pending migrations
+    ///
+    /// If `backups` are given and restoring on boot is enabled, a missing
+    /// database is first restored from the latest backup.
+    pub async fn new(
+        config: &DatabaseConfig,
+        plugins: Plugins,
+        backups: Option<&Backups>,
+    ) -> Result<Self> {
+        if let Some(backups) = backups {
+            backups.restore_if_missing(config).await?;
+        }
+
+        info!(?config, "Connecting to database");
+        let pool = Self::connect(config).await?;
+        sqlx::migrate!().run(&pool).await?;
+        let store = DataStore {
+            pool,
+            plugins,
+            projects_cache: ProjectsCache::default(),
+        };
+        // A dropped or shadowed index only shows once there's enough data, so
+        // debug builds catch it on startup
+        if cfg!(debug_assertions) {
+            store.check_query_plans().await?;
+        }
+
+        Ok(store)
+    }
+
+    /// Ensures none of the [`HOT_QUERIES`] scans a whole table
+    #[instrument(skip(self))]
+    pub async fn check_query_plans(&self) -> Result<()> {
+        let mut scans = Vec::new();
+        for (name, query) in HOT_QUERIES {
+            let plan: Vec<(i64, i64, i64, String)> =
+                sqlx::query_as(&format!("EXPLAIN QUERY PLAN {}", query))
+                    .fetch_all(&self.pool)
+                    .await?;
+            for (_, _, _, detail) in plan {
+                debug!(name, detail, "Query plan");
+                // Searches use an index, scans go through every row
+                if detail.starts_with("SCAN ") {
+                    scans.push(format!("{}: {}", name, detail));
+                }
+            }
+        }
+
+        if !scans.is_empty() {
+            error!(?scans, "Hot queries scan whole tables");
+            anyhow::bail!("Hot queries scan whole tables: {}", scans.join(", "));
+        }
+
+        Ok(())
+    }
+
+    /// Opens the database, retrying with a backoff until the connect timeout
+    ///
+    /// The volume holding the database may show up a moment after the server
+    /// starts, so failing right away would make the deploy fail with it.
+    async fn connect(config: &DatabaseConfig) -> Result<SqlitePool> {
+        let options = config.connect_options()?;
+        let started = Instant::now();
+        let mut attempt = 0;
+        loop {
+            let err = match SqlitePoolOptions::new()
+                .max_connections(config.max_connections)
+                .connect_with(options.clone())
+                .await
+            {
+                Ok(pool) => {
+                    if attempt > 0 {
+                        info!(attempt, "Connected to database");
+                    }
+                    return Ok(pool);
+                }
+                Err(err) => err,
+            };
+
+            attempt += 1;
+            let delay = connect_backoff(attempt);
+            if started.elapsed() + delay > config.connect_timeout {
+                error!(%err, attempt, "Unable to connect to database, giving up");
+                return Err(anyhow::Error::new(err).context(format!(
+                    "Unable to connect to the database after {} attempts",
+                    attempt
+                )));
+            }
+
+            warn!(%err, attempt, ?delay, "Unable to connect to database, retrying");
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// An empty in-memory database, for tests
+    #[cfg(test)]
+    pub(crate) async fn in_memory() -> Result<Self> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = SqlitePool::connect_with(options).await?;
+        sqlx::migrate!().run(&pool).await?;
+
+        Ok(DataStore {
+            pool,
+            plugins: Plugins::default(),
+            projects_cache: ProjectsCache::default(),
+        })
+    }
+
+    /// Runs `plugins` on the timers of this store, for tests
+    #[cfg(test)]
+    pub(crate) fn with_plugins(self, plugins: Plugins) -> Self {
+        Self { plugins, ..self }
+    }
+
+    /// Toggles the current timer for the given UID
+    #[instrument(skip(self))]
+    pub async fn toggle_current(&self, uid: &TagId) -> Result<i64> {
+        self.toggle(uid, &ToggleOptions::default()).await
+    }
+
+    /// Toggles a timer for the given UID
+    ///
+    /// Stops the running timer if there is one, otherwise starts a new timer.
+    /// See [`ToggleOptions`] for which timer is picked.
+    #[instrument(skip(self))]
+    pub async fn toggle(&self, uid: &TagId, options: &ToggleOptions) -> Result<i64> {
+        // The short timer policy may update one timer and delete another
+        let mut transaction = self.pool.begin().await?;
+        let timer_id = self.toggle_in(&mut transaction, uid, options).await?;
+        transaction.commit().await?;
+
+        self.spawn_stop_plugins(timer_id);
+        Ok(timer_id)
+    }
+
+    /// Toggles a timer on `conn`, so that a batch of toggles can share a transaction
+    async fn toggle_in(
+        &self,
+        conn: &mut SqliteConnection,
+        uid: &TagId,
+        options: &ToggleOptions,
+    ) -> Result<i64> {
+        if let Some(device) = options.device.as_ref().filter(|device| device.revoked) {
+            return Err(Error::Validation(format!("Device {} has been revoked", device.id)).into());
+        }
+
+        if let Some(project_id) = options.project_id {
+            Self::trackable_project_in(conn, uid, project_id).await?;
+        }
+
+        Self::touch_tag_in(conn, uid).await?;
+
+        let at = event_at(options.at)?;
+        let settings = Self::settings_in(conn, uid).await?;
+        if let Some(timer) = Self::running_timer(conn, uid, options.project_id, &settings).await? {
+            self.stop_timer(conn, timer, at, &settings).await
+        } else {
+            debug!(tag_id = uid.as_ref(), "Creating new timer");
+            let device_id = options.device.as_ref().map(|device| device.id);
+            Self::create_timer(conn, uid, options.project_id, device_id, at.into()).await
+        }
+    }
+
+    /// Stops a running timer retroactively at `end_time`
+    ///
+    /// For timers which were forgotten, `end_time` may lie arbitrarily far in
+    /// the past as long as it isn't before the timer started. Returns the timer
+    /// now holding the tracked time, which is `None` if the short timer policy
+    /// discarded it.
+    #[instrument(skip(self))]
+    pub async fn stop_timer_at(
+        &self,
+        uid: &TagId,
+        timer_id: i64,
+        end_time: i64,
+    ) -> Result<Option<Timer>> {
+        let mut transaction = self.pool.begin().await?;
+        let timer = Self::owned_timer_in(&mut transaction, uid, timer_id).await?;
+        if !timer.is_current {
+            return Err(Error::Conflict(format!("Timer {} is not running", timer_id)).into());
+        }
+        if end_time > Utc::now().timestamp() {
+            return Err(
+                Error::Validation(format!("Timer {} can't stop in the future", timer_id)).into(),
+            );
+        }
+
+        Self::touch_tag_in(&mut transaction, uid).await?;
+        let settings = Self::settings_in(&mut transaction, uid).await?;
+        let end = Utc.timestamp_opt(end_time, 0).single().unwrap_or_default();
+        let timer_id = self
+            .stop_timer(&mut transaction, timer, end, &settings)
+            .await?;
+        transaction.commit().await?;
+
+        self.spawn_stop_plugins(timer_id);
+        self.find_timer(timer_id).await
+    }
+
+    /// Merges two consecutive timers of the same project into the earlier one
+    ///
+    /// The merged timer covers both along with the gap between them, and is
+    /// still running if the later one was. It keeps the labels and notes of
+    /// both.
+    #[instrument(skip(self))]
+    pub async fn merge_timers(&self, uid: &TagId, timer_id: i64, other_id: i64) -> Result<Timer> {
+        if timer_id == other_id {
+            return Err(
+                Error::Validation("A timer can't be merged with itself".to_string()).into(),
+            );
+        }
+
+        let mut transaction = self.pool.begin().await?;
+        let mut first = Self::owned_timer_in(&mut transaction, uid, timer_id).await?;
+        let mut second = Self::owned_timer_in(&mut transaction, uid, other_id).await?;
+        if (second.start_time_ms, second.id) < (first.start_time_ms, first.id) {
+            std::mem::swap(&mut first, &mut second);
+        }
+
+        if first.project_id != second.project_id {
+            return Err(Error::Validation(format!(
+                "Timers {} and {} belong to different projects",
+                first.id, second.id
+            ))
+            .into());
+        }
+        if first.is_current {
+            return Err(Error::Conflict(format!("Timer {} is still running", first.id)).into());
+        }
+        Self::ensure_unlocked(&mut transaction, first.id).await?;
+        Self::ensure_unlocked(&mut transaction, second.id).await?;
+
+        let between = sqlx::query_scalar!(
+            r#"
+SELECT COUNT(*) AS "between!: i64" FROM TIMERS
+WHERE unique_id = ?1 AND project_id = ?2
+    AND start_time_ms BETWEEN ?3 AND ?4
+    AND id NOT IN (?5, ?6)
+            "#,
+            first.unique_id,
+            first.project_id,
+            first.start_time_ms,
+            second.start_time_ms,
+            first.id,
+            second.id
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        if between > 0 {
+            return Err(Error::Validation(format!(
+                "Timers {} and {} aren't next to each other",
+                first.id, second.id
+            ))
+            .into());
+        }
+
+        info!(timer = first.id, other = second.id, "Merging timers");
+        let tracked = if second.is_current {
+            TrackedDuration::default()
+        } else {
+            TrackedDuration::between(first.start_time_ms, first.ended_at().max(second.ended_at()))
+        };
+        let (_, duration) = second_columns(first.start_time_ms, tracked);
+        let note = match (first.note.take(), second.note.take()) {
+            (Some(first), Some(second)) => Some(format!("{}; {}", first, second)),
+            (first, second) => first.or(second),
+        };
+        sqlx::query!(
+            r#"
+INSERT OR IGNORE INTO TIMER_LABELS (timer_id, label_id)
+SELECT ?1, label_id FROM TIMER_LABELS WHERE timer_id = ?2
+            "#,
+            first.id,
+            second.id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        // Running timers are unique per project, so the later one has to go first
+        Self::delete_timer(&mut transaction, second.id).await?;
+        sqlx::query!(
+            r#"
+UPDATE TIMERS SET is_current = ?2, duration = ?3, duration_ms = ?4, note = ?5
+WHERE id = ?1
+            "#,
+            first.id,
+            second.is_current,
+            duration,
+            tracked,
+            note
+        )
+        .execute(&mut *transaction)
+        .await?;
+        let merged = Self::owned_timer_in(&mut transaction, uid, first.id).await?;
+        transaction.commit().await?;
+
+        Ok(merged)
+    }
+
+    /// Splits a finished timer in two at `split_at`
+    ///
+    /// The part after `split_at` moves to `project_id` if given, for dividing
+    /// a block of time between projects. Both parts keep the note and labels.
+    #[instrument(skip(self))]
+    pub async fn split_timer(
+        &self,
+        uid: &TagId,
+        timer_id: i64,
+        split_at: i64,
+        project_id: Option<i64>,
+    ) -> Result<(Timer, Timer)> {
+        let mut transaction = self.pool.begin().await?;
+        let timer = Self::owned_timer_in(&mut transaction, uid, timer_id).await?;
+        if timer.is_current {
+            return Err(Error::Conflict(format!(
+                "Timer {} is still running, stop it before splitting it",
+                timer_id
+            ))
+            .into());
+        }
+        let at = StartTime::from_seconds(split_at);
+        if at <= timer.start_time_ms || at.0 >= timer.ended_at() {
+            return Err(Error::Validation(format!(
+                "Timer {} can only be split between its start and end",
+                timer_id
+            ))
+            .into());
+        }
+        Self::ensure_unlocked(&mut transaction, timer_id).await?;
+        let project_id = match project_id {
+            Some(project_id) => {
+                Self::trackable_project_in(&mut transaction, uid, project_id)
+                    .await?
+                    .id
+            }
+            None => timer.project_id,
+        };
+
+        info!(timer_id, split_at, project_id, "Splitting timer");
+        let tag_id = uid.as_ref();
+        let first_tracked = TrackedDuration::between(timer.start_time_ms, at.0);
+        let second_tracked = TrackedDuration::between(at, timer.ended_at());
+        let (_, first_duration) = second_columns(timer.start_time_ms, first_tracked);
+        let (start_time, second_duration) = second_columns(at, second_tracked);
+        sqlx::query!(
+            "UPDATE TIMERS SET duration = ?2, duration_ms = ?3 WHERE id = ?1",
+            timer_id,
+            first_duration,
+            first_tracked
+        )
+        .execute(&mut *transaction)
+        .await?;
+        let second_id = sqlx::query!(
+            r#"
+INSERT INTO TIMERS (
+    unique_id, is_current, start_time, duration, project_id, note, device_id,
+    start_time_ms, duration_ms
+)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+            tag_id,
+            IsCurrent::No as i64,
+            start_time,
+            second_duration,
+            project_id,
+            timer.note,
+            timer.device_id,
+            at,
+            second_tracked
+        )
+        .execute(&mut *transaction)
+        .await?
+        .last_insert_rowid();
+        sqlx::query!(
+            r#"
+INSERT INTO TIMER_LABELS (timer_id, label_id)
+SELECT ?1, label_id FROM TIMER_LABELS WHERE timer_id = ?2
+            "#,
+            second_id,
+            timer_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        let first = Self::owned_timer_in(&mut transaction, uid, timer_id).await?;
+        let second = Self::owned_timer_in(&mut transaction, uid, second_id).await?;
+        transaction.commit().await?;
+
+        Ok((first, second))
+    }
+
+    /// Moves timers to another project, all of them or none
+    ///
+    /// A running timer can move as long as the tag isn't running another
+    /// timer on that project already. Returns how many timers moved.
+    #[instrument(skip(self))]
+    pub async fn reassign_timers(
+        &self,
+        uid: &TagId,
+        timer_ids: &[i64],
+        project_id: i64,
+    ) -> Result<u64> {
+        let tag_id = uid.as_ref();
+        let mut transaction = self.pool.begin().await?;
+        Self::trackable_project_in(&mut transaction, uid, project_id).await?;
+
+        let mut moved = 0;
+        for &timer_id in timer_ids {
+            let timer = Self::owned_timer_in(&mut transaction, uid, timer_id).await?;
+            if timer.project_id == project_id {
+                continue;
+            }
+            Self::ensure_unlocked(&mut transaction, timer_id).await?;
+            if timer.is_current {
+                let running = sqlx::query_scalar!(
+                    "SELECT id FROM TIMERS WHERE project_id = ?1 AND unique_id = ?2 AND is_current = ?3",
+                    project_id,
+                    tag_id,
+                    IsCurrent::Yes as i64
+                )
+                .fetch_optional(&mut *transaction)
+                .await?;
+                if let Some(running) = running {
+                    return Err(Error::Conflict(format!(
+                        "Timer {} is already running on project {}",
+                        running, project_id
+                    ))
+                    .into());
+                }
+            }
+
+            sqlx::query!(
+                "UPDATE TIMERS SET project_id = ?2 WHERE id = ?1",
+                timer_id,
+                project_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+            moved += 1;
+        }
+
+        info!(tag_id, project_id, moved, "Reassigned timers");
+        transaction.commit().await?;
+
+        Ok(moved)
+    }
+
+    /// Creates, updates and deletes finished timers of a tag, all of them or none
+    ///
+    /// Every operation is tried, so the result of each one is known even when
+    /// some fail, but the changes are only kept if all of them succeed.
+    /// Returns whether they were kept along with the result of each operation,
+    /// the timer it created or updated or `None` for deletions.
+    #[instrument(skip(self, operations), fields(count = operations.len()))]
+    pub async fn bulk_timers(
+        &self,
+        uid: &TagId,
+        operations: &[TimerOperation],
+        now: i64,
+    ) -> Result<(bool, Vec<Result<Option<Timer>>>)> {
+        let tag_id = uid.as_ref();
+        let mut transaction = self.pool.begin().await?;
+        Self::touch_tag_in(&mut transaction, uid).await?;
+
+        let mut results = Vec::with_capacity(operations.len());
+        for operation in operations {
+            results.push(Self::apply_timer_operation(&mut transaction, uid, operation, now).await);
+        }
+
+        let applied = results.iter().all(Result::is_ok);
+        if applied {
+            transaction.commit().await?;
+        } else {
+            transaction.rollback().await?;
+        }
+        info!(tag_id, applied, "Applied bulk timer operations");
+
+        Ok((applied, results))
+    }
+
+    async fn apply_timer_operation(
+        conn: &mut SqliteConnection,
+        uid: &TagId,
+        operation: &TimerOperation,
+        now: i64,
+    ) -> Result<Option<Timer>> {
+        let tag_id = uid.as_ref();
+        let timer_id = match operation {
+            TimerOperation::Create {
+                project_id,
+                start_time,
+                duration,
+                note,
+            } => {
+                Self::trackable_project_in(conn, uid, *project_id).await?;
+                let start = StartTime::from_seconds(*start_time);
+                let tracked = TrackedDuration::from_seconds(*duration);
+                validate_finished_timer(start, tracked, now)?;
+                let (start_time, duration) = second_columns(start, tracked);
+                sqlx::query!(
+                    r#"
+INSERT INTO TIMERS (
+    unique_id, is_current, start_time, duration, project_id, note, start_time_ms, duration_ms
+)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+                    tag_id,
+                    IsCurrent::No as i64,
+                    start_time,
+                    duration,
+                    project_id,
+                    note,
+                    start,
+                    tracked
+                )
+                .execute(&mut *conn)
+                .await?
+                .last_insert_rowid()
+            }
+            TimerOperation::Update {
+                timer_id,
+                project_id,
+                start_time,
+                duration,
+                note,
+            } => {
+                let timer = Self::finished_timer_in(conn, uid, *timer_id).await?;
+                if let Some(project_id) = project_id {
+                    Self::trackable_project_in(conn, uid, *project_id).await?;
+                }
+                let project_id = project_id.unwrap_or(timer.project_id);
+                let start = start_time.map_or(timer.start_time_ms, StartTime::from_seconds);
+                let tracked = duration.map_or(timer.duration_ms, TrackedDuration::from_seconds);
+                validate_finished_timer(start, tracked, now)?;
+                let (start_time, duration) = second_columns(start, tracked);
+                // An empty note removes it
+                let note = match note {
+                    Some(note) if note.is_empty() => None,
+                    Some(note) => Some(note.as_str()),
+                    None => timer.note.as_deref(),
+                };
+                sqlx::query!(
+                    r#"
+UPDATE TIMERS
+SET project_id = ?2, start_time = ?3, duration = ?4, note = ?5,
+    start_time_ms = ?6, duration_ms = ?7
+WHERE id = ?1"#,
+                    timer_id,
+                    project_id,
+                    start_time,
+                    duration,
+                    note,
+                    start,
+                    tracked
+                )
+                .execute(&mut *conn)
+                .await?;
+
+                *timer_id
+            }
+            TimerOperation::Delete { timer_id } => {
+                Self::finished_timer_in(conn, uid, *timer_id).await?;
+                Self::delete_timer(conn, *timer_id).await?;
+                return Ok(None);
+            }
+        };
+
+        Self::owned_timer_in(conn, uid, timer_id).await.map(Some)
+    }
+
+    /// Gets a finished timer of the tag which can still change
+    async fn finished_timer_in(
+        conn: &mut SqliteConnection,
+        uid: &TagId,
+        timer_id: i64,
+    ) -> Result<Timer> {
+        let timer = Self::owned_timer_in(conn, uid, timer_id).await?;
+        if timer.is_current {
+            return Err(Error::Conflict(format!("Timer {} is still running", timer_id)).into());
+        }
+        Self::ensure_unlocked(conn, timer_id).await?;
+
+        Ok(timer)
+    }
+
+    /// Gets a timer of the given [`TagId`][crate::uid::TagId]
+    #[instrument(skip(conn))]
+    async fn owned_timer_in(
+        conn: &mut SqliteConnection,
+        uid: &TagId,
+        timer_id: i64,
+    ) -> Result<Timer> {
+        let tag_id = uid.as_ref();
+        let timer = sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE id = ?1 AND unique_id = ?2
+            "#,
+            timer_id,
+            tag_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        timer.ok_or_else(|| {
+            Error::NotFound(format!("Timer {} does not belong to {}", timer_id, uid)).into()
+        })
+    }
+
+    /// Toggles a timer at most once per idempotency key
+    ///
+    /// Clients which replay requests, for example after losing their
+    /// connection, send the same key with every attempt. The first attempt
+    /// toggles, later ones return the timer toggled by the first along with
+    /// `true` to mark the result as a replay.
+    #[instrument(skip(self))]
+    pub async fn toggle_once(
+        &self,
+        uid: &TagId,
+        key: &str,
+        options: &ToggleOptions,
+    ) -> Result<(i64, bool)> {
+        let tag_id = uid.as_ref();
+        let now = Utc::now().timestamp();
+        // Claiming the key takes the write lock, so a concurrent attempt with
+        // the same key waits for this one and then sees it as a replay
+        let mut transaction = self.pool.begin().await?;
+        let claimed = sqlx::query!(
+            r#"
+INSERT INTO IDEMPOTENCY_KEYS (unique_id, key, created_at)
+VALUES (?1, ?2, ?3)
+ON CONFLICT (unique_id, key) DO NOTHING
+            "#,
+            tag_id,
+            key,
+            now
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected()
+            == 1;
+
+        if !claimed {
+            info!(tag_id, key, "Replayed toggle");
+            let timer_id = sqlx::query_scalar!(
+                "SELECT timer_id FROM IDEMPOTENCY_KEYS WHERE unique_id = ?1 AND key = ?2",
+                tag_id,
+                key
+            )
+            .fetch_one(&mut *transaction)
+            .await?;
+
+            return match timer_id {
+                Some(timer_id) => Ok((timer_id, true)),
+                None => Err(Error::Conflict(format!(
+                    "Toggle with key {:?} is still being applied",
+                    key
+                ))
+                .into()),
+            };
+        }
+
+        // A failed toggle rolls back the claim, so that a retry can apply it
+        let timer_id = self.toggle_in(&mut transaction, uid, options).await?;
+        sqlx::query!(
+            "UPDATE IDEMPOTENCY_KEYS SET timer_id = ?1 WHERE unique_id = ?2 AND key = ?3",
+            timer_id,
+            tag_id,
+            key
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        self.spawn_stop_plugins(timer_id);
+        Ok((timer_id, false))
+    }
+
+    /// Forgets the idempotency keys of toggles applied before `cutoff`
+    ///
+    /// Returns the number of forgotten keys.
+    #[instrument(skip(self))]
+    pub async fn prune_idempotency_keys(&self, cutoff: i64) -> Result<u64> {
+        let rows = sqlx::query!("DELETE FROM IDEMPOTENCY_KEYS WHERE created_at < ?1", cutoff)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+
+        Ok(rows)
+    }
+
+    /// Applies a batch of toggles in order, all or nothing
+    ///
+    /// Events whose key was seen before are skipped like in
+    /// [`DataStore::toggle_once`], so a device can resend a batch which it
+    /// never got an answer for. If any event fails, none of the batch is
+    /// applied. Returns the outcome of every event along with the state of
+    /// the timers they touched, leaving out short timers which were discarded.
+    #[instrument(skip(self, events), fields(count = events.len()))]
+    pub async fn sync(&self, events: &[SyncEvent]) -> Result<(Vec<SyncedToggle>, Vec<Timer>)> {
+        let now = Utc::now().timestamp();
+        let mut transaction = self.pool.begin().await?;
+        let mut synced = Vec::with_capacity(events.len());
+        for event in events {
+            let tag_id = event.uid.as_ref();
+            let key = event.key.as_str();
+            let seen = sqlx::query_scalar!(
+                "SELECT timer_id FROM IDEMPOTENCY_KEYS WHERE unique_id = ?1 AND key = ?2",
+                tag_id,
+                key
+            )
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+            match seen {
+                Some(Some(timer_id)) => {
+                    info!(tag_id, key, "Replayed toggle");
+                    synced.push(SyncedToggle {
+                        key: event.key.clone(),
+                        timer_id,
+                        replayed: true,
+                    });
+                    continue;
+                }
+                Some(None) => {
+                    return Err(Error::Conflict(format!(
+                        "Toggle with key {:?} is still being applied",
+                        key
+                    ))
+                    .into())
+                }
+                None => {}
+            }
+
+            let timer_id = match self
+                .toggle_in(&mut transaction, &event.uid, &event.options)
+                .await
+            {
+                Ok(timer_id) => timer_id,
+                Err(err) => {
+                    warn!(tag_id, key, %err, "Unable to sync toggle, dropping the batch");
+                    return Err(err);
+                }
+            };
+            sqlx::query!(
+                r#"
+INSERT INTO IDEMPOTENCY_KEYS (unique_id, key, created_at, timer_id)
+VALUES (?1, ?2, ?3, ?4)
+                "#,
+                tag_id,
+                key,
+                now,
+                timer_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+
+            synced.push(SyncedToggle {
+                key: event.key.clone(),
+                timer_id,
+                replayed: false,
+            });
+        }
+        transaction.commit().await?;
+
+        for toggle in synced.iter().filter(|toggle| !toggle.replayed) {
+            self.spawn_stop_plugins(toggle.timer_id);
+        }
+
+        let mut timers = Vec::new();
+        let mut timer_ids: Vec<i64> = synced.iter().map(|toggle| toggle.timer_id).collect();
+        timer_ids.sort_unstable();
+        timer_ids.dedup();
+        for timer_id in timer_ids {
+            timers.extend(self.find_timer(timer_id).await?);
+        }
+
+        Ok((synced, timers))
+    }
+
+    /// Finds the timer a toggle should stop, if any
+    ///
+    /// Unless concurrent timers are enabled for the tag, this is the single
+    /// running timer regardless of which project was asked for.
+    #[instrument(skip(conn, settings))]
+    async fn running_timer(
+        conn: &mut SqliteConnection,
+        uid: &TagId,
+        project_id: Option<i64>,
+        settings: &TagSettings,
+    ) -> Result<Option<Timer>> {
+        if !settings.concurrent_timers {
+            return Ok(Self::current_timer(conn, uid).await.ok());
+        }
+
+        let project_id = match project_id {
+            Some(project_id) => project_id,
+            None => match Self::current_project(conn, uid).await {
+                Ok(project) => project.id,
+                Err(_) => return Ok(None),
+            },
+        };
+
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE project_id = ?1 AND unique_id = ?2 AND is_current = ?3"#,
+            project_id,
+            tag_id,
+            IsCurrent::Yes as i64
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Stops a running timer at `end`, applying the short timer policy of the tag
+    ///
+    /// The `on_stop` plugins run only once the stop is committed, see
+    /// [`DataStore::spawn_stop_plugins`].
+    #[instrument(skip(self, conn, settings))]
+    async fn stop_timer(
+        &self,
+        conn: &mut SqliteConnection,
+        mut timer: Timer,
+        end: DateTime<Utc>,
+        settings: &TagSettings,
+    ) -> Result<i64> {
+        let timer_id = timer.id;
+        debug!(?timer, "Ending current timer");
+        if end < timer.start_time_ms.0 {
+            return Err(Error::Validation(format!(
+                "Timer {} can't stop before it started",
+                timer_id
+            ))
+            .into());
+        }
+        timer.stop_at(end);
+
+        let threshold = TrackedDuration::from_seconds(settings.short_timer_threshold);
+        if timer.duration_ms < threshold {
+            let policy = settings.short_timer_policy;
+            if let Some(id) = Self::handle_short_timer(conn, &timer, policy, threshold).await? {
+                return Ok(id);
+            }
+        }
+
+        if !Self::update_timer(conn, timer).await? {
+            error!(?timer_id, "Error updating timer");
+            return Err(anyhow::anyhow!("Unable to update timer"));
+        }
+
+        Ok(timer_id)
+    }
+
+    /// Runs the `on_stop` plugins for a timer which was just stopped in the
+    /// background, storing the note they return
+    ///
+    /// Plugins may run for a while, so the toggle doesn't wait for them. They
+    /// run on the blocking thread pool and outside of any transaction, and the
+    /// note is only replaced if it didn't change in the meantime. Like the
+    /// plugins themselves, failing here never fails the stop. Timers which are
+    /// running or were discarded are skipped.
+    fn spawn_stop_plugins(&self, timer_id: i64) {
+        if self.plugins.is_empty() {
+            return;
+        }
+
+        let store = self.clone();
+        tokio::spawn(async move { store.run_stop_plugins(timer_id).await });
+    }
+
+    #[instrument(skip(self))]
+    async fn run_stop_plugins(&self, timer_id: i64) {
+        let timer = match self.find_timer(timer_id).await {
+            Ok(Some(timer)) if !timer.is_current => timer,
+            Ok(_) => return,
+            Err(err) => {
+                warn!(%err, "Unable to load the stopped timer for plugins");
+                return;
+            }
+        };
+
+        let plugins = self.plugins.clone();
+        let previous = timer.note.clone();
+        let note = match tokio::task::spawn_blocking(move || plugins.on_stop(&timer)).await {
+            Ok(note) => note,
+            Err(err) => {
+                warn!(%err, "on_stop plugins panicked");
+                return;
+            }
+        };
+        if note == previous {
+            return;
+        }
+
+        if let Err(err) = sqlx::query!(
+            "UPDATE TIMERS SET note = ?1 WHERE id = ?2 AND note IS ?3",
+            note,
+            timer_id,
+            previous
+        )
+        .execute(&self.pool)
+        .await
+        {
+            warn!(%err, "Unable to store the note from plugins");
+        }
+    }
+
+    /// Applies the [`ShortTimerPolicy`] to a timer which is being stopped
+    ///
+    /// Returns the ID of the timer which now holds the tracked time, or `None`
+    /// if the timer should be stopped as usual. Short timers are only merged
+    /// into a timer which ended less than `threshold` before they started, so
+    /// the time in between was most likely spent on it too.
+    #[instrument(skip(conn))]
+    async fn handle_short_timer(
+        conn: &mut SqliteConnection,
+        timer: &Timer,
+        policy: ShortTimerPolicy,
+        threshold: TrackedDuration,
+    ) -> Result<Option<i64>> {
+        match policy {
+            ShortTimerPolicy::Keep => Ok(None),
+            ShortTimerPolicy::Discard => {
+                debug!(timer = timer.id, "Discarding short timer");
+                Self::delete_timer(conn, timer.id).await?;
+                Ok(Some(timer.id))
+            }
+            ShortTimerPolicy::Merge => {
+                let Some(mut previous) = Self::previous_timer(conn, timer).await? else {
+                    return Ok(None);
+                };
+                if TrackedDuration::between(StartTime(previous.ended_at()), timer.start_time_ms.0)
+                    >= threshold
+                {
+                    return Ok(None);
+                }
+                // Keep the short timer rather than changing an approved one
+                if Self::ensure_unlocked(conn, previous.id).await.is_err() {
+                    return Ok(None);
+                }
+
+                debug!(
+                    timer = timer.id,
+                    previous = previous.id,
+                    "Merging short timer"
+                );
+                let previous_id = previous.id;
+                // Extend the previous timer up to the end of the short one
+                previous.stop_at(timer.ended_at());
+                Self::update_timer(conn, previous).await?;
+                Self::delete_timer(conn, timer.id).await?;
+                Ok(Some(previous_id))
+            }
+        }
+    }
+
+    /// Gets the settings for the given [`TagId`][crate::uid::TagId]
+    ///
+    /// Tags without any stored settings get the defaults.
+    #[instrument(skip(self))]
+    pub async fn settings(&self, uid: &TagId) -> Result<TagSettings> {
+        Self::settings_in(&mut *self.pool.acquire().await?, uid).await
+    }
+
+    async fn settings_in(conn: &mut SqliteConnection, uid: &TagId) -> Result<TagSettings> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            TagSettings,
+            r#"
+SELECT
+    unique_id,
+    short_timer_threshold,
+    short_timer_policy AS "short_timer_policy: ShortTimerPolicy",
+    concurrent_timers,
+    default_project,
+    email,
+    weekly_digest,
+    reminder_schedule,
+    reminder_after,
+    reminder_webhook,
+    long_timer_after,
+    streak_minutes,
+    daily_goal,
+    goal_timezone,
+    goal_webhook,
+    export_columns,
+    duration_format AS "duration_format: DurationFormat",
+    duration_rounding AS "duration_rounding: DurationRounding",
+    duration_seconds,
+    split_at_midnight,
+    date_order AS "date_order: DateOrder",
+    clock AS "clock: Clock",
+    language,
+    theme AS "theme: Theme",
+    created_at,
+    updated_at
+FROM SETTINGS
+WHERE unique_id = ?1"#,
+            tag_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        Ok(result.unwrap_or_else(|| TagSettings {
+            unique_id: tag_id.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    /// Stores the settings for a tag, replacing any existing ones
+    #[instrument(skip(self))]
+    pub async fn update_settings(&self, settings: &TagSettings) -> Result<()> {
+        sqlx::query!(
+            r#"
+INSERT INTO SETTINGS (
+    unique_id, short_timer_threshold, short_timer_policy, concurrent_timers, email, weekly_digest,
+    reminder_schedule, reminder_after, reminder_webhook, long_timer_after, export_columns,
+    duration_format, date_order, clock, language, default_project, streak_minutes, daily_goal,
+    goal_timezone, goal_webhook, duration_rounding, duration_seconds, theme, split_at_midnight
+)
+VALUES (
+    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
+    ?21, ?22, ?23, ?24
+)
+ON CONFLICT (unique_id) DO UPDATE
+SET short_timer_threshold = excluded.short_timer_threshold,
+    short_timer_policy = excluded.short_timer_policy,
+    concurrent_timers = excluded.concurrent_timers,
+    email = excluded.email,
+    weekly_digest = excluded.weekly_digest,
+    reminder_schedule = excluded.reminder_schedule,
+    reminder_after = excluded.reminder_after,
+    reminder_webhook = excluded.reminder_webhook,
+    long_timer_after = excluded.long_timer_after,
+    export_columns = excluded.export_columns,
+    duration_format = excluded.duration_format,
+    date_order = excluded.date_order,
+    clock = excluded.clock,
+    language = excluded.language,
+    default_project = excluded.default_project,
+    streak_minutes = excluded.streak_minutes,
+    daily_goal = excluded.daily_goal,
+    goal_timezone = excluded.goal_timezone,
+    goal_webhook = excluded.goal_webhook,
+    duration_rounding = excluded.duration_rounding,
+    duration_seconds = excluded.duration_seconds,
+    theme = excluded.theme,
+    split_at_midnight = excluded.split_at_midnight
+            "#,
+            settings.unique_id,
+            settings.short_timer_threshold,
+            settings.short_timer_policy,
+            settings.concurrent_timers,
+            settings.email,
+            settings.weekly_digest,
+            settings.reminder_schedule,
+            settings.reminder_after,
+            settings.reminder_webhook,
+            settings.long_timer_after,
+            settings.export_columns,
+            settings.duration_format,
+            settings.date_order,
+            settings.clock,
+            settings.language,
+            settings.default_project,
+            settings.streak_minutes,
+            settings.daily_goal,
+            settings.goal_timezone,
+            settings.goal_webhook,
+            settings.duration_rounding,
+            settings.duration_seconds,
+            settings.theme,
+            settings.split_at_midnight
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(conn))]
+    /// Get the current project associated with the [`TagId`][crate::uid::TagId]
+    ///
+    /// Every project is associated with a **single** [`TagId`][crate::uid::TagId]
+    async fn current_project(conn: &mut SqliteConnection, uid: &TagId) -> Result<Project> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, "Getting current project");
+
+        let result = sqlx::query_as!(
+            Project,
+            r#"
+SELECT * FROM PROJECTS
+WHERE unique_id = ?1 AND is_current = ?2"#,
+            tag_id,
+            IsCurrent::Yes as i64
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Gets all projects associated with [`TagId`][crate::uid::TagId]
+    #[instrument(skip(self))]
+    async fn get_projects(&self, uid: &TagId) -> Result<Vec<Project>> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, "Getting projects");
+        let result = sqlx::query_as!(
+            Project,
+            "SELECT * FROM PROJECTS WHERE unique_id = ?1",
+            tag_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(result)
+    }
+
+    /// Creates a new project with the associated tag.
+    ///
+    /// If a project already exists, it ensures that the `is_current` status is handled properly.
+    /// The name is trimmed, and may not be empty or be taken by another active project of
+    /// the tag, ignoring case.
+    #[instrument(skip(self))]
+    pub async fn create_project(&self, uid: &TagId, project_name: &str) -> Result<i64> {
+        let project_name = validate_project_name(project_name)?;
+        let mut transaction = self.pool.begin().await?;
+        Self::ensure_project_name_free(&mut transaction, uid, project_name).await?;
+
+        let id = Self::create_project_in(&mut transaction, uid, project_name).await?;
+        transaction.commit().await?;
+
+        Ok(id)
+    }
+
+    /// Creates a new project with the client and budget of one of the tag's [`ProjectTemplate`]s
+    ///
+    /// The name is checked like the ones of [`DataStore::create_project`].
+    #[instrument(skip(self))]
+    pub async fn create_project_from_template(
+        &self,
+        uid: &TagId,
+        project_name: &str,
+        template_id: i64,
+    ) -> Result<i64> {
+        let project_name = validate_project_name(project_name)?;
+        let template = self.project_template(uid, template_id).await?;
+        let mut transaction = self.pool.begin().await?;
+        Self::ensure_project_name_free(&mut transaction, uid, project_name).await?;
+
+        info!(template_id, "Creating project from template");
+        let id = Self::create_project_in(&mut transaction, uid, project_name).await?;
+        sqlx::query!(
+            "UPDATE PROJECTS SET parent_id = ?2 WHERE id = ?1",
+            id,
+            template.parent_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        if let Some(budget) = template.budget {
+            sqlx::query!(
+                "INSERT INTO PROJECT_BUDGETS (project_id, unique_id, budget) VALUES (?1, ?2, ?3)",
+                id,
+                template.unique_id,
+                budget
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+        Ok(id)
+    }
+
+    async fn ensure_project_name_free(
+        conn: &mut SqliteConnection,
+        uid: &TagId,
+        project_name: &str,
+    ) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let taken = sqlx::query_scalar!(
+            r#"
+SELECT COUNT(*) AS "n!: i64" FROM PROJECTS
+WHERE unique_id = ?1 AND name = ?2 COLLATE NOCASE AND archived = 0"#,
+            tag_id,
+            project_name
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if taken > 0 {
+            return Err(Error::Conflict(format!(
+                "There already is a project named {:?}",
+                project_name
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Saves a template for new projects of the tag
+    #[instrument(skip(self))]
+    pub async fn create_project_template(
+        &self,
+        uid: &TagId,
+        name: &str,
+        budget: Option<i64>,
+        parent_id: Option<i64>,
+    ) -> Result<ProjectTemplate> {
+        let tag_id = uid.as_ref();
+        let name = validate_project_name(name)?;
+        if budget.is_some_and(|budget| budget <= 0) {
+            return Err(Error::Validation("Budget must be positive".to_string()).into());
+        }
+        if let Some(parent_id) = parent_id {
+            self.owned_project(uid, parent_id).await?;
+        }
+
+        let now = Utc::now().timestamp();
+        info!(tag_id, name, "Creating project template");
+        let result = sqlx::query_as!(
+            ProjectTemplate,
+            r#"
+INSERT INTO PROJECT_TEMPLATES (unique_id, name, budget, parent_id, created_at)
+VALUES (?1, ?2, ?3, ?4, ?5)
+ON CONFLICT (unique_id, name) DO NOTHING
+RETURNING id AS "id!", unique_id, name, budget, parent_id, created_at
+            "#,
+            tag_id,
+            name,
+            budget,
+            parent_id,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        result.ok_or_else(|| {
+            Error::Conflict(format!("There already is a template named {:?}", name)).into()
+        })
+    }
+
+    /// Gets the project templates of a tag, ordered by name
+    #[instrument(skip(self))]
+    pub async fn project_templates(&self, uid: &TagId) -> Result<Vec<ProjectTemplate>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            ProjectTemplate,
+            r#"
+SELECT id, unique_id, name, budget, parent_id, created_at FROM PROJECT_TEMPLATES
+WHERE unique_id = ?1
+ORDER BY name COLLATE NOCASE
+            "#,
+            tag_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Gets one of the project templates of a tag
+    #[instrument(skip(self))]
+    pub async fn project_template(&self, uid: &TagId, template_id: i64) -> Result<ProjectTemplate> {
+        let tag_id = uid.as_ref();
+        sqlx::query_as!(
+            ProjectTemplate,
+            r#"
+SELECT id, unique_id, name, budget, parent_id, created_at FROM PROJECT_TEMPLATES
+WHERE id = ?1 AND unique_id = ?2
+            "#,
+            template_id,
+            tag_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            Error::NotFound(format!("No project template {} for {}", template_id, uid)).into()
+        })
+    }
+
+    /// Removes a project template, leaving the projects created from it alone
+    #[instrument(skip(self))]
+    pub async fn remove_project_template(&self, uid: &TagId, template_id: i64) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let rows = sqlx::query!(
+            "DELETE FROM PROJECT_TEMPLATES WHERE id = ?1 AND unique_id = ?2",
+            template_id,
+            tag_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows != 1 {
+            return Err(Error::NotFound(format!(
+                "No project template {} for {}",
+                template_id, uid
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Copies a project of the tag with its client and budget, but without its timers
+    ///
+    /// The copy is named after the project with a "(copy)" suffix, numbered if
+    /// that's taken too, and becomes the current project.
+    #[instrument(skip(self))]
+    pub async fn clone_project(&self, uid: &TagId, project_id: i64) -> Result<Project> {
+        let tag_id = uid.as_ref();
+        let source = self.owned_project(uid, project_id).await?;
+        let mut transaction = self.pool.begin().await?;
+
+        let taken = sqlx::query_scalar!(
+            r#"
+SELECT name AS "name!" FROM PROJECTS
+WHERE unique_id = ?1 AND archived = 0"#,
+            tag_id
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+        let name = (1..)
+            .map(|n| copy_name(&source.name, n))
+            .find(|name| !taken.iter().any(|taken| taken.eq_ignore_ascii_case(name)))
+            .expect("There are fewer projects than copy numbers");
+
+        info!(tag_id, project_id, name, "Cloning project");
+        let id = Self::create_project_in(&mut transaction, uid, &name).await?;
+        sqlx::query!(
+            "UPDATE PROJECTS SET parent_id = ?2 WHERE id = ?1",
+            id,
+            source.parent_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            r#"
+INSERT INTO PROJECT_BUDGETS (project_id, unique_id, budget)
+SELECT ?2, unique_id, budget FROM PROJECT_BUDGETS WHERE project_id = ?1
+            "#,
+            project_id,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            r#"
+INSERT INTO TEAM_PROJECTS (project_id, team_id)
+SELECT ?2, team_id FROM TEAM_PROJECTS WHERE project_id = ?1
+            "#,
+            project_id,
+            id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+        self.project(id).await
+    }
+
+    async fn create_project_in(
+        conn: &mut SqliteConnection,
+        uid: &TagId,
+        project_name: &str,
+    ) -> Result<i64> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, "Creating new project");
+        Self::touch_tag_in(conn, uid).await?;
+
+        // Update the existing current project if necessary
+        if let Ok(p) = Self::current_project(conn, uid).await {
+            sqlx::query!(
+                r#"
+UPDATE projects
+SET is_current = ?1
+WHERE id = ?2;
+                "#,
+                IsCurrent::No as i64,
+                p.id
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        let now = Utc::now().timestamp();
+        let id = sqlx::query!(
+            r#"
+INSERT INTO PROJECTS (unique_id, is_current, name, created)
+VALUES (?1, ?2, ?3, ?4)"#,
+            tag_id,
+            IsCurrent::Yes as i64,
+            project_name,
+            now
+        )
+        .execute(&mut *conn)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    /// Gets a single project by its ID
+    #[instrument(skip(self))]
+    pub async fn project(&self, project_id: i64) -> Result<Project> {
+        sqlx::query_as!(Project, "SELECT * FROM PROJECTS WHERE id = ?1", project_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("There is no project {}", project_id)).into())
+    }
+
+    /// Searches the projects of a tag
+    ///
+    /// Returns one page of matching projects ordered by name along with the
+    /// total number of matches.
+    #[instrument(skip(self))]
+    pub async fn search_projects(
+        &self,
+        uid: &TagId,
+        query: &ProjectQuery,
+    ) -> Result<(Vec<Project>, i64)> {
+        let tag_id = uid.as_ref();
+        let pattern = query.name.as_deref().map(|name| {
+            let escaped = name
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        });
+
+        let projects = sqlx::query_as!(
+            Project,
+            r#"
+SELECT * FROM PROJECTS
+WHERE unique_id = ?1
+    AND (?2 IS NULL OR name LIKE ?2 ESCAPE '\')
+    AND (?3 IS NULL OR archived = ?3)
+ORDER BY name COLLATE NOCASE, id
+LIMIT ?4 OFFSET ?5
+            "#,
+            tag_id,
+            pattern,
+            query.archived,
+            query.limit,
+            query.offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+SELECT COUNT(*) AS "total!: i64" FROM PROJECTS
+WHERE unique_id = ?1
+    AND (?2 IS NULL OR name LIKE ?2 ESCAPE '\')
+    AND (?3 IS NULL OR archived = ?3)
+            "#,
+            tag_id,
+            pattern,
+            query.archived
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((projects, total))
+    }
+
+    /// Archives or restores a project of the given [`TagId`][crate::uid::TagId]
+    #[instrument(skip(self))]
+    pub async fn set_project_archived(
+        &self,
+        uid: &TagId,
+        project_id: i64,
+        archived: bool,
+    ) -> Result<()> {
+        self.owned_project(uid, project_id).await?;
+        sqlx::query!(
+            "UPDATE PROJECTS SET archived = ?1 WHERE id = ?2",
+            archived,
+            project_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Nests a project of the given [`TagId`][crate::uid::TagId] under another of its projects
+    ///
+    /// `None` moves the project back to the top level.
+    #[instrument(skip(self))]
+    pub async fn set_project_parent(
+        &self,
+        uid: &TagId,
+        project_id: i64,
+        parent_id: Option<i64>,
+    ) -> Result<Project> {
+        self.owned_project(uid, project_id).await?;
+        if let Some(parent_id) = parent_id {
+            self.owned_project(uid, parent_id).await?;
+
+            // Walking up from the new parent must not reach the project itself
+            let cycle = sqlx::query_scalar!(
+                r#"
+WITH RECURSIVE ancestors(id) AS (
+    SELECT ?1
+    UNION
+    SELECT PROJECTS.parent_id FROM PROJECTS
+    JOIN ancestors ON ancestors.id = PROJECTS.id
+    WHERE PROJECTS.parent_id IS NOT NULL
+)
+SELECT COUNT(*) AS "cycle!: i64" FROM ancestors WHERE id = ?2
+                "#,
+                parent_id,
+                project_id
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            if cycle > 0 {
+                return Err(Error::Validation(format!(
+                    "Project {} can't be nested under its own task {}",
+                    project_id, parent_id
+                ))
+                .into());
+            }
+        }
+
+        sqlx::query!(
+            "UPDATE PROJECTS SET parent_id = ?1 WHERE id = ?2",
+            parent_id,
+            project_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.project(project_id).await
+    }
+
+    /// Gets the top level project a project is nested under, the project itself if it isn't
+    #[instrument(skip(self))]
+    pub async fn root_project(&self, project_id: i64) -> Result<Project> {
+        let mut project = self.project(project_id).await?;
+        while let Some(parent_id) = project.parent_id {
+            project = self.project(parent_id).await?;
+        }
+
+        Ok(project)
+    }
+
+    /// Gets a project, ensuring it belongs to the given [`TagId`][crate::uid::TagId]
+    #[instrument(skip(self))]
+    pub async fn owned_project(&self, uid: &TagId, project_id: i64) -> Result<Project> {
+        let project = self.project(project_id).await?;
+        if project.unique_id != uid.as_ref() {
+            return Err(Error::NotFound(format!(
+                "Project {} does not belong to {}",
+                project_id, uid
+            ))
+            .into());
+        }
+
+        Ok(project)
+    }
+
+    /// Gets a project the given [`TagId`][crate::uid::TagId] may track time on
+    ///
+    /// That's any project of its own, and the projects of teams it is at least
+    /// a [`Role::Member`] of.
+    #[instrument(skip(conn))]
+    async fn trackable_project_in(
+        conn: &mut SqliteConnection,
+        uid: &TagId,
+        project_id: i64,
+    ) -> Result<Project> {
+        let project = sqlx::query_as!(Project, "SELECT * FROM PROJECTS WHERE id = ?1", project_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("There is no project {}", project_id)))?;
+        if project.unique_id == uid.as_ref() {
+            return Ok(project);
+        }
+
+        let tag_id = uid.as_ref();
+        let role = sqlx::query_scalar!(
+            r#"
+SELECT TEAM_MEMBERS.role AS "role: Role"
+FROM TEAM_PROJECTS
+JOIN TEAM_MEMBERS ON TEAM_MEMBERS.team_id = TEAM_PROJECTS.team_id
+WHERE TEAM_PROJECTS.project_id = ?1 AND TEAM_MEMBERS.unique_id = ?2
+            "#,
+            project_id,
+            tag_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        match role {
+            Some(role) if role >= Role::Member => Ok(project),
+            Some(_) => Err(Error::Forbidden(format!(
+                "Viewers can't track time on project {}",
+                project_id
+            ))
+            .into()),
+            None => Err(Error::NotFound(format!(
+                "Project {} does not belong to {}",
+                project_id, uid
+            ))
+            .into()),
+        }
+    }
+
+    /// Finds the timers whose note or project name match `text`
+    ///
+    /// Every word in `text` has to match, either in full or as a prefix.
+    /// Results are grouped by project, ordered by their most recent match.
+    #[instrument(skip(self))]
+    pub async fn search(&self, uid: &TagId, text: &str) -> Result<Vec<SearchResult>> {
+        let tag_id = uid.as_ref();
+        let Some(query) = fts_query(text) else {
+            return Ok(Vec::new());
+        };
+        info!(tag_id, query, "Searching timers");
+
+        let timers = sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE unique_id = ?1
+    AND (
+        id IN (SELECT rowid FROM TIMER_NOTES_FTS WHERE TIMER_NOTES_FTS MATCH ?2)
+        OR project_id IN (SELECT rowid FROM PROJECT_NAMES_FTS WHERE PROJECT_NAMES_FTS MATCH ?2)
+    )
+ORDER BY start_time_ms DESC
+            "#,
+            tag_id,
+            query
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut projects: HashMap<i64, Project> = self
+            .get_projects(uid)
+            .await?
+            .into_iter()
+            .map(|project| (project.id, project))
+            .collect();
+
+        let mut results: Vec<SearchResult> = Vec::new();
+        for timer in timers {
+            match results
+                .iter_mut()
+                .find(|result| result.project.id == timer.project_id)
+            {
+                Some(result) => result.timers.push(timer),
+                None => {
+                    let Some(project) = projects.remove(&timer.project_id) else {
+                        continue;
+                    };
+                    results.push(SearchResult {
+                        project,
+                        timers: vec![timer],
+                    });
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Links a timer to the issue, commit or branch it was spent on,
+    /// `None` removes the link
+    #[instrument(skip(self))]
+    pub async fn set_timer_link(
+        &self,
+        uid: &TagId,
+        timer_id: i64,
+        link: Option<&str>,
+    ) -> Result<Timer> {
+        let link = link.map(validate_link).transpose()?;
+        info!(tag_id = uid.as_ref(), timer_id, link, "Linking timer");
+
+        let mut transaction = self.pool.begin().await?;
+        Self::owned_timer_in(&mut transaction, uid, timer_id).await?;
+        Self::ensure_unlocked(&mut transaction, timer_id).await?;
+        sqlx::query!("UPDATE TIMERS SET link = ?2 WHERE id = ?1", timer_id, link)
+            .execute(&mut *transaction)
+            .await?;
+        let timer = Self::owned_timer_in(&mut transaction, uid, timer_id).await?;
+        transaction.commit().await?;
+
+        Ok(timer)
+    }
+
+    /// Links the running timer of a tag, returning it unless nothing is running
+    #[instrument(skip(self))]
+    pub async fn link_running_timer(&self, uid: &TagId, link: &str) -> Result<Option<Timer>> {
+        let tag_id = uid.as_ref();
+        let link = validate_link(link)?;
+        let mut transaction = self.pool.begin().await?;
+        let running = sqlx::query_scalar!(
+            "SELECT id FROM TIMERS WHERE unique_id = ?1 AND is_current = 1",
+            tag_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        let Some(timer_id) = running else {
+            return Ok(None);
+        };
+
+        info!(tag_id, timer_id, link, "Linking running timer");
+        sqlx::query!("UPDATE TIMERS SET link = ?2 WHERE id = ?1", timer_id, link)
+            .execute(&mut *transaction)
+            .await?;
+        let timer = Self::owned_timer_in(&mut transaction, uid, timer_id).await?;
+        transaction.commit().await?;
+
+        Ok(Some(timer))
+    }
+
+    /// Attaches a label to a timer, creating the label if needed
+    #[instrument(skip(self))]
+    pub async fn add_timer_label(&self, uid: &TagId, timer_id: i64, label: &str) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let label = validate_label(label)?;
+        info!(tag_id, timer_id, label, "Labelling timer");
+
+        let owned = sqlx::query_scalar!(
+            "SELECT id FROM TIMERS WHERE id = ?1 AND unique_id = ?2",
+            timer_id,
+            tag_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if owned.is_none() {
+            return Err(
+                Error::NotFound(format!("Timer {} does not belong to {}", timer_id, uid)).into(),
+            );
+        }
+
+        let mut transaction = self.pool.begin().await?;
+        Self::ensure_unlocked(&mut transaction, timer_id).await?;
+        let label_id = sqlx::query_scalar!(
+            r#"
+INSERT INTO LABELS (unique_id, name)
+VALUES (?1, ?2)
+ON CONFLICT (unique_id, name) DO UPDATE
+SET name = excluded.name
+RETURNING id
+            "#,
+            tag_id,
+            label
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        sqlx::query!(
+            "INSERT OR IGNORE INTO TIMER_LABELS (timer_id, label_id) VALUES (?1, ?2)",
+            timer_id,
+            label_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Detaches a label from a timer
+    #[instrument(skip(self))]
+    pub async fn remove_timer_label(&self, uid: &TagId, timer_id: i64, label: &str) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let label = label.trim();
+        info!(tag_id, timer_id, label, "Removing label from timer");
+        Self::ensure_unlocked(&mut *self.pool.acquire().await?, timer_id).await?;
+        sqlx::query!(
+            r#"
+DELETE FROM TIMER_LABELS
+WHERE timer_id = ?1
+    AND label_id IN (SELECT id FROM LABELS WHERE unique_id = ?2 AND name = ?3)
+            "#,
+            timer_id,
+            tag_id,
+            label
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the labels of every timer of the given [`TagId`][crate::uid::TagId]
+    #[instrument(skip(self))]
+    pub async fn labels_by_tag(&self, uid: &TagId) -> Result<TimerLabels> {
+        let tag_id = uid.as_ref();
+        let rows = sqlx::query!(
+            r#"
+SELECT tl.timer_id, l.name
+FROM TIMER_LABELS tl
+JOIN LABELS l ON l.id = tl.label_id
+WHERE l.unique_id = ?1
+ORDER BY l.name
+            "#,
+            tag_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut labels = TimerLabels::new();
+        for row in rows {
+            labels.entry(row.timer_id).or_default().push(row.name);
+        }
+
+        Ok(labels)
+    }
+
+    /// Gets the labels of every timer in a project
+    #[instrument(skip(self))]
+    pub async fn labels_by_project(&self, project_id: i64) -> Result<TimerLabels> {
+        let rows = sqlx::query!(
+            r#"
+SELECT tl.timer_id, l.name
+FROM TIMER_LABELS tl
+JOIN LABELS l ON l.id = tl.label_id
+JOIN TIMERS t ON t.id = tl.timer_id
+WHERE t.project_id = ?1
+ORDER BY l.name
+            "#,
+            project_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut labels = TimerLabels::new();
+        for row in rows {
+            labels.entry(row.timer_id).or_default().push(row.name);
+        }
+
+        Ok(labels)
+    }
+
+    /// Inserts already finished timers into a project
+    ///
+    /// Takes `(start, duration)` pairs and inserts them all in a single
+    /// transaction, returning the number of timers created.
+    #[instrument(skip(self, timers))]
+    pub async fn import_timers(
+        &self,
+        project: &Project,
+        timers: impl IntoIterator<Item = (StartTime, TrackedDuration)>,
+    ) -> Result<u64> {
+        info!(project = project.id, "Importing timers");
+        let mut transaction = self.pool.begin().await?;
+        let mut imported = 0;
+        for (start, tracked) in timers {
+            let (start_time, duration) = second_columns(start, tracked);
+            sqlx::query!(
+                r#"
+INSERT INTO TIMERS (
+    UNIQUE_ID, IS_CURRENT, START_TIME, DURATION, PROJECT_ID, START_TIME_MS, DURATION_MS
+)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+                project.unique_id,
+                IsCurrent::No as i64,
+                start_time,
+                duration,
+                project.id,
+                start,
+                tracked
+            )
+            .execute(&mut *transaction)
+            .await?;
+            imported += 1;
+        }
+        transaction.commit().await?;
+
+        Ok(imported)
+    }
+
+    /// Creates a new preset for a project of the given [`TagId`][crate::uid::TagId]
+    #[instrument(skip(self))]
+    pub async fn create_preset(
+        &self,
+        uid: &TagId,
+        project_id: i64,
+        name: &str,
+        duration: i64,
+        note: Option<&str>,
+    ) -> Result<i64> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, project_id, "Creating preset");
+
+        self.owned_project(uid, project_id).await?;
+
+        let id = sqlx::query!(
+            r#"
+INSERT INTO PRESETS (unique_id, project_id, name, duration, note)
+VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            tag_id,
+            project_id,
+            name,
+            duration,
+            note
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    /// Gets all presets associated with [`TagId`][crate::uid::TagId]
+    #[instrument(skip(self))]
+    pub async fn presets(&self, uid: &TagId) -> Result<Vec<Preset>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            Preset,
+            "SELECT * FROM PRESETS WHERE unique_id = ?1 ORDER BY name",
+            tag_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Inserts a finished timer ending now, using the values of a preset
+    ///
+    /// Returns the ID of the new timer.
+    #[instrument(skip(self))]
+    pub async fn apply_preset(&self, uid: &TagId, preset_id: i64) -> Result<i64> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, preset_id, "Applying preset");
+
+        let preset = sqlx::query_as!(
+            Preset,
+            "SELECT * FROM PRESETS WHERE id = ?1 AND unique_id = ?2",
+            preset_id,
+            tag_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.touch_tag(uid).await?;
+        let tracked = TrackedDuration::from_seconds(preset.duration);
+        let start = StartTime(Utc::now() - tracked.0);
+        let (start_time, duration) = second_columns(start, tracked);
+        let id = sqlx::query!(
+            r#"
+INSERT INTO TIMERS (
+    UNIQUE_ID, IS_CURRENT, START_TIME, DURATION, PROJECT_ID, NOTE, START_TIME_MS, DURATION_MS
+)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+            tag_id,
+            IsCurrent::No as i64,
+            start_time,
+            duration,
+            preset.project_id,
+            preset.note,
+            start,
+            tracked
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    /// Gets the device with the given details, registering it on first use
+    ///
+    /// Also records that the device has just been seen.
+    #[instrument(skip(self))]
+    pub async fn identify_device(&self, uid: &TagId, details: &str) -> Result<Device> {
+        let tag_id = uid.as_ref();
+        let now = Utc::now().timestamp();
+        let device = sqlx::query_as!(
+            Device,
+            r#"
+INSERT INTO DEVICES (unique_id, details, first_seen, last_seen)
+VALUES (?1, ?2, ?3, ?3)
+ON CONFLICT (unique_id, details) DO UPDATE
+SET last_seen = excluded.last_seen
+RETURNING id, unique_id, details, first_seen, last_seen, revoked
+            "#,
+            tag_id,
+            details,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(device)
+    }
+
+    /// Gets all devices which have toggled timers for [`TagId`][crate::uid::TagId]
+    #[instrument(skip(self))]
+    pub async fn devices(&self, uid: &TagId) -> Result<Vec<Device>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            Device,
+            "SELECT * FROM DEVICES WHERE unique_id = ?1 ORDER BY last_seen DESC",
+            tag_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Revokes a device, so it can no longer toggle timers
+    #[instrument(skip(self))]
+    pub async fn revoke_device(&self, uid: &TagId, device_id: i64) -> Result<()> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, device_id, "Revoking device");
+        let rows = sqlx::query!(
+            "UPDATE DEVICES SET revoked = 1 WHERE id = ?1 AND unique_id = ?2",
+            device_id,
+            tag_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows != 1 {
+            return Err(Error::NotFound(format!("No device {} for {}", device_id, uid)).into());
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new share link or API token for a tag
+    #[instrument(skip(self))]
+    pub async fn create_token(&self, uid: &TagId, kind: TokenKind) -> Result<NewToken> {
+        let tag_id = uid.as_ref();
+        let kind_name = kind.as_str();
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let token_hash = hash_secret(&token);
+        let now = Utc::now().timestamp();
+        info!(tag_id, kind = kind_name, "Creating token");
+        let token_id = sqlx::query!(
+            r#"
+INSERT INTO TOKENS (kind, token_hash, unique_id, created_at)
+VALUES (?1, ?2, ?3, ?4)
+            "#,
+            kind_name,
+            token_hash,
+            tag_id,
+            now
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(NewToken {
+            token,
+            info: Token {
+                token_id,
+                unique_id: tag_id.to_string(),
+                created_at: now,
+                revoked: false,
+            },
+        })
+    }
+
+    /// Gets all share links or API tokens of a tag, newest first
+    #[instrument(skip(self))]
+    pub async fn tokens(&self, uid: &TagId, kind: TokenKind) -> Result<Vec<Token>> {
+        let tag_id = uid.as_ref();
+        let kind = kind.as_str();
+        let result = sqlx::query_as!(
+            Token,
+            r#"
+SELECT token_id AS "token_id!", unique_id, created_at, revoked AS "revoked: bool"
+FROM TOKENS
+WHERE unique_id = ?1 AND kind = ?2
+ORDER BY created_at DESC, token_id DESC
+            "#,
+            tag_id,
+            kind
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Resolves the tag a share link or API token grants access to, if it's
+    /// still valid
+    #[instrument(skip(self, token))]
+    pub async fn token_tag(&self, kind: TokenKind, token: &str) -> Result<TagId> {
+        let token_hash = hash_secret(token);
+        let kind_name = kind.as_str();
+        let result = sqlx::query_scalar!(
+            "SELECT unique_id FROM TOKENS WHERE token_hash = ?1 AND kind = ?2 AND revoked = 0",
+            token_hash,
+            kind_name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        result.map(TagId::from).ok_or_else(|| {
+            let message = format!("This {} isn't valid", kind.name());
+            match kind {
+                TokenKind::Share => Error::NotFound(message).into(),
+                TokenKind::Api => Error::Forbidden(message).into(),
+            }
+        })
+    }
+
+    /// Revokes a share link or API token, so it no longer grants access
+    #[instrument(skip(self))]
+    pub async fn revoke_token(&self, uid: &TagId, kind: TokenKind, token_id: i64) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let kind_name = kind.as_str();
+        info!(tag_id, kind = kind_name, "Revoking token");
+        let rows = sqlx::query!(
+            "UPDATE TOKENS SET revoked = 1 WHERE token_id = ?1 AND unique_id = ?2 AND kind = ?3",
+            token_id,
+            tag_id,
+            kind_name
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows != 1 {
+            return Err(
+                Error::NotFound(format!("No {} {} for {}", kind.name(), token_id, uid)).into(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Issues a new owner key for a tag, replacing the previous one
+    ///
+    /// Only a hash is stored, so the key is returned this one time.
+    #[instrument(skip(self))]
+    pub async fn issue_owner_key(&self, uid: &TagId) -> Result<String> {
+        let tag_id = uid.as_ref();
+        let key = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let key_hash = hash_secret(&key);
+        let now = Utc::now().timestamp();
+        info!(tag_id, "Issuing owner key");
+        sqlx::query!(
+            r#"
+INSERT INTO OWNER_KEYS (unique_id, key_hash, created_at)
+VALUES (?1, ?2, ?3)
+ON CONFLICT (unique_id) DO UPDATE
+SET key_hash = excluded.key_hash, created_at = excluded.created_at
+            "#,
+            tag_id,
+            key_hash,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(key)
+    }
+
+    /// Resolves the tag an owner key belongs to
+    #[instrument(skip_all)]
+    pub async fn owner_tag(&self, key: &str) -> Result<TagId> {
+        let key_hash = hash_secret(key);
+        let result = sqlx::query_scalar!(
+            "SELECT unique_id FROM OWNER_KEYS WHERE key_hash = ?1",
+            key_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        result
+            .map(TagId::from)
+            .ok_or_else(|| Error::Forbidden("This owner key isn't valid".to_string()).into())
+    }
+
+    /// Creates a short slug for the timer page of a tag
+    ///
+    /// Without a chosen `slug`, a random one of [`SLUG_LENGTH`] characters is generated.
+    #[instrument(skip(self))]
+    pub async fn create_slug(&self, uid: &TagId, slug: Option<&str>) -> Result<Slug> {
+        let tag_id = uid.as_ref();
+        if let Some(slug) = slug {
+            validate_slug(slug)?;
+        }
+
+        // A few attempts make a collision of random slugs vanishingly unlikely
+        let attempts = if slug.is_some() { 1 } else { 5 };
+        for _ in 0..attempts {
+            let slug = slug.map_or_else(random_slug, str::to_string);
+            let now = Utc::now().timestamp();
+            let result = sqlx::query_as!(
+                Slug,
+                r#"
+INSERT INTO SLUGS (slug, unique_id, created_at)
+VALUES (?1, ?2, ?3)
+ON CONFLICT (slug) DO NOTHING
+RETURNING slug, unique_id, created_at
+                "#,
+                slug,
+                tag_id,
+                now
+            )
+            .fetch_optional(&self.pool)
+            .await?;
+
+            if let Some(result) = result {
+                info!(tag_id, slug, "Created slug");
+                return Ok(result);
+            }
+        }
+
+        Err(match slug {
+            Some(slug) => Error::Conflict(format!("Slug {:?} is already taken", slug)),
+            None => Error::Conflict("Unable to find a free slug, try again".to_string()),
+        }
+        .into())
+    }
+
+    /// Gets the slugs of a tag, newest first
+    #[instrument(skip(self))]
+    pub async fn slugs(&self, uid: &TagId) -> Result<Vec<Slug>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            Slug,
+            "SELECT * FROM SLUGS WHERE unique_id = ?1 ORDER BY created_at DESC",
+            tag_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Resolves the tag a slug leads to
+    #[instrument(skip(self))]
+    pub async fn slug_tag(&self, slug: &str) -> Result<TagId> {
+        let result = sqlx::query_scalar!("SELECT unique_id FROM SLUGS WHERE slug = ?1", slug)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        result
+            .map(TagId::from)
+            .ok_or_else(|| Error::NotFound(format!("There is no link {:?}", slug)).into())
+    }
+
+    /// Removes a slug, freeing it for reuse
+    #[instrument(skip(self))]
+    pub async fn remove_slug(&self, uid: &TagId, slug: &str) -> Result<()> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, slug, "Removing slug");
+        let rows = sqlx::query!(
+            "DELETE FROM SLUGS WHERE slug = ?1 AND unique_id = ?2",
+            slug,
+            tag_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if rows != 1 {
+            return Err(Error::NotFound(format!("No slug {} for {}", slug, uid)).into());
+        }
+
+        Ok(())
+    }
+
+    /// Records a use of a tag's toggle link, unless it was used less than
+    /// `window` seconds ago
+    ///
+    /// Phones sometimes open a link twice for a single tap, and browsers
+    /// reopen it when restoring tabs. Returns whether the use should toggle.
+    #[instrument(skip(self))]
+    pub async fn claim_toggle_link(&self, uid: &TagId, window: i64) -> Result<bool> {
+        let tag_id = uid.as_ref();
+        let now = Utc::now().timestamp();
+        let since = now - window;
+        let claimed = sqlx::query!(
+            r#"
+INSERT INTO TOGGLE_LINKS (unique_id, used_at)
+VALUES (?1, ?2)
+ON CONFLICT (unique_id) DO UPDATE
+SET used_at = excluded.used_at
+WHERE used_at <= ?3
+            "#,
+            tag_id,
+            now,
+            since
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            == 1;
+
+        if !claimed {
+            info!(tag_id, "Ignoring replayed toggle link");
+        }
+
+        Ok(claimed)
+    }
+
+    /// Starts a new generation of a tag's toggle link, so links of earlier
+    /// generations no longer toggle, returning the new one
+    #[instrument(skip(self))]
+    pub async fn rotate_toggle_link(&self, uid: &TagId) -> Result<i64> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, "Rotating toggle link");
+        let generation = sqlx::query_scalar!(
+            r#"
+INSERT INTO TOGGLE_LINKS (unique_id, used_at, generation)
+VALUES (?1, 0, 1)
+ON CONFLICT (unique_id) DO UPDATE
+SET generation = generation + 1
+RETURNING generation
+            "#,
+            tag_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(generation)
+    }
+
+    /// The current generation of a tag's toggle link, 0 if it never had one
+    #[instrument(skip(self))]
+    pub async fn toggle_link_generation(&self, uid: &TagId) -> Result<i64> {
+        let tag_id = uid.as_ref();
+        let generation = sqlx::query_scalar!(
+            "SELECT generation FROM TOGGLE_LINKS WHERE unique_id = ?1",
+            tag_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(generation.unwrap_or_default())
+    }
+
+    /// Creates a team, with the given [`TagId`][crate::uid::TagId] as its owner
+    #[instrument(skip(self))]
+    pub async fn create_team(&self, uid: &TagId, name: &str) -> Result<Team> {
+        let tag_id = uid.as_ref();
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::Validation("Team name must not be empty".to_string()).into());
+        }
+
+        info!(tag_id, name, "Creating team");
+        let now = Utc::now().timestamp();
+        let mut transaction = self.pool.begin().await?;
+        let team = sqlx::query_as!(
+            Team,
+            r#"
+INSERT INTO TEAMS (name, created_at, last_member_id) VALUES (?1, ?2, 1)
+RETURNING id, name, created_at
+            "#,
+            name,
+            now
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        sqlx::query!(
+            r#"
+INSERT INTO TEAM_MEMBERS (team_id, unique_id, role, joined_at, member_id)
+VALUES (?1, ?2, ?3, ?4, 1)
+            "#,
+            team.id,
+            tag_id,
+            Role::Owner,
+            now
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        Ok(team)
+    }
+
+    /// Gets the role of a member of a team
+    ///
+    /// Teams the [`TagId`][crate::uid::TagId] isn't a member of are reported
+    /// as missing, so their existence isn't revealed.
+    #[instrument(skip(self))]
+    pub async fn team_role(&self, team_id: i64, uid: &TagId) -> Result<Role> {
+        let tag_id = uid.as_ref();
+        let role = sqlx::query_scalar!(
+            r#"SELECT role AS "role: Role" FROM TEAM_MEMBERS WHERE team_id = ?1 AND unique_id = ?2"#,
+            team_id,
+            tag_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        role.ok_or_else(|| Error::NotFound(format!("There is no team {}", team_id)).into())
+    }
+
+    /// Ensures a member has at least the `required` role in a team
+    async fn require_role(&self, team_id: i64, uid: &TagId, required: Role) -> Result<Role> {
+        let role = self.team_role(team_id, uid).await?;
+        if role < required {
+            return Err(Error::Forbidden(format!(
+                "This needs the {} role in team {}, not {}",
+                required, team_id, role
+            ))
+            .into());
+        }
+
+        Ok(role)
+    }
+
+    /// Adds a member to a team or changes their role, which only owners may do
+    #[instrument(skip(self))]
+    pub async fn set_team_member(
+        &self,
+        uid: &TagId,
+        team_id: i64,
+        member: &TagId,
+        role: Role,
+    ) -> Result<()> {
+        self.require_role(team_id, uid, Role::Owner).await?;
+        if member.as_ref() == uid.as_ref() {
+            return Err(Error::Validation("Owners can't change their own role".to_string()).into());
+        }
+
+        let tag_id = member.as_ref();
+        info!(team_id, tag_id, ?role, "Setting team member");
+        let now = Utc::now().timestamp();
+        let mut transaction = self.pool.begin().await?;
+        let changed = sqlx::query!(
+            "UPDATE TEAM_MEMBERS SET role = ?3 WHERE team_id = ?1 AND unique_id = ?2",
+            team_id,
+            tag_id,
+            role
+        )
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+        if changed == 0 {
+            let member_id = sqlx::query_scalar!(
+                r#"
+UPDATE TEAMS SET last_member_id = last_member_id + 1 WHERE id = ?1
+RETURNING last_member_id
+                "#,
+                team_id
+            )
+            .fetch_one(&mut *transaction)
+            .await?;
+            sqlx::query!(
+                r#"
+INSERT INTO TEAM_MEMBERS (team_id, unique_id, role, joined_at, member_id)
+VALUES (?1, ?2, ?3, ?4, ?5)
+                "#,
+                team_id,
+                tag_id,
+                role,
+                now,
+                member_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Removes a member from a team, which only owners may do
+    ///
+    /// The timers the member tracked on team projects are kept.
+    #[instrument(skip(self))]
+    pub async fn remove_team_member(
+        &self,
+        uid: &TagId,
+        team_id: i64,
+        member_id: i64,
+    ) -> Result<()> {
+        self.require_role(team_id, uid, Role::Owner).await?;
+
+        let tag_id = uid.as_ref();
+        let member = sqlx::query_scalar!(
+            "SELECT unique_id FROM TEAM_MEMBERS WHERE team_id = ?1 AND member_id = ?2",
+            team_id,
+            member_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            Error::NotFound(format!(
+                "There is no member {} in team {}",
+                member_id, team_id
+            ))
+        })?;
+        if member == tag_id {
+            return Err(Error::Validation("Owners can't remove themselves".to_string()).into());
+        }
+
+        info!(team_id, member_id, "Removing team member");
+        sqlx::query!(
+            "DELETE FROM TEAM_MEMBERS WHERE team_id = ?1 AND member_id = ?2",
+            team_id,
+            member_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Creates a project shared by all members of a team, which only owners may do
+    ///
+    /// The project belongs to the owner creating it, but doesn't become their
+    /// current project.
+    #[instrument(skip(self))]
+    pub async fn create_team_project(&self, uid: &TagId, team_id: i64, name: &str) -> Result<i64> {
+        self.require_role(team_id, uid, Role::Owner).await?;
+
+        let tag_id = uid.as_ref();
+        info!(tag_id, team_id, "Creating team project");
+        let now = Utc::now().timestamp();
+        let mut transaction = self.pool.begin().await?;
+        let project_id = sqlx::query!(
+            r#"
+INSERT INTO PROJECTS (unique_id, is_current, name, created)
+VALUES (?1, ?2, ?3, ?4)"#,
+            tag_id,
+            IsCurrent::No as i64,
+            name,
+            now
+        )
+        .execute(&mut *transaction)
+        .await?
+        .last_insert_rowid();
+
+        sqlx::query!(
+            "INSERT INTO TEAM_PROJECTS (project_id, team_id) VALUES (?1, ?2)",
+            project_id,
+            team_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        Ok(project_id)
+    }
+
+    /// Gets the members, projects and timers of a team for one of its members
+    #[instrument(skip(self))]
+    pub async fn team_report(&self, uid: &TagId, team_id: i64) -> Result<TeamReport> {
+        let role = self.require_role(team_id, uid, Role::Viewer).await?;
+
+        let team = sqlx::query_as!(
+            Team,
+            "SELECT id, name, created_at FROM TEAMS WHERE id = ?1",
+            team_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let members = sqlx::query_as!(
+            TeamMember,
+            r#"
+SELECT member_id AS id, role AS "role: Role", joined_at
+FROM TEAM_MEMBERS
+WHERE team_id = ?1
+ORDER BY member_id
+            "#,
+            team_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let projects = sqlx::query!(
+            r#"
+SELECT id, name, archived AS "archived: bool" FROM PROJECTS
+WHERE id IN (SELECT project_id FROM TEAM_PROJECTS WHERE team_id = ?1)
+ORDER BY name
+            "#,
+            team_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let timers = sqlx::query_as!(
+            TeamTimer,
+            r#"
+SELECT t.id, t.project_id, m.member_id AS "member_id?",
+    t.start_time_ms AS "start_time: StartTime", t.is_current,
+    t.duration_ms AS "duration: TrackedDuration", t.note, t.link, t.invoiced
+FROM TIMERS t
+LEFT JOIN TEAM_MEMBERS m ON m.team_id = ?1 AND m.unique_id = t.unique_id
+WHERE t.project_id IN (SELECT project_id FROM TEAM_PROJECTS WHERE team_id = ?1)
+ORDER BY t.start_time_ms DESC
+            "#,
+            team_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut timers_by_project: HashMap<i64, Vec<TeamTimer>> = HashMap::new();
+        for timer in timers {
+            timers_by_project
+                .entry(timer.project_id)
+                .or_default()
+                .push(timer);
+        }
+
+        let projects = projects
+            .into_iter()
+            .map(|project| TeamProject {
+                timers: timers_by_project.remove(&project.id).unwrap_or_default(),
+                id: project.id,
+                name: project.name,
+                archived: project.archived,
+            })
+            .collect();
+
+        Ok(TeamReport {
+            team,
+            role,
+            members,
+            projects,
+        })
+    }
+
+    /// Submits a member's timers on the projects of a team in a week for approval
+    ///
+    /// Rejected timesheets can be submitted again, ones waiting for review or
+    /// approved already can't.
+    #[instrument(skip(self))]
+    pub async fn submit_timesheet(
+        &self,
+        uid: &TagId,
+        team_id: i64,
+        (week_start, week_end): (i64, i64),
+    ) -> Result<Timesheet> {
+        self.require_role(team_id, uid, Role::Member).await?;
+
+        let tag_id = uid.as_ref();
+        let (week_start_ms, week_end_ms) = (
+            StartTime::from_seconds(week_start),
+            StartTime::from_seconds(week_end),
+        );
+        let running = sqlx::query_scalar!(
+            r#"
+SELECT COUNT(*) AS "running!: i64" FROM TIMERS
+WHERE unique_id = ?1
+    AND is_current = 1
+    AND start_time_ms >= ?3 AND start_time_ms < ?4
+    AND project_id IN (SELECT project_id FROM TEAM_PROJECTS WHERE team_id = ?2)
+            "#,
+            tag_id,
+            team_id,
+            week_start_ms,
+            week_end_ms
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if running > 0 {
+            return Err(Error::Validation(
+                "Stop the running timer before submitting its week".to_string(),
+            )
+            .into());
+        }
+
+        info!(tag_id, team_id, week_start, "Submitting timesheet");
+        let now = Utc::now().timestamp();
+        let timesheet_id = sqlx::query_scalar!(
+            r#"
+INSERT INTO TIMESHEETS (team_id, unique_id, week_start, week_end, status, submitted_at)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+ON CONFLICT (team_id, unique_id, week_start) DO UPDATE
+SET status = excluded.status,
+    comment = NULL,
+    submitted_at = excluded.submitted_at,
+    reviewed_by = NULL,
+    reviewed_at = NULL
+WHERE TIMESHEETS.status = ?7
+RETURNING id
+            "#,
+            team_id,
+            tag_id,
+            week_start,
+            week_end,
+            TimesheetStatus::Submitted,
+            now,
+            TimesheetStatus::Rejected
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(timesheet_id) = timesheet_id else {
+            return Err(Error::Conflict(format!(
+                "The timesheet of {} for the week starting at {} was already submitted",
+                uid, week_start
+            ))
+            .into());
+        };
+
+        self.timesheet(timesheet_id).await
+    }
+
+    /// Gets a timesheet, naming its member and reviewer by their [`TeamMember::id`]
+    async fn timesheet(&self, timesheet_id: i64) -> Result<Timesheet> {
+        let timesheet = sqlx::query_as!(
+            Timesheet,
+            r#"
+SELECT s.id, s.team_id, m.member_id AS "member_id?", s.week_start, s.week_end,
+    s.status AS "status: TimesheetStatus", s.comment, s.submitted_at,
+    r.member_id AS "reviewed_by?", s.reviewed_at
+FROM TIMESHEETS s
+LEFT JOIN TEAM_MEMBERS m ON m.team_id = s.team_id AND m.unique_id = s.unique_id
+LEFT JOIN TEAM_MEMBERS r ON r.team_id = s.team_id AND r.unique_id = s.reviewed_by
+WHERE s.id = ?1
+            "#,
+            timesheet_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(timesheet)
+    }
+
+    /// Approves or rejects a submitted timesheet, which only owners of its team may do
+    ///
+    /// Rejecting needs a comment telling the member what to fix. Owners can't
+    /// review their own timesheets.
+    #[instrument(skip(self))]
+    pub async fn review_timesheet(
+        &self,
+        uid: &TagId,
+        team_id: i64,
+        timesheet_id: i64,
+        status: TimesheetStatus,
+        comment: Option<&str>,
+    ) -> Result<Timesheet> {
+        self.require_role(team_id, uid, Role::Owner).await?;
+
+        let comment = comment.map(str::trim).filter(|comment| !comment.is_empty());
+        match status {
+            TimesheetStatus::Submitted => {
+                return Err(Error::Validation(
+                    "Timesheets can only be approved or rejected".to_string(),
+                )
+                .into())
+            }
+            TimesheetStatus::Rejected if comment.is_none() => {
+                return Err(
+                    Error::Validation("Rejecting a timesheet needs a comment".to_string()).into(),
+                )
+            }
+            _ => {}
+        }
+
+        let tag_id = uid.as_ref();
+        info!(
+            tag_id,
+            team_id,
+            timesheet_id,
+            ?status,
+            "Reviewing timesheet"
+        );
+        let now = Utc::now().timestamp();
+        let reviewed = sqlx::query_scalar!(
+            r#"
+UPDATE TIMESHEETS
+SET status = ?3, comment = ?4, reviewed_by = ?5, reviewed_at = ?6
+WHERE id = ?1 AND team_id = ?2 AND status = ?7 AND unique_id != ?5
+RETURNING id AS "id!"
+            "#,
+            timesheet_id,
+            team_id,
+            status,
+            comment,
+            tag_id,
+            now,
+            TimesheetStatus::Submitted
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(timesheet_id) = reviewed {
+            return self.timesheet(timesheet_id).await;
+        }
+
+        let submitter = sqlx::query_scalar!(
+            "SELECT unique_id FROM TIMESHEETS WHERE id = ?1 AND team_id = ?2",
+            timesheet_id,
+            team_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        match submitter {
+            Some(submitter) if submitter == tag_id => {
+                Err(Error::Forbidden("Owners can't review their own timesheets".to_string()).into())
+            }
+            Some(_) => Err(Error::Conflict(format!(
+                "Timesheet {} isn't waiting for review",
+                timesheet_id
+            ))
+            .into()),
+            None => Err(Error::NotFound(format!(
+                "There is no timesheet {} in team {}",
+                timesheet_id, team_id
+            ))
+            .into()),
+        }
+    }
+
+    /// Gets the timesheets of a team, owners see every member's and everyone else their own
+    #[instrument(skip(self))]
+    pub async fn timesheets(&self, uid: &TagId, team_id: i64) -> Result<Vec<Timesheet>> {
+        let role = self.require_role(team_id, uid, Role::Viewer).await?;
+
+        let tag_id = uid.as_ref();
+        let everyone = role == Role::Owner;
+        let result = sqlx::query_as!(
+            Timesheet,
+            r#"
+SELECT s.id, s.team_id, m.member_id AS "member_id?", s.week_start, s.week_end,
+    s.status AS "status: TimesheetStatus", s.comment, s.submitted_at,
+    r.member_id AS "reviewed_by?", s.reviewed_at
+FROM TIMESHEETS s
+LEFT JOIN TEAM_MEMBERS m ON m.team_id = s.team_id AND m.unique_id = s.unique_id
+LEFT JOIN TEAM_MEMBERS r ON r.team_id = s.team_id AND r.unique_id = s.reviewed_by
+WHERE s.team_id = ?1 AND (?2 OR s.unique_id = ?3)
+ORDER BY s.week_start DESC, m.member_id
+            "#,
+            team_id,
+            everyone,
+            tag_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Ensures a timer can still change, which it can't once it was invoiced
+    /// or is part of an approved [`Timesheet`]
+    #[instrument(skip(conn))]
+    async fn ensure_unlocked(conn: &mut SqliteConnection, timer_id: i64) -> Result<()> {
+        let invoiced = sqlx::query_scalar!(
+            r#"SELECT invoiced AS "invoiced: bool" FROM TIMERS WHERE id = ?1"#,
+            timer_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        if invoiced == Some(true) {
+            return Err(Error::Conflict(format!("Timer {} was invoiced", timer_id)).into());
+        }
+
+        let approved = sqlx::query_scalar!(
+            r#"
+SELECT s.id
+FROM TIMERS t
+JOIN TEAM_PROJECTS p ON p.project_id = t.project_id
+JOIN TIMESHEETS s ON s.team_id = p.team_id AND s.unique_id = t.unique_id
+WHERE t.id = ?1
+    AND s.status = ?2
+    AND t.start_time_ms >= s.week_start * 1000 AND t.start_time_ms < s.week_end * 1000
+            "#,
+            timer_id,
+            TimesheetStatus::Approved
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some(timesheet_id) = approved {
+            return Err(Error::Conflict(format!(
+                "Timer {} is part of the approved timesheet {}",
+                timer_id, timesheet_id
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Marks the finished timers of a project started in `[from, to)` as invoiced, or not
+    ///
+    /// Returns how many timers changed.
+    #[instrument(skip(self))]
+    pub async fn set_invoiced(
+        &self,
+        uid: &TagId,
+        project_id: i64,
+        (from, to): (i64, i64),
+        invoiced: bool,
+    ) -> Result<u64> {
+        self.owned_project(uid, project_id).await?;
+        if from >= to {
+            return Err(Error::Validation("The range must end after it starts".to_string()).into());
+        }
+
+        let tag_id = uid.as_ref();
+        info!(
+            tag_id,
+            project_id, from, to, invoiced, "Marking timers invoiced"
+        );
+        let (from, to) = (StartTime::from_seconds(from), StartTime::from_seconds(to));
+        let result = sqlx::query!(
+            r#"
+UPDATE TIMERS
+SET invoiced = ?4
+WHERE project_id = ?1
+    AND is_current = 0
+    AND start_time_ms >= ?2 AND start_time_ms < ?3
+    AND invoiced != ?4
+            "#,
+            project_id,
+            from,
+            to,
+            invoiced
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Gets the tags which want the digest of the week starting at `week_start` but haven't had it
+    #[instrument(skip(self))]
+    pub async fn digest_recipients(&self, week_start: i64) -> Result<Vec<DigestRecipient>> {
+        let result = sqlx::query_as!(
+            DigestRecipient,
+            r#"
+SELECT unique_id, email AS "email!"
+FROM SETTINGS
+WHERE weekly_digest = 1 AND email IS NOT NULL
+    AND unique_id NOT IN (SELECT unique_id FROM DIGESTS_SENT WHERE week_start = ?1)
+            "#,
+            week_start
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Records that the digest of the week starting at `week_start` was sent
+    #[instrument(skip(self))]
+    pub async fn mark_digest_sent(&self, uid: &TagId, week_start: i64) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let now = Utc::now().timestamp();
+        sqlx::query!(
+            r#"
+INSERT OR IGNORE INTO DIGESTS_SENT (unique_id, week_start, sent_at)
+VALUES (?1, ?2, ?3)
+            "#,
+            tag_id,
+            week_start,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sums up the finished timers of a tag started in `[from, to)` per project
+    ///
+    /// Projects are ordered by the time spent on them, most first.
+    #[instrument(skip(self))]
+    pub async fn hours_by_project(
+        &self,
+        uid: &TagId,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<ProjectHours>> {
+        let tag_id = uid.as_ref();
+        let (from, to) = (StartTime::from_seconds(from), StartTime::from_seconds(to));
+        let result = sqlx::query_as!(
+            ProjectHours,
+            r#"
+SELECT PROJECTS.name, SUM(TIMERS.duration_ms) AS "duration!: TrackedDuration"
+FROM TIMERS
+JOIN PROJECTS ON PROJECTS.id = TIMERS.project_id
+WHERE TIMERS.unique_id = ?1 AND TIMERS.is_current = 0
+    AND TIMERS.start_time_ms >= ?2 AND TIMERS.start_time_ms < ?3
+GROUP BY PROJECTS.id
+ORDER BY 2 DESC, PROJECTS.name
+            "#,
+            tag_id,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Gets the hours a tag tracked within `from..to`, rolled up into top level projects
+    ///
+    /// The time of every nested project counts towards the project at the top
+    /// of its hierarchy, like a client.
+    #[instrument(skip(self))]
+    pub async fn hours_by_client(
+        &self,
+        uid: &TagId,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<ProjectHours>> {
+        let tag_id = uid.as_ref();
+        let (from, to) = (StartTime::from_seconds(from), StartTime::from_seconds(to));
+        let result = sqlx::query_as!(
+            ProjectHours,
+            r#"
+WITH RECURSIVE ancestors(project_id, id, parent_id) AS (
+    SELECT id, id, parent_id FROM PROJECTS
+    WHERE id IN (SELECT project_id FROM TIMERS WHERE unique_id = ?1)
+    UNION ALL
+    SELECT ancestors.project_id, PROJECTS.id, PROJECTS.parent_id FROM PROJECTS
+    JOIN ancestors ON ancestors.parent_id = PROJECTS.id
+)
+SELECT PROJECTS.name AS "name!", SUM(TIMERS.duration_ms) AS "duration!: TrackedDuration"
+FROM TIMERS
+JOIN ancestors ON ancestors.project_id = TIMERS.project_id AND ancestors.parent_id IS NULL
+JOIN PROJECTS ON PROJECTS.id = ancestors.id
+WHERE TIMERS.unique_id = ?1 AND TIMERS.is_current = 0
+    AND TIMERS.start_time_ms >= ?2 AND TIMERS.start_time_ms < ?3
+GROUP BY PROJECTS.id
+ORDER BY 2 DESC, PROJECTS.name
+            "#,
+            tag_id,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Sums up the finished timers of a tag started in `[from, to)` per label
+    ///
+    /// A timer counts towards every label it carries, so the totals can add
+    /// up to more than the time tracked, and unlabelled timers are left out.
+    /// Labels are ordered by the time spent on them, most first.
+    #[instrument(skip(self))]
+    pub async fn hours_by_label(&self, uid: &TagId, from: i64, to: i64) -> Result<Vec<LabelHours>> {
+        let tag_id = uid.as_ref();
+        let (from, to) = (StartTime::from_seconds(from), StartTime::from_seconds(to));
+        let result = sqlx::query_as!(
+            LabelHours,
+            r#"
+SELECT LABELS.name AS label, SUM(TIMERS.duration_ms) AS "duration!: TrackedDuration"
+FROM TIMERS
+JOIN TIMER_LABELS ON TIMER_LABELS.timer_id = TIMERS.id
+JOIN LABELS ON LABELS.id = TIMER_LABELS.label_id
+WHERE TIMERS.unique_id = ?1 AND TIMERS.is_current = 0
+    AND TIMERS.start_time_ms >= ?2 AND TIMERS.start_time_ms < ?3
+GROUP BY LABELS.id
+ORDER BY 2 DESC, LABELS.name
+            "#,
+            tag_id,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Summarizes the finished timers of a project
+    ///
+    /// Weekdays are those in `timezone` when each timer started, so timers
+    /// on either side of a daylight saving time change land on their own
+    /// local day. The time is summed up per [`TIME_BUCKET`] in the database,
+    /// and only the buckets are put on a weekday.
+    #[instrument(skip(self))]
+    pub async fn project_stats(&self, project_id: i64, timezone: Tz) -> Result<ProjectStats> {
+        let totals = sqlx::query!(
+            r#"
+SELECT
+    COUNT(*) AS "sessions!: i64",
+    COALESCE(SUM(duration_ms), 0) AS "total!: TrackedDuration",
+    CAST(COALESCE(AVG(duration_ms), 0) AS INTEGER) AS "average!: TrackedDuration",
+    COALESCE(MAX(duration_ms), 0) AS "longest!: TrackedDuration",
+    MIN(start_time_ms) AS "first_activity: StartTime",
+    MAX(start_time_ms + duration_ms) AS "last_activity: StartTime"
+FROM TIMERS
+WHERE project_id = ?1 AND is_current = 0
+            "#,
+            project_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        // Buckets never straddle a local midnight, so each lies on one weekday
+        let buckets = sqlx::query_as!(
+            TimeBucket,
+            r#"
+SELECT start_time_ms / ?2 * ?3 AS "start!: i64", SUM(duration_ms) AS "tracked!: TrackedDuration"
+FROM TIMERS
+WHERE project_id = ?1 AND is_current = 0
+GROUP BY start_time_ms / ?2
+            "#,
+            project_id,
+            TIME_BUCKET_MS,
+            TIME_BUCKET
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mut by_weekday = BTreeMap::new();
+        for bucket in buckets {
+            let Some(start) = timezone.timestamp_opt(bucket.start, 0).single() else {
+                continue;
+            };
+            *by_weekday
+                .entry(start.weekday().num_days_from_monday())
+                .or_insert_with(TrackedDuration::default) += bucket.tracked;
+        }
+        // Ties go to the earliest day of the week
+        let busiest_weekday = by_weekday
+            .into_iter()
+            .min_by_key(|&(day, tracked)| (Reverse(tracked), day))
+            .map(|(day, _)| Weekday::try_from(day as u8))
+            .transpose()?;
+
+        Ok(ProjectStats {
+            project_id,
+            sessions: totals.sessions,
+            total_seconds: totals.total.seconds(),
+            average_session_seconds: totals.average.seconds(),
+            longest_session_seconds: totals.longest.seconds(),
+            busiest_weekday,
+            first_activity: totals.first_activity.map(StartTime::seconds),
+            last_activity: totals.last_activity.map(StartTime::seconds),
+        })
+    }
+
+    /// Works out the streaks of a tag, of days with at least `minutes` tracked
+    ///
+    /// Days are those in `timezone`, like in [`DataStore::project_stats`], and
+    /// only finished timers count, on the day they started. The days are
+    /// folded from the tag's [`DataStore::time_buckets`].
+    #[instrument(skip(self))]
+    pub async fn streaks(
+        &self,
+        uid: &TagId,
+        minutes: i64,
+        timezone: Tz,
+        today: NaiveDate,
+    ) -> Result<Streaks> {
+        // Buckets never straddle a local midnight, so each lies on one day
+        let (first, last) = (
+            DateTime::<Utc>::MIN_UTC.timestamp(),
+            DateTime::<Utc>::MAX_UTC.timestamp(),
+        );
+        let buckets = self.time_buckets(uid, first, last).await?;
+        let mut by_day = BTreeMap::new();
+        for bucket in buckets {
+            let Some(start) = timezone.timestamp_opt(bucket.start, 0).single() else {
+                continue;
+            };
+            *by_day
+                .entry(start.date_naive())
+                .or_insert_with(TrackedDuration::default) += bucket.tracked;
+        }
+
+        // The last day and length of each run of consecutive days, oldest first
+        let enough = TrackedDuration::from_seconds(minutes * 60);
+        let mut runs: Vec<(NaiveDate, i64)> = Vec::new();
+        for (day, tracked) in by_day {
+            if tracked < enough {
+                continue;
+            }
+            match runs.last_mut() {
+                Some((last_day, days)) if *last_day + chrono::Duration::days(1) == day => {
+                    *last_day = day;
+                    *days += 1;
+                }
+                _ => runs.push((day, 1)),
+            }
+        }
+
+        let current = match runs.last() {
+            Some(&(last_day, days)) if last_day >= today - chrono::Duration::days(1) => days,
+            _ => 0,
+        };
+        let longest = runs.iter().map(|&(_, days)| days).max().unwrap_or(0);
+
+        Ok(Streaks {
+            minutes,
+            current,
+            longest,
+        })
+    }
+
+    /// Gets the tags with reminders set up which have no running timer
+    #[instrument(skip(self))]
+    pub async fn idle_tags(&self) -> Result<Vec<IdleTag>> {
+        let result = sqlx::query_as!(
+            IdleTag,
+            r#"
+SELECT
+    SETTINGS.unique_id,
+    SETTINGS.email,
+    SETTINGS.reminder_webhook,
+    SETTINGS.reminder_schedule AS "reminder_schedule!",
+    SETTINGS.reminder_after,
+    (
+        SELECT MAX(start_time_ms + duration_ms) FROM TIMERS
+        WHERE TIMERS.unique_id = SETTINGS.unique_id
+    ) AS "last_stopped: StartTime",
+    REMINDERS.reminded_at AS "reminded_at?"
+FROM SETTINGS
+LEFT JOIN REMINDERS ON REMINDERS.unique_id = SETTINGS.unique_id
+WHERE SETTINGS.reminder_schedule IS NOT NULL
+    AND (SETTINGS.email IS NOT NULL OR SETTINGS.reminder_webhook IS NOT NULL)
+    AND NOT EXISTS (
+        SELECT 1 FROM TIMERS
+        WHERE TIMERS.unique_id = SETTINGS.unique_id AND TIMERS.is_current = 1
+    )
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Records when a tag was last reminded to start a timer
+    #[instrument(skip(self))]
+    pub async fn mark_reminded(&self, uid: &TagId, reminded_at: i64) -> Result<()> {
+        let tag_id = uid.as_ref();
+        sqlx::query!(
+            r#"
+INSERT INTO REMINDERS (unique_id, reminded_at)
+VALUES (?1, ?2)
+ON CONFLICT (unique_id) DO UPDATE SET reminded_at = excluded.reminded_at
+            "#,
+            tag_id,
+            reminded_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the running timers of the tags which split them at midnight
+    #[instrument(skip(self))]
+    pub async fn midnight_timers(&self) -> Result<Vec<MidnightTimer>> {
+        let result = sqlx::query_as!(
+            MidnightTimer,
+            r#"
+SELECT
+    TIMERS.id AS timer_id,
+    TIMERS.unique_id,
+    TIMERS.start_time_ms AS "start_time_ms: StartTime",
+    SETTINGS.goal_timezone AS timezone
+FROM TIMERS
+JOIN SETTINGS ON SETTINGS.unique_id = TIMERS.unique_id
+WHERE TIMERS.is_current = 1 AND SETTINGS.split_at_midnight = 1
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Stops a running timer at `at` and starts the same timer again right then
+    ///
+    /// The new timer keeps the project, device, note and labels. Returns the
+    /// timer which is running now.
+    #[instrument(skip(self))]
+    pub async fn split_running_timer(&self, timer_id: i64, at: StartTime) -> Result<Timer> {
+        let mut transaction = self.pool.begin().await?;
+        let Some(mut timer) = sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE id = ?1
+            "#,
+            timer_id
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        else {
+            return Err(Error::NotFound(format!("No timer with id {}", timer_id)).into());
+        };
+        if !timer.is_current {
+            return Err(
+                Error::Conflict(format!("Timer {} isn't running anymore", timer_id)).into(),
+            );
+        }
+        if at <= timer.start_time_ms {
+            return Err(Error::Validation(format!(
+                "Timer {} can only be split after it started",
+                timer_id
+            ))
+            .into());
+        }
+
+        info!(timer_id, at = at.seconds(), "Splitting running timer");
+        timer.stop_at(at.0);
+        Self::update_timer(&mut transaction, timer.clone()).await?;
+        let start_time = at.seconds();
+        let start_time_ms = at.millis();
+        let running_id = sqlx::query!(
+            r#"
+INSERT INTO TIMERS (unique_id, is_current, start_time, project_id, note, device_id, start_time_ms)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            timer.unique_id,
+            IsCurrent::Yes as i64,
+            start_time,
+            timer.project_id,
+            timer.note,
+            timer.device_id,
+            start_time_ms
+        )
+        .execute(&mut *transaction)
+        .await?
+        .last_insert_rowid();
+        sqlx::query!(
+            r#"
+INSERT INTO TIMER_LABELS (timer_id, label_id)
+SELECT ?1, label_id FROM TIMER_LABELS WHERE timer_id = ?2
+            "#,
+            running_id,
+            timer_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        let running = sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE id = ?1
+            "#,
+            running_id
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        Ok(running)
+    }
+
+    /// Gets the running timers which have run too long and haven't been alerted yet
+    #[instrument(skip(self))]
+    pub async fn long_running_timers(&self, now: i64) -> Result<Vec<LongTimer>> {
+        let result = sqlx::query_as!(
+            LongTimer,
+            r#"
+SELECT
+    TIMERS.id AS timer_id,
+    TIMERS.unique_id,
+    PROJECTS.name AS project,
+    TIMERS.start_time_ms AS "start_time: StartTime",
+    SETTINGS.email,
+    SETTINGS.reminder_webhook
+FROM TIMERS
+JOIN PROJECTS ON PROJECTS.id = TIMERS.project_id
+JOIN SETTINGS ON SETTINGS.unique_id = TIMERS.unique_id
+WHERE TIMERS.is_current = 1
+    AND SETTINGS.long_timer_after IS NOT NULL
+    AND TIMERS.start_time_ms / 1000 + SETTINGS.long_timer_after * 60 <= ?1
+    AND TIMERS.id NOT IN (SELECT timer_id FROM LONG_TIMER_ALERTS)
+            "#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Sets the hour budget of a project, or removes it when `budget` is `None`
+    ///
+    /// Changing the budget re-arms its alerts.
+    #[instrument(skip(self))]
+    pub async fn set_project_budget(
+        &self,
+        uid: &TagId,
+        project_id: i64,
+        budget: Option<i64>,
+    ) -> Result<()> {
+        let tag_id = uid.as_ref();
+        self.owned_project(uid, project_id).await?;
+
+        match budget {
+            Some(budget) if budget <= 0 => {
+                return Err(Error::Validation("Budget must be positive".to_string()).into())
+            }
+            Some(budget) => {
+                info!(tag_id, project_id, budget, "Setting project budget");
+                sqlx::query!(
+                    r#"
+INSERT INTO PROJECT_BUDGETS (project_id, unique_id, budget)
+VALUES (?1, ?2, ?3)
+ON CONFLICT (project_id) DO UPDATE
+SET budget = excluded.budget, alerted = 0
+                    "#,
+                    project_id,
+                    tag_id,
+                    budget
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                info!(tag_id, project_id, "Removing project budget");
+                sqlx::query!(
+                    "DELETE FROM PROJECT_BUDGETS WHERE project_id = ?1",
+                    project_id
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the budgets of a tag's projects, keyed by project
+    #[instrument(skip(self))]
+    pub async fn project_budgets(
+        &self,
+        uid: &TagId,
+        now: i64,
+    ) -> Result<HashMap<i64, ProjectBudget>> {
+        let tag_id = uid.as_ref();
+        let now = StartTime::from_seconds(now);
+        let result = sqlx::query_as!(
+            ProjectBudget,
+            r#"
+SELECT
+    b.project_id AS "project_id!",
+    b.unique_id AS "unique_id!",
+    p.name AS "project!",
+    b.budget AS "budget!",
+    COALESCE(SUM(
+        CASE WHEN t.is_current = 1 THEN MAX(?2 - t.start_time_ms, 0) ELSE t.duration_ms END
+    ), 0) AS "consumed!: TrackedDuration",
+    b.alerted AS "alerted!",
+    s.email,
+    s.reminder_webhook
+FROM PROJECT_BUDGETS b
+JOIN PROJECTS p ON p.id = b.project_id
+LEFT JOIN TIMERS t ON t.project_id = b.project_id
+LEFT JOIN SETTINGS s ON s.unique_id = b.unique_id
+WHERE b.unique_id = ?1
+GROUP BY b.project_id
+            "#,
+            tag_id,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result
+            .into_iter()
+            .map(|budget| (budget.project_id, budget))
+            .collect())
+    }
+
+    /// Gets the budgets of every tag which reached a threshold they weren't alerted for
+    #[instrument(skip(self))]
+    pub async fn budgets_to_alert(&self, now: i64) -> Result<Vec<ProjectBudget>> {
+        let now = StartTime::from_seconds(now);
+        let result = sqlx::query_as!(
+            ProjectBudget,
+            r#"
+SELECT
+    b.project_id AS "project_id!",
+    b.unique_id AS "unique_id!",
+    p.name AS "project!",
+    b.budget AS "budget!",
+    COALESCE(SUM(
+        CASE WHEN t.is_current = 1 THEN MAX(?1 - t.start_time_ms, 0) ELSE t.duration_ms END
+    ), 0) AS "consumed!: TrackedDuration",
+    b.alerted AS "alerted!",
+    s.email,
+    s.reminder_webhook
+FROM PROJECT_BUDGETS b
+JOIN PROJECTS p ON p.id = b.project_id
+LEFT JOIN TIMERS t ON t.project_id = b.project_id
+LEFT JOIN SETTINGS s ON s.unique_id = b.unique_id
+GROUP BY b.project_id
+            "#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result
+            .into_iter()
+            .filter(|budget| budget.due_threshold().is_some())
+            .collect())
+    }
+
+    /// Records that the alert for reaching `threshold` percent of a budget was sent
+    #[instrument(skip(self))]
+    pub async fn mark_budget_alerted(&self, project_id: i64, threshold: i64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE PROJECT_BUDGETS SET alerted = ?2 WHERE project_id = ?1 AND alerted < ?2",
+            project_id,
+            threshold
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Schedules a project's export to be emailed to `email` and/or posted to `webhook`
+    #[instrument(skip(self))]
+    pub async fn create_scheduled_export(
+        &self,
+        uid: &TagId,
+        project_id: i64,
+        schedule: ExportSchedule,
+        timezone: &str,
+        (email, webhook): (Option<&str>, Option<&str>),
+    ) -> Result<ScheduledExport> {
+        let tag_id = uid.as_ref();
+        self.owned_project(uid, project_id).await?;
+        if email.is_none() && webhook.is_none() {
+            return Err(Error::Validation(
+                "Scheduled exports need an email address or a webhook".to_string(),
+            )
+            .into());
+        }
+
+        info!(tag_id, project_id, ?schedule, "Scheduling export");
+        let created_at = Utc::now().timestamp();
+        let result = sqlx::query_as!(
+            ScheduledExport,
+            r#"
+INSERT INTO SCHEDULED_EXPORTS (project_id, unique_id, schedule, timezone, email, webhook, created_at)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+RETURNING id, project_id, unique_id, schedule AS "schedule: ExportSchedule", timezone,
+    email, webhook, created_at, last_sent
+            "#,
+            project_id,
+            tag_id,
+            schedule,
+            timezone,
+            email,
+            webhook,
+            created_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Gets the scheduled exports of a tag's projects
+    #[instrument(skip(self))]
+    pub async fn scheduled_exports(&self, uid: &TagId) -> Result<Vec<ScheduledExport>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            ScheduledExport,
+            r#"
+SELECT id, project_id, unique_id, schedule AS "schedule: ExportSchedule", timezone,
+    email, webhook, created_at, last_sent
+FROM SCHEDULED_EXPORTS
+WHERE unique_id = ?1
+ORDER BY id
+            "#,
+            tag_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Gets the scheduled exports of every tag
+    #[instrument(skip(self))]
+    pub async fn all_scheduled_exports(&self) -> Result<Vec<ScheduledExport>> {
+        let result = sqlx::query_as!(
+            ScheduledExport,
+            r#"
+SELECT id, project_id, unique_id, schedule AS "schedule: ExportSchedule", timezone,
+    email, webhook, created_at, last_sent
+FROM SCHEDULED_EXPORTS
+ORDER BY id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Stops delivering a scheduled export
+    #[instrument(skip(self))]
+    pub async fn remove_scheduled_export(&self, uid: &TagId, id: i64) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query!(
+            "DELETE FROM SCHEDULED_EXPORTS WHERE id = ?1 AND unique_id = ?2",
+            id,
+            tag_id
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::NotFound(format!("No scheduled export {} for {}", id, uid)).into());
+        }
+
+        Ok(())
+    }
+
+    /// Records that the export of the period starting at `period_start` was delivered
+    #[instrument(skip(self))]
+    pub async fn mark_export_sent(&self, id: i64, period_start: i64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE SCHEDULED_EXPORTS SET last_sent = ?2 WHERE id = ?1",
+            id,
+            period_start
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The secret a tag's webhooks to `url` are signed with, if any
+    #[instrument(skip(self))]
+    pub async fn webhook_secret(&self, uid: &TagId, url: &str) -> Result<Option<String>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_scalar!(
+            "SELECT secret FROM WEBHOOK_SECRETS WHERE unique_id = ?1 AND url = ?2",
+            tag_id,
+            url
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Makes a new secret to sign a tag's webhooks to `url` with, replacing the previous one
+    ///
+    /// Webhooks to the URL are signed from then on.
+    #[instrument(skip(self))]
+    pub async fn rotate_webhook_secret(&self, uid: &TagId, url: &str) -> Result<String> {
+        let tag_id = uid.as_ref();
+        let secret = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let now = Utc::now().timestamp();
+        info!(tag_id, url, "Rotating webhook secret");
+        sqlx::query!(
+            r#"
+INSERT INTO WEBHOOK_SECRETS (unique_id, url, secret, created_at)
+VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT (unique_id, url) DO UPDATE
+SET secret = excluded.secret, created_at = excluded.created_at
+            "#,
+            tag_id,
+            url,
+            secret,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(secret)
+    }
+
+    /// Stops signing a tag's webhooks to `url`
+    #[instrument(skip(self))]
+    pub async fn remove_webhook_secret(&self, uid: &TagId, url: &str) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query!(
+            "DELETE FROM WEBHOOK_SECRETS WHERE unique_id = ?1 AND url = ?2",
+            tag_id,
+            url
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(
+                Error::NotFound(format!("No webhook secret for {} of {}", url, uid)).into(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Keeps a webhook delivery which kept failing for redelivery, ignoring its `id`
+    #[instrument(skip(self, webhook), fields(tag_id = webhook.unique_id, url = webhook.url))]
+    pub async fn record_failed_webhook(&self, webhook: &FailedWebhook) -> Result<i64> {
+        let id = sqlx::query_scalar!(
+            r#"
+INSERT INTO FAILED_WEBHOOKS (
+    unique_id, url, kind, content_type, filename, body, status, response, attempts,
+    failed_at, next_attempt_at
+)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+RETURNING id
+            "#,
+            webhook.unique_id,
+            webhook.url,
+            webhook.kind,
+            webhook.content_type,
+            webhook.filename,
+            webhook.body,
+            webhook.status,
+            webhook.response,
+            webhook.attempts,
+            webhook.failed_at,
+            webhook.next_attempt_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Gets every webhook delivery which kept failing, oldest first
+    #[instrument(skip(self))]
+    pub async fn failed_webhooks(&self) -> Result<Vec<FailedWebhook>> {
+        let result = sqlx::query_as!(
+            FailedWebhook,
+            r#"
+SELECT id, unique_id, url, kind, content_type, filename, body, status, response,
+    attempts, failed_at, next_attempt_at
+FROM FAILED_WEBHOOKS
+ORDER BY id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Gets a webhook delivery which kept failing
+    #[instrument(skip(self))]
+    pub async fn failed_webhook(&self, id: i64) -> Result<FailedWebhook> {
+        let result = sqlx::query_as!(
+            FailedWebhook,
+            r#"
+SELECT id, unique_id, url, kind, content_type, filename, body, status, response,
+    attempts, failed_at, next_attempt_at
+FROM FAILED_WEBHOOKS
+WHERE id = ?1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        result.ok_or_else(|| Error::NotFound(format!("No failed webhook {}", id)).into())
+    }
+
+    /// Gets the failed webhook deliveries which are due to be retried at `now`, oldest first
+    #[instrument(skip(self))]
+    pub async fn due_webhooks(&self, now: i64) -> Result<Vec<FailedWebhook>> {
+        let result = sqlx::query_as!(
+            FailedWebhook,
+            r#"
+SELECT id, unique_id, url, kind, content_type, filename, body, status, response,
+    attempts, failed_at, next_attempt_at
+FROM FAILED_WEBHOOKS
+WHERE next_attempt_at <= ?1
+ORDER BY id
+            "#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Records another failed attempt at redelivering a webhook
+    #[instrument(skip(self, response))]
+    pub async fn webhook_failed_again(
+        &self,
+        id: i64,
+        status: Option<i64>,
+        response: &str,
+        (failed_at, next_attempt_at): (i64, Option<i64>),
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+UPDATE FAILED_WEBHOOKS
+SET status = ?2, response = ?3, attempts = attempts + 1, failed_at = ?4,
+    next_attempt_at = ?5
+WHERE id = ?1
+            "#,
+            id,
+            status,
+            response,
+            failed_at,
+            next_attempt_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forgets a failed webhook delivery, once it was redelivered
+    #[instrument(skip(self))]
+    pub async fn remove_failed_webhook(&self, id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM FAILED_WEBHOOKS WHERE id = ?1", id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Connects a Google account to a tag, or updates the tokens of the connected one
+    ///
+    /// Reconnecting keeps the calendar and which timers were pushed already.
+    #[instrument(skip(self, refresh_token, access_token))]
+    pub async fn connect_google_account(
+        &self,
+        uid: &TagId,
+        refresh_token: &str,
+        access_token: &str,
+        expires_at: i64,
+    ) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let now = Utc::now().timestamp();
+        info!(tag_id, "Connecting Google account");
+        sqlx::query!(
+            r#"
+INSERT INTO GOOGLE_ACCOUNTS (unique_id, refresh_token, access_token, expires_at, created_at)
+VALUES (?1, ?2, ?3, ?4, ?5)
+ON CONFLICT (unique_id) DO UPDATE
+SET refresh_token = excluded.refresh_token,
+    access_token = excluded.access_token,
+    expires_at = excluded.expires_at
+            "#,
+            tag_id,
+            refresh_token,
+            access_token,
+            expires_at,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the Google account a tag connected, if any
+    #[instrument(skip(self))]
+    pub async fn google_account(&self, uid: &TagId) -> Result<Option<GoogleAccount>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            GoogleAccount,
+            "SELECT * FROM GOOGLE_ACCOUNTS WHERE unique_id = ?1",
+            tag_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Stores a refreshed access token of a tag's Google account
+    #[instrument(skip(self, access_token))]
+    pub async fn update_google_token(
+        &self,
+        uid: &TagId,
+        access_token: &str,
+        expires_at: i64,
+    ) -> Result<()> {
+        let tag_id = uid.as_ref();
+        sqlx::query!(
+            "UPDATE GOOGLE_ACCOUNTS SET access_token = ?2, expires_at = ?3 WHERE unique_id = ?1",
+            tag_id,
+            access_token,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stops pushing a tag's timers to Google Calendar
+    #[instrument(skip(self))]
+    pub async fn disconnect_google_account(&self, uid: &TagId) -> Result<()> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, "Disconnecting Google account");
+        sqlx::query!("DELETE FROM GOOGLE_ACCOUNTS WHERE unique_id = ?1", tag_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets the calendar a project's timers are pushed to, or goes back to
+    /// the account's calendar when `calendar_id` is `None`
+    #[instrument(skip(self))]
+    pub async fn set_google_calendar(
+        &self,
+        uid: &TagId,
+        project_id: i64,
+        calendar_id: Option<&str>,
+    ) -> Result<()> {
+        let tag_id = uid.as_ref();
+        self.owned_project(uid, project_id).await?;
+
+        match calendar_id.map(str::trim) {
+            Some("") => {
+                return Err(Error::Validation("The calendar ID can't be empty".to_string()).into())
+            }
+            Some(calendar_id) => {
+                sqlx::query!(
+                    r#"
+INSERT INTO GOOGLE_CALENDARS (project_id, unique_id, calendar_id)
+VALUES (?1, ?2, ?3)
+ON CONFLICT (project_id) DO UPDATE SET calendar_id = excluded.calendar_id
+                    "#,
+                    project_id,
+                    tag_id,
+                    calendar_id
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                sqlx::query!(
+                    "DELETE FROM GOOGLE_CALENDARS WHERE project_id = ?1",
+                    project_id
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets up to `limit` finished timers of connected tags which weren't
+    /// pushed to Google Calendar yet, oldest first
+    #[instrument(skip(self))]
+    pub async fn unsynced_google_timers(&self, limit: i64) -> Result<Vec<GoogleTimer>> {
+        let result = sqlx::query_as!(
+            GoogleTimer,
+            r#"
+SELECT
+    TIMERS.id AS timer_id,
+    TIMERS.unique_id,
+    PROJECTS.name AS project,
+    TIMERS.start_time_ms AS "start_time_ms: StartTime",
+    TIMERS.duration_ms AS "duration_ms: TrackedDuration",
+    TIMERS.note,
+    COALESCE(GOOGLE_CALENDARS.calendar_id, GOOGLE_ACCOUNTS.calendar_id) AS "calendar_id!: String"
+FROM GOOGLE_ACCOUNTS
+JOIN TIMERS ON TIMERS.unique_id = GOOGLE_ACCOUNTS.unique_id
+JOIN PROJECTS ON PROJECTS.id = TIMERS.project_id
+LEFT JOIN GOOGLE_CALENDARS ON GOOGLE_CALENDARS.project_id = TIMERS.project_id
+WHERE TIMERS.is_current = 0
+    AND TIMERS.start_time_ms + TIMERS.duration_ms >= GOOGLE_ACCOUNTS.created_at * 1000
+    AND TIMERS.id NOT IN (SELECT timer_id FROM GOOGLE_EVENTS)
+ORDER BY TIMERS.start_time_ms, TIMERS.id
+LIMIT ?1
+            "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Records the event a timer was pushed to Google Calendar as
+    #[instrument(skip(self))]
+    pub async fn mark_google_synced(&self, timer: &GoogleTimer, event_id: &str) -> Result<()> {
+        let now = Utc::now().timestamp();
+        sqlx::query!(
+            r#"
+INSERT OR IGNORE INTO GOOGLE_EVENTS (timer_id, unique_id, event_id, synced_at)
+VALUES (?1, ?2, ?3, ?4)
+            "#,
+            timer.timer_id,
+            timer.unique_id,
+            event_id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Connects a Jira site to a tag, or updates the credentials of the connected one
+    ///
+    /// Reconnecting keeps which timers were pushed already.
+    #[instrument(skip(self, api_token))]
+    pub async fn connect_jira_account(
+        &self,
+        uid: &TagId,
+        site: &str,
+        email: &str,
+        api_token: &str,
+    ) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let site = validate_jira_site(site)?;
+        let email = email.trim();
+        let api_token = api_token.trim();
+        if email.is_empty() || api_token.is_empty() {
+            return Err(Error::Validation(
+                "Jira needs both the email and the API token of an account".to_string(),
+            )
+            .into());
+        }
+
+        let now = Utc::now().timestamp();
+        info!(tag_id, site, "Connecting Jira site");
+        sqlx::query!(
+            r#"
+INSERT INTO JIRA_ACCOUNTS (unique_id, site, email, api_token, created_at)
+VALUES (?1, ?2, ?3, ?4, ?5)
+ON CONFLICT (unique_id) DO UPDATE
+SET site = excluded.site,
+    email = excluded.email,
+    api_token = excluded.api_token
+            "#,
+            tag_id,
+            site,
+            email,
+            api_token,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the Jira site a tag connected, if any
+    #[instrument(skip(self))]
+    pub async fn jira_account(&self, uid: &TagId) -> Result<Option<JiraAccount>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            JiraAccount,
+            "SELECT * FROM JIRA_ACCOUNTS WHERE unique_id = ?1",
+            tag_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Stops pushing a tag's timers to Jira
+    #[instrument(skip(self))]
+    pub async fn disconnect_jira_account(&self, uid: &TagId) -> Result<()> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, "Disconnecting Jira site");
+        sqlx::query!("DELETE FROM JIRA_ACCOUNTS WHERE unique_id = ?1", tag_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets the Jira issue a project's timers are logged on, or stops
+    /// logging them when `issue_key` is `None`
+    ///
+    /// Timers which ended before a project got its first issue aren't pushed,
+    /// changing the issue later only affects the timers still to be pushed.
+    #[instrument(skip(self))]
+    pub async fn set_jira_issue(
+        &self,
+        uid: &TagId,
+        project_id: i64,
+        issue_key: Option<&str>,
+    ) -> Result<()> {
+        let tag_id = uid.as_ref();
+        self.owned_project(uid, project_id).await?;
+
+        match issue_key.map(validate_issue_key).transpose()? {
+            Some(issue_key) => {
+                let now = Utc::now().timestamp();
+                sqlx::query!(
+                    r#"
+INSERT INTO JIRA_ISSUES (project_id, unique_id, issue_key, created_at)
+VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT (project_id) DO UPDATE SET issue_key = excluded.issue_key
+                    "#,
+                    project_id,
+                    tag_id,
+                    issue_key,
+                    now
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                sqlx::query!("DELETE FROM JIRA_ISSUES WHERE project_id = ?1", project_id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets up to `limit` finished timers of connected tags which weren't
+    /// logged in Jira yet, oldest first
+    ///
+    /// Only timers of projects with an issue are logged, and only those of at
+    /// least a minute since Jira refuses shorter worklogs.
+    #[instrument(skip(self))]
+    pub async fn unsynced_jira_timers(&self, limit: i64) -> Result<Vec<JiraTimer>> {
+        let result = sqlx::query_as!(
+            JiraTimer,
+            r#"
+SELECT
+    TIMERS.id AS timer_id,
+    TIMERS.unique_id,
+    JIRA_ISSUES.issue_key,
+    TIMERS.start_time_ms AS "start_time_ms: StartTime",
+    TIMERS.duration_ms AS "duration_ms: TrackedDuration",
+    TIMERS.note
+FROM JIRA_ACCOUNTS
+JOIN TIMERS ON TIMERS.unique_id = JIRA_ACCOUNTS.unique_id
+JOIN JIRA_ISSUES ON JIRA_ISSUES.project_id = TIMERS.project_id
+WHERE TIMERS.is_current = 0
+    AND TIMERS.duration_ms >= ?2 * 1000
+    AND TIMERS.start_time_ms + TIMERS.duration_ms
+        >= MAX(JIRA_ACCOUNTS.created_at, JIRA_ISSUES.created_at) * 1000
+    AND TIMERS.id NOT IN (SELECT timer_id FROM JIRA_WORKLOGS)
+ORDER BY TIMERS.start_time_ms, TIMERS.id
+LIMIT ?1
+            "#,
+            limit,
+            MIN_WORKLOG_SECONDS
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Records the worklog a timer was pushed to Jira as
+    #[instrument(skip(self))]
+    pub async fn mark_jira_synced(&self, timer: &JiraTimer, worklog_id: &str) -> Result<()> {
+        let now = Utc::now().timestamp();
+        sqlx::query!(
+            r#"
+INSERT OR IGNORE INTO JIRA_WORKLOGS (timer_id, unique_id, issue_key, worklog_id, synced_at)
+VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            timer.timer_id,
+            timer.unique_id,
+            timer.issue_key,
+            worklog_id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records that the alert for a long running timer was sent
+    #[instrument(skip(self))]
+    pub async fn mark_long_timer_alerted(&self, timer_id: i64, alerted_at: i64) -> Result<()> {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO LONG_TIMER_ALERTS (timer_id, alerted_at) VALUES (?1, ?2)",
+            timer_id,
+            alerted_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gets the tags with a daily goal
+    #[instrument(skip(self))]
+    pub async fn daily_goals(&self) -> Result<Vec<DailyGoal>> {
+        let result = sqlx::query_as!(
+            DailyGoal,
+            r#"
+SELECT
+    SETTINGS.unique_id,
+    SETTINGS.daily_goal AS "minutes!",
+    SETTINGS.goal_timezone AS timezone,
+    CASE WHEN SETTINGS.goal_webhook THEN SETTINGS.reminder_webhook END AS "webhook: String",
+    GOALS_REACHED.reached_at AS "reached_at?"
+FROM SETTINGS
+LEFT JOIN GOALS_REACHED ON GOALS_REACHED.unique_id = SETTINGS.unique_id
+WHERE SETTINGS.daily_goal IS NOT NULL
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Records when a tag reached its daily goal
+    #[instrument(skip(self))]
+    pub async fn mark_goal_reached(&self, uid: &TagId, reached_at: i64) -> Result<()> {
+        let tag_id = uid.as_ref();
+        sqlx::query!(
+            r#"
+INSERT INTO GOALS_REACHED (unique_id, reached_at)
+VALUES (?1, ?2)
+ON CONFLICT (unique_id) DO UPDATE SET reached_at = excluded.reached_at
+            "#,
+            tag_id,
+            reached_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// How much time a tag tracked in `[from, now)`, including its running timers
+    ///
+    /// Timers crossing `from` only count with the part after it.
+    #[instrument(skip(self))]
+    pub async fn tracked_since(
+        &self,
+        uid: &TagId,
+        from: i64,
+        now: DateTime<Utc>,
+    ) -> Result<TrackedDuration> {
+        let tag_id = uid.as_ref();
+        let from = StartTime::from_seconds(from);
+        let now = StartTime(now);
+        let tracked = sqlx::query_scalar!(
+            r#"
+SELECT COALESCE(SUM(MAX(
+    MIN(CASE WHEN is_current = 1 THEN ?3 ELSE start_time_ms + duration_ms END, ?3)
+        - MAX(start_time_ms, ?2),
+    0
+)), 0) AS "tracked!: TrackedDuration"
+FROM TIMERS
+WHERE unique_id = ?1 AND start_time_ms < ?3
+    AND (is_current = 1 OR start_time_ms + duration_ms > ?2)
+            "#,
+            tag_id,
+            from,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(tracked)
+    }
+
+    /// Sums up the finished timers of a tag started in `[from, to)` per [`TimeBucket`]
+    #[instrument(skip(self))]
+    pub async fn time_buckets(&self, uid: &TagId, from: i64, to: i64) -> Result<Vec<TimeBucket>> {
+        let tag_id = uid.as_ref();
+        let (from, to) = (StartTime::from_seconds(from), StartTime::from_seconds(to));
+        let result = sqlx::query_as!(
+            TimeBucket,
+            r#"
+SELECT start_time_ms / ?4 * ?5 AS "start!: i64", SUM(duration_ms) AS "tracked!: TrackedDuration"
+FROM TIMERS
+WHERE unique_id = ?1 AND is_current = 0 AND start_time_ms >= ?2 AND start_time_ms < ?3
+GROUP BY start_time_ms / ?4
+ORDER BY 1
+            "#,
+            tag_id,
+            from,
+            to,
+            TIME_BUCKET_MS,
+            TIME_BUCKET
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Adds a tag to a wall display group
+    #[instrument(skip(self))]
+    pub async fn add_group_member(&self, group_id: &str, uid: &TagId) -> Result<()> {
+        let tag_id = uid.as_ref();
+        sqlx::query!(
+            "INSERT OR IGNORE INTO GROUP_MEMBERS (group_id, unique_id) VALUES (?1, ?2)",
+            group_id,
+            tag_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the live status of every tag in a wall display group
+    #[instrument(skip(self))]
+    pub async fn group_status(&self, group_id: &str) -> Result<Vec<TagStatus>> {
+        info!(group_id, "Fetching group status");
+        let result = sqlx::query_as!(
+            TagStatus,
+            r#"
+SELECT
+    g.unique_id AS unique_id,
+    COALESCE(tp.name, cp.name) AS "project_name?: String",
+    t.start_time_ms / 1000 AS "start_time?: i64"
+FROM GROUP_MEMBERS g
+LEFT JOIN TIMERS t
+    ON t.unique_id = g.unique_id AND t.is_current = ?2
+LEFT JOIN PROJECTS tp
+    ON tp.id = t.project_id
+LEFT JOIN PROJECTS cp
+    ON cp.unique_id = g.unique_id AND cp.is_current = ?2
+WHERE g.group_id = ?1
+ORDER BY g.unique_id
+            "#,
+            group_id,
+            IsCurrent::Yes as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Gets the projects of several tags with their running timers and the
+    /// time tracked on them since `since`, in a single query
+    ///
+    /// Running timers count up to `now`. Every tag asked for gets a
+    /// dashboard, in the order they were given, also when it has no projects.
+    #[instrument(skip(self, tags), fields(count = tags.len()))]
+    pub async fn dashboard(
+        &self,
+        tags: &[TagId],
+        since: i64,
+        now: i64,
+    ) -> Result<Vec<TagDashboard>> {
+        let tag_ids = serde_json::to_string(&tags.iter().map(TagId::as_ref).collect::<Vec<_>>())?;
+        let (since, now) = (StartTime::from_seconds(since), StartTime::from_seconds(now));
+        let rows = sqlx::query!(
+            r#"
+SELECT
+    p.id AS "id!",
+    p.name,
+    p.unique_id,
+    p.is_current AS "is_current: bool",
+    p.created,
+    p.updated_at,
+    p.archived AS "archived: bool",
+    p.parent_id,
+    MIN(CASE WHEN t.is_current = 1 THEN t.start_time_ms END) AS "running_since?: StartTime",
+    COALESCE(SUM(
+        CASE
+            WHEN t.is_current = 1 THEN ?3 - MAX(t.start_time_ms, ?2)
+            WHEN t.start_time_ms >= ?2 THEN t.duration_ms
+            ELSE 0
+        END
+    ), 0) AS "tracked!: TrackedDuration"
+FROM PROJECTS p
+LEFT JOIN TIMERS t ON t.project_id = p.id
+WHERE p.unique_id IN (SELECT value FROM json_each(?1))
+GROUP BY p.id
+ORDER BY p.created DESC, p.id DESC
+            "#,
+            tag_ids,
+            since,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut dashboards: Vec<_> = tags
+            .iter()
+            .map(|uid| TagDashboard {
+                unique_id: uid.as_ref().to_string(),
+                projects: Vec::new(),
+            })
+            .collect();
+        for row in rows {
+            let Some(dashboard) = dashboards
+                .iter_mut()
+                .find(|dashboard| dashboard.unique_id == row.unique_id)
+            else {
+                continue;
+            };
+            dashboard.projects.push(DashboardProject {
+                project: Project {
+                    id: row.id,
+                    name: row.name,
+                    unique_id: row.unique_id,
+                    is_current: row.is_current,
+                    created: row.created,
+                    updated_at: row.updated_at,
+                    archived: row.archived,
+                    parent_id: row.parent_id,
+                },
+                running_since: row.running_since.map(StartTime::seconds),
+                tracked: row.tracked.seconds(),
+            });
+        }
+
+        Ok(dashboards)
+    }
+
+    /// Gets the timers of a tag grouped by the day they started on
+    ///
+    /// Each day is given as its date with the unix epochs of its start and
+    /// end, so days can follow the local midnights of a timezone. Every day
+    /// asked for is returned in order, also when nothing was tracked on it.
+    /// Running timers count up to `now`.
+    #[instrument(skip(self, days), fields(count = days.len()))]
+    pub async fn timers_by_day(
+        &self,
+        uid: &TagId,
+        days: &[(NaiveDate, i64, i64)],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<TimerDay>> {
+        let tag_id = uid.as_ref();
+        let now_ms = now.timestamp_millis();
+        let buckets = serde_json::to_string(
+            &days
+                .iter()
+                .map(|(date, start, end)| (date.to_string(), start, end))
+                .collect::<Vec<_>>(),
+        )?;
+        let rows = sqlx::query!(
+            r#"
+WITH days AS (
+    SELECT
+        json_extract(value, '$[0]') AS date,
+        json_extract(value, '$[1]') AS day_start,
+        json_extract(value, '$[2]') AS day_end
+    FROM json_each(?2)
+)
+SELECT
+    d.date AS "date!: String",
+    t.id AS "timer_id!: i64",
+    p.name AS "project!: String",
+    t.start_time_ms AS "start_time!: StartTime",
+    CASE WHEN t.is_current = 1 THEN MAX(?3 - t.start_time_ms, 0) ELSE t.duration_ms END
+        AS "duration!: TrackedDuration",
+    t.is_current AS "is_current!: bool",
+    t.note
+FROM days d
+JOIN TIMERS t
+    ON t.unique_id = ?1
+        AND t.start_time_ms >= d.day_start * 1000 AND t.start_time_ms < d.day_end * 1000
+JOIN PROJECTS p ON p.id = t.project_id
+ORDER BY t.start_time_ms, t.id
+            "#,
+            tag_id,
+            buckets,
+            now_ms
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut timer_days: Vec<_> = days
+            .iter()
+            .map(|(date, _, _)| TimerDay {
+                date: *date,
+                entries: Vec::new(),
+                total: TrackedDuration::default(),
+            })
+            .collect();
+        for row in rows {
+            let date = NaiveDate::from_str(&row.date)?;
+            let Some(day) = timer_days.iter_mut().find(|day| day.date == date) else {
+                continue;
+            };
+            day.total += row.duration;
+            day.entries.push(DayEntry {
+                timer_id: row.timer_id,
+                project: row.project,
+                start_time: row.start_time,
+                duration: row.duration,
+                is_current: row.is_current,
+                note: row.note,
+            });
+        }
+
+        Ok(timer_days)
+    }
+
+    /// Gets the last `limit` entries of a tag's activity log, newest first
+    #[instrument(skip(self))]
+    pub async fn activity(&self, uid: &TagId, limit: i64) -> Result<Vec<ActivityEntry>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            ActivityEntry,
+            r#"
+SELECT id AS "id!", kind AS "kind: ActivityKind", project, at, duration
+FROM ACTIVITY_LOG
+WHERE unique_id = ?1
+ORDER BY at DESC, id DESC
+LIMIT ?2
+            "#,
+            tag_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Returns the live status of a single tag
+    ///
+    /// With concurrent timers enabled, the status is that of the oldest running timer.
+    #[instrument(skip(self))]
+    pub async fn tag_status(&self, uid: &TagId) -> Result<TagStatus> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_as!(
+            TagStatus,
+            r#"
+SELECT
+    tag.unique_id AS "unique_id!: String",
+    COALESCE(tp.name, cp.name) AS "project_name?: String",
+    t.start_time_ms / 1000 AS "start_time?: i64"
+FROM (SELECT ?1 AS unique_id) tag
+LEFT JOIN TIMERS t
+    ON t.unique_id = tag.unique_id AND t.is_current = ?2
+LEFT JOIN PROJECTS tp
+    ON tp.id = t.project_id
+LEFT JOIN PROJECTS cp
+    ON cp.unique_id = tag.unique_id AND cp.is_current = ?2
+ORDER BY t.start_time_ms
+LIMIT 1
+            "#,
+            tag_id,
+            IsCurrent::Yes as i64
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_timer(&self, timer_id: i64) -> Result<Timer> {
+        self.find_timer(timer_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Timer {} not found", timer_id)).into())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn find_timer(&self, timer_id: i64) -> Result<Option<Timer>> {
+        Ok(sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE id = ?1"#,
+            timer_id,
+        )
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// Returns a map of projects->timers associated with given [`TagId`][crate::uid::TagId]
+    ///
+    /// Served from a cache while the tag's projects and timers are unchanged.
+    pub async fn projects_by_tag(&self, timer_tag: &TagId) -> Result<HashMap<Project, Vec<Timer>>> {
+        let tag = timer_tag.as_ref();
+        // Read before loading, so a change made meanwhile leaves the cached map stale
+        let version =
+            sqlx::query_scalar!("SELECT version FROM TAG_VERSIONS WHERE unique_id = ?1", tag)
+                .fetch_optional(&self.pool)
+                .await?
+                .unwrap_or_default();
+        if let Some(projects) = self.projects_cache.get(tag, version) {
+            debug!(tag, version, "Using cached project->timer map");
+            return Ok(projects);
+        }
+
+        let projects = self.load_projects_by_tag(tag).await?;
+        self.projects_cache.insert(tag, version, projects.clone());
+
+        Ok(projects)
+    }
+
+    async fn load_projects_by_tag(&self, tag: &str) -> Result<HashMap<Project, Vec<Timer>>> {
+        info!(tag, "Generating project->timer map");
+        struct JoinResult {
+            project_name: String,
+            project_id: i64,
+            created: i64,
+            project_updated_at: i64,
+            archived: bool,
+            parent_id: Option<i64>,
+            unique_id: String,
+            project_is_current: bool,
+            timer_id: Option<i64>,
+            timer_is_current: Option<bool>,
+            note: Option<String>,
+            link: Option<String>,
+            timer_created_at: Option<i64>,
+            timer_updated_at: Option<i64>,
+            device_id: Option<i64>,
+            invoiced: Option<bool>,
+            start_time_ms: Option<StartTime>,
+            duration_ms: Option<TrackedDuration>,
+        }
+
+        let result = sqlx::query_as!(
+            JoinResult,
+            r#"
+SELECT 
+    p.id AS project_id,
+    p.name AS project_name, 
+    p.created AS created,
+    p.updated_at AS project_updated_at,
+    p.archived AS archived,
+    p.parent_id AS parent_id,
+    p.unique_id AS unique_id, 
+    p.is_current AS project_is_current, 
+    t.id AS timer_id,
+    t.is_current AS timer_is_current, 
+    t.note AS note,
+    t.link AS link,
+    t.created_at AS timer_created_at,
+    t.updated_at AS timer_updated_at,
+    t.device_id AS device_id,
+    t.invoiced AS "invoiced: bool",
+    t.start_time_ms AS "start_time_ms: StartTime",
+    t.duration_ms AS "duration_ms: TrackedDuration"
+FROM projects p 
+LEFT JOIN timers t
+    ON p.id = t.project_id
+WHERE
+    p.unique_id = ?1
+ORDER BY p.created DESC;
+            "#,
+            tag
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut map = HashMap::new();
+
+        for row in result {
+            let project = Project {
+                name: row.project_name,
+                id: row.project_id,
+                is_current: row.project_is_current,
+                unique_id: row.unique_id.clone(),
+                created: row.created,
+                updated_at: row.project_updated_at,
+                archived: row.archived,
+                parent_id: row.parent_id,
+            };
+
+            // timer_id is the primary key in the timer table and cannot be 0
+            if let Some(timer_id) = row.timer_id.filter(|id| *id > 0) {
+                let timer = Timer {
+                    id: timer_id,
+                    unique_id: row.unique_id,
+                    project_id: project.id,
+                    is_current: row
+                        .timer_is_current
+                        .expect("Timer values should be present"),
+                    note: row.note,
+                    link: row.link,
+                    created_at: row
+                        .timer_created_at
+                        .expect("Timer values should be present"),
+                    updated_at: row
+                        .timer_updated_at
+                        .expect("Timer values should be present"),
+                    device_id: row.device_id,
+                    invoiced: row.invoiced.expect("Timer values should be present"),
+                    start_time_ms: row.start_time_ms.expect("Timer values should be present"),
+                    duration_ms: row.duration_ms.expect("Timer values should be present"),
+                };
+                (map.entry(project).or_insert_with(Vec::new)).push(timer)
+            } else {
+                // When a project doesn't have any timers
+                map.insert(project, vec![]);
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Gets the details of the devices which toggled the timers of a project, by device ID
+    #[instrument(skip(self))]
+    pub async fn devices_by_project(&self, project_id: i64) -> Result<HashMap<i64, String>> {
+        let rows = sqlx::query!(
+            r#"
+SELECT DISTINCT DEVICES.id, DEVICES.details
+FROM DEVICES
+JOIN TIMERS ON TIMERS.device_id = DEVICES.id
+WHERE TIMERS.project_id = ?1
+            "#,
+            project_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.id, row.details)).collect())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn exportable_timers_by_project(&self, project_id: &i64) -> Result<Vec<Timer>> {
+        info!(project_id, "Exporting timers");
+
+        let result = sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE project_id = ?1 AND is_current = ?2
+ORDER BY start_time_ms DESC
+            "#,
+            project_id,
+            IsCurrent::No as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Gets up to `limit` finished timers of a project, newest first, starting after `after`
+    ///
+    /// Pages are keyed by the start time and ID of the last timer on the
+    /// previous one, so they stay cheap however deep and don't shift when
+    /// timers are added meanwhile. Only the timers `filter` keeps are on them.
+    #[instrument(skip(self))]
+    pub async fn exportable_timers_page(
+        &self,
+        project_id: i64,
+        after: Option<TimerCursor>,
+        limit: u32,
+        filter: &ExportFilter,
+    ) -> Result<TimerPage> {
+        let after = after.unwrap_or(TimerCursor {
+            start_time_ms: i64::MAX,
+            id: i64::MAX,
+        });
+        // One more than asked for tells whether there's another page
+        let fetch = i64::from(limit) + 1;
+        let mut labels = filter.labels.clone();
+        labels.sort_unstable();
+        labels.dedup();
+        let label_count = labels.len() as i64;
+        let labels = serde_json::to_string(&labels)?;
+        let match_all = filter.label_match == LabelMatch::All;
+        // Timers carry any of the labels if one of them exists, and all of
+        // them if as many distinct ones do
+        let mut timers = sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE project_id = ?1 AND is_current = ?2 AND (start_time_ms, id) < (?3, ?4)
+    AND (?6 OR invoiced = 0)
+    AND (
+        ?8 = 0
+        OR (NOT ?9 AND EXISTS (
+            SELECT 1 FROM TIMER_LABELS
+            JOIN LABELS ON LABELS.id = TIMER_LABELS.label_id
+            WHERE TIMER_LABELS.timer_id = TIMERS.id
+                AND LABELS.name IN (SELECT value FROM json_each(?7))
+        ))
+        OR (?9 AND (
+            SELECT COUNT(DISTINCT LABELS.name) FROM TIMER_LABELS
+            JOIN LABELS ON LABELS.id = TIMER_LABELS.label_id
+            WHERE TIMER_LABELS.timer_id = TIMERS.id
+                AND LABELS.name IN (SELECT value FROM json_each(?7))
+        ) = ?8)
+    )
+ORDER BY start_time_ms DESC, id DESC
+LIMIT ?5
+            "#,
+            project_id,
+            IsCurrent::No as i64,
+            after.start_time_ms,
+            after.id,
+            fetch,
+            filter.invoiced,
+            labels,
+            label_count,
+            match_all
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let next = if timers.len() > limit as usize {
+            timers.truncate(limit as usize);
+            timers.last().map(TimerCursor::of)
+        } else {
+            None
+        };
+
+        Ok(TimerPage { timers, next })
+    }
+
+    /// Creates a new timer starting at `started_at`
+    ///
+    /// The timer belongs to `project_id` if given, otherwise to the current
+    /// project. Without a current project this fails with [`Error::NoProject`],
+    /// unless the tag opted into [`TagSettings::default_project`].
+    #[instrument(skip(conn))]
+    async fn create_timer(
+        conn: &mut SqliteConnection,
+        uid: &TagId,
+        project_id: Option<i64>,
+        device_id: Option<i64>,
+        started_at: StartTime,
+    ) -> Result<i64> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, "Creating a new timer");
+        let (start_time, start_time_ms) = (started_at.seconds(), started_at.millis());
+
+        let current_project = match project_id {
+            Some(project_id) => Self::trackable_project_in(conn, uid, project_id).await?,
+            None => match Self::current_project(conn, uid).await {
+                Ok(p) => p,
+                Err(_) if Self::settings_in(conn, uid).await?.default_project => {
+                    debug!(tag_id, "No current project found, creating a default");
+                    let _ = Self::create_project_in(conn, uid, "new-project").await?;
+                    Self::current_project(conn, uid).await?
+                }
+                Err(_) => return Err(no_project(uid)),
+            },
+        };
+
+        let id = sqlx::query!(
+            r#"
+INSERT INTO TIMERS (UNIQUE_ID, IS_CURRENT, START_TIME, PROJECT_ID, DEVICE_ID, START_TIME_MS)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            tag_id,
+            IsCurrent::Yes as i64,
+            start_time,
+            current_project.id,
+            device_id,
+            start_time_ms
+        )
+        .execute(&mut *conn)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    #[instrument(skip_all)]
+    async fn update_timer(conn: &mut SqliteConnection, timer: Timer) -> Result<bool> {
+        info!(timer = timer.id, "Updating timer");
+        let (_, duration) = timer.seconds();
+        let rows = sqlx::query!(
+            r#"
+UPDATE TIMERS
+SET is_current = ?1, duration = ?2, note = ?3, duration_ms = ?5
+WHERE id = ?4
+            "#,
+            IsCurrent::No as i64,
+            duration,
+            timer.note,
+            timer.id,
+            timer.duration_ms,
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+        Ok(rows == 1)
+    }
+
+    /// Records activity for the given [`TagId`][crate::uid::TagId]
+    ///
+    /// Any activity un-archives a previously archived tag.
+    #[instrument(skip(self))]
+    async fn touch_tag(&self, uid: &TagId) -> Result<()> {
+        Self::touch_tag_in(&mut *self.pool.acquire().await?, uid).await
+    }
+
+    async fn touch_tag_in(conn: &mut SqliteConnection, uid: &TagId) -> Result<()> {
+        let tag_id = uid.as_ref();
+        let now = Utc::now().timestamp();
+        sqlx::query!(
+            r#"
+INSERT INTO TAGS (unique_id, last_seen, archived)
+VALUES (?1, ?2, 0)
+ON CONFLICT (unique_id) DO UPDATE
+SET last_seen = excluded.last_seen, archived = 0
+            "#,
+            tag_id,
+            now
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Registers a tag, optionally giving it a human friendly alias
+    ///
+    /// Registering an already known tag updates its alias.
+    #[instrument(skip(self))]
+    pub async fn register_tag(&self, uid: &TagId, alias: Option<&str>) -> Result<()> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, alias, "Registering tag");
+        if let Some(alias) = alias {
+            validate_alias(alias)?;
+            let taken = sqlx::query_scalar!(
+                r#"
+SELECT EXISTS (
+    SELECT 1 FROM TAGS WHERE unique_id = ?1
+    UNION ALL
+    SELECT 1 FROM TAG_REDIRECTS WHERE legacy_id = ?1
+) AS "taken!: bool"
+                "#,
+                alias
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            if taken {
+                return Err(
+                    Error::Conflict(format!("Alias {:?} is the ID of another tag", alias)).into(),
+                );
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        sqlx::query!(
+            r#"
+INSERT INTO TAGS (unique_id, last_seen, archived, alias)
+VALUES (?1, ?2, 0, ?3)
+ON CONFLICT (unique_id) DO UPDATE
+SET alias = excluded.alias
+            "#,
+            tag_id,
+            now,
+            alias
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|err| -> anyhow::Error {
+            match err {
+                sqlx::Error::Database(err) if err.is_unique_violation() => {
+                    Error::Conflict(format!("Alias {:?} is already taken", alias)).into()
+                }
+                err => err.into(),
+            }
+        })?;
+
+        Ok(())
+    }
+
+    /// Resolves a tag from a URL, which is either an alias, a legacy
+    /// [`TagId`][crate::uid::TagId] or the [`TagId`][crate::uid::TagId] itself
+    ///
+    /// Real tag IDs win over legacy ones, which win over aliases, so an alias
+    /// can never take over another tag.
+    #[instrument(skip(self))]
+    pub async fn resolve_tag(&self, tag: &str) -> Result<TagId> {
+        let result = sqlx::query_scalar!(
+            r#"
+SELECT unique_id AS "unique_id!" FROM (
+    SELECT unique_id, 0 AS rank FROM TAGS WHERE unique_id = ?1
+    UNION ALL
+    SELECT unique_id, 1 AS rank FROM TAG_REDIRECTS WHERE legacy_id = ?1
+    UNION ALL
+    SELECT unique_id, 2 AS rank FROM TAGS WHERE alias = ?1
+)
+ORDER BY rank
+LIMIT 1
+            "#,
+            tag
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.unwrap_or_else(|| tag.to_string()).into())
+    }
+
+    /// Resolves a tag like [`DataStore::resolve_tag`], but fails if nothing is stored for it
+    #[instrument(skip(self))]
+    pub async fn find_tag(&self, tag: &str) -> Result<TagId> {
+        let uid = self.resolve_tag(tag).await?;
+        let tag_id = uid.as_ref();
+        let known = sqlx::query_scalar!(
+            r#"
+SELECT EXISTS (SELECT 1 FROM TAGS WHERE unique_id = ?1)
+    OR EXISTS (SELECT 1 FROM PROJECTS WHERE unique_id = ?1)
+    AS "known!: bool"
+            "#,
+            tag_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        if !known {
+            return Err(Error::NotFound(format!("There is no tag {}", tag)).into());
+        }
+
+        Ok(uid)
+    }
+
+    /// Moves all data stored under a legacy [`TagId`][crate::uid::TagId] to its stable replacement
+    ///
+    /// The old ID keeps working in URLs through the `TAG_REDIRECTS` table.
+    /// Does nothing if the legacy ID doesn't own any data.
+    #[instrument(skip(self))]
+    pub async fn migrate_legacy_tag(&self, legacy: &TagId, uid: &TagId) -> Result<()> {
+        let (legacy_id, tag_id) = (legacy.as_ref(), uid.as_ref());
+        let mut transaction = self.pool.begin().await?;
+
+        let owns_data = sqlx::query_scalar!(
+            r#"
+SELECT EXISTS (SELECT 1 FROM TAGS WHERE unique_id = ?1)
+    OR EXISTS (SELECT 1 FROM PROJECTS WHERE unique_id = ?1)
+    AS "owns_data!: bool"
+            "#,
+            legacy_id
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        if !owns_data {
+            return Ok(());
+        }
+
+        info!(legacy_id, tag_id, "Migrating legacy tag");
+
+        // Labels and devices are unique per tag, so legacy ones which the new
+        // ID already has are dropped below. Their timers move over first.
+        sqlx::query!(
+            r#"
+UPDATE OR IGNORE TIMER_LABELS SET label_id = (
+    SELECT kept.id FROM LABELS legacy
+    JOIN LABELS kept ON kept.name = legacy.name AND kept.unique_id = ?2
+    WHERE legacy.id = TIMER_LABELS.label_id
+)
+WHERE label_id IN (
+    SELECT legacy.id FROM LABELS legacy
+    JOIN LABELS kept ON kept.name = legacy.name AND kept.unique_id = ?2
+    WHERE legacy.unique_id = ?1
+)
+            "#,
+            legacy_id,
+            tag_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            r#"
+UPDATE TIMERS SET device_id = (
+    SELECT kept.id FROM DEVICES legacy
+    JOIN DEVICES kept ON kept.details = legacy.details AND kept.unique_id = ?2
+    WHERE legacy.id = TIMERS.device_id
+)
+WHERE device_id IN (
+    SELECT legacy.id FROM DEVICES legacy
+    JOIN DEVICES kept ON kept.details = legacy.details AND kept.unique_id = ?2
+    WHERE legacy.unique_id = ?1
+)
+            "#,
+            legacy_id,
+            tag_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        for table in TAG_TABLES {
+            // Tables keyed by the tag may already have a row for the new ID,
+            // in which case that row wins and the legacy one is dropped.
+            sqlx::query(&format!(
+                "UPDATE OR IGNORE {table} SET unique_id = ?2 WHERE unique_id = ?1"
+            ))
+            .bind(legacy_id)
+            .bind(tag_id)
+            .execute(&mut *transaction)
+            .await?;
+            sqlx::query(&format!("DELETE FROM {table} WHERE unique_id = ?1"))
+                .bind(legacy_id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        sqlx::query!(
+            "INSERT OR REPLACE INTO TAG_REDIRECTS (legacy_id, unique_id) VALUES (?1, ?2)",
+            legacy_id,
+            tag_id
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Gets the alias of a tag, if it has one
+    #[instrument(skip(self))]
+    pub async fn tag_alias(&self, uid: &TagId) -> Result<Option<String>> {
+        let tag_id = uid.as_ref();
+        let result = sqlx::query_scalar!("SELECT alias FROM TAGS WHERE unique_id = ?1", tag_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(result.flatten())
+    }
+
+    /// Returns all tags which haven't seen any activity in the last `days` days
+    #[instrument(skip(self))]
+    pub async fn inactive_tags(&self, days: i64) -> Result<Vec<TagActivity>> {
+        info!(days, "Fetching inactive tags");
+        let cutoff = days_before(Utc::now().timestamp(), days)?;
+        let result = sqlx::query_as!(
+            TagActivity,
+            r#"
+SELECT unique_id, last_seen, archived FROM TAGS
+WHERE last_seen < ?1
+ORDER BY last_seen ASC
+            "#,
+            cutoff
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Takes or renews the lease on a background task for the given holder
+    ///
+    /// Only one holder has the lease at a time, until it expires after `ttl`
+    /// seconds without being renewed. Returns whether `holder` has it now.
+    #[instrument(skip(self))]
+    pub async fn acquire_lease(
+        &self,
+        task: &str,
+        holder: &str,
+        now: i64,
+        ttl: i64,
+    ) -> Result<bool> {
+        let expires_at = now + ttl;
+        let acquired = sqlx::query!(
+            r#"
+INSERT INTO TASK_LEASES (task, holder, expires_at)
+VALUES (?1, ?2, ?3)
+ON CONFLICT (task) DO UPDATE
+SET holder = excluded.holder, expires_at = excluded.expires_at
+WHERE TASK_LEASES.holder = excluded.holder OR TASK_LEASES.expires_at <= ?4
+            "#,
+            task,
+            holder,
+            expires_at,
+            now
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            == 1;
+
+        Ok(acquired)
+    }
+
+    /// Archives all tags which haven't seen any activity in the last `days` days
+    ///
+    /// Returns the number of newly archived tags.
+    #[instrument(skip(self))]
+    pub async fn archive_inactive_tags(&self, days: i64) -> Result<u64> {
+        info!(days, "Archiving inactive tags");
+        let cutoff = days_before(Utc::now().timestamp(), days)?;
+        let rows = sqlx::query!(
+            r#"
+UPDATE TAGS
+SET archived = 1
+WHERE last_seen < ?1 AND archived = 0
+            "#,
+            cutoff
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows)
+    }
+
+    /// Gets the IDs of all archived tags
+    #[instrument(skip(self))]
+    pub async fn archived_tags(&self) -> Result<Vec<TagId>> {
+        let tags = sqlx::query_scalar!("SELECT unique_id FROM TAGS WHERE archived = 1")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(tags.into_iter().map(TagId::from).collect())
+    }
+
+    /// Permanently deletes all data stored for the given [`TagId`][crate::uid::TagId]
+    ///
+    /// Returns the number of deleted rows.
+    #[instrument(skip(self))]
+    pub async fn purge_tag(&self, uid: &TagId) -> Result<u64> {
+        info!(tag_id = uid.as_ref(), "Purging tag");
+        let mut transaction = self.pool.begin().await?;
+        let deleted = Self::purge_in(&mut transaction, uid).await?;
+
+        transaction.commit().await?;
+        Ok(deleted)
+    }
+
+    /// Issues the token a tag needs to confirm deleting its account with
+    ///
+    /// Asking again replaces the previous token.
+    #[instrument(skip(self))]
+    pub async fn request_account_deletion(&self, uid: &TagId, now: i64) -> Result<AccountDeletion> {
+        let tag_id = uid.as_ref();
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let expires_at = now + ACCOUNT_DELETION_TTL;
+        let result = sqlx::query_as!(
+            AccountDeletion,
+            r#"
+INSERT INTO ACCOUNT_DELETIONS (unique_id, token, expires_at)
+VALUES (?1, ?2, ?3)
+ON CONFLICT (unique_id) DO UPDATE
+SET token = excluded.token, expires_at = excluded.expires_at
+RETURNING token, expires_at
+            "#,
+            tag_id,
+            token,
+            expires_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Deletes all data of a tag, if `token` confirms that it asked for it
+    ///
+    /// Returns the number of deleted rows.
+    #[instrument(skip(self, token))]
+    pub async fn delete_account(&self, uid: &TagId, token: &str, now: i64) -> Result<u64> {
+        let tag_id = uid.as_ref();
+        let mut transaction = self.pool.begin().await?;
+        let confirmed = sqlx::query_scalar!(
+            r#"
+SELECT 1 AS "confirmed!: bool" FROM ACCOUNT_DELETIONS
+WHERE unique_id = ?1 AND token = ?2 AND expires_at > ?3
+            "#,
+            tag_id,
+            token,
+            now
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if confirmed.is_none() {
+            return Err(Error::Forbidden(
+                "The confirmation token is invalid or has expired".to_string(),
+            )
+            .into());
+        }
+
+        info!(tag_id, "Deleting account");
+        let deleted = Self::purge_in(&mut transaction, uid).await?;
+
+        transaction.commit().await?;
+        Ok(deleted)
+    }
+
+    async fn purge_in(conn: &mut SqliteConnection, uid: &TagId) -> Result<u64> {
+        let tag_id = uid.as_ref();
+        let mut deleted = 0;
+
+        // Dependent tables come last in TAG_TABLES, so delete from them first
+        for table in TAG_TABLES.iter().rev() {
+            deleted += sqlx::query(&format!("DELETE FROM {table} WHERE unique_id = ?1"))
+                .bind(tag_id)
+                .execute(&mut *conn)
+                .await?
+                .rows_affected();
+        }
+
+        deleted += sqlx::query!("DELETE FROM TAG_REDIRECTS WHERE unique_id = ?1", tag_id)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+
+        Ok(deleted)
+    }
+
+    /// Writes a consistent copy of the whole database to `path`
+    ///
+    /// Uses `VACUUM INTO`, so the copy is compacted and toggles can continue
+    /// while it's being written.
+    #[instrument(skip(self))]
+    pub async fn snapshot(&self, path: &Path) -> Result<()> {
+        let path = path
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("Snapshot path must be valid UTF-8"))?;
+        sqlx::query("VACUUM INTO ?1")
+            .bind(path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    async fn set_last_seen(&self, uid: &TagId, last_seen: i64) -> Result<()> {
+        let tag_id = uid.as_ref();
+        sqlx::query!(
+            "UPDATE TAGS SET last_seen = ?1 WHERE unique_id = ?2",
+            last_seen,
+            tag_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[instrument(skip(conn))]
+    async fn delete_timer(conn: &mut SqliteConnection, timer_id: i64) -> Result<()> {
+        info!(timer_id, "Deleting timer");
+        sqlx::query!("DELETE FROM TIMERS WHERE id = ?1", timer_id)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Gets the finished timer of the same tag and project which started right
+    /// before `timer`
+    ///
+    /// Other members track their own timers on team projects, which are left alone.
+    #[instrument(skip_all)]
+    async fn previous_timer(conn: &mut SqliteConnection, timer: &Timer) -> Result<Option<Timer>> {
+        let result = sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE project_id = ?1 AND unique_id = ?5 AND is_current = ?2 AND start_time_ms <= ?3
+    AND id != ?4
+ORDER BY start_time_ms DESC, id DESC
+LIMIT 1
+            "#,
+            timer.project_id,
+            IsCurrent::No as i64,
+            timer.start_time_ms,
+            timer.id,
+            timer.unique_id
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        Ok(result)
+    }
+
+    #[instrument(skip(conn))]
+    async fn current_timer(conn: &mut SqliteConnection, uid: &TagId) -> anyhow::Result<Timer> {
+        let tag_id = uid.as_ref();
+        info!(tag_id, "Fetching current timer");
+        Ok(sqlx::query_as!(
+            Timer,
+            r#"
+SELECT
+    id, unique_id, project_id, is_current, note, link, created_at, updated_at,
+    device_id, invoiced, start_time_ms, duration_ms
+FROM TIMERS
+WHERE unique_id = ?1 AND is_current = ?2"#,
+            tag_id,
+            IsCurrent::Yes as i64
+        )
+        .fetch_one(&mut *conn)
+        .await?)
+    }
+}
+
+/// The core of tracking time: the projects of a tag and the timers toggled on them
+///
+/// Implemented by the SQLite backed [`DataStore`] and by
+/// [`MemoryStore`][crate::memory_store::MemoryStore], so code which only
+/// tracks time can run against either.
+#[async_trait]
+pub trait TimerStore: std::fmt::Debug + Send + Sync {
+    /// Resolves an alias or legacy ID to the [`TagId`] it stands for
+    async fn resolve_tag(&self, tag: &str) -> Result<TagId>;
+
+    /// Resolves a tag like [`TimerStore::resolve_tag`], but fails if nothing is stored for it
+    async fn find_tag(&self, tag: &str) -> Result<TagId>;
+
+    /// Creates a project and makes it the current project of the tag
+    ///
+    /// Fails with a validation error for an empty or overly long name, and with a conflict
+    /// if another active project of the tag has the same name.
+    async fn create_project(&self, uid: &TagId, project_name: &str) -> Result<i64>;
+
+    /// Gets a project by its ID
+    async fn project(&self, project_id: i64) -> Result<Project>;
+
+    /// Gets every project of a tag along with its timers
+    async fn projects_by_tag(&self, uid: &TagId) -> Result<HashMap<Project, Vec<Timer>>>;
+
+    /// Registers the device toggling timers for a tag, or updates when it was last seen
+    async fn identify_device(&self, uid: &TagId, details: &str) -> Result<Device>;
+
+    /// Stops the running timer of a tag, or starts one. Returns the ID of the timer.
+    async fn toggle(&self, uid: &TagId, options: &ToggleOptions) -> Result<i64>;
+
+    /// Gets a timer by its ID, if it exists
+    async fn find_timer(&self, timer_id: i64) -> Result<Option<Timer>>;
+
+    /// Gets the finished timers of a project, most recent first
+    async fn exportable_timers_by_project(&self, project_id: &i64) -> Result<Vec<Timer>>;
+}
+
+#[async_trait]
+impl TimerStore for DataStore {
+    async fn resolve_tag(&self, tag: &str) -> Result<TagId> {
+        DataStore::resolve_tag(self, tag).await
+    }
+
+    async fn find_tag(&self, tag: &str) -> Result<TagId> {
+        DataStore::find_tag(self, tag).await
+    }
+
+    async fn create_project(&self, uid: &TagId, project_name: &str) -> Result<i64> {
+        DataStore::create_project(self, uid, project_name).await
+    }
+
+    async fn project(&self, project_id: i64) -> Result<Project> {
+        DataStore::project(self, project_id).await
+    }
+
+    async fn projects_by_tag(&self, uid: &TagId) -> Result<HashMap<Project, Vec<Timer>>> {
+        DataStore::projects_by_tag(self, uid).await
+    }
+
+    async fn identify_device(&self, uid: &TagId, details: &str) -> Result<Device> {
+        DataStore::identify_device(self, uid, details).await
+    }
+
+    async fn toggle(&self, uid: &TagId, options: &ToggleOptions) -> Result<i64> {
+        DataStore::toggle(self, uid, options).await
+    }
+
+    async fn find_timer(&self, timer_id: i64) -> Result<Option<Timer>> {
+        DataStore::find_timer(self, timer_id).await
+    }
+
+    async fn exportable_timers_by_project(&self, project_id: &i64) -> Result<Vec<Timer>> {
+        DataStore::exportable_timers_by_project(self, project_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::time::in_seconds;
+    use pretty_assertions::assert_eq;
+    use tracing_test::traced_test;
+
+    async fn setup() -> Result<DataStore> {
+        DataStore::in_memory().await
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn toggle_create_when_timer_does_not_exist() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+
+        let result = store.toggle_current(&uid).await.unwrap();
+
+        assert_eq!(result, 1);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn toggle_end_timer_when_current_already_exists() {
+        let uid = TagId::new("test-tag").unwrap();
+        let store = setup().await.unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+
+        let result = store.toggle_current(&uid).await.unwrap();
+        assert_eq!(result, 1);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let result = store.toggle_current(&uid).await.unwrap();
+        assert_eq!(result, 1);
+
+        let timer = store.get_timer(result).await.unwrap();
+
+        assert!(!timer.is_current, "Timer hasn't been turned off");
+
+        assert!(timer.duration_ms.seconds() >= 2);
+        assert!(!timer.is_current)
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn toggle_timer_creates_new_current_timer() {
+        let uid = TagId::new("test-tag").unwrap();
+        let store = setup().await.unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+
+        let result = store.toggle_current(&uid).await.unwrap();
+        assert_eq!(result, 1);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+
+        let result = store.toggle_current(&uid).await.unwrap();
+        assert_eq!(result, 1);
+
+        let timer = store.get_timer(result).await.unwrap();
+
+        assert!(!timer.is_current, "Timer hasn't been turned off");
+
+        assert!(timer.duration_ms.seconds() >= 2);
+        assert!(!timer.is_current);
+
+        let timer_id = store.toggle_current(&uid).await.unwrap();
+        assert_eq!(timer_id, 2);
+    }
+
+    #[tokio::test]
+    async fn timers_need_a_project_unless_opted_in() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+
+        let err = store.toggle_current(&uid).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::NoProject(_))));
+        assert!(store.projects_by_tag(&uid).await.unwrap().is_empty());
+
+        let mut settings = store.settings(&uid).await.unwrap();
+        settings.default_project = true;
+        store.update_settings(&settings).await.unwrap();
+
+        let timer_id = store.toggle_current(&uid).await.unwrap();
+        let timer = store.get_timer(timer_id).await.unwrap();
+        assert_eq!(
+            store.project(timer.project_id).await.unwrap().name,
+            "new-project"
+        );
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn projects_by_tag_success() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        let current_project =
+            DataStore::current_project(&mut store.pool.acquire().await.unwrap(), &uid)
+                .await
+                .unwrap();
+
+        for _ in 0..20 {
+            store.toggle_current(&uid).await.unwrap();
+            store.toggle_current(&uid).await.unwrap();
+        }
+
+        let timers = store.projects_by_tag(&uid).await.unwrap();
+        assert_eq!(timers.get(&current_project).unwrap().len(), 20);
+    }
+
+    #[tokio::test]
+    async fn project_names_are_validated() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+
+        let project_id = store.create_project(&uid, "  writing ").await.unwrap();
+        assert_eq!(store.project(project_id).await.unwrap().name, "writing");
+
+        for name in ["", "   ", &"x".repeat(81)] {
+            let err = store.create_project(&uid, name).await.unwrap_err();
+            assert!(matches!(err.downcast_ref(), Some(Error::Validation(_))));
+        }
+        let err = store.create_project(&uid, "Writing").await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Conflict(_))));
+
+        // Names are only unique among the active projects of a tag
+        store.create_project(&other, "writing").await.unwrap();
+        store
+            .set_project_archived(&uid, project_id, true)
+            .await
+            .unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn get_exportable_timers_by_tag_returns_only_complete_timers() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+
+        for _ in 0..20 {
+            store.toggle_current(&uid).await.unwrap();
+            store.toggle_current(&uid).await.unwrap();
+        }
+
+        store.toggle_current(&uid).await.unwrap();
+
+        let timers = store.exportable_timers_by_project(&1).await.unwrap();
+
+        assert_eq!(timers.len(), 20);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn inactive_tags_are_reported_and_archived() {
+        let store = setup().await.unwrap();
+        let active = TagId::new("active-tag").unwrap();
+        let inactive = TagId::new("inactive-tag").unwrap();
+        store.create_project(&active, "test-project").await.unwrap();
+        store
+            .create_project(&inactive, "test-project")
+            .await
+            .unwrap();
+
+        let long_ago = Utc::now().timestamp() - 45 * SECONDS_PER_DAY;
+        store.set_last_seen(&inactive, long_ago).await.unwrap();
+
+        let tags = store.inactive_tags(30).await.unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].unique_id, inactive.as_ref());
+        assert!(!tags[0].archived);
+
+        assert_eq!(store.archive_inactive_tags(30).await.unwrap(), 1);
+        assert_eq!(store.archive_inactive_tags(30).await.unwrap(), 0);
+        assert!(store.inactive_tags(30).await.unwrap()[0].archived);
+
+        // Any activity brings the tag back
+        store.toggle_current(&inactive).await.unwrap();
+        assert!(store.inactive_tags(30).await.unwrap().is_empty());
+
+        let err = store.inactive_tags(i64::MAX).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Validation(_))));
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn short_timers_are_discarded() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                short_timer_threshold: 30,
+                short_timer_policy: ShortTimerPolicy::Discard,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        store.toggle_current(&uid).await.unwrap();
+        let timer_id = store.toggle_current(&uid).await.unwrap();
+
+        assert!(store.get_timer(timer_id).await.is_err());
+        assert!(store
+            .exportable_timers_by_project(&1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn short_timers_are_merged_into_previous() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+
+        let first = store.toggle_current(&uid).await.unwrap();
+        store.toggle_current(&uid).await.unwrap();
+
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                short_timer_threshold: 30,
+                short_timer_policy: ShortTimerPolicy::Merge,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        store.toggle_current(&uid).await.unwrap();
+        let merged = store.toggle_current(&uid).await.unwrap();
+
+        assert_eq!(merged, first);
+        assert_eq!(
+            store.exportable_timers_by_project(&1).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn short_timers_are_not_merged_across_a_long_gap() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        let friday = Utc::now().timestamp() - 3 * SECONDS_PER_DAY;
+        store
+            .import_timers(&project, [(friday, 3600)].map(in_seconds))
+            .await
+            .unwrap();
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                short_timer_threshold: 30,
+                short_timer_policy: ShortTimerPolicy::Merge,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // A tap days later doesn't stretch the old timer over the time in between
+        store.toggle_current(&uid).await.unwrap();
+        store.toggle_current(&uid).await.unwrap();
+
+        let timers = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap();
+        assert_eq!(timers.len(), 2);
+        assert_eq!(timers[1].duration_ms.seconds(), 3600);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn short_timers_are_not_merged_into_other_members_timers() {
+        let store = setup().await.unwrap();
+        let owner = TagId::new("owner-tag").unwrap();
+        let member = TagId::new("member-tag").unwrap();
+        let team = store.create_team(&owner, "Agency").await.unwrap();
+        store
+            .set_team_member(&owner, team.id, &member, Role::Member)
+            .await
+            .unwrap();
+        let project_id = store
+            .create_team_project(&owner, team.id, "Client work")
+            .await
+            .unwrap();
+        let options = ToggleOptions {
+            project_id: Some(project_id),
+            ..Default::default()
+        };
+        store.toggle(&member, &options).await.unwrap();
+        let members_timer = store.toggle(&member, &options).await.unwrap();
+
+        store
+            .update_settings(&TagSettings {
+                unique_id: owner.as_ref().to_string(),
+                short_timer_threshold: 30,
+                short_timer_policy: ShortTimerPolicy::Merge,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store.toggle(&owner, &options).await.unwrap();
+        let owners_timer = store.toggle(&owner, &options).await.unwrap();
+
+        assert_ne!(owners_timer, members_timer);
+        let timers = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap();
+        assert_eq!(timers.len(), 2);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn import_timers_creates_finished_timers() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+
+        let imported = store
+            .import_timers(&project, [(1000, 60), (2000, 120)].map(in_seconds))
+            .await
+            .unwrap();
+        assert_eq!(imported, 2);
+
+        let timers = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap();
+        assert_eq!(timers.len(), 2);
+        assert_eq!(timers[0].end_time(), 2120);
+        assert!(
+            DataStore::current_timer(&mut store.pool.acquire().await.unwrap(), &uid)
+                .await
+                .is_err()
+        );
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn apply_preset_creates_finished_timer() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        let preset_id = store
+            .create_preset(&uid, project_id, "standup", 15 * 60, Some("daily standup"))
+            .await
+            .unwrap();
+
+        let timer_id = store.apply_preset(&uid, preset_id).await.unwrap();
+        let timer = store.get_timer(timer_id).await.unwrap();
+
+        assert!(!timer.is_current);
+        assert_eq!(timer.project_id, project_id);
+        assert_eq!(timer.duration_ms.seconds(), 15 * 60);
+        assert_eq!(timer.note.as_deref(), Some("daily standup"));
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn presets_are_scoped_to_their_tag() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+
+        assert!(store
+            .create_preset(&other, project_id, "standup", 60, None)
+            .await
+            .is_err());
+
+        let preset_id = store
+            .create_preset(&uid, project_id, "standup", 60, None)
+            .await
+            .unwrap();
+        assert!(store.apply_preset(&other, preset_id).await.is_err());
+        assert_eq!(store.presets(&uid).await.unwrap().len(), 1);
+        assert!(store.presets(&other).await.unwrap().is_empty());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn group_status_reports_running_and_idle_tags() {
+        let store = setup().await.unwrap();
+        let running = TagId::new("running-tag").unwrap();
+        let idle = TagId::new("idle-tag").unwrap();
+        store
+            .create_project(&running, "running-project")
+            .await
+            .unwrap();
+        store.create_project(&idle, "idle-project").await.unwrap();
+        store.add_group_member("studio", &running).await.unwrap();
+        store.add_group_member("studio", &idle).await.unwrap();
+        store.add_group_member("studio", &idle).await.unwrap();
+
+        store.toggle_current(&running).await.unwrap();
+
+        let status = store.group_status("studio").await.unwrap();
+        assert_eq!(status.len(), 2);
+        for tag in status {
+            if tag.unique_id == running.as_ref() {
+                assert_eq!(tag.project_name.as_deref(), Some("running-project"));
+                assert!(tag.start_time.is_some());
+            } else {
+                assert_eq!(tag.project_name.as_deref(), Some("idle-project"));
+                assert!(tag.start_time.is_none());
+            }
+        }
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn registered_aliases_resolve_to_tag() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+
+        store.register_tag(&uid, Some("workshop")).await.unwrap();
+        assert_eq!(
+            store.resolve_tag("workshop").await.unwrap().as_ref(),
+            uid.as_ref()
+        );
+        assert_eq!(
+            store.resolve_tag(uid.as_ref()).await.unwrap().as_ref(),
+            uid.as_ref()
+        );
+        assert_eq!(
+            store.tag_alias(&uid).await.unwrap().as_deref(),
+            Some("workshop")
+        );
+
+        assert!(store.register_tag(&other, Some("workshop")).await.is_err());
+        assert!(store
+            .register_tag(&other, Some("not a slug"))
+            .await
+            .is_err());
+        assert!(store.register_tag(&other, Some("toggle")).await.is_err());
+        store.register_tag(&other, None).await.unwrap();
+        assert_eq!(store.tag_alias(&other).await.unwrap(), None);
+
+        // Aliases can't be, or look like, the ID of another tag
+        assert!(store
+            .register_tag(&uid, Some(other.as_ref()))
+            .await
+            .is_err());
+        let unused = TagId::new("unused-tag").unwrap();
+        assert!(store
+            .register_tag(&uid, Some(unused.as_ref()))
+            .await
+            .is_err());
+
+        // Even an alias stored before that was checked doesn't take over the tag
+        sqlx::query("UPDATE TAGS SET alias = ?1 WHERE unique_id = ?2")
+            .bind(other.as_ref())
+            .bind(uid.as_ref())
+            .execute(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            store.resolve_tag(other.as_ref()).await.unwrap().as_ref(),
+            other.as_ref()
+        );
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn legacy_tags_are_migrated_and_redirected() {
+        let store = setup().await.unwrap();
+        let legacy = TagId::legacy("test-tag");
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&legacy, "test-project").await.unwrap();
+        store.toggle_current(&legacy).await.unwrap();
+
+        store.migrate_legacy_tag(&legacy, &uid).await.unwrap();
+
+        assert!(
+            DataStore::current_timer(&mut store.pool.acquire().await.unwrap(), &uid)
+                .await
+                .is_ok()
+        );
+        assert!(store.projects_by_tag(&legacy).await.unwrap().is_empty());
+        assert_eq!(store.projects_by_tag(&uid).await.unwrap().len(), 1);
+        let resolved = store.resolve_tag(legacy.as_ref()).await.unwrap();
+        assert_eq!(resolved.as_ref(), uid.as_ref());
+
+        // Migrating again is a no-op
+        store.migrate_legacy_tag(&legacy, &uid).await.unwrap();
+        assert_eq!(store.projects_by_tag(&uid).await.unwrap().len(), 1);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn legacy_labels_and_devices_merge_into_the_new_tag() {
+        let store = setup().await.unwrap();
+        let legacy = TagId::legacy("test-tag");
+        let uid = TagId::new("test-tag").unwrap();
+        for tag in [&legacy, &uid] {
+            store.create_project(tag, "test-project").await.unwrap();
+        }
+
+        // Both IDs have the same label and reader, each with their own rows
+        let mut timer_ids = Vec::new();
+        for tag in [&legacy, &uid] {
+            let device = store.identify_device(tag, "reader").await.unwrap();
+            let options = ToggleOptions {
+                device: Some(device),
+                ..Default::default()
+            };
+            let timer_id = store.toggle(tag, &options).await.unwrap();
+            store.toggle(tag, &options).await.unwrap();
+            store
+                .add_timer_label(tag, timer_id, "deep work")
+                .await
+                .unwrap();
+            timer_ids.push(timer_id);
+        }
+        let device = store.identify_device(&uid, "reader").await.unwrap();
+
+        store.migrate_legacy_tag(&legacy, &uid).await.unwrap();
+
+        let labels = store.labels_by_tag(&uid).await.unwrap();
+        let devices = store.devices(&uid).await.unwrap();
+        assert_eq!(devices.len(), 1);
+        for timer_id in timer_ids {
+            assert_eq!(labels[&timer_id], vec!["deep work".to_string()]);
+            let timer = store.get_timer(timer_id).await.unwrap();
+            assert_eq!(timer.device_id, Some(device.id));
+        }
+        assert!(store.labels_by_tag(&legacy).await.unwrap().is_empty());
+        assert!(store.devices(&legacy).await.unwrap().is_empty());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn entities_record_creation_and_modification() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let before = Utc::now().timestamp();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+
+        let project = store.project(project_id).await.unwrap();
+        assert!(project.updated_at >= before);
+
+        let timer_id = store.toggle_current(&uid).await.unwrap();
+        let timer = store.get_timer(timer_id).await.unwrap();
+        assert!(timer.created_at >= before);
+        assert!(timer.updated_at >= timer.created_at);
+
+        store.toggle_current(&uid).await.unwrap();
+        let stopped = store.get_timer(timer_id).await.unwrap();
+        assert_eq!(stopped.created_at, timer.created_at);
+        assert!(stopped.updated_at >= timer.updated_at);
+
+        assert_eq!(store.settings(&uid).await.unwrap().created_at, 0);
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let settings = store.settings(&uid).await.unwrap();
+        assert!(settings.created_at >= before);
+        assert!(settings.updated_at >= settings.created_at);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn timers_remember_their_device() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+
+        let device = store.identify_device(&uid, "reader").await.unwrap();
+        let same = store.identify_device(&uid, "reader").await.unwrap();
+        assert_eq!(device.id, same.id);
+
+        let options = ToggleOptions {
+            device: Some(device),
+            ..Default::default()
+        };
+        let timer_id = store.toggle(&uid, &options).await.unwrap();
+        assert_eq!(
+            store.get_timer(timer_id).await.unwrap().device_id,
+            Some(same.id)
+        );
+        assert_eq!(store.devices(&uid).await.unwrap().len(), 1);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn revoked_devices_cannot_toggle() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        let device = store.identify_device(&uid, "lost phone").await.unwrap();
+
+        assert!(store.revoke_device(&other, device.id).await.is_err());
+        store.revoke_device(&uid, device.id).await.unwrap();
+
+        let device = store.identify_device(&uid, "lost phone").await.unwrap();
+        assert!(device.revoked);
+        let options = ToggleOptions {
+            device: Some(device),
+            ..Default::default()
+        };
+        assert!(store.toggle(&uid, &options).await.is_err());
+        assert!(
+            DataStore::current_timer(&mut store.pool.acquire().await.unwrap(), &uid)
+                .await
+                .is_err()
+        );
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn concurrent_timers_run_per_project() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let first = store.create_project(&uid, "first").await.unwrap();
+        let second = store.create_project(&uid, "second").await.unwrap();
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                concurrent_timers: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let on_project = |project_id| ToggleOptions {
+            project_id: Some(project_id),
+            ..Default::default()
+        };
+        let first_timer = store.toggle(&uid, &on_project(first)).await.unwrap();
+        let second_timer = store.toggle(&uid, &on_project(second)).await.unwrap();
+        assert_ne!(first_timer, second_timer);
+        assert!(store.get_timer(first_timer).await.unwrap().is_current);
+        assert!(store.get_timer(second_timer).await.unwrap().is_current);
+
+        // Stops only the timer of the first project
+        assert_eq!(
+            store.toggle(&uid, &on_project(first)).await.unwrap(),
+            first_timer
+        );
+        assert!(!store.get_timer(first_timer).await.unwrap().is_current);
+        assert!(store.get_timer(second_timer).await.unwrap().is_current);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn explicit_project_without_concurrent_timers_stops_running_timer() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        let first = store.create_project(&uid, "first").await.unwrap();
+        let second = store.create_project(&uid, "second").await.unwrap();
+        let foreign = store.create_project(&other, "foreign").await.unwrap();
+
+        let on_project = |project_id| ToggleOptions {
+            project_id: Some(project_id),
+            ..Default::default()
+        };
+        assert!(store.toggle(&uid, &on_project(foreign)).await.is_err());
+
+        let timer_id = store.toggle(&uid, &on_project(first)).await.unwrap();
+        assert_eq!(store.get_timer(timer_id).await.unwrap().project_id, first);
+        assert_eq!(
+            store.toggle(&uid, &on_project(second)).await.unwrap(),
+            timer_id
+        );
+        assert!(
+            DataStore::current_timer(&mut store.pool.acquire().await.unwrap(), &uid)
+                .await
+                .is_err()
+        );
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn search_projects_filters_and_paginates() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        for name in ["client-a", "client-b", "internal", "100%_done"] {
+            store.create_project(&uid, name).await.unwrap();
+        }
+        let archived = store.create_project(&uid, "client-old").await.unwrap();
+        store
+            .set_project_archived(&uid, archived, true)
+            .await
+            .unwrap();
+
+        let query = ProjectQuery {
+            name: Some("client".to_string()),
+            archived: Some(false),
+            limit: 1,
+            offset: 1,
+        };
+        let (projects, total) = store.search_projects(&uid, &query).await.unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].name, "client-b");
+
+        let query = ProjectQuery {
+            name: Some("%_".to_string()),
+            limit: 10,
+            ..Default::default()
+        };
+        let (projects, _) = store.search_projects(&uid, &query).await.unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].name, "100%_done");
+
+        let query = ProjectQuery {
+            archived: Some(true),
+            limit: 10,
+            ..Default::default()
+        };
+        let (projects, total) = store.search_projects(&uid, &query).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(projects[0].id, archived);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn timers_can_be_labelled() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        let timer_id = store.toggle_current(&uid).await.unwrap();
+        store.toggle_current(&uid).await.unwrap();
+
+        store
+            .add_timer_label(&uid, timer_id, " meeting ")
+            .await
+            .unwrap();
+        store
+            .add_timer_label(&uid, timer_id, "deep-work")
+            .await
+            .unwrap();
+        store
+            .add_timer_label(&uid, timer_id, "meeting")
+            .await
+            .unwrap();
+        assert!(store
+            .add_timer_label(&other, timer_id, "meeting")
+            .await
+            .is_err());
+
+        let labels = store.labels_by_tag(&uid).await.unwrap();
+        assert_eq!(labels[&timer_id], vec!["deep-work", "meeting"]);
+
+        store
+            .remove_timer_label(&uid, timer_id, "meeting")
+            .await
+            .unwrap();
+        let project = DataStore::current_project(&mut store.pool.acquire().await.unwrap(), &uid)
+            .await
+            .unwrap();
+        let labels = store.labels_by_project(project.id).await.unwrap();
+        assert_eq!(labels[&timer_id], vec!["deep-work"]);
+
+        DataStore::delete_timer(&mut store.pool.acquire().await.unwrap(), timer_id)
+            .await
+            .unwrap();
+        assert!(store.labels_by_tag(&uid).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn fts_query_quotes_terms() {
+        assert_eq!(
+            fts_query("db migr\"ation OR"),
+            Some(r#""db"* "migr""ation"* "OR"*"#.to_string())
+        );
+        assert_eq!(fts_query("   "), None);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn search_matches_notes_and_project_names() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = store.create_project(&uid, "Other").await.unwrap();
+        let migration = store.create_project(&uid, "Migration").await.unwrap();
+        store.toggle_current(&uid).await.unwrap();
+        store.toggle_current(&uid).await.unwrap();
+
+        let preset = store
+            .create_preset(
+                &uid,
+                other,
+                "review",
+                600,
+                Some("reviewed the migration plan"),
+            )
+            .await
+            .unwrap();
+        store.apply_preset(&uid, preset).await.unwrap();
+        let preset = store
+            .create_preset(&uid, other, "lunch", 600, Some("lunch"))
+            .await
+            .unwrap();
+        store.apply_preset(&uid, preset).await.unwrap();
+
+        let results = store.search(&uid, "migr").await.unwrap();
+        let mut matched: Vec<_> = results
+            .iter()
+            .map(|result| (result.project.id, result.timers.len()))
+            .collect();
+        matched.sort();
+        assert_eq!(matched, vec![(other, 1), (migration, 1)]);
+
+        assert!(store
+            .search(&uid, "migration lunch")
+            .await
+            .unwrap()
+            .is_empty());
+        let other_tag = TagId::new("other-tag").unwrap();
+        assert!(store.search(&other_tag, "migr").await.unwrap().is_empty());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn purge_removes_all_tag_data() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let kept = TagId::new("kept-tag").unwrap();
+        for tag in [&uid, &kept] {
+            let project = store.create_project(tag, "test-project").await.unwrap();
+            let timer = store.toggle_current(tag).await.unwrap();
+            store.add_timer_label(tag, timer, "meeting").await.unwrap();
+            store
+                .create_preset(tag, project, "preset", 60, None)
+                .await
+                .unwrap();
+        }
+
+        assert!(store.purge_tag(&uid).await.unwrap() > 0);
+        assert!(store.get_projects(&uid).await.unwrap().is_empty());
+        assert!(store.presets(&uid).await.unwrap().is_empty());
+        assert!(store.labels_by_tag(&uid).await.unwrap().is_empty());
+        assert!(
+            DataStore::current_timer(&mut store.pool.acquire().await.unwrap(), &uid)
+                .await
+                .is_err()
+        );
+
+        assert_eq!(store.get_projects(&kept).await.unwrap().len(), 1);
+        assert_eq!(store.labels_by_tag(&kept).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn accounts_are_deleted_once_confirmed() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        for tag in [&uid, &other] {
+            store.create_project(tag, "test-project").await.unwrap();
+            store.toggle_current(tag).await.unwrap();
+        }
+        let now = 1_700_000_000;
+
+        assert!(store.delete_account(&uid, "guess", now).await.is_err());
+        let deletion = store.request_account_deletion(&uid, now).await.unwrap();
+        let other_deletion = store.request_account_deletion(&other, now).await.unwrap();
+        assert!(store
+            .delete_account(&uid, &other_deletion.token, now)
+            .await
+            .is_err());
+        assert!(store
+            .delete_account(&uid, &deletion.token, deletion.expires_at)
+            .await
+            .is_err());
+        assert_eq!(store.get_projects(&uid).await.unwrap().len(), 1);
+
+        assert!(
+            store
+                .delete_account(&uid, &deletion.token, now + 60)
+                .await
+                .unwrap()
+                > 0
+        );
+        assert!(store.get_projects(&uid).await.unwrap().is_empty());
+        // The token went with the rest of the data
+        assert!(store
+            .delete_account(&uid, &deletion.token, now + 60)
+            .await
+            .is_err());
+
+        assert_eq!(store.get_projects(&other).await.unwrap().len(), 1);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn toggles_apply_once_at_the_reported_time() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        let start = Utc::now().timestamp() - 3600;
+
+        let options = ToggleOptions {
+            at: Some(start),
+            ..Default::default()
+        };
+        let (started, replayed) = store.toggle_once(&uid, "start", &options).await.unwrap();
+        assert!(!replayed);
+        assert_eq!(
+            store.toggle_once(&uid, "start", &options).await.unwrap(),
+            (started, true)
+        );
+
+        let options = ToggleOptions {
+            at: Some(start + 600),
+            ..Default::default()
+        };
+        let (stopped, replayed) = store.toggle_once(&uid, "stop", &options).await.unwrap();
+        assert_eq!((stopped, replayed), (started, false));
+        assert_eq!(
+            store.toggle_once(&uid, "stop", &options).await.unwrap(),
+            (started, true)
+        );
+
+        let timer = store.get_timer(started).await.unwrap();
+        assert_eq!(timer.start_time_ms.seconds(), start);
+        assert_eq!(timer.duration_ms.seconds(), 600);
+        assert!(!timer.is_current);
+        assert!(
+            DataStore::current_timer(&mut store.pool.acquire().await.unwrap(), &uid)
+                .await
+                .is_err()
+        );
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn forgotten_timers_can_be_stopped_retroactively() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        let start = Utc::now().timestamp() - 20 * SECONDS_PER_DAY;
+        let timer_id = DataStore::create_timer(
+            &mut store.pool.acquire().await.unwrap(),
+            &uid,
+            None,
+            None,
+            StartTime::from_millis(start * 1000),
+        )
+        .await
+        .unwrap();
+
+        assert!(store
+            .stop_timer_at(&other, timer_id, start + 60)
+            .await
+            .is_err());
+        assert!(store
+            .stop_timer_at(&uid, timer_id, start - 60)
+            .await
+            .is_err());
+        assert!(store
+            .stop_timer_at(&uid, timer_id, Utc::now().timestamp() + 3600)
+            .await
+            .is_err());
+
+        let timer = store
+            .stop_timer_at(&uid, timer_id, start + 8 * 3600)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(timer.duration_ms.seconds(), 8 * 3600);
+        assert!(!timer.is_current);
+
+        assert!(store
+            .stop_timer_at(&uid, timer_id, start + 60)
+            .await
+            .is_err());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn consecutive_timers_can_be_merged() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        let other_project = store.create_project(&uid, "other-project").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(
+                &project,
+                [(1000, 60), (1100, 60), (2000, 60)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+        let timers = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap();
+        let (third, second, first) = (timers[0].id, timers[1].id, timers[2].id);
+        store
+            .add_timer_label(&uid, second, "meeting")
+            .await
+            .unwrap();
+
+        let err = store.merge_timers(&uid, first, third).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Validation(_))));
+        let other = TagId::new("other-tag").unwrap();
+        let err = store.merge_timers(&other, first, second).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::NotFound(_))));
+
+        // The order of the IDs doesn't matter, the earlier timer is kept
+        let merged = store.merge_timers(&uid, second, first).await.unwrap();
+        assert_eq!(merged.id(), first);
+        assert_eq!(
+            (merged.start_time_ms.seconds(), merged.end_time()),
+            (1000, 1160)
+        );
+        let labels = store.labels_by_project(project_id).await.unwrap();
+        assert_eq!(labels[&first], vec!["meeting".to_string()]);
+        assert!(store.find_timer(second).await.unwrap().is_none());
+
+        // Merging into a running timer keeps it running
+        let options = ToggleOptions {
+            project_id: Some(project_id),
+            ..Default::default()
+        };
+        let running = store.toggle(&uid, &options).await.unwrap();
+        let merged = store.merge_timers(&uid, third, running).await.unwrap();
+        assert_eq!(merged.id(), third);
+        assert!(merged.is_current);
+        assert_eq!(
+            DataStore::current_timer(&mut store.pool.acquire().await.unwrap(), &uid)
+                .await
+                .unwrap()
+                .id(),
+            third
+        );
+
+        let other_project = store.project(other_project).await.unwrap();
+        store
+            .import_timers(&other_project, [(1200, 60)].map(in_seconds))
+            .await
+            .unwrap();
+        let other_timer = store
+            .exportable_timers_by_project(&other_project.id)
+            .await
+            .unwrap()[0]
+            .id();
+        let err = store
+            .merge_timers(&uid, first, other_timer)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Validation(_))));
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn timers_can_be_split_between_projects() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        let other_project = store.create_project(&uid, "other-project").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(&project, [(1000, 6 * 3600)].map(in_seconds))
+            .await
+            .unwrap();
+        let timer_id = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap()[0]
+            .id();
+        store
+            .add_timer_label(&uid, timer_id, "client")
+            .await
+            .unwrap();
+
+        for split_at in [1000, 1000 + 6 * 3600, 500] {
+            let err = store
+                .split_timer(&uid, timer_id, split_at, None)
+                .await
+                .unwrap_err();
+            assert!(matches!(err.downcast_ref(), Some(Error::Validation(_))));
+        }
+        let other = TagId::new("other-tag").unwrap();
+        let err = store
+            .split_timer(&other, timer_id, 2000, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::NotFound(_))));
+
+        let (first, second) = store
+            .split_timer(&uid, timer_id, 1000 + 4 * 3600, Some(other_project))
+            .await
+            .unwrap();
+        assert_eq!(first.id(), timer_id);
+        assert_eq!(
+            (first.project_id, first.duration_ms.seconds()),
+            (project_id, 4 * 3600)
+        );
+        assert_eq!(
+            (second.project_id, second.duration_ms.seconds()),
+            (other_project, 2 * 3600)
+        );
+        assert_eq!(second.start_time_ms.seconds(), first.end_time());
+        let labels = store.labels_by_project(other_project).await.unwrap();
+        assert_eq!(labels[&second.id()], vec!["client".to_string()]);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn timers_are_reassigned_together() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        let other_project = store.create_project(&uid, "other-project").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(&project, [(1000, 60), (2000, 60)].map(in_seconds))
+            .await
+            .unwrap();
+        let timer_ids: Vec<_> = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap()
+            .iter()
+            .map(Timer::id)
+            .collect();
+        let options = ToggleOptions {
+            project_id: Some(project_id),
+            ..Default::default()
+        };
+        let running = store.toggle(&uid, &options).await.unwrap();
+
+        // One timer of another tag fails the whole batch
+        let other = TagId::new("other-tag").unwrap();
+        let other_project_id = store.create_project(&other, "theirs").await.unwrap();
+        let theirs = store.project(other_project_id).await.unwrap();
+        store
+            .import_timers(&theirs, [(1000, 60)].map(in_seconds))
+            .await
+            .unwrap();
+        let their_timer = store
+            .exportable_timers_by_project(&other_project_id)
+            .await
+            .unwrap()[0]
+            .id();
+        let err = store
+            .reassign_timers(&uid, &[timer_ids[0], their_timer], other_project)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::NotFound(_))));
+        assert_eq!(
+            store
+                .exportable_timers_by_project(&project_id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let moved = store
+            .reassign_timers(&uid, &[timer_ids[0], timer_ids[1], running], other_project)
+            .await
+            .unwrap();
+        assert_eq!(moved, 3);
+        assert!(store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap()
+            .is_empty());
+        let timer = store.find_timer(running).await.unwrap().unwrap();
+        assert_eq!(timer.project_id, other_project);
+        assert!(timer.is_current);
+    }
+
+    #[test]
+    fn event_time_is_bounded() {
+        let now = Utc::now().timestamp();
+
+        assert!(event_time(None).unwrap() >= now);
+        assert_eq!(event_time(Some(now - 60)).unwrap(), now - 60);
+        assert!(event_time(Some(now + 60)).unwrap() <= now + 1);
+        assert!(event_time(Some(now + 3600)).is_err());
+        assert!(event_time(Some(now - 30 * SECONDS_PER_DAY)).is_err());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn failed_toggles_release_their_key() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        let now = Utc::now().timestamp();
+        store.toggle_current(&uid).await.unwrap();
+
+        let options = ToggleOptions {
+            at: Some(now - 3600),
+            ..Default::default()
+        };
+        assert!(store.toggle_once(&uid, "key", &options).await.is_err());
+
+        let options = ToggleOptions::default();
+        let (_, replayed) = store.toggle_once(&uid, "key", &options).await.unwrap();
+        assert!(!replayed);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn old_idempotency_keys_are_pruned() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        let options = ToggleOptions::default();
+        let (timer_id, _) = store.toggle_once(&uid, "key", &options).await.unwrap();
+
+        let now = Utc::now().timestamp();
+        let pruned = store
+            .prune_idempotency_keys(now - IDEMPOTENCY_KEY_TTL)
+            .await
+            .unwrap();
+        assert_eq!(pruned, 0);
+        let (replayed_id, replayed) = store.toggle_once(&uid, "key", &options).await.unwrap();
+        assert_eq!((replayed_id, replayed), (timer_id, true));
+
+        let pruned = store.prune_idempotency_keys(now + 1).await.unwrap();
+        assert_eq!(pruned, 1);
+        let (_, replayed) = store.toggle_once(&uid, "key", &options).await.unwrap();
+        assert!(!replayed);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn synced_batches_apply_once_and_atomically() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        let start = Utc::now().timestamp() - 3600;
+        let event = |key: &str, at: i64| SyncEvent {
+            uid: TagId::new("test-tag").unwrap(),
+            key: key.to_string(),
+            options: ToggleOptions {
+                at: Some(start + at),
+                ..Default::default()
+            },
+        };
+
+        let (synced, timers) = store.sync(&[event("a", 0), event("b", 600)]).await.unwrap();
+        assert!(synced.iter().all(|toggle| !toggle.replayed));
+        assert_eq!(synced[0].timer_id, synced[1].timer_id);
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].duration_ms.seconds(), 600);
+        assert!(!timers[0].is_current);
+
+        // The stop lies before the start of the timer it stops, failing the whole batch
+        let batch = [
+            event("a", 0),
+            event("b", 600),
+            event("c", 1200),
+            event("d", 900),
+        ];
+        assert!(store.sync(&batch).await.is_err());
+        let mut conn = store.pool.acquire().await.unwrap();
+        assert!(DataStore::current_timer(&mut conn, &uid).await.is_err());
+        drop(conn);
+
+        let (synced, timers) = store
+            .sync(&[event("b", 600), event("c", 1200)])
+            .await
+            .unwrap();
+        assert!(synced[0].replayed);
+        assert!(!synced[1].replayed);
+        assert_eq!(timers.len(), 2);
+        assert!(timers.iter().any(|timer| timer.is_current));
+    }
+
+    #[test]
+    fn database_file_path() {
+        let config = |url: &str| DatabaseConfig {
+            url: url.to_string(),
+            max_connections: 1,
+            busy_timeout: Duration::from_secs(1),
+            connect_timeout: Duration::from_secs(1),
+        };
+
+        assert_eq!(
+            config("sqlite:///data/sprite.db").file_path(),
+            Some(PathBuf::from("/data/sprite.db"))
+        );
+        assert_eq!(
+            config("sqlite:sprite.db?mode=rwc").file_path(),
+            Some(PathBuf::from("sprite.db"))
+        );
+        assert_eq!(config("sqlite::memory:").file_path(), None);
+    }
+
+    #[test]
+    fn connect_backoff_doubles_up_to_a_cap() {
+        assert_eq!(connect_backoff(1), Duration::from_millis(100));
+        assert_eq!(connect_backoff(2), Duration::from_millis(200));
+        assert_eq!(connect_backoff(4), Duration::from_millis(800));
+        assert_eq!(connect_backoff(10), Duration::from_secs(5));
+        assert_eq!(connect_backoff(u32::MAX), Duration::from_secs(5));
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn connecting_waits_for_the_database_to_show_up() {
+        let dir = env::temp_dir().join(format!("sprite-mount-{}", std::process::id()));
+        let config = DatabaseConfig {
+            url: format!("sqlite://{}?mode=rwc", dir.join("sprite.db").display()),
+            max_connections: 1,
+            busy_timeout: Duration::from_secs(1),
+            connect_timeout: Duration::from_millis(300),
+        };
+
+        // The directory stands in for a volume which never gets mounted
+        assert!(DataStore::new(&config, Plugins::default(), None)
+            .await
+            .is_err());
+
+        let mount = {
+            let dir = dir.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                tokio::fs::create_dir_all(dir).await.unwrap();
+            })
+        };
+        let config = DatabaseConfig {
+            connect_timeout: Duration::from_secs(10),
+            ..config
+        };
+        let store = DataStore::new(&config, Plugins::default(), None).await;
+        mount.await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(store.is_ok());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn file_databases_use_wal() {
+        let path = env::temp_dir().join(format!("sprite-wal-{}.db", std::process::id()));
+        let config = DatabaseConfig {
+            url: format!("sqlite://{}?mode=rwc", path.display()),
+            max_connections: 2,
+            busy_timeout: Duration::from_secs(1),
+            connect_timeout: Duration::from_secs(1),
+        };
+        let store = DataStore::new(&config, Plugins::default(), None)
+            .await
+            .unwrap();
+
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        let foreign_keys: bool = sqlx::query_scalar("PRAGMA foreign_keys")
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(journal_mode, "wal");
+        assert!(foreign_keys);
+
+        store.pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn snapshots_contain_all_data() {
+        // Snapshots of in-memory databases stay in memory, so use a file
+        let database = env::temp_dir().join(format!("sprite-live-{}.db", std::process::id()));
+        let config = DatabaseConfig {
+            url: format!("sqlite://{}?mode=rwc", database.display()),
+            max_connections: 2,
+            busy_timeout: Duration::from_secs(1),
+            connect_timeout: Duration::from_secs(1),
+        };
+        let store = DataStore::new(&config, Plugins::default(), None)
+            .await
+            .unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+
+        let path = env::temp_dir().join(format!("sprite-snapshot-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        store.snapshot(&path).await.unwrap();
+        store.pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", database.display(), suffix));
+        }
+
+        let snapshot = SqlitePool::connect(&format!("sqlite://{}", path.display()))
+            .await
+            .unwrap();
+        let projects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM PROJECTS")
+            .fetch_one(&snapshot)
+            .await
+            .unwrap();
+        assert_eq!(projects, 1);
+
+        snapshot.close().await;
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn unknown_tags_are_not_found() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+
+        let err = store.find_tag(uid.as_ref()).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::NotFound(_))));
+
+        store.create_project(&uid, "test-project").await.unwrap();
+        let found = store.find_tag(uid.as_ref()).await.unwrap();
+        assert_eq!(found.as_ref(), uid.as_ref());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn share_links_can_be_revoked() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+
+        let share = store.create_token(&uid, TokenKind::Share).await.unwrap();
+        let shared = store
+            .token_tag(TokenKind::Share, &share.token)
+            .await
+            .unwrap();
+        assert_eq!(shared.as_ref(), uid.as_ref());
+        assert_eq!(
+            store.tokens(&uid, TokenKind::Share).await.unwrap(),
+            vec![share.info]
+        );
+
+        // A share link is no API token, and only a hash of it is stored
+        assert!(store.token_tag(TokenKind::Api, &share.token).await.is_err());
+        let stored: Vec<String> = sqlx::query_scalar("SELECT token_hash FROM TOKENS")
+            .fetch_all(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, [hash_secret(&share.token)]);
+
+        let token_id = store.tokens(&uid, TokenKind::Share).await.unwrap()[0].token_id;
+        assert!(store
+            .revoke_token(&other, TokenKind::Share, token_id)
+            .await
+            .is_err());
+        assert!(store
+            .revoke_token(&uid, TokenKind::Api, token_id)
+            .await
+            .is_err());
+        store
+            .revoke_token(&uid, TokenKind::Share, token_id)
+            .await
+            .unwrap();
+
+        assert!(store
+            .token_tag(TokenKind::Share, &share.token)
+            .await
+            .is_err());
+        assert!(store.tokens(&uid, TokenKind::Share).await.unwrap()[0].revoked);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn api_tokens_report_the_tag_status() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+
+        let token = store.create_token(&uid, TokenKind::Api).await.unwrap();
+        let tag = store.token_tag(TokenKind::Api, &token.token).await.unwrap();
+        let status = store.tag_status(&tag).await.unwrap();
+        assert_eq!(status.project_name.as_deref(), Some("test-project"));
+        assert_eq!(status.start_time, None);
+
+        store.toggle_current(&uid).await.unwrap();
+        assert!(store.tag_status(&tag).await.unwrap().start_time.is_some());
+
+        store
+            .revoke_token(&uid, TokenKind::Api, token.info.token_id)
+            .await
+            .unwrap();
+        assert!(store.token_tag(TokenKind::Api, &token.token).await.is_err());
+        assert!(store.tokens(&uid, TokenKind::Api).await.unwrap()[0].revoked);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn owner_keys_are_replaced_when_issued_again() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+
+        let first = store.issue_owner_key(&uid).await.unwrap();
+        let owner = store.owner_tag(&first).await.unwrap();
+        assert_eq!(owner.as_ref(), uid.as_ref());
+
+        let second = store.issue_owner_key(&uid).await.unwrap();
+        assert_ne!(first, second);
+        assert!(store.owner_tag(&first).await.is_err());
+        assert!(store.owner_tag(&second).await.is_ok());
+        assert!(store.owner_tag("").await.is_err());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn toggle_links_ignore_replays() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+
+        assert!(store.claim_toggle_link(&uid, 10).await.unwrap());
+        assert!(!store.claim_toggle_link(&uid, 10).await.unwrap());
+        assert!(store.claim_toggle_link(&other, 10).await.unwrap());
+        // Once the window has passed the link toggles again
+        assert!(store.claim_toggle_link(&uid, -1).await.unwrap());
+        assert_eq!(store.toggle_link_generation(&uid).await.unwrap(), 0);
+        assert_eq!(store.rotate_toggle_link(&uid).await.unwrap(), 1);
+        assert_eq!(store.rotate_toggle_link(&uid).await.unwrap(), 2);
+        assert_eq!(store.toggle_link_generation(&uid).await.unwrap(), 2);
+        assert_eq!(store.toggle_link_generation(&other).await.unwrap(), 0);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn slugs_lead_to_their_tag() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+
+        let slug = store.create_slug(&uid, None).await.unwrap().slug;
+        assert_eq!(slug.len(), SLUG_LENGTH);
+        assert_eq!(store.slug_tag(&slug).await.unwrap().as_ref(), uid.as_ref());
+
+        store.create_slug(&uid, Some("desk")).await.unwrap();
+        assert!(store.create_slug(&other, Some("desk")).await.is_err());
+        assert!(store.create_slug(&other, Some("Desk")).await.is_err());
+        assert_eq!(store.slugs(&uid).await.unwrap().len(), 2);
+
+        assert!(store.remove_slug(&other, "desk").await.is_err());
+        store.remove_slug(&uid, "desk").await.unwrap();
+        assert!(store.slug_tag("desk").await.is_err());
+        store.create_slug(&other, Some("desk")).await.unwrap();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn budgets_are_alerted_once_per_threshold() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        let now = Utc::now().timestamp();
+
+        assert!(store
+            .set_project_budget(&other, project_id, Some(3600))
+            .await
+            .is_err());
+        assert!(store
+            .set_project_budget(&uid, project_id, Some(0))
+            .await
+            .is_err());
+        store
+            .set_project_budget(&uid, project_id, Some(3600))
+            .await
+            .unwrap();
+        assert!(store.budgets_to_alert(now).await.unwrap().is_empty());
+
+        // The running timer counts towards the budget
+        let mut conn = store.pool.acquire().await.unwrap();
+        DataStore::create_timer(
+            &mut conn,
+            &uid,
+            None,
+            None,
+            StartTime::from_millis((now - 3000) * 1000),
+        )
+        .await
+        .unwrap();
+        drop(conn);
+        let budgets = store.project_budgets(&uid, now).await.unwrap();
+        assert_eq!(budgets[&project_id].consumed.seconds(), 3000);
+        assert_eq!(budgets[&project_id].percent(), 83);
+
+        let due = store.budgets_to_alert(now).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].due_threshold(), Some(80));
+        store.mark_budget_alerted(project_id, 80).await.unwrap();
+        assert!(store.budgets_to_alert(now).await.unwrap().is_empty());
+        assert_eq!(
+            store.budgets_to_alert(now + 600).await.unwrap()[0].due_threshold(),
+            Some(100)
+        );
+
+        store
+            .set_project_budget(&uid, project_id, None)
+            .await
+            .unwrap();
+        assert!(store.project_budgets(&uid, now).await.unwrap().is_empty());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn scheduled_exports_belong_to_their_tag() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        let destination = (Some("billing@example.com"), None);
+
+        assert!(store
+            .create_scheduled_export(
+                &other,
+                project_id,
+                ExportSchedule::Monthly,
+                "UTC",
+                destination
+            )
+            .await
+            .is_err());
+        assert!(store
+            .create_scheduled_export(
+                &uid,
+                project_id,
+                ExportSchedule::Monthly,
+                "UTC",
+                (None, None)
+            )
+            .await
+            .is_err());
+
+        let export = store
+            .create_scheduled_export(
+                &uid,
+                project_id,
+                ExportSchedule::Monthly,
+                "UTC",
+                destination,
+            )
+            .await
+            .unwrap();
+        assert_eq!(export.schedule, ExportSchedule::Monthly);
+        assert_eq!(export.last_sent, None);
+
+        store.mark_export_sent(export.id, 1000).await.unwrap();
+        assert_eq!(
+            store.all_scheduled_exports().await.unwrap()[0].last_sent,
+            Some(1000)
+        );
+
+        assert!(store
+            .remove_scheduled_export(&other, export.id)
+            .await
+            .is_err());
+        store
+            .remove_scheduled_export(&uid, export.id)
+            .await
+            .unwrap();
+        assert!(store.scheduled_exports(&uid).await.unwrap().is_empty());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn team_roles_are_enforced() {
+        let store = setup().await.unwrap();
+        let owner = TagId::new("owner-tag").unwrap();
+        let member = TagId::new("member-tag").unwrap();
+        let viewer = TagId::new("viewer-tag").unwrap();
+        let outsider = TagId::new("outsider-tag").unwrap();
+
+        let team = store.create_team(&owner, "Agency").await.unwrap();
+        store
+            .set_team_member(&owner, team.id, &member, Role::Member)
+            .await
+            .unwrap();
+        store
+            .set_team_member(&owner, team.id, &viewer, Role::Viewer)
+            .await
+            .unwrap();
+        let project_id = store
+            .create_team_project(&owner, team.id, "Client work")
+            .await
+            .unwrap();
+
+        // Members can't manage the team
+        let err = store
+            .set_team_member(&member, team.id, &outsider, Role::Owner)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Forbidden(_))));
+
+        // Every member tracks their own timer on the shared project
+        let options = ToggleOptions {
+            project_id: Some(project_id),
+            ..Default::default()
+        };
+        store.toggle(&owner, &options).await.unwrap();
+        store.toggle(&member, &options).await.unwrap();
+        let err = store.toggle(&viewer, &options).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Forbidden(_))));
+        let err = store.toggle(&outsider, &options).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::NotFound(_))));
+
+        // Members are known by their ID within the team rather than their tag
+        let report = store.team_report(&viewer, team.id).await.unwrap();
+        assert_eq!(report.role, Role::Viewer);
+        let members: Vec<_> = report
+            .members
+            .iter()
+            .map(|member| (member.id, member.role))
+            .collect();
+        assert_eq!(
+            members,
+            vec![(1, Role::Owner), (2, Role::Member), (3, Role::Viewer)]
+        );
+        let mut trackers: Vec<_> = report.projects[0]
+            .timers
+            .iter()
+            .map(|timer| timer.member_id)
+            .collect();
+        trackers.sort();
+        assert_eq!(trackers, vec![Some(1), Some(2)]);
+        let report = serde_json::to_string(&report).unwrap();
+        assert!(!report.contains(member.as_ref()));
+        assert!(!report.contains(owner.as_ref()));
+
+        // Owners remove members by that ID, but not themselves
+        let err = store
+            .remove_team_member(&owner, team.id, 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Validation(_))));
+        store.remove_team_member(&owner, team.id, 3).await.unwrap();
+        let err = store.team_report(&viewer, team.id).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::NotFound(_))));
+        store
+            .set_team_member(&owner, team.id, &viewer, Role::Viewer)
+            .await
+            .unwrap();
+        let report = store.team_report(&viewer, team.id).await.unwrap();
+        assert_eq!(report.members.last().unwrap().id, 4);
+
+        let err = store.team_report(&outsider, team.id).await.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::NotFound(_))));
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn invoiced_timers_are_locked() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(
+                &project,
+                [(1000, 60), (2000, 120), (3000, 60)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+
+        assert!(store
+            .set_invoiced(&other, project_id, (0, 2500), true)
+            .await
+            .is_err());
+        assert_eq!(
+            store
+                .set_invoiced(&uid, project_id, (0, 2500), true)
+                .await
+                .unwrap(),
+            2
+        );
+        // Marking them again changes nothing
+        assert_eq!(
+            store
+                .set_invoiced(&uid, project_id, (0, 2500), true)
+                .await
+                .unwrap(),
+            0
+        );
+
+        let timers = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap();
+        let invoiced: Vec<_> = timers.iter().map(|timer| timer.invoiced).collect();
+        assert_eq!(invoiced, vec![false, true, true]);
+
+        let err = store
+            .add_timer_label(&uid, timers[1].id, "billable")
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Conflict(_))));
+        store
+            .add_timer_label(&uid, timers[0].id, "billable")
+            .await
+            .unwrap();
+
+        store
+            .set_invoiced(&uid, project_id, (0, 2500), false)
+            .await
+            .unwrap();
+        store
+            .add_timer_label(&uid, timers[1].id, "billable")
+            .await
+            .unwrap();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn approved_timesheets_lock_their_timers() {
+        let store = setup().await.unwrap();
+        let owner = TagId::new("owner-tag").unwrap();
+        let member = TagId::new("member-tag").unwrap();
+        let team = store.create_team(&owner, "Agency").await.unwrap();
+        store
+            .set_team_member(&owner, team.id, &member, Role::Member)
+            .await
+            .unwrap();
+        let project_id = store
+            .create_team_project(&owner, team.id, "Client work")
+            .await
+            .unwrap();
+        let now = Utc::now().timestamp();
+        let week = (now - 3600, now + 3600);
+
+        let options = ToggleOptions {
+            project_id: Some(project_id),
+            ..Default::default()
+        };
+        let timer_id = store.toggle(&member, &options).await.unwrap();
+        let err = store
+            .submit_timesheet(&member, team.id, week)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Validation(_))));
+        store.toggle(&member, &options).await.unwrap();
+        store
+            .add_timer_label(&member, timer_id, "billable")
+            .await
+            .unwrap();
+
+        let timesheet = store
+            .submit_timesheet(&member, team.id, week)
+            .await
+            .unwrap();
+        assert_eq!(timesheet.status, TimesheetStatus::Submitted);
+        let err = store
+            .submit_timesheet(&member, team.id, week)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Conflict(_))));
+
+        // Only owners review, and rejecting needs a reason
+        let err = store
+            .review_timesheet(
+                &member,
+                team.id,
+                timesheet.id,
+                TimesheetStatus::Approved,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Forbidden(_))));
+        let err = store
+            .review_timesheet(
+                &owner,
+                team.id,
+                timesheet.id,
+                TimesheetStatus::Rejected,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Validation(_))));
+        let rejected = store
+            .review_timesheet(
+                &owner,
+                team.id,
+                timesheet.id,
+                TimesheetStatus::Rejected,
+                Some("Missing the Friday meeting"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(rejected.status, TimesheetStatus::Rejected);
+        assert_eq!(rejected.member_id, Some(2));
+        assert_eq!(rejected.reviewed_by, Some(1));
+
+        // Nobody reviews their own timesheet, not even an owner
+        let own = store.submit_timesheet(&owner, team.id, week).await.unwrap();
+        let err = store
+            .review_timesheet(&owner, team.id, own.id, TimesheetStatus::Approved, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Forbidden(_))));
+
+        // Resubmitting clears the review
+        let resubmitted = store
+            .submit_timesheet(&member, team.id, week)
+            .await
+            .unwrap();
+        assert_eq!(resubmitted.id, timesheet.id);
+        assert_eq!(resubmitted.comment, None);
+        store
+            .review_timesheet(
+                &owner,
+                team.id,
+                timesheet.id,
+                TimesheetStatus::Approved,
+                None,
+            )
+            .await
+            .unwrap();
+        let err = store
+            .review_timesheet(
+                &owner,
+                team.id,
+                timesheet.id,
+                TimesheetStatus::Rejected,
+                Some("No"),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Conflict(_))));
+
+        let err = store
+            .add_timer_label(&member, timer_id, "late")
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Conflict(_))));
+        let err = store
+            .remove_timer_label(&member, timer_id, "billable")
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Conflict(_))));
+
+        assert_eq!(store.timesheets(&owner, team.id).await.unwrap().len(), 2);
+        assert_eq!(store.timesheets(&member, team.id).await.unwrap().len(), 1);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn digests_sum_hours_once_per_week() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        store
+            .import_timers(
+                &store.project(project_id).await.unwrap(),
+                vec![(1_000, 600), (2_000, 1_200), (9_000, 60)]
+                    .into_iter()
+                    .map(in_seconds),
+            )
+            .await
+            .unwrap();
+
+        let hours = store.hours_by_project(&uid, 0, 5_000).await.unwrap();
+        assert_eq!(
+            hours,
+            vec![ProjectHours {
+                name: "test-project".to_string(),
+                duration: TrackedDuration::from_seconds(1_800),
+            }]
+        );
+
+        assert!(store.digest_recipients(0).await.unwrap().is_empty());
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                email: Some("me@example.com".to_string()),
+                weekly_digest: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(store.digest_recipients(0).await.unwrap().len(), 1);
+
+        store.mark_digest_sent(&uid, 0).await.unwrap();
+        assert!(store.digest_recipients(0).await.unwrap().is_empty());
+        assert_eq!(store.digest_recipients(604_800).await.unwrap().len(), 1);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn idle_tags_have_no_running_timer() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        store
+            .import_timers(
+                &store.project(project_id).await.unwrap(),
+                vec![(1_000, 600)].into_iter().map(in_seconds),
+            )
+            .await
+            .unwrap();
+
+        assert!(store.idle_tags().await.unwrap().is_empty());
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                reminder_schedule: Some("Mon-Fri 09:00-17:00".to_string()),
+                reminder_webhook: Some("https://example.com/hook".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let idle = store.idle_tags().await.unwrap();
+        assert_eq!(idle.len(), 1);
+        assert_eq!(idle[0].last_stopped, Some(StartTime::from_seconds(1_600)));
+        assert_eq!(idle[0].reminder_after, 15);
+        assert_eq!(idle[0].reminded_at, None);
+
+        store.mark_reminded(&uid, 2_000).await.unwrap();
+        assert_eq!(store.idle_tags().await.unwrap()[0].reminded_at, Some(2_000));
+
+        store.toggle_current(&uid).await.unwrap();
+        assert!(store.idle_tags().await.unwrap().is_empty());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn time_buckets_sum_finished_timers() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "test-project").await.unwrap();
+        store
+            .import_timers(
+                &store.project(project_id).await.unwrap(),
+                vec![
+                    (0, 60),
+                    (TIME_BUCKET - 1, 120),
+                    (TIME_BUCKET, 600),
+                    (10_000, 5),
+                ]
+                .into_iter()
+                .map(in_seconds),
+            )
+            .await
+            .unwrap();
+        store.toggle_current(&uid).await.unwrap();
+
+        let buckets = store.time_buckets(&uid, 0, 10_000).await.unwrap();
+        assert_eq!(
+            buckets,
+            vec![
+                TimeBucket {
+                    start: 0,
+                    tracked: TrackedDuration::from_seconds(180),
+                },
+                TimeBucket {
+                    start: TIME_BUCKET,
+                    tracked: TrackedDuration::from_seconds(600),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn daily_goals_count_the_time_tracked_since() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "practice").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        assert!(store.daily_goals().await.unwrap().is_empty());
+
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                daily_goal: Some(60),
+                reminder_webhook: Some("https://example.com/hook".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let goals = store.daily_goals().await.unwrap();
+        assert_eq!(
+            goals,
+            [DailyGoal {
+                unique_id: uid.as_ref().to_string(),
+                minutes: 60,
+                timezone: "US/Pacific".to_string(),
+                webhook: None,
+                reached_at: None,
+            }]
+        );
+
+        // Only the part of the first timer after midnight counts
+        let midnight = 1_700_000_000;
+        store
+            .import_timers(
+                &project,
+                [(midnight - 600, 1_200), (midnight + 3_600, 1_800)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+        let now = midnight + 7_200;
+        assert_eq!(
+            store
+                .tracked_since(&uid, midnight, StartTime::from_seconds(now).0)
+                .await
+                .unwrap(),
+            TrackedDuration::from_seconds(2_400)
+        );
+
+        let timer_id = store.toggle_current(&uid).await.unwrap();
+        let started = store.get_timer(timer_id).await.unwrap().start_time_ms;
+        let five_minutes = TrackedDuration::from_seconds(300);
+        assert_eq!(
+            store
+                .tracked_since(&uid, midnight, started.end(five_minutes))
+                .await
+                .unwrap(),
+            TrackedDuration::from_seconds(2_400) + five_minutes
+        );
+
+        store.mark_goal_reached(&uid, now).await.unwrap();
+        assert_eq!(store.daily_goals().await.unwrap()[0].reached_at, Some(now));
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn long_running_timers_are_alerted_once() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
+        let timer_id = store.toggle_current(&uid).await.unwrap();
+        let started = store
+            .get_timer(timer_id)
+            .await
+            .unwrap()
+            .start_time_ms
+            .seconds();
+
+        // Alerts are off unless the tag sets a limit
+        let later = started + 4 * 60 * 60;
+        assert!(store.long_running_timers(later).await.unwrap().is_empty());
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                long_timer_after: Some(4 * 60),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(store
+            .long_running_timers(later - 1)
+            .await
+            .unwrap()
+            .is_empty());
+        let timers = store.long_running_timers(later).await.unwrap();
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].timer_id, timer_id);
+        assert_eq!(timers[0].project, "test-project");
+
+        store
+            .mark_long_timer_alerted(timer_id, later)
+            .await
+            .unwrap();
+        assert!(store.long_running_timers(later).await.unwrap().is_empty());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn timer_update_end_time_success() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        store.create_project(&uid, "test-project").await.unwrap();
 
-        let result = sqlx::query_as!(
-            JoinResult,
-            r#"
-SELECT 
-    p.id AS project_id,
-    p.name AS project_name, 
-    p.created AS created,
-    p.unique_id AS unique_id, 
-    p.is_current AS project_is_current, 
-    t.id AS timer_id,
-    t.start_time AS start_time, 
-    t.is_current AS timer_is_current, 
-    t.duration AS duration 
-FROM projects p 
-LEFT JOIN timers t
-    ON p.id = t.project_id
-WHERE
-    p.unique_id = ?1
-ORDER BY p.created DESC;
-            "#,
-            tag
-        )
-        .fetch_all(&self.pool)
-        .await?;
+        // Start and stop a timer after sleeping for 2 seconds
+        store.toggle_current(&uid).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        let timer_id = store.toggle_current(&uid).await.unwrap();
 
-        let mut map = HashMap::new();
+        let timer = store.get_timer(timer_id).await.unwrap();
 
-        for row in result {
-            let project = Project {
-                name: row.project_name,
-                id: row.project_id,
-                is_current: row.project_is_current,
-                unique_id: row.unique_id.clone(),
-                created: row.created,
-            };
+        let (start_time, duration) = timer.seconds();
+        assert_eq!(timer.end_time(), start_time + duration)
+    }
 
-            // timer_id is the primary key in the timer table and cannot be 0
-            if row.timer_id.is_some() && row.timer_id.is_some_and(|id| id > 0) {
-                let timer = Timer {
-                    id: row.timer_id.expect("Timer values should be present"),
-                    unique_id: row.unique_id,
-                    project_id: project.id,
-                    start_time: row.start_time.expect("Timer values should be present"),
-                    is_current: row
-                        .timer_is_current
-                        .expect("Timer values should be present"),
-                    duration: row.duration.expect("Timer values should be present"),
-                };
-                (map.entry(project).or_insert_with(|| vec![])).push(timer)
-            } else {
-                // When a project doesn't have any timers
-                map.insert(project, vec![]);
+    #[tokio::test]
+    async fn project_stats_summarize_finished_timers() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        let empty = store
+            .project_stats(project_id, chrono_tz::US::Pacific)
+            .await
+            .unwrap();
+        assert_eq!(empty.sessions, 0);
+        assert_eq!(empty.busiest_weekday, None);
+
+        // Tuesday 2023-11-14 22:13 UTC, which is still Tuesday in US/Pacific
+        let tuesday = 1_700_000_000;
+        let wednesday = tuesday + SECONDS_PER_DAY;
+        store
+            .import_timers(
+                &project,
+                [
+                    (tuesday, 3_600),
+                    (wednesday, 600),
+                    (wednesday + 3_600, 1_200),
+                ]
+                .map(in_seconds),
+            )
+            .await
+            .unwrap();
+        store.toggle_current(&uid).await.unwrap();
+
+        let stats = store
+            .project_stats(project_id, chrono_tz::US::Pacific)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            ProjectStats {
+                project_id,
+                sessions: 3,
+                total_seconds: 5_400,
+                average_session_seconds: 1_800,
+                longest_session_seconds: 3_600,
+                busiest_weekday: Some(Weekday::Tue),
+                first_activity: Some(tuesday),
+                last_activity: Some(wednesday + 4_800),
             }
-        }
+        );
 
-        Ok(map)
+        // A day further east, the Tuesday timer started on Wednesday
+        let stats = store
+            .project_stats(project_id, chrono_tz::Europe::Moscow)
+            .await
+            .unwrap();
+        assert_eq!(stats.busiest_weekday, Some(Weekday::Wed));
     }
 
-    #[instrument(skip(self))]
-    pub(crate) async fn exportable_timers_by_project(
-        &self,
-        project_id: &i64,
-    ) -> Result<Vec<Timer>> {
-        info!(project_id, "Exporting timers");
+    #[tokio::test]
+    async fn busiest_weekdays_follow_daylight_saving_time() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
 
-        let result = sqlx::query_as!(
-            Timer,
-            r#"
-SELECT * FROM TIMERS
-WHERE project_id = ?1 AND is_current = ?2
-ORDER BY start_time DESC
-            "#,
-            project_id,
-            IsCurrent::No as i64
-        )
-        .fetch_all(&self.pool)
-        .await?;
+        // 00:30 on Wednesday 2023-07-05 in US/Pacific, which is on Tuesday
+        // with the winter offset
+        let wednesday = 1_688_542_200;
+        store
+            .import_timers(&project, [(wednesday, 3_600)].map(in_seconds))
+            .await
+            .unwrap();
 
-        Ok(result)
+        let stats = store
+            .project_stats(project_id, chrono_tz::US::Pacific)
+            .await
+            .unwrap();
+        assert_eq!(stats.busiest_weekday, Some(Weekday::Wed));
     }
 
-    /// Creates a new timer with the start time set to the unix epoch in UTC
-    ///
-    /// If the current project does not exist for the given
-    /// [`TagId`][crate::uid::TagId] a new project is created.
-    #[instrument(skip(self))]
-    async fn create_timer(&self, uid: &TagId) -> Result<i64> {
-        let tag_id = uid.as_ref();
-        info!(tag_id, "Creating a new timer");
+    #[tokio::test]
+    async fn streaks_count_days_with_enough_time() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "reading").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
 
-        let current_project = match self.current_project(uid).await {
-            Ok(p) => p,
-            Err(_) => {
-                debug!(tag_id, "No current project found, creating a default");
-                let _ = self.create_project(uid, "new-project").await?;
-                self.current_project(uid).await?
+        // Midnight UTC of 2023-11-14
+        let first = 1_699_920_000;
+        let day = |n: i64| first + n * SECONDS_PER_DAY;
+        store
+            .import_timers(
+                &project,
+                [
+                    (day(0), 40 * 60),
+                    (day(1), 30 * 60),
+                    (day(2), 10 * 60),
+                    (day(3), 20 * 60),
+                    (day(3) + 3_600, 15 * 60),
+                    (day(4), 45 * 60),
+                    (day(5), 31 * 60),
+                ]
+                .map(in_seconds),
+            )
+            .await
+            .unwrap();
+        let date =
+            |n: i64| NaiveDate::from_ymd_opt(2023, 11, 14).unwrap() + chrono::Duration::days(n);
+
+        let streaks = store.streaks(&uid, 30, Tz::UTC, date(5)).await.unwrap();
+        assert_eq!(
+            streaks,
+            Streaks {
+                minutes: 30,
+                current: 3,
+                longest: 3,
             }
-        };
+        );
 
-        let start_epoch = chrono::Utc::now().timestamp();
+        // Today can still continue yesterday's streak, but not the day after
+        assert_eq!(
+            store
+                .streaks(&uid, 30, Tz::UTC, date(6))
+                .await
+                .unwrap()
+                .current,
+            3
+        );
+        assert_eq!(
+            store
+                .streaks(&uid, 30, Tz::UTC, date(7))
+                .await
+                .unwrap()
+                .current,
+            0
+        );
 
-        let id = sqlx::query!(
-            r#"
-INSERT INTO TIMERS (UNIQUE_ID, IS_CURRENT, START_TIME, PROJECT_ID)
-VALUES (?1, ?2, ?3, ?4)"#,
-            tag_id,
-            IsCurrent::Yes as i64,
-            start_epoch,
-            current_project.id
-        )
-        .execute(&self.pool)
-        .await?
-        .last_insert_rowid();
+        // The 10 minutes on the third day count when that's enough
+        let streaks = store.streaks(&uid, 10, Tz::UTC, date(5)).await.unwrap();
+        assert_eq!((streaks.current, streaks.longest), (6, 6));
 
-        Ok(id)
+        let streaks = store.streaks(&uid, 60, Tz::UTC, date(5)).await.unwrap();
+        assert_eq!((streaks.current, streaks.longest), (0, 0));
     }
 
-    #[instrument(skip_all)]
-    async fn update_timer(&self, timer: Timer) -> Result<bool> {
-        info!(timer = timer.id, "Updating timer");
-        let rows = sqlx::query!(
-            r#"
-UPDATE TIMERS
-SET is_current = ?1, duration = ?2
-WHERE id = ?3
-            "#,
-            IsCurrent::No as i64,
-            timer.duration,
-            timer.id,
-        )
-        .execute(&self.pool)
-        .await?
-        .rows_affected();
+    #[tokio::test]
+    async fn streak_days_follow_daylight_saving_time() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "reading").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
 
-        Ok(rows == 1)
-    }
+        // 23:30 on 2023-07-04 and 00:30 on 2023-07-06 in US/Pacific, a day
+        // apart. With the winter offset they'd be on consecutive days.
+        store
+            .import_timers(
+                &project,
+                [(1_688_538_600, 45 * 60), (1_688_628_600, 45 * 60)].map(in_seconds),
+            )
+            .await
+            .unwrap();
 
-    #[instrument(skip(self))]
-    async fn current_timer(&self, uid: &TagId) -> anyhow::Result<Timer> {
-        let tag_id = uid.as_ref();
-        info!(tag_id, "Fetching current timer");
-        Ok(sqlx::query_as!(
-            Timer,
-            r#"
-SELECT * FROM TIMERS
-WHERE unique_id = ?1 AND is_current = ?2"#,
-            tag_id,
-            IsCurrent::Yes as i64
-        )
-        .fetch_one(&self.pool)
-        .await?)
+        let today = NaiveDate::from_ymd_opt(2023, 7, 6).unwrap();
+        let streaks = store
+            .streaks(&uid, 30, chrono_tz::US::Pacific, today)
+            .await
+            .unwrap();
+        assert_eq!((streaks.current, streaks.longest), (1, 1));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::str::FromStr;
+    #[tokio::test]
+    async fn projects_are_cloned_without_timers() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let client = store.create_project(&uid, "acme").await.unwrap();
+        let project_id = store.create_project(&uid, "acme-website").await.unwrap();
+        store
+            .set_project_parent(&uid, project_id, Some(client))
+            .await
+            .unwrap();
+        store
+            .set_project_budget(&uid, project_id, Some(3_600))
+            .await
+            .unwrap();
+        store.toggle_current(&uid).await.unwrap();
 
-    use super::*;
-    use pretty_assertions::assert_eq;
-    use sqlx::sqlite::SqliteConnectOptions;
-    use tracing_test::traced_test;
+        let copy = store.clone_project(&uid, project_id).await.unwrap();
+        assert_eq!(copy.name, "acme-website (copy)");
+        assert_eq!(copy.parent_id, Some(client));
+        assert!(copy.is_current);
+        let budgets = store.project_budgets(&uid, 0).await.unwrap();
+        assert_eq!(budgets[&copy.id].budget, 3_600);
+        assert_eq!(budgets[&copy.id].consumed, TrackedDuration::default());
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        let (_, timers) = projects
+            .iter()
+            .find(|(project, _)| project.id == copy.id)
+            .unwrap();
+        assert!(timers.is_empty());
 
-    async fn setup() -> Result<DataStore> {
-        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
-        let pool = SqlitePool::connect_with(options).await?;
+        let second = store.clone_project(&uid, project_id).await.unwrap();
+        assert_eq!(second.name, "acme-website (copy 2)");
 
-        sqlx::migrate!().run(&pool).await?;
-        let store = DataStore::new_test(pool).await?;
-        Ok(store)
+        let other = TagId::new("other-tag").unwrap();
+        assert!(store.clone_project(&other, project_id).await.is_err());
+
+        assert_eq!(copy_name(&"a".repeat(80), 2).chars().count(), 80);
     }
 
-    #[traced_test]
     #[tokio::test]
-    async fn toggle_create_when_timer_does_not_exist() {
+    async fn projects_start_from_templates() {
         let store = setup().await.unwrap();
         let uid = TagId::new("test-tag").unwrap();
-        store.create_project(&uid, "test-project").await.unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        let client = store.create_project(&uid, "acme").await.unwrap();
+        let foreign = store.create_project(&other, "initech").await.unwrap();
 
-        let result = store.toggle_current(&uid).await.unwrap();
+        assert!(store
+            .create_project_template(&uid, "retainer", Some(0), None)
+            .await
+            .is_err());
+        assert!(store
+            .create_project_template(&uid, "retainer", None, Some(foreign))
+            .await
+            .is_err());
+        let template = store
+            .create_project_template(&uid, "retainer", Some(36_000), Some(client))
+            .await
+            .unwrap();
+        assert!(store
+            .create_project_template(&uid, "retainer", None, None)
+            .await
+            .is_err());
+        assert_eq!(store.project_templates(&uid).await.unwrap(), [template]);
+        let template_id = store.project_templates(&uid).await.unwrap()[0].id;
 
-        assert_eq!(result, 1);
+        let project_id = store
+            .create_project_from_template(&uid, "acme-october", template_id)
+            .await
+            .unwrap();
+        let project = store.project(project_id).await.unwrap();
+        assert_eq!(project.parent_id, Some(client));
+        assert!(project.is_current);
+        let budgets = store.project_budgets(&uid, 0).await.unwrap();
+        assert_eq!(budgets[&project_id].budget, 36_000);
+
+        // Names are checked as usual, and templates are private to their tag
+        assert!(store
+            .create_project_from_template(&uid, "ACME-october", template_id)
+            .await
+            .is_err());
+        assert!(store
+            .create_project_from_template(&other, "initech-october", template_id)
+            .await
+            .is_err());
+
+        store
+            .remove_project_template(&uid, template_id)
+            .await
+            .unwrap();
+        assert!(store.project_templates(&uid).await.unwrap().is_empty());
+        assert_eq!(
+            store.project(project_id).await.unwrap().parent_id,
+            Some(client)
+        );
     }
 
-    #[traced_test]
     #[tokio::test]
-    async fn toggle_end_timer_when_current_already_exists() {
-        let uid = TagId::new("test-tag").unwrap();
+    async fn bulk_timer_operations_apply_all_or_nothing() {
         let store = setup().await.unwrap();
-        store.create_project(&uid, "test-project").await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let writing = store.create_project(&uid, "writing").await.unwrap();
+        let reading = store.create_project(&uid, "reading").await.unwrap();
+        let project = store.project(writing).await.unwrap();
+        store
+            .import_timers(
+                &project,
+                [(1_700_000_000, 600), (1_700_010_000, 1_200)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+        let timers = store.exportable_timers_by_project(&writing).await.unwrap();
+        // Newest first
+        let (first, second) = (timers[1].id(), timers[0].id());
+        let now = 1_800_000_000;
 
-        let result = store.toggle_current(&uid).await.unwrap();
-        assert_eq!(result, 1);
+        let create = TimerOperation::Create {
+            project_id: reading,
+            start_time: 1_700_020_000,
+            duration: 900,
+            note: Some("chapter 3".to_string()),
+        };
+        let update = TimerOperation::Update {
+            timer_id: first,
+            project_id: None,
+            start_time: None,
+            duration: Some(300),
+            note: Some("draft".to_string()),
+        };
 
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        // A single failure keeps everything as it was
+        let (applied, results) = store
+            .bulk_timers(
+                &uid,
+                &[
+                    create.clone(),
+                    update.clone(),
+                    TimerOperation::Delete { timer_id: 404 },
+                ],
+                now,
+            )
+            .await
+            .unwrap();
+        assert!(!applied);
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert!(results[2].is_err());
+        assert_eq!(
+            store.get_timer(first).await.unwrap().duration_ms.seconds(),
+            600
+        );
+        assert!(store
+            .exportable_timers_by_project(&reading)
+            .await
+            .unwrap()
+            .is_empty());
 
-        let result = store.toggle_current(&uid).await.unwrap();
-        assert_eq!(result, 1);
+        let (applied, results) = store
+            .bulk_timers(
+                &uid,
+                &[create, update, TimerOperation::Delete { timer_id: second }],
+                now,
+            )
+            .await
+            .unwrap();
+        assert!(applied);
+        let created = results[0].as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(created.project_id, reading);
+        assert_eq!(created.note.as_deref(), Some("chapter 3"));
+        let updated = results[1].as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(
+            (updated.duration_ms.seconds(), updated.note.as_deref()),
+            (300, Some("draft"))
+        );
+        assert!(results[2].as_ref().unwrap().is_none());
+        assert!(store.find_timer(second).await.unwrap().is_none());
 
-        let timer = store.get_timer(result).await.unwrap();
+        // Timers can't end in the future or belong to someone else
+        let other = TagId::new("other-tag").unwrap();
+        let invalid = [
+            TimerOperation::Create {
+                project_id: reading,
+                start_time: now - 60,
+                duration: 120,
+                note: None,
+            },
+            TimerOperation::Update {
+                timer_id: first,
+                project_id: None,
+                start_time: None,
+                duration: Some(0),
+                note: None,
+            },
+        ];
+        let (applied, results) = store.bulk_timers(&uid, &invalid, now).await.unwrap();
+        assert!(!applied);
+        assert!(results.iter().all(Result::is_err));
+        let (_, results) = store
+            .bulk_timers(&other, &[TimerOperation::Delete { timer_id: first }], now)
+            .await
+            .unwrap();
+        assert!(results[0].is_err());
+    }
 
-        assert!(!timer.is_current, "Timer hasn't been turned off");
+    #[tokio::test]
+    async fn leases_are_held_by_one_instance_at_a_time() {
+        let store = setup().await.unwrap();
 
-        assert!(timer.duration >= 2);
-        assert!(!timer.is_current)
+        assert!(store
+            .acquire_lease("janitor", "a", 1_000, 60)
+            .await
+            .unwrap());
+        assert!(!store
+            .acquire_lease("janitor", "b", 1_030, 60)
+            .await
+            .unwrap());
+        // Tasks are leased separately
+        assert!(store.acquire_lease("digest", "b", 1_030, 60).await.unwrap());
+
+        // Renewing pushes back the expiry, which lets the next instance take over
+        assert!(store
+            .acquire_lease("janitor", "a", 1_050, 60)
+            .await
+            .unwrap());
+        assert!(!store
+            .acquire_lease("janitor", "b", 1_100, 60)
+            .await
+            .unwrap());
+        assert!(store
+            .acquire_lease("janitor", "b", 1_110, 60)
+            .await
+            .unwrap());
+        assert!(!store
+            .acquire_lease("janitor", "a", 1_120, 60)
+            .await
+            .unwrap());
     }
 
-    #[traced_test]
     #[tokio::test]
-    async fn toggle_timer_creates_new_current_timer() {
-        let uid = TagId::new("test-tag").unwrap();
+    async fn cached_projects_follow_every_change() {
         let store = setup().await.unwrap();
-        store.create_project(&uid, "test-project").await.unwrap();
-
-        let result = store.toggle_current(&uid).await.unwrap();
-        assert_eq!(result, 1);
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
 
-        tokio::time::sleep(Duration::from_secs(2)).await;
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        assert!(projects.values().all(Vec::is_empty));
+        assert_eq!(store.projects_by_tag(&uid).await.unwrap(), projects);
 
-        let result = store.toggle_current(&uid).await.unwrap();
-        assert_eq!(result, 1);
+        store.toggle_current(&uid).await.unwrap();
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        assert!(projects.values().flatten().any(|timer| timer.is_current));
 
-        let timer = store.get_timer(result).await.unwrap();
+        // Also changes which don't go through the store, like from another instance
+        sqlx::query("UPDATE PROJECTS SET name = 'reading' WHERE id = ?1")
+            .bind(project_id)
+            .execute(&store.pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM TIMERS")
+            .execute(&store.pool)
+            .await
+            .unwrap();
+        let projects = store.projects_by_tag(&uid).await.unwrap();
+        let (project, timers) = projects.iter().next().unwrap();
+        assert_eq!(project.name, "reading");
+        assert!(timers.is_empty());
 
-        assert!(!timer.is_current, "Timer hasn't been turned off");
+        store.purge_tag(&uid).await.unwrap();
+        assert!(store.projects_by_tag(&uid).await.unwrap().is_empty());
+    }
 
-        assert!(timer.duration >= 2);
-        assert!(!timer.is_current);
+    #[tokio::test]
+    async fn hot_queries_use_indexes() {
+        let store = setup().await.unwrap();
+        store.check_query_plans().await.unwrap();
 
-        let timer_id = store.toggle_current(&uid).await.unwrap();
-        assert_eq!(timer_id, 2);
+        sqlx::query("DROP INDEX TIMERS_BY_TAG")
+            .execute(&store.pool)
+            .await
+            .unwrap();
+        let err = store.check_query_plans().await.unwrap_err();
+        assert!(err.to_string().contains("current timer"));
     }
 
-    #[traced_test]
     #[tokio::test]
-    async fn projects_by_tag_success() {
+    async fn finished_timers_are_paged_newest_first() {
         let store = setup().await.unwrap();
         let uid = TagId::new("test-tag").unwrap();
-        store.create_project(&uid, "test-project").await.unwrap();
-        let current_project = store.current_project(&uid).await.unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        // Two timers share a start time, which the ID breaks the tie of
+        store
+            .import_timers(
+                &project,
+                [(1000, 60), (2000, 60), (2000, 30), (3000, 60)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+        store.toggle_current(&uid).await.unwrap();
 
-        for _ in 0..20 {
-            store.toggle_current(&uid).await.unwrap();
+        let everything = ExportFilter {
+            invoiced: true,
+            ..Default::default()
+        };
+        let mut pages = Vec::new();
+        let mut after = None;
+        loop {
+            let page = store
+                .exportable_timers_page(project_id, after, 3, &everything)
+                .await
+                .unwrap();
+            pages.push(page.timers);
+            after = page.next;
+            if after.is_none() {
+                break;
+            }
+            // Timers started meanwhile don't shift the pages
             store.toggle_current(&uid).await.unwrap();
         }
 
-        let timers = store.projects_by_tag(&uid).await.unwrap();
-        assert_eq!(timers.get(&current_project).unwrap().len(), 20);
+        let paged: Vec<_> = pages.iter().flatten().map(|timer| timer.id()).collect();
+        let all: Vec<_> = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|timer| timer.start_time_ms.seconds() <= 3000)
+            .map(Timer::id)
+            .collect();
+        assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), [3, 1]);
+        assert_eq!(paged.len(), 4);
+        assert!(paged.iter().all(|id| all.contains(id)));
+        assert!(pages[0]
+            .windows(2)
+            .all(|pair| (pair[0].start_time_ms, pair[0].id) > (pair[1].start_time_ms, pair[1].id)));
+
+        let cursor = TimerCursor {
+            start_time_ms: 2000,
+            id: 7,
+        };
+        assert_eq!(cursor.to_string().parse::<TimerCursor>().unwrap(), cursor);
+        assert!("2000".parse::<TimerCursor>().is_err());
     }
 
-    #[traced_test]
     #[tokio::test]
-    async fn get_exportable_timers_by_tag_returns_only_complete_timers() {
+    async fn dashboards_cover_several_tags_at_once() {
+        let store = setup().await.unwrap();
+        let alice = TagId::new("alice").unwrap();
+        let bob = TagId::new("bob").unwrap();
+        let nobody = TagId::new("nobody").unwrap();
+        let writing = store.create_project(&alice, "writing").await.unwrap();
+        let reading = store.create_project(&alice, "reading").await.unwrap();
+        let coding = store.create_project(&bob, "coding").await.unwrap();
+        let now = Utc::now().timestamp();
+        let week_ago = now - 7 * 24 * 60 * 60;
+
+        let project = store.project(writing).await.unwrap();
+        store
+            .import_timers(
+                &project,
+                [(week_ago - 600, 300), (week_ago + 60, 600)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+        let options = ToggleOptions {
+            project_id: Some(coding),
+            at: Some(now - 120),
+            ..Default::default()
+        };
+        store.toggle(&bob, &options).await.unwrap();
+
+        let tags = [bob, nobody, alice];
+        let dashboards = store.dashboard(&tags, week_ago, now).await.unwrap();
+        let order: Vec<_> = dashboards.iter().map(|d| d.unique_id.as_str()).collect();
+        assert_eq!(order, tags.iter().map(TagId::as_ref).collect::<Vec<_>>());
+
+        let bob = &dashboards[0].projects;
+        assert_eq!(bob.len(), 1);
+        assert_eq!(bob[0].running_since, Some(now - 120));
+        assert_eq!(bob[0].tracked, 120);
+        assert!(dashboards[1].projects.is_empty());
+
+        // Newest project first, only counting time since the start of the period
+        let alice: Vec<_> = dashboards[2]
+            .projects
+            .iter()
+            .map(|p| (p.project.id, p.running_since, p.tracked))
+            .collect();
+        assert_eq!(alice, [(reading, None, 0), (writing, None, 600)]);
+    }
+
+    #[tokio::test]
+    async fn timers_are_grouped_by_day() {
         let store = setup().await.unwrap();
         let uid = TagId::new("test-tag").unwrap();
-        store.create_project(&uid, "test-project").await.unwrap();
+        let writing = store.create_project(&uid, "writing").await.unwrap();
+        let reading = store.create_project(&uid, "reading").await.unwrap();
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let days: Vec<_> = monday
+            .iter_days()
+            .take(3)
+            .zip(1..)
+            .map(|(day, n)| (day, n * 1_000_000, (n + 1) * 1_000_000))
+            .collect();
 
-        for _ in 0..20 {
-            store.toggle_current(&uid).await.unwrap();
-            store.toggle_current(&uid).await.unwrap();
-        }
+        let project = store.project(writing).await.unwrap();
+        store
+            .import_timers(
+                &project,
+                [
+                    (1_000_000 - 60, 60),
+                    (1_000_000 + 600, 300),
+                    (1_000_000 + 60, 120),
+                ]
+                .map(in_seconds),
+            )
+            .await
+            .unwrap();
+        let project = store.project(reading).await.unwrap();
+        store
+            .import_timers(&project, [(3_000_100, 900)].map(in_seconds))
+            .await
+            .unwrap();
+        let options = ToggleOptions {
+            project_id: Some(reading),
+            at: Some(Utc::now().timestamp() - 60),
+            ..Default::default()
+        };
+        store.toggle(&uid, &options).await.unwrap();
+
+        let now = Utc::now();
+        let grouped = store.timers_by_day(&uid, &days, now).await.unwrap();
+        let summary: Vec<_> = grouped
+            .iter()
+            .map(|day| {
+                let entries: Vec<_> = day
+                    .entries
+                    .iter()
+                    .map(|entry| {
+                        let start_time = entry.start_time.seconds();
+                        (entry.project.as_str(), start_time, entry.duration.seconds())
+                    })
+                    .collect();
+                (day.date, entries, day.total.seconds())
+            })
+            .collect();
+        // Timers belong to the day they started on, oldest first
+        assert_eq!(
+            summary,
+            [
+                (
+                    monday,
+                    vec![("writing", 1_000_060, 120), ("writing", 1_000_600, 300)],
+                    420
+                ),
+                (monday.succ_opt().unwrap(), vec![], 0),
+                (
+                    monday + chrono::Duration::days(2),
+                    vec![("reading", 3_000_100, 900)],
+                    900
+                ),
+            ]
+        );
 
+        // The running timer counts up to now on its day
+        let today = [(monday, now.timestamp() - 3600, now.timestamp() + 3600)];
+        let grouped = store.timers_by_day(&uid, &today, now).await.unwrap();
+        assert_eq!(grouped[0].entries.len(), 1);
+        assert!(grouped[0].entries[0].is_current);
+        assert_eq!(grouped[0].total.seconds(), 60);
+    }
+
+    #[tokio::test]
+    async fn activity_is_logged() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let options = ToggleOptions {
+            at: Some(Utc::now().timestamp() - 7200),
+            ..Default::default()
+        };
+        store.toggle(&uid, &options).await.unwrap();
         store.toggle_current(&uid).await.unwrap();
 
-        let timers = store.exportable_timers_by_project(&1).await.unwrap();
+        // Added timers weren't started by anyone
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(&project, [(1_000_000, 60)].map(in_seconds))
+            .await
+            .unwrap();
 
-        assert_eq!(timers.len(), 20);
+        store
+            .update_settings(&TagSettings {
+                unique_id: uid.as_ref().to_string(),
+                short_timer_threshold: 30,
+                short_timer_policy: ShortTimerPolicy::Discard,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        store.toggle_current(&uid).await.unwrap();
+        store.toggle_current(&uid).await.unwrap();
+
+        let activity = store.activity(&uid, 10).await.unwrap();
+        let kinds: Vec<_> = activity.iter().map(|entry| entry.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ActivityKind::Discarded,
+                ActivityKind::Started,
+                ActivityKind::Stopped,
+                ActivityKind::Created,
+                ActivityKind::Started,
+            ]
+        );
+        assert!(activity.iter().all(|entry| entry.project == "writing"));
+        assert!((7199..=7201).contains(&activity[2].duration.unwrap()));
+        assert_eq!(activity[3].duration, None);
+
+        assert_eq!(store.activity(&uid, 2).await.unwrap().len(), 2);
+        let other = TagId::new("other-tag").unwrap();
+        assert!(store.activity(&other, 10).await.unwrap().is_empty());
     }
 
-    #[traced_test]
     #[tokio::test]
-    async fn timer_update_end_time_success() {
+    async fn timers_keep_milliseconds() {
         let store = setup().await.unwrap();
         let uid = TagId::new("test-tag").unwrap();
-        store.create_project(&uid, "test-project").await.unwrap();
+        store.create_project(&uid, "writing").await.unwrap();
 
-        // Start and stop a timer after sleeping for 2 seconds
+        let before = StartTime::from_millis(Utc::now().timestamp_millis());
+        let id = store.toggle_current(&uid).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
         store.toggle_current(&uid).await.unwrap();
-        tokio::time::sleep(Duration::from_secs(2)).await;
-        let timer_id = store.toggle_current(&uid).await.unwrap();
+        let after = Utc::now();
 
-        let timer = store.get_timer(timer_id).await.unwrap();
+        let timer = store.get_timer(id).await.unwrap();
+        assert!((before.0..=after).contains(&timer.start_time_ms.0));
+        let start_time = sqlx::query_scalar!("SELECT start_time FROM TIMERS WHERE id = ?1", id)
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(start_time, timer.start_time_ms.seconds());
+        assert!(timer.duration_ms >= TrackedDuration::from_millis(20));
+        assert!(timer.duration_ms <= TrackedDuration::between(before, after));
+        assert_eq!(timer.end_time(), timer.ended_at().timestamp());
+
+        // The milliseconds win over changes in seconds only
+        sqlx::query("UPDATE TIMERS SET duration = duration + 60 WHERE id = ?1")
+            .bind(id)
+            .execute(&store.pool)
+            .await
+            .unwrap();
+        let edited = store.get_timer(id).await.unwrap();
+        assert_eq!(
+            (edited.start_time_ms, edited.duration_ms),
+            (timer.start_time_ms, timer.duration_ms)
+        );
+        let duration = sqlx::query_scalar!("SELECT duration FROM TIMERS WHERE id = ?1", id)
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(duration, timer.end_time() - start_time);
+
+        // And the seconds follow changes in milliseconds
+        sqlx::query("UPDATE TIMERS SET duration_ms = duration_ms + 60000 WHERE id = ?1")
+            .bind(id)
+            .execute(&store.pool)
+            .await
+            .unwrap();
+        let duration = sqlx::query_scalar!("SELECT duration FROM TIMERS WHERE id = ?1", id)
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(duration, timer.end_time() + 60 - start_time);
+
+        let project = store.project(timer.project_id).await.unwrap();
+        store
+            .import_timers(&project, [(1000, 90)].map(in_seconds))
+            .await
+            .unwrap();
+        let imported = store
+            .exportable_timers_by_project(&project.id)
+            .await
+            .unwrap();
+        let imported = imported
+            .iter()
+            .find(|t| t.start_time_ms.seconds() == 1000)
+            .unwrap();
+        assert_eq!(
+            (
+                imported.start_time_ms.millis(),
+                imported.duration_ms.millis()
+            ),
+            (1_000_000, 90_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn hours_roll_up_into_clients() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let other = TagId::new("other-tag").unwrap();
+        let client = store.create_project(&uid, "acme").await.unwrap();
+        let project = store.create_project(&uid, "acme-website").await.unwrap();
+        let task = store
+            .create_project(&uid, "acme-website-design")
+            .await
+            .unwrap();
+        let solo = store.create_project(&uid, "side-project").await.unwrap();
+        let foreign = store.create_project(&other, "other-project").await.unwrap();
+
+        store
+            .set_project_parent(&uid, project, Some(client))
+            .await
+            .unwrap();
+        let nested = store
+            .set_project_parent(&uid, task, Some(project))
+            .await
+            .unwrap();
+        assert_eq!(nested.parent_id, Some(project));
+
+        // Neither cycles nor another tag's projects make a hierarchy
+        let err = store
+            .set_project_parent(&uid, client, Some(task))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Validation(_))));
+        assert!(store
+            .set_project_parent(&uid, client, Some(client))
+            .await
+            .is_err());
+        assert!(store
+            .set_project_parent(&uid, solo, Some(foreign))
+            .await
+            .is_err());
+
+        for (project_id, duration) in [(task, 60), (project, 120), (solo, 30)] {
+            let project = store.project(project_id).await.unwrap();
+            store
+                .import_timers(&project, [(1000, duration)].map(in_seconds))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            store.hours_by_client(&uid, 0, 2000).await.unwrap(),
+            vec![
+                ProjectHours {
+                    name: "acme".to_string(),
+                    duration: TrackedDuration::from_seconds(180),
+                },
+                ProjectHours {
+                    name: "side-project".to_string(),
+                    duration: TrackedDuration::from_seconds(30),
+                },
+            ]
+        );
+        assert_eq!(store.root_project(task).await.unwrap().id, client);
+
+        store.set_project_parent(&uid, project, None).await.unwrap();
+        assert_eq!(store.root_project(task).await.unwrap().id, project);
+    }
+
+    #[tokio::test]
+    async fn hours_are_grouped_by_label() {
+        let store = setup().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let project_id = store.create_project(&uid, "writing").await.unwrap();
+        let project = store.project(project_id).await.unwrap();
+        store
+            .import_timers(
+                &project,
+                [(1000, 60), (2000, 120), (3000, 600), (9000, 30)].map(in_seconds),
+            )
+            .await
+            .unwrap();
+        let timers = store
+            .exportable_timers_by_project(&project_id)
+            .await
+            .unwrap();
+        let id = |start: i64| {
+            timers
+                .iter()
+                .find(|timer| timer.start_time_ms.seconds() == start)
+                .unwrap()
+                .id
+        };
+        for (start, label) in [
+            (1000, "meeting"),
+            (2000, "meeting"),
+            (2000, "deep-work"),
+            (9000, "meeting"),
+        ] {
+            store.add_timer_label(&uid, id(start), label).await.unwrap();
+        }
+        // A running timer doesn't count yet
+        let running = store.toggle_current(&uid).await.unwrap();
+        store
+            .add_timer_label(&uid, running, "deep-work")
+            .await
+            .unwrap();
 
-        assert_eq!(timer.end_time(), timer.duration + timer.start_time)
+        // Timers count towards each of their labels, unlabelled ones towards none
+        assert_eq!(
+            store.hours_by_label(&uid, 0, 5000).await.unwrap(),
+            vec![
+                LabelHours {
+                    label: "meeting".to_string(),
+                    duration: TrackedDuration::from_seconds(180),
+                },
+                LabelHours {
+                    label: "deep-work".to_string(),
+                    duration: TrackedDuration::from_seconds(120),
+                },
+            ]
+        );
+        let other = TagId::new("other-tag").unwrap();
+        assert!(store
+            .hours_by_label(&other, 0, 5000)
+            .await
+            .unwrap()
+            .is_empty());
     }
 }
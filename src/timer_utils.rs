@@ -1,45 +1,566 @@
+use std::{
+    collections::HashMap,
+    io::{Cursor, Write},
+    str::FromStr,
+};
+
 use crate::{
-    templates::{self, extract_timer},
-    timer_store::Timer,
+    date_format::DateFormat,
+    duration::{DurationDisplay, DurationFormat},
+    error::Error,
+    plugins::Plugins,
+    templates,
+    timer_store::{DataStore, Preset, Project, TagSettings, Timer, TimerLabels},
+    uid::TagId,
 };
 use anyhow::Result;
+use chrono::Utc;
 use csv::{Writer, WriterBuilder};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
 
-/// Serializes timers into a CSV writer
-pub(crate) fn export_timers(timers: Vec<Timer>, timezone: &str) -> Result<Writer<Vec<u8>>> {
-    let data = vec![];
-    let mut writer = WriterBuilder::new().from_writer(data);
+/// A column exports can have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportColumn {
+    Start,
+    End,
+    Duration,
+    Project,
+    /// The top level project the project is nested under, like its client
+    Client,
+    Note,
+    /// The labels of the timer, separated by `;`
+    Tags,
+    /// The device which toggled the timer
+    Device,
+}
+
+/// The columns of exports when neither the request nor the tag picks any
+pub const DEFAULT_COLUMNS: [ExportColumn; 4] = [
+    ExportColumn::Start,
+    ExportColumn::End,
+    ExportColumn::Duration,
+    ExportColumn::Tags,
+];
 
-    #[derive(Debug, Serialize)]
-    struct ExportRecord {
-        start_time: String,
-        end_time: String,
-        duration: String,
+impl ExportColumn {
+    /// The name of the column in the header row
+    fn header(&self) -> &'static str {
+        match self {
+            ExportColumn::Start => "start_time",
+            ExportColumn::End => "end_time",
+            ExportColumn::Duration => "duration",
+            ExportColumn::Project => "project",
+            ExportColumn::Client => "client",
+            ExportColumn::Note => "note",
+            ExportColumn::Tags => "labels",
+            ExportColumn::Device => "device",
+        }
     }
+}
 
-    let timezone: chrono_tz::Tz = templates::from_render_timezone(&timezone)?;
+impl FromStr for ExportColumn {
+    type Err = Error;
 
-    for timer in timers {
-        let duration = if timer.duration > 0 {
-            let duration = timer.duration;
-            format!(
-                "{}:{}",
-                extract_timer(templates::TimerPart::Hour, duration)?,
-                extract_timer(templates::TimerPart::Min, duration)?
-            )
-        } else {
-            String::new()
+    fn from_str(column: &str) -> Result<Self, Self::Err> {
+        Ok(match column.trim() {
+            "start" => ExportColumn::Start,
+            "end" => ExportColumn::End,
+            "duration" => ExportColumn::Duration,
+            "project" => ExportColumn::Project,
+            "client" => ExportColumn::Client,
+            "note" => ExportColumn::Note,
+            "tags" | "labels" => ExportColumn::Tags,
+            "device" => ExportColumn::Device,
+            _ => {
+                return Err(Error::Validation(format!(
+                    "Unknown export column {:?}, expected start, end, duration, project, client, note, tags or device",
+                    column
+                )))
+            }
+        })
+    }
+}
+
+/// Parses a comma separated list of [`ExportColumn`]s
+pub fn parse_columns(columns: &str) -> Result<Vec<ExportColumn>> {
+    let columns = columns
+        .split(',')
+        .filter(|column| !column.trim().is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<ExportColumn>, _>>()?;
+    if columns.is_empty() {
+        return Err(Error::Validation("Exports need at least one column".to_string()).into());
+    }
+
+    Ok(columns)
+}
+
+/// Which columns an export has and how its durations and timestamps are written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub columns: Vec<ExportColumn>,
+    pub duration: DurationDisplay,
+    pub date_format: DateFormat,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            columns: DEFAULT_COLUMNS.to_vec(),
+            duration: DurationDisplay::default(),
+            date_format: DateFormat::default(),
+        }
+    }
+}
+
+impl ExportOptions {
+    /// The options of the request, falling back to the defaults of the tag
+    pub fn new(
+        settings: &TagSettings,
+        columns: Option<&str>,
+        duration_format: Option<DurationFormat>,
+    ) -> Result<Self> {
+        let columns = match columns.or(settings.export_columns.as_deref()) {
+            Some(columns) => parse_columns(columns)?,
+            None => DEFAULT_COLUMNS.to_vec(),
         };
 
-        let export_timer = ExportRecord {
-            start_time: templates::format_time(&timer.start_time, timezone, "%F %H:%M")?,
-            end_time: templates::format_time(&timer.end_time(), timezone, "%F %H:%M")?,
-            duration, // convert to minutes
+        Ok(Self {
+            columns,
+            duration: settings.duration_display(duration_format),
+            date_format: settings.date_format(),
+        })
+    }
+}
+
+/// How a timer has to carry the labels of an [`ExportFilter`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelMatch {
+    /// At least one of the labels
+    #[default]
+    Any,
+
+    /// Every one of the labels
+    All,
+}
+
+/// Which of a project's finished timers go into an export
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportFilter {
+    /// Also export the timers which were invoiced already
+    pub invoiced: bool,
+
+    /// Only export the timers carrying these labels, all timers when empty
+    pub labels: Vec<String>,
+    pub label_match: LabelMatch,
+}
+
+impl ExportFilter {
+    /// Filters by a comma separated list of labels, like `meeting,billable`
+    pub fn with_labels(mut self, labels: &str, label_match: LabelMatch) -> Self {
+        self.labels = labels
+            .split(',')
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+            .map(str::to_string)
+            .collect();
+        self.label_match = label_match;
+        self
+    }
+}
+
+/// Exports the finished timers of a project as CSV
+///
+/// The options default to the export settings of the tag owning the project,
+/// and `filter` picks the timers.
+pub async fn export_project(
+    store: &DataStore,
+    project: &Project,
+    timezone: &str,
+    columns: Option<&str>,
+    duration_format: Option<DurationFormat>,
+    filter: &ExportFilter,
+    plugins: &Plugins,
+) -> Result<Vec<u8>> {
+    let settings = store
+        .settings(&TagId::from(project.unique_id.clone()))
+        .await?;
+    let options = ExportOptions::new(&settings, columns, duration_format)?;
+
+    write_export(
+        store,
+        project,
+        (filter, |_: &Timer| true, None),
+        &options,
+        timezone,
+        plugins,
+    )
+    .await
+}
+
+/// Exports the finished timers of a project started in `[from, to)` which
+/// weren't invoiced as CSV, with the export settings of the tag owning the project
+pub async fn export_period(
+    store: &DataStore,
+    project: &Project,
+    timezone: &str,
+    (from, to): (i64, i64),
+    plugins: &Plugins,
+) -> Result<Vec<u8>> {
+    let settings = store
+        .settings(&TagId::from(project.unique_id.clone()))
+        .await?;
+    let options = ExportOptions::new(&settings, None, None)?;
+    let keep = |timer: &Timer| (from..to).contains(&timer.start_time_ms.seconds());
+
+    write_export(
+        store,
+        project,
+        (&ExportFilter::default(), keep, Some(from)),
+        &options,
+        timezone,
+        plugins,
+    )
+    .await
+}
+
+/// Everything stored about a tag besides its timers, the `account.json` of [`export_account`]
+#[derive(Debug, Serialize)]
+struct AccountExport {
+    unique_id: String,
+    exported_at: i64,
+    settings: TagSettings,
+    projects: Vec<Project>,
+    /// The labels of each timer, by timer ID
+    labels: TimerLabels,
+    presets: Vec<Preset>,
+}
+
+/// Exports all data of a tag as a ZIP archive, for backups or moving it elsewhere
+///
+/// The archive has an `account.json` with the projects, labels and settings
+/// of the tag and a CSV with every column for each project's finished
+/// timers, invoiced or not.
+pub async fn export_account(
+    store: &DataStore,
+    uid: &TagId,
+    timezone: &str,
+    plugins: &Plugins,
+) -> Result<Vec<u8>> {
+    let mut projects: Vec<Project> = store.projects_by_tag(uid).await?.into_keys().collect();
+    projects.sort_by_key(|project| project.id);
+    let all_columns = "start,end,duration,project,client,note,tags,device";
+
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for project in &projects {
+        let everything = ExportFilter {
+            invoiced: true,
+            ..Default::default()
         };
-        writer.serialize(export_timer)?;
+        let csv = export_project(
+            store,
+            project,
+            timezone,
+            Some(all_columns),
+            None,
+            &everything,
+            plugins,
+        )
+        .await?;
+        zip.start_file(
+            format!("projects/{}-{}.csv", project.id, file_name(&project.name)),
+            options,
+        )?;
+        zip.write_all(&csv)?;
     }
 
+    let account = AccountExport {
+        unique_id: uid.as_ref().to_string(),
+        exported_at: Utc::now().timestamp(),
+        settings: store.settings(uid).await?,
+        labels: store.labels_by_tag(uid).await?,
+        presets: store.presets(uid).await?,
+        projects,
+    };
+    zip.start_file("account.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, &account)?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Turns a project name into something safe to use in a file name
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// How many timers are loaded at a time while writing an export
+const EXPORT_PAGE_SIZE: u32 = 500;
+
+/// Writes the finished timers of a project which `filter` keeps and are `keep`, newest first
+///
+/// Timers are loaded a page at a time, down to the ones started before
+/// `since` if given, so the timers of a long history aren't all held at once.
+async fn write_export(
+    store: &DataStore,
+    project: &Project,
+    (filter, keep, since): (&ExportFilter, impl Fn(&Timer) -> bool, Option<i64>),
+    options: &ExportOptions,
+    timezone: &str,
+    plugins: &Plugins,
+) -> Result<Vec<u8>> {
+    let labels = store.labels_by_project(project.id).await?;
+    let devices = store.devices_by_project(project.id).await?;
+    let client = store.root_project(project.id).await?;
+    let export = ExportContext {
+        project,
+        client: &client,
+        labels: &labels,
+        devices: &devices,
+        options,
+    };
+
+    let timezone: chrono_tz::Tz = templates::from_render_timezone(timezone)?;
+    let mut writer = WriterBuilder::new().from_writer(vec![]);
+    let mut header = Vec::new();
+    let mut after = None;
+    loop {
+        let page = store
+            .exportable_timers_page(project.id, after, EXPORT_PAGE_SIZE, filter)
+            .await?;
+        let done = page.next.is_none()
+            || since.is_some_and(|since| {
+                page.timers
+                    .iter()
+                    .any(|timer| timer.start_time_ms.seconds() < since)
+            });
+        let timers = page.timers.into_iter().filter(|timer| keep(timer));
+        write_timers(&mut writer, timers, &export, timezone, plugins, &mut header)?;
+
+        if done {
+            break;
+        }
+        after = page.next;
+    }
+    write_header(&mut writer, &options.columns, &header)?;
+
+    writer.flush()?;
+    Ok(writer.into_inner()?)
+}
+
+/// Everything besides the timers an export is written from
+#[derive(Debug)]
+pub struct ExportContext<'a> {
+    pub project: &'a Project,
+    pub client: &'a Project,
+    pub labels: &'a TimerLabels,
+    pub devices: &'a HashMap<i64, String>,
+    pub options: &'a ExportOptions,
+}
+
+/// Serializes timers into a CSV writer
+///
+/// Every row is passed through the `on_export_row` hook of the loaded plugins.
+pub fn export_timers(
+    timers: Vec<Timer>,
+    export: &ExportContext<'_>,
+    timezone: &str,
+    plugins: &Plugins,
+) -> Result<Writer<Vec<u8>>> {
+    let data = vec![];
+    let mut writer = WriterBuilder::new().from_writer(data);
+
+    let timezone: chrono_tz::Tz = templates::from_render_timezone(timezone)?;
+    let mut header = Vec::new();
+    write_timers(&mut writer, timers, export, timezone, plugins, &mut header)?;
+    write_header(&mut writer, &export.options.columns, &header)?;
+
     writer.flush()?;
     Ok(writer)
 }
+
+/// Writes the rows of timers, along with the header if `header` is still empty
+fn write_timers(
+    writer: &mut Writer<Vec<u8>>,
+    timers: impl IntoIterator<Item = Timer>,
+    export: &ExportContext<'_>,
+    timezone: chrono_tz::Tz,
+    plugins: &Plugins,
+    header: &mut Vec<String>,
+) -> Result<()> {
+    let date_format = export.options.date_format.short();
+    for timer in timers {
+        let mut row = Map::new();
+        for column in &export.options.columns {
+            let value = match column {
+                ExportColumn::Start => {
+                    templates::format_time(&timer.start_time_ms.seconds(), timezone, &date_format)?
+                }
+                ExportColumn::End => {
+                    templates::format_time(&timer.ended_at().timestamp(), timezone, &date_format)?
+                }
+                ExportColumn::Duration if !timer.duration_ms.is_zero() => {
+                    export.options.duration.format(timer.duration_ms.seconds())
+                }
+                ExportColumn::Duration => String::new(),
+                ExportColumn::Project => export.project.name.clone(),
+                ExportColumn::Client => export.client.name.clone(),
+                ExportColumn::Note => timer.note.clone().unwrap_or_default(),
+                ExportColumn::Tags => export
+                    .labels
+                    .get(&timer.id())
+                    .map(|labels| labels.join(";"))
+                    .unwrap_or_default(),
+                ExportColumn::Device => timer
+                    .device_id
+                    .and_then(|id| export.devices.get(&id).cloned())
+                    .unwrap_or_default(),
+            };
+            row.insert(column.header().to_string(), Value::String(value));
+        }
+
+        if plugins.is_empty() {
+            write_columns(writer, &export.options.columns, &row, header)?;
+            continue;
+        }
+        write_row(writer, plugins.on_export_row(row), header)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the header of the requested columns if no row did, so empty exports still have one
+fn write_header(
+    writer: &mut Writer<Vec<u8>>,
+    columns: &[ExportColumn],
+    header: &[String],
+) -> Result<()> {
+    if header.is_empty() {
+        writer.write_record(columns.iter().map(|column| column.header()))?;
+    }
+
+    Ok(())
+}
+
+/// Writes a row in the order of the requested columns, with the header before the first row
+///
+/// Without plugins, `serde_json` maps are sorted by key so they can't be
+/// relied on for the column order.
+fn write_columns(
+    writer: &mut Writer<Vec<u8>>,
+    columns: &[ExportColumn],
+    row: &Map<String, Value>,
+    header: &mut Vec<String>,
+) -> Result<()> {
+    if header.is_empty() {
+        header.extend(columns.iter().map(|column| column.header().to_string()));
+        writer.write_record(header.iter())?;
+    }
+
+    let values = columns.iter().map(|column| match row.get(column.header()) {
+        Some(Value::String(value)) => value.as_str(),
+        _ => "",
+    });
+    writer.write_record(values)?;
+
+    Ok(())
+}
+
+/// Keeps only the timers which have `label` attached
+pub fn retain_labelled(
+    projects: &mut HashMap<Project, Vec<Timer>>,
+    labels: &TimerLabels,
+    label: &str,
+) {
+    for timers in projects.values_mut() {
+        timers.retain(|timer| {
+            labels
+                .get(&timer.id())
+                .is_some_and(|labels| labels.iter().any(|name| name == label))
+        });
+    }
+}
+
+/// Writes a row produced by plugins, using the columns of the first row as the header
+fn write_row(
+    writer: &mut Writer<Vec<u8>>,
+    row: Map<String, Value>,
+    header: &mut Vec<String>,
+) -> Result<()> {
+    if header.is_empty() {
+        header.extend(row.keys().cloned());
+        writer.write_record(header.iter())?;
+    }
+
+    let values = header.iter().map(|column| match row.get(column) {
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(value) => value.to_string(),
+    });
+    writer.write_record(values)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::date_format::DateOrder;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn project_names_become_file_names() {
+        assert_eq!(file_name("acme-website"), "acme-website");
+        assert_eq!(file_name("../Über uns/2024"), "---Über-uns-2024");
+    }
+
+    #[test]
+    fn columns_are_parsed_in_order() {
+        assert_eq!(
+            parse_columns("project, start,duration,tags,").unwrap(),
+            vec![
+                ExportColumn::Project,
+                ExportColumn::Start,
+                ExportColumn::Duration,
+                ExportColumn::Tags,
+            ]
+        );
+        assert!(parse_columns("start,billable").is_err());
+        assert!(parse_columns(" , ").is_err());
+    }
+
+    #[test]
+    fn request_options_override_the_tag_defaults() {
+        let settings = TagSettings {
+            export_columns: Some("note,duration".to_string()),
+            duration_format: DurationFormat::Decimal,
+            duration_seconds: true,
+            date_order: DateOrder::Dmy,
+            ..Default::default()
+        };
+
+        let options = ExportOptions::new(&settings, None, None).unwrap();
+        assert_eq!(
+            options.columns,
+            vec![ExportColumn::Note, ExportColumn::Duration]
+        );
+        assert_eq!(options.duration.format, DurationFormat::Decimal);
+        assert_eq!(options.date_format.short(), "%d/%m/%Y %H:%M");
+
+        let options =
+            ExportOptions::new(&settings, Some("device"), Some(DurationFormat::Clock)).unwrap();
+        assert_eq!(options.columns, vec![ExportColumn::Device]);
+        assert_eq!(options.duration.format, DurationFormat::Clock);
+        assert_eq!(options.duration.format(42), "0:00:42");
+
+        assert_eq!(
+            ExportOptions::new(&TagSettings::default(), None, None).unwrap(),
+            ExportOptions::default()
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Serving HTTPS without a proxy in front
+//!
+//! On fly.io the proxy terminates TLS, but on a home network the NFC readers
+//! talk to sprite directly. Setting `TLS_CERT_PATH` and `TLS_KEY_PATH` to PEM
+//! files with the certificate chain and its private key makes the server speak
+//! HTTPS itself.
+
+use std::{env, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use axum_server::tls_rustls::RustlsConfig;
+
+/// Where the certificate and key to serve HTTPS with are stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TlsConfig {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl TlsConfig {
+    /// Reads the certificate and key paths from the environment, if HTTPS is configured
+    pub(crate) fn from_env() -> Result<Option<Self>> {
+        Self::new(
+            env::var_os("TLS_CERT_PATH").map(PathBuf::from),
+            env::var_os("TLS_KEY_PATH").map(PathBuf::from),
+        )
+    }
+
+    fn new(cert_path: Option<PathBuf>, key_path: Option<PathBuf>) -> Result<Option<Self>> {
+        match (cert_path, key_path) {
+            (Some(cert_path), Some(key_path)) => Ok(Some(Self {
+                cert_path,
+                key_path,
+            })),
+            (None, None) => Ok(None),
+            _ => bail!("TLS_CERT_PATH and TLS_KEY_PATH must be set together"),
+        }
+    }
+
+    /// Loads the certificate and key, failing if either can't be read or parsed
+    pub(crate) async fn load(&self) -> Result<RustlsConfig> {
+        RustlsConfig::from_pem_file(&self.cert_path, &self.key_path)
+            .await
+            .with_context(|| {
+                format!(
+                    "Loading TLS certificate {} with key {}",
+                    self.cert_path.display(),
+                    self.key_path.display()
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn both_paths_are_needed() {
+        assert_eq!(TlsConfig::new(None, None).unwrap(), None);
+        assert!(TlsConfig::new(Some("cert.pem".into()), None).is_err());
+        assert!(TlsConfig::new(None, Some("key.pem".into())).is_err());
+
+        let config = TlsConfig::new(Some("cert.pem".into()), Some("key.pem".into()))
+            .unwrap()
+            .unwrap();
+        let err = config.load().await.unwrap_err();
+        assert!(err.to_string().contains("cert.pem"));
+    }
+}
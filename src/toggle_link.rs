@@ -0,0 +1,128 @@
+//! Links which toggle a tag's timer when opened
+//!
+//! Many phones can only open a URL from an NFC tag, so opening the link
+//! toggles the timer and then shows the timer page. Opening it again within a
+//! few seconds doesn't toggle back, so a link preview or a tag read twice
+//! toggles only once.
+//!
+//! The link carries the tag's ID and the generation of its link along with a
+//! signature, so knowing a tag ID isn't enough to toggle its timer. Only the
+//! owner of a tag can get a link, and getting a new one starts a new
+//! generation, which invalidates the links handed out before.
+
+use std::env;
+
+use anyhow::{anyhow, Result};
+
+use crate::uid::TagId;
+
+/// Bytes of the keyed BLAKE3 digest kept in a link, short enough for small tags
+const SIGNATURE_BYTES: usize = 16;
+
+/// Signs and verifies toggle links
+///
+/// Configured through `TOGGLE_LINK_SECRET`, any string which is kept private.
+/// Changing it invalidates every link written so far.
+#[derive(Clone)]
+pub(crate) struct ToggleLinks {
+    key: [u8; blake3::KEY_LEN],
+}
+
+impl std::fmt::Debug for ToggleLinks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToggleLinks").finish_non_exhaustive()
+    }
+}
+
+impl ToggleLinks {
+    /// Reads the signing secret from the environment, if one is configured
+    pub(crate) fn from_env() -> Result<Option<Self>> {
+        let Ok(secret) = env::var("TOGGLE_LINK_SECRET") else {
+            return Ok(None);
+        };
+        if secret.len() < 16 {
+            return Err(anyhow!("TOGGLE_LINK_SECRET must be at least 16 characters"));
+        }
+
+        Ok(Some(Self::new(&secret)))
+    }
+
+    pub(crate) fn new(secret: &str) -> Self {
+        Self {
+            key: blake3::derive_key("sprite 2026-10-15 toggle links", secret.as_bytes()),
+        }
+    }
+
+    fn signature(&self, uid: &TagId, generation: i64) -> [u8; SIGNATURE_BYTES] {
+        let signed = format!("{}.{}", uid.as_ref(), generation);
+        let hash = blake3::keyed_hash(&self.key, signed.as_bytes());
+        let mut signature = [0; SIGNATURE_BYTES];
+        signature.copy_from_slice(&hash.as_bytes()[..SIGNATURE_BYTES]);
+
+        signature
+    }
+
+    /// The token identifying a tag and the generation of its link in its
+    /// toggle link
+    pub(crate) fn sign(&self, uid: &TagId, generation: i64) -> String {
+        let signature: String = self
+            .signature(uid, generation)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        format!("{}.{}.{}", uid.as_ref(), generation, signature)
+    }
+
+    /// The tag and link generation a token was signed for, unless it was
+    /// tampered with
+    pub(crate) fn verify(&self, token: &str) -> Option<(TagId, i64)> {
+        let (uid, rest) = token.split_once('.')?;
+        let (generation, signature) = rest.split_once('.')?;
+        if signature.len() != SIGNATURE_BYTES * 2 || !signature.is_ascii() {
+            return None;
+        }
+        let uid = TagId::from(uid.to_string());
+        let generation = generation.parse().ok()?;
+
+        // Compare every byte so the time taken doesn't reveal how much matched
+        let expected = self.signature(&uid, generation);
+        let mut difference = 0;
+        for (i, expected) in expected.iter().enumerate() {
+            let byte = u8::from_str_radix(&signature[i * 2..i * 2 + 2], 16).ok()?;
+            difference |= byte ^ expected;
+        }
+
+        (difference == 0).then_some((uid, generation))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn links_are_signed() {
+        let links = ToggleLinks::new("a secret which is long enough");
+        let uid = TagId::new("test-tag").unwrap();
+        let token = links.sign(&uid, 2);
+
+        let (verified, generation) = links.verify(&token).unwrap();
+        assert_eq!((verified.as_ref(), generation), (uid.as_ref(), 2));
+
+        let other = TagId::new("other-tag").unwrap();
+        let signature = token.rsplit('.').next().unwrap();
+        assert!(links
+            .verify(&format!("{}.2.{}", other.as_ref(), signature))
+            .is_none());
+        assert!(links
+            .verify(&format!("{}.3.{}", uid.as_ref(), signature))
+            .is_none());
+        assert!(links.verify(uid.as_ref()).is_none());
+        assert!(links.verify(&token[..token.len() - 2]).is_none());
+        assert!(ToggleLinks::new("a different secret entirely")
+            .verify(&token)
+            .is_none());
+    }
+}
@@ -5,6 +5,10 @@ use std::{
     fmt::Display,
     hash::{Hash, Hasher},
 };
+
+/// Number of bytes of the BLAKE3 digest kept in a [`TagId`]
+const TAG_ID_BYTES: usize = 16;
+
 /// The unique identifier associated with a NFC tag
 #[derive(Debug, Clone, Serialize)]
 pub struct TagId(String);
@@ -28,10 +32,51 @@ impl Display for TagId {
 }
 
 impl TagId {
-    pub(crate) fn new(name: &str) -> Result<Self> {
+    /// Derives the ID for a tag from its name
+    ///
+    /// Uses a truncated BLAKE3 digest, which is stable across releases and
+    /// builds unlike the standard library hashers.
+    pub fn new(name: &str) -> Result<Self> {
+        let hash = blake3::hash(name.as_bytes());
+        let id = hash.as_bytes()[..TAG_ID_BYTES]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Ok(TagId(id))
+    }
+
+    /// Whether a value has the shape of a [`TagId`], which aliases mustn't
+    /// have so they can't be mistaken for one
+    pub fn has_shape(value: &str) -> bool {
+        value.len() == TAG_ID_BYTES * 2 && value.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    /// Derives the ID a tag used to have before IDs were stable
+    ///
+    /// Only used to find data stored under the old ID, see
+    /// [`DataStore::migrate_legacy_tag`][crate::timer_store::DataStore::migrate_legacy_tag].
+    pub fn legacy(name: &str) -> Self {
         let mut hasher = DefaultHasher::new();
         name.hash(&mut hasher);
 
-        Ok(TagId(format!("{:x}", hasher.finish())))
+        TagId(format!("{:x}", hasher.finish()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn tag_ids_are_stable() {
+        let uid = TagId::new("test-tag").unwrap();
+
+        assert_eq!(uid.as_ref(), "82aecb4f0b5883abe11c525d66cd8e1a");
+        assert_eq!(uid.as_ref(), TagId::new("test-tag").unwrap().as_ref());
+        assert!(TagId::has_shape(uid.as_ref()));
+        assert!(!TagId::has_shape("test-tag"));
+        assert!(!TagId::has_shape(TagId::legacy("test-tag").as_ref()));
     }
 }
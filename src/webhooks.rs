@@ -0,0 +1,437 @@
+//! Posting to outgoing webhooks, signed and kept for redelivery when they fail
+//!
+//! A tag can make a secret for each of its webhook URLs. Deliveries to the URL
+//! then carry an `X-Sprite-Signature` header like `t=1700000000,sha256=<hex>`,
+//! the HMAC-SHA256 of the timestamp, a `.` and the body keyed with the secret.
+//! Receivers recompute it to check a delivery came from Sprite, and refuse
+//! old timestamps so it can't be replayed. A new secret replaces the old one
+//! right away.
+//!
+//! Each delivery is tried once in the background, so a receiver which is
+//! down doesn't hold up whatever posted it. A failed delivery is kept with the
+//! last response and retried in the background a few times, further apart
+//! each time. After that admins can redeliver it once the receiver is fixed.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::header;
+use serde::Serialize;
+use sha2::Sha256;
+use tokio::task::JoinHandle;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    leases,
+    timer_store::{DataStore, FailedWebhook},
+    uid::TagId,
+};
+
+/// The header deliveries are signed in
+pub(crate) const SIGNATURE_HEADER: &str = "x-sprite-signature";
+
+/// How often the retry queue checks for failed deliveries which are due
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long after each failed attempt a delivery is retried, in seconds
+///
+/// Once these run out the delivery is left for an admin to redeliver.
+const RETRY_DELAYS: [i64; 3] = [60, 10 * 60, 60 * 60];
+
+/// How long a single attempt may take
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How much of a receiver's response is kept
+const MAX_RESPONSE_LENGTH: usize = 2048;
+
+/// Something posted to a webhook
+#[derive(Debug, Clone)]
+pub(crate) struct Payload {
+    /// What the webhook is about, like `budget` or `export`
+    pub(crate) kind: String,
+    pub(crate) content_type: String,
+
+    /// Sent as an attachment with this file name
+    pub(crate) filename: Option<String>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Payload {
+    pub(crate) fn json(kind: &str, value: &impl Serialize) -> Result<Self> {
+        Ok(Self {
+            kind: kind.to_string(),
+            content_type: "application/json".to_string(),
+            filename: None,
+            body: serde_json::to_vec(value)?,
+        })
+    }
+}
+
+/// When to retry a delivery which failed `attempts` times, the last time at `failed_at`
+fn next_attempt(attempts: i64, failed_at: i64) -> Option<i64> {
+    let retry = usize::try_from(attempts - 1).ok()?;
+    RETRY_DELAYS.get(retry).map(|delay| failed_at + delay)
+}
+
+/// Why an attempt failed
+struct Failure {
+    status: Option<i64>,
+    response: String,
+}
+
+/// Signs `body` sent at `timestamp` with a webhook secret
+pub(crate) fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    format!(
+        "t={},sha256={}",
+        timestamp,
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+/// Delivers webhooks, shared by every task and handler posting them
+#[derive(Debug, Clone)]
+pub(crate) struct Webhooks {
+    store: DataStore,
+    client: reqwest::Client,
+}
+
+impl Webhooks {
+    pub(crate) fn new(store: DataStore) -> Self {
+        Self {
+            store,
+            client: reqwest::Client::builder()
+                .timeout(ATTEMPT_TIMEOUT)
+                .build()
+                .expect("The reqwest client should build without custom TLS config"),
+        }
+    }
+
+    /// Posts `payload` to a tag's webhook in the background, queueing it for
+    /// retries if that fails
+    ///
+    /// Returns right away so that a slow receiver never holds up the caller.
+    /// The handle is only awaited by tests.
+    pub(crate) fn deliver(&self, uid: &TagId, url: &str, payload: Payload) -> JoinHandle<()> {
+        let webhooks = self.clone();
+        let uid = uid.clone();
+        let url = url.to_string();
+        tokio::spawn(async move {
+            if let Err(err) = webhooks.deliver_now(&uid, &url, payload).await {
+                error!(%err, "Unable to queue failed webhook");
+            }
+        })
+    }
+
+    /// Posts `payload` once, queueing it for retries if that fails
+    ///
+    /// Failed deliveries are logged rather than returned, so this only fails
+    /// when they can't be queued.
+    #[instrument(skip(self, payload), fields(tag_id = uid.as_ref(), kind = payload.kind))]
+    async fn deliver_now(&self, uid: &TagId, url: &str, payload: Payload) -> Result<()> {
+        let Err(failure) = self.attempt(uid, url, &payload).await? else {
+            return Ok(());
+        };
+
+        warn!(
+            status = failure.status,
+            response = failure.response,
+            "Unable to deliver webhook, retrying later"
+        );
+        let failed_at = Utc::now().timestamp();
+        self.store
+            .record_failed_webhook(&FailedWebhook {
+                id: 0,
+                unique_id: uid.as_ref().to_string(),
+                url: url.to_string(),
+                kind: payload.kind,
+                content_type: payload.content_type,
+                filename: payload.filename,
+                body: payload.body,
+                status: failure.status,
+                response: failure.response,
+                attempts: 1,
+                failed_at,
+                next_attempt_at: next_attempt(1, failed_at),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Tries a failed delivery once more, forgetting it when it goes through
+    ///
+    /// Answers whether it went through. If not, it's retried later unless
+    /// it ran out of retries.
+    #[instrument(skip(self))]
+    pub(crate) async fn redeliver(&self, id: i64) -> Result<bool> {
+        let webhook = self.store.failed_webhook(id).await?;
+        let uid = TagId::from(webhook.unique_id.clone());
+        let payload = Payload {
+            kind: webhook.kind,
+            content_type: webhook.content_type,
+            filename: webhook.filename,
+            body: webhook.body,
+        };
+
+        match self.attempt(&uid, &webhook.url, &payload).await? {
+            Ok(()) => {
+                self.store.remove_failed_webhook(id).await?;
+                Ok(true)
+            }
+            Err(failure) => {
+                let failed_at = Utc::now().timestamp();
+                let next_attempt_at = next_attempt(webhook.attempts + 1, failed_at);
+                self.store
+                    .webhook_failed_again(
+                        id,
+                        failure.status,
+                        &failure.response,
+                        (failed_at, next_attempt_at),
+                    )
+                    .await?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Retries the failed deliveries which are due at `now`
+    async fn retry_due(&self, now: i64) -> Result<()> {
+        for webhook in self.store.due_webhooks(now).await? {
+            if self.redeliver(webhook.id).await? {
+                info!(id = webhook.id, "Redelivered failed webhook");
+            } else if webhook.attempts + 1 > RETRY_DELAYS.len() as i64 {
+                warn!(
+                    id = webhook.id,
+                    "Gave up retrying webhook, left for an admin"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Posts a payload once, signed if the tag has a secret for the URL
+    async fn attempt(
+        &self,
+        uid: &TagId,
+        url: &str,
+        payload: &Payload,
+    ) -> Result<Result<(), Failure>> {
+        let mut request = self
+            .client
+            .post(url)
+            .header(header::CONTENT_TYPE, &payload.content_type)
+            .body(payload.body.clone());
+        if let Some(filename) = &payload.filename {
+            request = request.header(
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            );
+        }
+        if let Some(secret) = self.store.webhook_secret(uid, url).await? {
+            let timestamp = Utc::now().timestamp();
+            request = request.header(
+                SIGNATURE_HEADER,
+                signature(&secret, timestamp, &payload.body),
+            );
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(err) => {
+                return Ok(Err(Failure {
+                    status: None,
+                    response: err.to_string(),
+                }))
+            }
+        };
+        let status = response.status();
+        if status.is_success() {
+            return Ok(Ok(()));
+        }
+
+        let mut body = response.text().await.unwrap_or_default();
+        if body.len() > MAX_RESPONSE_LENGTH {
+            let end = (0..=MAX_RESPONSE_LENGTH)
+                .rev()
+                .find(|&end| body.is_char_boundary(end))
+                .unwrap_or(0);
+            body.truncate(end);
+        }
+
+        Ok(Err(Failure {
+            status: Some(i64::from(status.as_u16())),
+            response: body,
+        }))
+    }
+}
+
+/// Spawns the background task retrying failed webhook deliveries
+pub(crate) fn spawn(webhooks: Webhooks) {
+    tokio::spawn(run(webhooks));
+}
+
+#[instrument(skip_all)]
+async fn run(webhooks: Webhooks) {
+    let mut interval = tokio::time::interval(RETRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        if !leases::held(&webhooks.store, "webhook_retries", RETRY_INTERVAL).await {
+            continue;
+        }
+        if let Err(err) = webhooks.retry_due(Utc::now().timestamp()).await {
+            error!(%err, "Unable to retry failed webhooks");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{
+        atomic::{AtomicU16, Ordering},
+        Arc, Mutex,
+    };
+
+    use axum::{http::HeaderMap, routing::post, Router};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    type Received = Arc<Mutex<Vec<(Option<String>, String)>>>;
+
+    /// A receiver answering with `status`, which records the signatures and bodies it got
+    async fn receiver(status: Arc<AtomicU16>) -> (String, Received) {
+        let received = Received::default();
+        let recorded = received.clone();
+        let router = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: String| async move {
+                let signature = headers
+                    .get(SIGNATURE_HEADER)
+                    .map(|signature| signature.to_str().unwrap().to_string());
+                recorded.lock().unwrap().push((signature, body));
+                let status = status.load(Ordering::SeqCst);
+                (
+                    axum::http::StatusCode::from_u16(status).unwrap(),
+                    "receiver says no",
+                )
+            }),
+        );
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(router.into_make_service()),
+        );
+
+        (url, received)
+    }
+
+    fn payload() -> Payload {
+        Payload::json("idle", &serde_json::json!({"text": "Still there?"})).unwrap()
+    }
+
+    #[test]
+    fn signatures_cover_the_timestamp_and_body() {
+        let signed = signature("secret", 1_700_000_000, b"{}");
+        assert!(signed.starts_with("t=1700000000,sha256="));
+        assert_eq!(signed, signature("secret", 1_700_000_000, b"{}"));
+
+        assert_ne!(signed, signature("other", 1_700_000_000, b"{}"));
+        assert_ne!(signed, signature("secret", 1_700_000_001, b"{}"));
+        assert_ne!(signed, signature("secret", 1_700_000_000, b"[]"));
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed_with_the_current_secret() {
+        let store = DataStore::in_memory().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let (url, received) = receiver(Arc::new(AtomicU16::new(200))).await;
+        let webhooks = Webhooks::new(store.clone());
+
+        webhooks.deliver(&uid, &url, payload()).await.unwrap();
+        let first = store.rotate_webhook_secret(&uid, &url).await.unwrap();
+        webhooks.deliver(&uid, &url, payload()).await.unwrap();
+        let second = store.rotate_webhook_secret(&uid, &url).await.unwrap();
+        assert_ne!(first, second);
+        webhooks.deliver(&uid, &url, payload()).await.unwrap();
+
+        let received = received.lock().unwrap().clone();
+        let body = r#"{"text":"Still there?"}"#;
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[0], (None, body.to_string()));
+        for ((signed, got), secret) in received[1..].iter().zip([first, second]) {
+            assert_eq!(got, body);
+            let signed = signed.as_deref().unwrap();
+            let timestamp = signed
+                .strip_prefix("t=")
+                .and_then(|rest| rest.split_once(','))
+                .unwrap()
+                .0;
+            assert_eq!(
+                signed,
+                signature(&secret, timestamp.parse().unwrap(), body.as_bytes())
+            );
+        }
+        assert!(store.failed_webhooks().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_are_retried_until_they_give_up() {
+        let store = DataStore::in_memory().await.unwrap();
+        let uid = TagId::new("test-tag").unwrap();
+        let status = Arc::new(AtomicU16::new(500));
+        let (url, received) = receiver(status.clone()).await;
+        let webhooks = Webhooks::new(store.clone());
+
+        // Tried once right away
+        webhooks.deliver(&uid, &url, payload()).await.unwrap();
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        let failed = store.failed_webhooks().await.unwrap();
+        assert_eq!(failed.len(), 1);
+        let id = failed[0].id;
+        assert_eq!(failed[0].url, url);
+        assert_eq!(failed[0].kind, "idle");
+        assert_eq!(failed[0].status, Some(500));
+        assert_eq!(failed[0].response, "receiver says no");
+        assert_eq!(failed[0].attempts, 1);
+        let next_attempt_at = failed[0].next_attempt_at.unwrap();
+        assert_eq!(next_attempt_at, failed[0].failed_at + RETRY_DELAYS[0]);
+
+        // Nothing is due before then
+        webhooks.retry_due(next_attempt_at - 1).await.unwrap();
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        status.store(404, Ordering::SeqCst);
+        for attempts in 2..=RETRY_DELAYS.len() as i64 + 1 {
+            webhooks.retry_due(i64::MAX).await.unwrap();
+            let failed = store.failed_webhook(id).await.unwrap();
+            assert_eq!(failed.status, Some(404));
+            assert_eq!(failed.attempts, attempts);
+        }
+        // Out of retries, so it waits for an admin
+        assert_eq!(
+            store.failed_webhook(id).await.unwrap().next_attempt_at,
+            None
+        );
+        webhooks.retry_due(i64::MAX).await.unwrap();
+        assert_eq!(received.lock().unwrap().len(), RETRY_DELAYS.len() + 1);
+
+        status.store(204, Ordering::SeqCst);
+        assert!(webhooks.redeliver(id).await.unwrap());
+        assert!(store.failed_webhooks().await.unwrap().is_empty());
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.last().unwrap().1, r#"{"text":"Still there?"}"#);
+    }
+}